@@ -0,0 +1,30 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 1001 => "Already initialized",
+    NotOwner = 1002 => "This operation is restricted to the contract owner",
+    NotFeeCollector = 1003 => "Only registered fee collectors can collect fees in this token",
+    FeeCollectorAlreadyRegistered = 1004 => "The account is already registered as a fee collector",
+    FeeCollectorNotRegistered = 1005 => "The account is not registered as a fee collector",
+    NotMinter = 1006 => "This operation is restricted to token minters",
+    MinterAlreadyRegistered = 1007 => "The account is already registered as a minter",
+    MinterNotRegistered = 1008 => "The account is not registered as a minter",
+    AmountLessThanRegistrationFee = 1009 => "Amount is not enough to cover the storage deposit fee",
+    ConfigContractNotSet = 1010 => "Config contract is not set",
+    UnexpectedPromiseResult = 1011 => "Unexpected value result from promise",
+    Paused = 1012 => "Activity token is paused",
+    SpendLimitExceeded = 1013 => "Daily spend limit of the account is exceeded",
+    DepositRequired = 1014 => "Requires attached deposit of at least 1 yoctoNEAR",
+    NotPendingOwner = 1015 => "This operation is restricted to the proposed contract owner",
+    InvalidBridgeField = 1016 => "Bridge chain, address or transaction is empty or too long",
+    ZeroBridgeAmount = 1017 => "Bridge amount must be greater than zero",
+    BridgeEscrowInsufficient = 1018 => "Amount exceeds the tokens locked for the bridge",
+    BridgeProofUsed = 1019 => "The bridge transfer is released already",
+    InvalidGiftCodeHash = 1020 => "Gift code hash must be a base64-encoded sha256 hash (32 bytes)",
+    ZeroGiftCodeAmount = 1021 => "Gift code amount must be greater than zero",
+    GiftCodeExists = 1022 => "Gift code with this hash already exists",
+    GiftCodeNotFound = 1023 => "Gift code is not found or redeemed already",
+    InvalidAmountFormat = 1024 => "Amount must be a decimal number with at most 'decimals' fraction digits",
+    BalanceBatchTooLarge = 1025 => "Up to 100 accounts can be queried at once",
+}
@@ -0,0 +1,15 @@
+use near_sdk::ext_contract;
+use artfans_common::ConfigDTO;
+
+pub use artfans_common::TGAS;
+
+
+#[ext_contract(ext_config)]
+pub trait Config {
+    fn get_config(&self) -> ConfigDTO;
+}
+
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn on_config_refreshed(&mut self) -> bool;
+}
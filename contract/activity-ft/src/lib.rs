@@ -2,19 +2,77 @@ use near_contract_standards::fungible_token::metadata::{
     FungibleTokenMetadata, FungibleTokenMetadataProvider,
 };
 use near_contract_standards::fungible_token::FungibleToken;
+use near_contract_standards::non_fungible_token::refund_deposit;
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupSet};
-use near_sdk::json_types::U128;
-use near_sdk::{env, near_bindgen, assert_one_yocto, AccountId, Balance, PanicOnDefault, PromiseOrValue, BorshStorageKey};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet};
+use near_sdk::json_types::{U128, U64, Base64VecU8};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, assert_one_yocto, promise_result_as_success, AccountId, Balance, Gas, PanicOnDefault, Promise, PromiseOrValue, BorshStorageKey};
+use artfans_common::{CachedConfig, CachedConfigDTO, ConfigDTO, Ownable, Ownership, Pausable, ACTIVITY_FT_EXCHANGE_RATE, NANOSECONDS_PER_DAY};
+
+pub mod external;
+pub use crate::external::*;
+pub mod errors;
+pub use crate::errors::*;
+
+pub const EVENT_STANDARD: &str = "artfans_activity_ft";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+pub const MAX_BRIDGE_CHAIN_LEN: usize = 32;
+pub const MAX_BRIDGE_ADDRESS_LEN: usize = 128;
+pub const MAX_BRIDGE_TX_LEN: usize = 128;
+pub const MAX_BALANCE_BATCH: usize = 100;
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
     token: FungibleToken,
     metadata: LazyOption<FungibleTokenMetadata>,
-    owner: AccountId,
+    ownership: Ownership,
     fee_collectors: LookupSet<AccountId>,
-    minters: LookupSet<AccountId>
+    minters: LookupSet<AccountId>,
+    config_contract: Option<AccountId>,
+    cached_config: CachedConfig,
+    spend_limits: LookupMap<AccountId, SpendLimit>,
+    bridge_locked: Balance,
+    bridge_nonce: u64,
+    bridge_releases: LookupSet<Vec<u8>>,
+    gift_codes: LookupMap<Vec<u8>, Balance>
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SpendLimit {
+    amount_per_day: Balance,
+    day: u64,
+    spent: Balance
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SpendLimitDTO {
+    amount_per_day: U128,
+    spent_today: U128
+}
+
+// Tokens are escrowed on this contract until they are released back by the owner.
+// The relayer watching 'bridge_lock' events is external, so this is only a sketch of the multichain path
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BridgeLockDTO {
+    nonce: U64,
+    account_id: AccountId,
+    amount: U128,
+    dest_chain: String,
+    dest_address: String
+}
+
+// 'source_chain' and 'source_tx' identify the burn (or lock) on the other chain and can be released only once
+#[derive(BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BridgeReleaseProof {
+    source_chain: String,
+    source_tx: String,
+    receiver_id: AccountId,
+    amount: U128
 }
 
 #[derive(BorshStorageKey, BorshSerialize)]
@@ -22,7 +80,10 @@ pub enum StorageKeys {
     Token,
     Metadata,
     FeeCollectors,
-    Minters
+    Minters,
+    SpendLimits,
+    BridgeReleases,
+    GiftCodes
 }
 
 #[near_bindgen]
@@ -34,66 +95,131 @@ impl Contract {
         owner: AccountId,
         metadata: FungibleTokenMetadata,
     ) -> Self {
-        assert!(!env::state_exists(), "Already initialized");
+        if env::state_exists() {
+            ContractError::AlreadyInitialized.panic();
+        };
         metadata.assert_valid();
         let mut this = Self {
             token: FungibleToken::new(StorageKeys::Token),
             metadata: LazyOption::new(StorageKeys::Metadata, Some(&metadata)),
-            owner: owner.clone(),
+            ownership: Ownership::new(owner.clone()),
             fee_collectors: LookupSet::new(StorageKeys::FeeCollectors),
             minters: LookupSet::new(StorageKeys::Minters),
+            config_contract: None,
+            cached_config: CachedConfig::new(ACTIVITY_FT_EXCHANGE_RATE),
+            spend_limits: LookupMap::new(StorageKeys::SpendLimits),
+            bridge_locked: 0,
+            bridge_nonce: 0,
+            bridge_releases: LookupSet::new(StorageKeys::BridgeReleases),
+            gift_codes: LookupMap::new(StorageKeys::GiftCodes)
         };
         this.token.internal_register_account(&owner);
         this.minters.insert(&owner);
         this
     }
 
-    pub fn ft_collect_fee(&mut self, amount: U128) {
-        assert!(self.fee_collectors.contains(&env::predecessor_account_id()), "Only registered fee collectors can collect fees in this token");
+    /// Transfers the fee from `payer_id` to the calling fee collector. Fee collectors are whitelisted by the owner
+    /// and name the account that called them, which is the sender of a delegated (NEP-366) action, not the relayer
+    pub fn ft_collect_fee(&mut self, payer_id: AccountId, amount: U128) {
+        self.assert_not_paused();
+        if !self.fee_collectors.contains(&env::predecessor_account_id()) {
+            ContractError::NotFeeCollector.panic();
+        };
         if !self.token.accounts.contains_key(&env::predecessor_account_id()) {
             self.token.accounts.insert(&env::predecessor_account_id(), &0);
         }
         let amount: Balance = amount.into();
-        self.token.internal_transfer(&env::signer_account_id(), &env::predecessor_account_id(), amount, None);
+        self.charge_spend_limit(&payer_id, amount);
+        self.token.internal_transfer(&payer_id, &env::predecessor_account_id(), amount, None);
+    }
+
+    /// Sets the daily limit of fees that can be collected from the caller, `None` removes the limit.
+    /// Requires a deposit to cover the storage, so function-call access keys cannot change it
+    #[payable]
+    pub fn set_spend_limit(&mut self, amount_per_day: Option<U128>) {
+        if env::attached_deposit() == 0 {
+            ContractError::DepositRequired.panic();
+        };
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        match amount_per_day {
+            Some(amount) => {
+                let spend_limit = match self.spend_limits.get(&account_id) {
+                    Some(existing) => SpendLimit { amount_per_day: amount.into(), ..existing },
+                    None => SpendLimit { amount_per_day: amount.into(), day: 0, spent: 0 }
+                };
+                self.spend_limits.insert(&account_id, &spend_limit);
+            },
+            None => {
+                self.spend_limits.remove(&account_id);
+            }
+        };
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    pub fn get_spend_limit(&self, account_id: AccountId) -> Option<SpendLimitDTO> {
+        self.spend_limits.get(&account_id).map(|spend_limit| {
+            let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+            SpendLimitDTO {
+                amount_per_day: U128(spend_limit.amount_per_day),
+                spent_today: U128(if spend_limit.day == today { spend_limit.spent } else { 0 })
+            }
+        })
+    }
+
+    fn charge_spend_limit(&mut self, account_id: &AccountId, amount: Balance) {
+        if let Some(mut spend_limit) = self.spend_limits.get(account_id) {
+            let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+            if spend_limit.day != today {
+                spend_limit.day = today;
+                spend_limit.spent = 0;
+            };
+            spend_limit.spent = spend_limit.spent.saturating_add(amount);
+            if spend_limit.spent > spend_limit.amount_per_day {
+                ContractError::SpendLimitExceeded.panic();
+            };
+            self.spend_limits.insert(account_id, &spend_limit);
+        };
     }
 
     pub fn add_fee_collector(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.fee_collectors.insert(&account_id) {
-            env::panic_str("The account is already registered as a fee collector");
+            ContractError::FeeCollectorAlreadyRegistered.panic();
         }
     }
     
     pub fn remove_fee_collector(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.fee_collectors.remove(&account_id) {
-            env::panic_str("The account is not registered as a fee collector");
+            ContractError::FeeCollectorNotRegistered.panic();
         }
     }
 
     pub fn add_minter(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.minters.insert(&account_id) {
-            env::panic_str("The account is already registered as a minter");
+            ContractError::MinterAlreadyRegistered.panic();
         }
     }
 
     pub fn remove_minter(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.minters.remove(&account_id) {
-            env::panic_str("The account is not registered as a minter");
+            ContractError::MinterNotRegistered.panic();
         }
     }
 
     fn assert_minter(&self) {
-        assert!(self.minters.contains(&env::predecessor_account_id()),
-            "This operation is restricted to token minters"
-        );
+        if !self.minters.contains(&env::predecessor_account_id()) {
+            ContractError::NotMinter.panic();
+        };
     }
 
     #[payable]
     pub fn ft_mint(&mut self, account_id: AccountId, amount: U128, registration_fee: Option<U128>) -> U128 {
         self.assert_minter();
+        self.assert_not_paused();
         let amount_to_mint: u128 = if self.token.accounts.contains_key(&account_id) {
             amount.into()
         } else {
@@ -102,7 +228,7 @@ impl Contract {
                     let total: u128 = amount.into();
                     let correction: u128 = fee.into();
                     if total < correction {
-                        env::panic_str("Amount is not enough to cover the storage deposit fee");
+                        ContractError::AmountLessThanRegistrationFee.panic();
                     };
                     total - correction
                 },
@@ -121,6 +247,176 @@ impl Contract {
         self.token.internal_withdraw(&account_id, amount.into());
     }
 
+    pub fn set_config_contract(&mut self, config_contract: AccountId) {
+        self.assert_owner();
+        self.config_contract = Some(config_contract);
+    }
+
+    pub fn refresh_config(&mut self) -> Promise {
+        let config_contract = self.config_contract.clone().unwrap_or_else(|| ContractError::ConfigContractNotSet.panic());
+        ext_config::ext(config_contract)
+            .with_static_gas(Gas(5*TGAS))
+            .get_config()
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .on_config_refreshed()
+                )
+    }
+
+    #[private]
+    pub fn on_config_refreshed(&mut self) -> bool {
+        if let Some(result) = promise_result_as_success() {
+            let config = near_sdk::serde_json::from_slice::<ConfigDTO>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            self.cached_config.update(&config, config.activity_ft_paused);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_cached_config(&self) -> CachedConfigDTO {
+        self.cached_config.to_dto()
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
+    }
+
+    #[payable]
+    pub fn lock_for_bridge(&mut self, amount: U128, dest_chain: String, dest_address: String) -> U64 {
+        assert_one_yocto();
+        self.assert_not_paused();
+        assert_bridge_field(&dest_chain, MAX_BRIDGE_CHAIN_LEN);
+        assert_bridge_field(&dest_address, MAX_BRIDGE_ADDRESS_LEN);
+        let amount: Balance = amount.into();
+        if amount == 0 {
+            ContractError::ZeroBridgeAmount.panic();
+        };
+
+        let account_id = env::predecessor_account_id();
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            self.token.accounts.insert(&escrow_id, &0);
+        };
+        self.token.internal_transfer(&account_id, &escrow_id, amount, Some("bridge lock".to_string()));
+        self.bridge_locked += amount;
+
+        let nonce = self.bridge_nonce;
+        self.bridge_nonce += 1;
+        log_event("bridge_lock", BridgeLockDTO {
+            nonce: U64(nonce),
+            account_id,
+            amount: U128(amount),
+            dest_chain,
+            dest_address
+        });
+        U64(nonce)
+    }
+
+    pub fn release_from_bridge(&mut self, proof: BridgeReleaseProof) {
+        self.assert_owner();
+        self.assert_not_paused();
+        assert_bridge_field(&proof.source_chain, MAX_BRIDGE_CHAIN_LEN);
+        assert_bridge_field(&proof.source_tx, MAX_BRIDGE_TX_LEN);
+        let amount: Balance = proof.amount.into();
+        if amount == 0 {
+            ContractError::ZeroBridgeAmount.panic();
+        };
+        if amount > self.bridge_locked {
+            ContractError::BridgeEscrowInsufficient.panic();
+        };
+        if !self.bridge_releases.insert(&get_bridge_release_key(&proof)) {
+            ContractError::BridgeProofUsed.panic();
+        };
+
+        self.token.internal_transfer(&env::current_account_id(), &proof.receiver_id, amount, Some("bridge release".to_string()));
+        self.bridge_locked -= amount;
+        log_event("bridge_release", proof);
+    }
+
+    pub fn get_bridge_locked(&self) -> U128 {
+        U128(self.bridge_locked)
+    }
+
+    pub fn is_bridge_proof_used(&self, proof: BridgeReleaseProof) -> bool {
+        self.bridge_releases.contains(&get_bridge_release_key(&proof))
+    }
+
+    /// Mints `amount` into a gift code identified by `code_hash` (sha256 of the code). The tokens are
+    /// escrowed on this contract until the code is redeemed. Requires a deposit to cover the storage
+    #[payable]
+    pub fn create_gift_code(&mut self, code_hash: Base64VecU8, amount: U128) {
+        self.assert_minter();
+        self.assert_not_paused();
+        if env::attached_deposit() == 0 {
+            ContractError::DepositRequired.panic();
+        };
+        let code_hash: Vec<u8> = code_hash.into();
+        if code_hash.len() != 32 {
+            ContractError::InvalidGiftCodeHash.panic();
+        };
+        let amount: Balance = amount.into();
+        if amount == 0 {
+            ContractError::ZeroGiftCodeAmount.panic();
+        };
+        if self.gift_codes.contains_key(&code_hash) {
+            ContractError::GiftCodeExists.panic();
+        };
+
+        let initial_storage_usage = env::storage_usage();
+        let escrow_id = env::current_account_id();
+        if !self.token.accounts.contains_key(&escrow_id) {
+            self.token.accounts.insert(&escrow_id, &0);
+        };
+        self.token.internal_deposit(&escrow_id, amount);
+        self.gift_codes.insert(&code_hash, &amount);
+        refund_deposit(env::storage_usage().saturating_sub(initial_storage_usage));
+    }
+
+    /// Transfers the gift code balance to the caller, the code can be redeemed only once.
+    /// An unregistered caller attaches the storage deposit, it is registered the same way as in `storage_deposit`
+    #[payable]
+    pub fn redeem_code(&mut self, preimage: String) -> U128 {
+        self.assert_not_paused();
+        let code_hash = env::sha256(preimage.as_bytes());
+        let amount = self.gift_codes.remove(&code_hash).unwrap_or_else(|| ContractError::GiftCodeNotFound.panic());
+
+        let account_id = env::predecessor_account_id();
+        if !self.token.accounts.contains_key(&account_id) {
+            self.storage_deposit(Some(account_id.clone()), None);
+        };
+        self.token.internal_transfer(&env::current_account_id(), &account_id, amount, Some("gift code".to_string()));
+        U128(amount)
+    }
+
+    pub fn get_gift_code(&self, code_hash: Base64VecU8) -> Option<U128> {
+        self.gift_codes.get(&code_hash.into()).map(U128)
+    }
+
+    /// Formats the raw amount with the token `decimals`, e.g. "1.5" for 1.5 * 10^decimals. Trailing zeros are trimmed
+    pub fn format_amount(&self, amount: U128) -> String {
+        format_decimal_amount(amount.into(), self.metadata.get().unwrap().decimals)
+    }
+
+    /// Parses a decimal amount, e.g. "1.5", into the raw amount with the token `decimals`
+    pub fn parse_amount(&self, amount: String) -> U128 {
+        parse_decimal_amount(&amount, self.metadata.get().unwrap().decimals)
+            .map(U128)
+            .unwrap_or_else(|| ContractError::InvalidAmountFormat.panic())
+    }
+
+    /// Balances of the given accounts in the same order, unregistered accounts have zero balance
+    pub fn ft_balance_of_batch(&self, account_ids: Vec<AccountId>) -> Vec<U128> {
+        if account_ids.len() > MAX_BALANCE_BATCH {
+            ContractError::BalanceBatchTooLarge.panic();
+        };
+        account_ids
+            .iter()
+            .map(|account_id| U128(self.token.accounts.get(account_id).unwrap_or(0)))
+            .collect()
+    }
+
     #[payable]
     pub fn set_metadata(&mut self, metadata: FungibleTokenMetadata) {
         self.assert_owner();
@@ -130,30 +426,64 @@ impl Contract {
 
 }
 
-pub trait Ownable {
-    fn assert_owner(&self) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.get_owner(),
-            "This operation is restricted to the contract owner."
-        );
+fn assert_bridge_field(value: &str, max_len: usize) {
+    if value.is_empty() || value.len() > max_len {
+        ContractError::InvalidBridgeField.panic();
+    };
+}
+
+// Only the source of the proof is hashed, so the same transfer cannot be released twice with another receiver or amount
+fn get_bridge_release_key(proof: &BridgeReleaseProof) -> Vec<u8> {
+    env::sha256(&(&proof.source_chain, &proof.source_tx).try_to_vec().unwrap())
+}
+
+fn format_decimal_amount(amount: u128, decimals: u8) -> String {
+    let digits = format!("{:0>width$}", amount, width = usize::from(decimals) + 1);
+    let (integer, fraction) = digits.split_at(digits.len() - usize::from(decimals));
+    let fraction = fraction.trim_end_matches('0');
+    if fraction.is_empty() {
+        integer.to_string()
+    } else {
+        format!("{}.{}", integer, fraction)
     }
-    fn get_owner(&self) -> AccountId;
-    fn set_owner(&mut self, owner: AccountId);
 }
 
-#[near_bindgen]
-impl Ownable for Contract {
-    fn get_owner(&self) -> AccountId {
-        self.owner.clone()
+// None is returned for malformed amounts, amounts with more fraction digits than 'decimals' and overflows
+fn parse_decimal_amount(amount: &str, decimals: u8) -> Option<u128> {
+    let (integer, fraction) = match amount.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (amount, "")
+    };
+    let is_digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+    if integer.is_empty() || amount.ends_with('.') || !is_digits(integer) || !is_digits(fraction) || fraction.len() > usize::from(decimals) {
+        return None
     }
 
-    fn set_owner(&mut self, owner: AccountId) {
-        self.assert_owner();
-        self.owner = owner;
+    let multiplier = 10u128.checked_pow(u32::from(decimals))?;
+    let fraction_multiplier = 10u128.checked_pow(u32::from(decimals) - fraction.len() as u32)?;
+    let fraction_amount = if fraction.is_empty() { 0 } else { fraction.parse::<u128>().ok()? };
+    integer.parse::<u128>().ok()?
+        .checked_mul(multiplier)?
+        .checked_add(fraction_amount.checked_mul(fraction_multiplier)?)
+}
+
+fn log_event<T: Serialize>(event: &str, data: T) {
+    artfans_common::log_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, data);
+}
+
+impl Pausable for Contract {
+    fn is_paused(&self) -> bool {
+        self.cached_config.is_paused()
+    }
+
+    fn panic_paused(&self) -> ! {
+        ContractError::Paused.panic()
     }
 }
 
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
+
 near_contract_standards::impl_fungible_token_core!(Contract, token);
 near_contract_standards::impl_fungible_token_storage!(Contract, token);
 
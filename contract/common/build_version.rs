@@ -0,0 +1,32 @@
+use std::process::Command;
+
+// Embeds the git commit of the source tree as `ARTFANS_GIT_COMMIT`, returned by the `version` method.
+// Commits of a tree with uncommitted changes are suffixed with "-dirty"
+fn main() {
+    let commit = git(&["rev-parse", "HEAD"]);
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|status| !status.is_empty());
+    let commit = match commit {
+        Some(commit) if dirty => format!("{}-dirty", commit),
+        Some(commit) => commit,
+        None => String::from("unknown")
+    };
+    println!("cargo:rustc-env=ARTFANS_GIT_COMMIT={}", commit);
+
+    if let Some(head) = git(&["rev-parse", "--git-path", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}", head);
+    }
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]).and_then(|branch| git(&["rev-parse", "--git-path", &branch])) {
+        println!("cargo:rerun-if-changed={}", branch);
+    }
+    if let Some(index) = git(&["rev-parse", "--git-path", "index"]) {
+        println!("cargo:rerun-if-changed={}", index);
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None
+    }
+    String::from_utf8(output.stdout).ok().map(|output| String::from(output.trim()))
+}
@@ -0,0 +1,65 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+use crate::ConfigDTO;
+
+/// Config of the config contract cached by the contracts reading it with `refresh_config`.
+/// `paused` is the pause flag of the caching contract
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct CachedConfig {
+    version: u64,
+    activity_ft_exchange_rate: u128,
+    treasury: Option<AccountId>,
+    paused: bool
+}
+
+/// Cached config as returned by `get_cached_config`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CachedConfigDTO {
+    pub version: U64,
+    pub activity_ft_exchange_rate: U128,
+    pub treasury: Option<AccountId>,
+    pub paused: bool
+}
+
+impl CachedConfig {
+    pub fn new(activity_ft_exchange_rate: u128) -> Self {
+        Self { version: 0, activity_ft_exchange_rate, treasury: None, paused: false }
+    }
+
+    pub fn activity_ft_exchange_rate(&self) -> u128 {
+        self.activity_ft_exchange_rate
+    }
+
+    pub fn treasury(&self) -> Option<&AccountId> {
+        self.treasury.as_ref()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn to_dto(&self) -> CachedConfigDTO {
+        CachedConfigDTO {
+            version: U64(self.version),
+            activity_ft_exchange_rate: U128(self.activity_ft_exchange_rate),
+            treasury: self.treasury.clone(),
+            paused: self.paused
+        }
+    }
+
+    /// The config is skipped if it is older than the cached one, e.g. when refreshes are resolved out of order
+    pub fn update(&mut self, config: &ConfigDTO, paused: bool) {
+        let version = u64::from(config.version);
+        if version >= self.version {
+            *self = Self {
+                version,
+                activity_ft_exchange_rate: config.activity_ft_exchange_rate.into(),
+                treasury: Some(config.treasury.clone()),
+                paused
+            };
+        };
+    }
+}
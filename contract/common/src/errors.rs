@@ -0,0 +1,49 @@
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ErrorCodeDTO {
+    pub code: u16,
+    pub message: String
+}
+
+/// Declares the `ContractError` enum of a contract and the `error_codes` function listing its errors.
+/// Every error panics with the message prefixed by its code, e.g. "E1001: Already initialized"
+#[macro_export]
+macro_rules! contract_errors {
+    ($($name:ident = $code:literal => $message:literal),* $(,)?) => {
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        pub enum ContractError {
+            $($name),*
+        }
+
+        impl ContractError {
+            pub fn code(&self) -> u16 {
+                match self {
+                    $(ContractError::$name => $code),*
+                }
+            }
+
+            pub fn message(&self) -> &'static str {
+                match self {
+                    $(ContractError::$name => $message),*
+                }
+            }
+
+            pub fn all() -> Vec<ContractError> {
+                vec![$(ContractError::$name),*]
+            }
+
+            pub fn panic(&self) -> ! {
+                near_sdk::env::panic_str(&format!("E{}: {}", self.code(), self.message()))
+            }
+        }
+
+        pub fn error_codes() -> Vec<$crate::ErrorCodeDTO> {
+            ContractError::all()
+                .into_iter()
+                .map(|error| $crate::ErrorCodeDTO { code: error.code(), message: String::from(error.message()) })
+                .collect()
+        }
+    };
+}
@@ -0,0 +1,13 @@
+use near_sdk::log;
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::json;
+
+/// Logs NEP-297 event, e.g. `EVENT_JSON:{"standard":"artfans_marketplace","version":"1.0.0","event":"...","data":[...]}`
+pub fn log_event<T: Serialize>(standard: &str, version: &str, event: &str, data: T) {
+    log!("EVENT_JSON:{}", json!({
+        "standard": standard,
+        "version": version,
+        "event": event,
+        "data": [data]
+    }));
+}
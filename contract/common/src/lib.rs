@@ -0,0 +1,33 @@
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
+
+pub mod errors;
+pub use crate::errors::*;
+pub mod ownable;
+pub use crate::ownable::*;
+pub mod pausable;
+pub use crate::pausable::*;
+pub mod events;
+pub use crate::events::*;
+pub mod version;
+pub use crate::version::*;
+pub mod config;
+pub use crate::config::*;
+
+pub const TGAS: u64 = 1_000_000_000_000;
+pub const ACTIVITY_FT_EXCHANGE_RATE: u128 = 100;
+pub const NANOSECONDS_PER_DAY: u64 = 86_400_000_000_000;
+
+// Config as returned by 'get_config' of the config contract
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigDTO {
+    pub version: U64,
+    pub activity_ft_exchange_rate: U128,
+    pub treasury: AccountId,
+    pub social_network_paused: bool,
+    pub marketplace_paused: bool,
+    pub activity_ft_paused: bool,
+    pub nft_paused: bool
+}
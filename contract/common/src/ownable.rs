@@ -0,0 +1,88 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, AccountId};
+
+/// Owner of a contract. Ownership is transferred in two steps: the owner proposes a new owner
+/// and the proposed account accepts it, so it can not be passed to a mistyped account
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Ownership {
+    owner: AccountId,
+    pending_owner: Option<AccountId>
+}
+
+impl Ownership {
+    pub fn new(owner: AccountId) -> Self {
+        Self { owner, pending_owner: None }
+    }
+
+    pub fn owner(&self) -> &AccountId {
+        &self.owner
+    }
+
+    pub fn pending_owner(&self) -> Option<&AccountId> {
+        self.pending_owner.as_ref()
+    }
+
+    pub fn propose(&mut self, owner: AccountId) {
+        self.pending_owner = Some(owner);
+    }
+
+    /// Returns false if the account is not the proposed owner
+    pub fn accept(&mut self, account_id: &AccountId) -> bool {
+        if self.pending_owner.as_ref() != Some(account_id) {
+            return false
+        }
+        self.owner = account_id.clone();
+        self.pending_owner = None;
+        true
+    }
+}
+
+pub trait Ownable {
+    fn ownership(&self) -> &Ownership;
+    fn panic_not_owner(&self) -> !;
+
+    fn assert_owner(&self) {
+        if &env::predecessor_account_id() != self.ownership().owner() {
+            self.panic_not_owner();
+        };
+    }
+}
+
+/// Implements `Ownable` for the contract storing `Ownership` in `$ownership` and exposes
+/// `get_owner`, `get_pending_owner`, `propose_owner` and `accept_ownership` methods
+#[macro_export]
+macro_rules! impl_ownable {
+    ($contract:ident, $ownership:ident, $not_owner:expr, $not_pending_owner:expr) => {
+        impl $crate::Ownable for $contract {
+            fn ownership(&self) -> &$crate::Ownership {
+                &self.$ownership
+            }
+
+            fn panic_not_owner(&self) -> ! {
+                $not_owner.panic()
+            }
+        }
+
+        #[near_sdk::near_bindgen]
+        impl $contract {
+            pub fn get_owner(&self) -> near_sdk::AccountId {
+                self.$ownership.owner().clone()
+            }
+
+            pub fn get_pending_owner(&self) -> Option<near_sdk::AccountId> {
+                self.$ownership.pending_owner().cloned()
+            }
+
+            pub fn propose_owner(&mut self, owner: near_sdk::AccountId) {
+                $crate::Ownable::assert_owner(self);
+                self.$ownership.propose(owner);
+            }
+
+            pub fn accept_ownership(&mut self) {
+                if !self.$ownership.accept(&near_sdk::env::predecessor_account_id()) {
+                    $not_pending_owner.panic();
+                };
+            }
+        }
+    };
+}
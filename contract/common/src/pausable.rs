@@ -0,0 +1,11 @@
+/// Contract which can be paused, e.g. by the config contract
+pub trait Pausable {
+    fn is_paused(&self) -> bool;
+    fn panic_paused(&self) -> !;
+
+    fn assert_not_paused(&self) {
+        if self.is_paused() {
+            self.panic_paused();
+        };
+    }
+}
@@ -0,0 +1,25 @@
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VersionDTO {
+    pub version: String,
+    pub git_commit: String
+}
+
+/// Exposes `version` method returning the crate version and the git commit the contract was built from.
+/// The commit is embedded by the build script of the contract as `ARTFANS_GIT_COMMIT`
+#[macro_export]
+macro_rules! impl_version {
+    ($contract:ident) => {
+        #[near_sdk::near_bindgen]
+        impl $contract {
+            pub fn version(&self) -> $crate::VersionDTO {
+                $crate::VersionDTO {
+                    version: String::from(env!("CARGO_PKG_VERSION")),
+                    git_commit: String::from(option_env!("ARTFANS_GIT_COMMIT").unwrap_or("unknown"))
+                }
+            }
+        }
+    };
+}
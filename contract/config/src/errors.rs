@@ -0,0 +1,9 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 6001 => "Already initialized",
+    NotOwner = 6002 => "This operation is restricted to the contract owner",
+    ZeroExchangeRate = 6003 => "'activity_ft_exchange_rate' must be greater than 0",
+    NotPendingOwner = 6004 => "This operation is restricted to the proposed contract owner",
+}
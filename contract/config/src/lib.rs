@@ -0,0 +1,121 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, AccountId, PanicOnDefault};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use artfans_common::{ConfigDTO, Ownable, Ownership, ACTIVITY_FT_EXCHANGE_RATE};
+
+pub mod errors;
+pub use crate::errors::*;
+
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    ownership: Ownership,
+    config: Config,
+    version: u64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Clone)]
+pub struct Config {
+    activity_ft_exchange_rate: u128,
+    treasury: AccountId,
+    social_network_paused: bool,
+    marketplace_paused: bool,
+    activity_ft_paused: bool,
+    nft_paused: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigData {
+    activity_ft_exchange_rate: Option<U128>,
+    treasury: Option<AccountId>,
+    social_network_paused: Option<bool>,
+    marketplace_paused: Option<bool>,
+    activity_ft_paused: Option<bool>,
+    nft_paused: Option<bool>
+}
+
+
+#[near_bindgen]
+impl Contract {
+
+    #[init]
+    pub fn new(owner: AccountId, config: ConfigData) -> Self {
+        if env::state_exists() {
+            ContractError::AlreadyInitialized.panic();
+        }
+        let this = Self {
+            ownership: Ownership::new(owner.clone()),
+            config: Config {
+                activity_ft_exchange_rate: match config.activity_ft_exchange_rate {
+                    Some(activity_ft_exchange_rate) => activity_ft_exchange_rate.into(),
+                    None => ACTIVITY_FT_EXCHANGE_RATE
+                },
+                treasury: match config.treasury {
+                    Some(treasury) => treasury,
+                    None => owner
+                },
+                social_network_paused: config.social_network_paused.unwrap_or(false),
+                marketplace_paused: config.marketplace_paused.unwrap_or(false),
+                activity_ft_paused: config.activity_ft_paused.unwrap_or(false),
+                nft_paused: config.nft_paused.unwrap_or(false)
+            },
+            version: 1
+        };
+        this.assert_config();
+        this
+    }
+
+    pub fn update_config(&mut self, config: ConfigData) {
+        self.assert_owner();
+        if let Some(activity_ft_exchange_rate) = config.activity_ft_exchange_rate {
+            self.config.activity_ft_exchange_rate = activity_ft_exchange_rate.into();
+        }
+        if let Some(treasury) = config.treasury {
+            self.config.treasury = treasury;
+        }
+        if let Some(social_network_paused) = config.social_network_paused {
+            self.config.social_network_paused = social_network_paused;
+        }
+        if let Some(marketplace_paused) = config.marketplace_paused {
+            self.config.marketplace_paused = marketplace_paused;
+        }
+        if let Some(activity_ft_paused) = config.activity_ft_paused {
+            self.config.activity_ft_paused = activity_ft_paused;
+        }
+        if let Some(nft_paused) = config.nft_paused {
+            self.config.nft_paused = nft_paused;
+        }
+        self.assert_config();
+        self.version += 1;
+    }
+
+    pub fn get_config(&self) -> ConfigDTO {
+        ConfigDTO {
+            version: U64(self.version),
+            activity_ft_exchange_rate: U128(self.config.activity_ft_exchange_rate),
+            treasury: self.config.treasury.clone(),
+            social_network_paused: self.config.social_network_paused,
+            marketplace_paused: self.config.marketplace_paused,
+            activity_ft_paused: self.config.activity_ft_paused,
+            nft_paused: self.config.nft_paused
+        }
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
+    }
+
+    fn assert_config(&self) {
+        if self.config.activity_ft_exchange_rate == 0 {
+            ContractError::ZeroExchangeRate.panic();
+        };
+    }
+
+}
+
+
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
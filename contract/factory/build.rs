@@ -0,0 +1,2 @@
+// The build script is shared by all the contracts
+include!("../common/build_version.rs");
@@ -0,0 +1,13 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 5001 => "Already initialized",
+    NotOwner = 5002 => "This operation is restricted to the contract owner",
+    EmptyContractCode = 5003 => "Contract code is empty",
+    ContractCodeNotStored = 5004 => "Contract code is not stored",
+    NotEnoughDeposit = 5005 => "Attached deposit is not enough to cover the storage of deployed contracts",
+    InvalidPrefix = 5006 => "'prefix' must consist of lowercase alphanumeric characters or '_'",
+    InvalidSuiteAccountId = 5007 => "Invalid suite account id",
+    NotPendingOwner = 5008 => "This operation is restricted to the proposed contract owner",
+}
@@ -0,0 +1,21 @@
+use near_sdk::{ext_contract, AccountId};
+use crate::SuiteAccounts;
+
+pub use artfans_common::TGAS;
+
+pub const ACTIVITY_FT_NEW_GAS: u64 = 10*TGAS;
+pub const ACTIVITY_FT_SETUP_GAS: u64 = 5*TGAS;
+pub const SOCIAL_NETWORK_NEW_GAS: u64 = 80*TGAS;
+pub const NFT_NEW_GAS: u64 = 10*TGAS;
+pub const NFT_SETUP_GAS: u64 = 5*TGAS;
+pub const SOCIAL_NETWORK_SETUP_GAS: u64 = 5*TGAS;
+pub const MARKETPLACE_NEW_GAS: u64 = 5*TGAS;
+pub const MARKETPLACE_SETUP_GAS: u64 = 5*TGAS;
+pub const CONFIG_NEW_GAS: u64 = 5*TGAS;
+pub const ON_SUITE_DEPLOYED_GAS: u64 = 10*TGAS;
+
+
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn on_suite_deployed(&mut self, suite: SuiteAccounts, deployer_id: AccountId, deposit: u128) -> bool;
+}
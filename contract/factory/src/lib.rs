@@ -0,0 +1,354 @@
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::{env, near_bindgen, log, AccountId, Balance, Gas, Promise, PanicOnDefault, BorshStorageKey};
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::collections::LookupMap;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, json, Value};
+use artfans_common::{Ownable, Ownership};
+
+pub mod external;
+pub use crate::external::*;
+pub mod errors;
+pub use crate::errors::*;
+
+// Storage reserved on top of the contract code for the initial state of a deployed contract
+pub const CONTRACT_STATE_STORAGE: u64 = 100_000;
+// Contracts of the suite in the order their deployment results are passed to 'on_suite_deployed'
+pub const SUITE_CONTRACTS: [ContractKind; 5] = [
+    ContractKind::ActivityFt,
+    ContractKind::SocialNetwork,
+    ContractKind::Nft,
+    ContractKind::Marketplace,
+    ContractKind::Config
+];
+
+
+#[near_bindgen]
+#[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
+pub struct Contract {
+    ownership: Ownership,
+    contracts_code: LookupMap<ContractKind, Vec<u8>>,
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+pub enum StorageKeys {
+    ContractsCode,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ContractKind {
+    ActivityFt,
+    SocialNetwork,
+    Nft,
+    Marketplace,
+    Config
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SuiteAccounts {
+    activity_ft: AccountId,
+    social_network: AccountId,
+    nft: AccountId,
+    marketplace: AccountId,
+    config: AccountId
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SuiteConfig {
+    prefix: String,
+    owner: AccountId,
+    ft_metadata: Value,
+    social_network_settings: Value,
+    nft_contract_metadata: Value,
+    nft_default_token_metadata: Value,
+    nft_beneficiary: AccountId,
+    config_settings: Value
+}
+
+
+#[near_bindgen]
+impl Contract {
+
+    #[init]
+    pub fn new(owner: AccountId) -> Self {
+        if env::state_exists() {
+            ContractError::AlreadyInitialized.panic();
+        }
+        Self {
+            ownership: Ownership::new(owner),
+            contracts_code: LookupMap::new(StorageKeys::ContractsCode)
+        }
+    }
+
+    pub fn store_contract_code(&mut self, kind: ContractKind, code: Base64VecU8) {
+        self.assert_owner();
+        let code: Vec<u8> = code.into();
+        if code.is_empty() {
+            ContractError::EmptyContractCode.panic();
+        };
+        self.contracts_code.insert(&kind, &code);
+    }
+
+    pub fn get_suite_accounts(&self, prefix: String) -> SuiteAccounts {
+        self.assert_prefix(&prefix);
+        self.suite_accounts(&prefix)
+    }
+
+    pub fn get_suite_deposit(&self) -> U128 {
+        U128(self.calc_suite_deposit())
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
+    }
+
+    /// Deploys activity FT, social network, NFT, marketplace and config contracts as subaccounts of the factory
+    /// and wires them together: social network charges fees in the activity FT, marketplace mints it and sells
+    /// the NFT, and all of them read the shared config. Activity FT, social network, NFT and marketplace
+    /// are initialized with the factory as an owner, so the factory can wire them, after that the ownership
+    /// is proposed to the suite owner, who has to accept it. The NFT registers the marketplace on initialization.
+    #[payable]
+    pub fn deploy_suite(&mut self, config: SuiteConfig) -> Promise {
+        self.assert_owner();
+        self.assert_prefix(&config.prefix);
+
+        let deposit = env::attached_deposit();
+        let suite_deposit = self.calc_suite_deposit();
+        if deposit < suite_deposit {
+            ContractError::NotEnoughDeposit.panic();
+        };
+
+        let suite = self.suite_accounts(&config.prefix);
+        let factory_id = env::current_account_id();
+
+        let activity_ft = self.create_contract_account(ContractKind::ActivityFt, &suite.activity_ft)
+            .function_call(
+                "new".to_string(),
+                self.to_args(json!({ "owner": factory_id, "metadata": config.ft_metadata })),
+                0,
+                Gas(ACTIVITY_FT_NEW_GAS)
+            )
+            .function_call(
+                "add_fee_collector".to_string(),
+                self.to_args(json!({ "account_id": suite.social_network })),
+                0,
+                Gas(ACTIVITY_FT_SETUP_GAS)
+            )
+            .function_call(
+                "add_minter".to_string(),
+                self.to_args(json!({ "account_id": suite.marketplace })),
+                0,
+                Gas(ACTIVITY_FT_SETUP_GAS)
+            )
+            .function_call(
+                "remove_minter".to_string(),
+                self.to_args(json!({ "account_id": factory_id })),
+                0,
+                Gas(ACTIVITY_FT_SETUP_GAS)
+            )
+            .function_call(
+                "set_config_contract".to_string(),
+                self.to_args(json!({ "config_contract": suite.config })),
+                0,
+                Gas(ACTIVITY_FT_SETUP_GAS)
+            )
+            .function_call(
+                "propose_owner".to_string(),
+                self.to_args(json!({ "owner": config.owner })),
+                0,
+                Gas(ACTIVITY_FT_SETUP_GAS)
+            );
+
+        let social_network = self.create_contract_account(ContractKind::SocialNetwork, &suite.social_network)
+            .function_call(
+                "new".to_string(),
+                self.to_args(json!({ "owner": factory_id, "fee_ft": suite.activity_ft, "settings": config.social_network_settings })),
+                0,
+                Gas(SOCIAL_NETWORK_NEW_GAS)
+            )
+            .function_call(
+                "set_config_contract".to_string(),
+                self.to_args(json!({ "config_contract": suite.config })),
+                0,
+                Gas(SOCIAL_NETWORK_SETUP_GAS)
+            )
+            .function_call(
+                "set_marketplace".to_string(),
+                self.to_args(json!({ "marketplace": suite.marketplace })),
+                0,
+                Gas(SOCIAL_NETWORK_SETUP_GAS)
+            )
+            .function_call(
+                "propose_owner".to_string(),
+                self.to_args(json!({ "owner": config.owner })),
+                0,
+                Gas(SOCIAL_NETWORK_SETUP_GAS)
+            );
+
+        let nft = self.create_contract_account(ContractKind::Nft, &suite.nft)
+            .function_call(
+                "new".to_string(),
+                self.to_args(json!({
+                    "owner": factory_id,
+                    "contract_metadata": config.nft_contract_metadata,
+                    "default_token_metadata": config.nft_default_token_metadata,
+                    "beneficiary": config.nft_beneficiary,
+                    "marketplace": suite.marketplace
+                })),
+                0,
+                Gas(NFT_NEW_GAS)
+            )
+            .function_call(
+                "set_config_contract".to_string(),
+                self.to_args(json!({ "config_contract": suite.config })),
+                0,
+                Gas(NFT_SETUP_GAS)
+            )
+            .function_call(
+                "add_token_metadata_admin".to_string(),
+                self.to_args(json!({ "account_id": config.owner })),
+                0,
+                Gas(NFT_SETUP_GAS)
+            )
+            .function_call(
+                "remove_token_metadata_admin".to_string(),
+                self.to_args(json!({ "account_id": factory_id })),
+                0,
+                Gas(NFT_SETUP_GAS)
+            )
+            .function_call(
+                "propose_owner".to_string(),
+                self.to_args(json!({ "owner": config.owner })),
+                0,
+                Gas(NFT_SETUP_GAS)
+            );
+
+        let marketplace = self.create_contract_account(ContractKind::Marketplace, &suite.marketplace)
+            .function_call(
+                "new".to_string(),
+                self.to_args(json!({ "owner": factory_id, "activity_ft": suite.activity_ft, "activity_ft_beneficiary": suite.social_network })),
+                0,
+                Gas(MARKETPLACE_NEW_GAS)
+            )
+            .function_call(
+                "set_artfans_nft".to_string(),
+                self.to_args(json!({ "artfans_nft": suite.nft })),
+                0,
+                Gas(MARKETPLACE_SETUP_GAS)
+            )
+            .function_call(
+                "set_config_contract".to_string(),
+                self.to_args(json!({ "config_contract": suite.config })),
+                0,
+                Gas(MARKETPLACE_SETUP_GAS)
+            )
+            .function_call(
+                "set_social_network".to_string(),
+                self.to_args(json!({ "social_network": suite.social_network })),
+                0,
+                Gas(MARKETPLACE_SETUP_GAS)
+            )
+            .function_call(
+                "propose_owner".to_string(),
+                self.to_args(json!({ "owner": config.owner })),
+                0,
+                Gas(MARKETPLACE_SETUP_GAS)
+            );
+
+        let config_contract = self.create_contract_account(ContractKind::Config, &suite.config)
+            .function_call(
+                "new".to_string(),
+                self.to_args(json!({ "owner": config.owner, "config": config.config_settings })),
+                0,
+                Gas(CONFIG_NEW_GAS)
+            );
+
+        activity_ft
+            .and(social_network)
+            .and(nft)
+            .and(marketplace)
+            .and(config_contract)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(ON_SUITE_DEPLOYED_GAS))
+                    .on_suite_deployed(suite, env::predecessor_account_id(), deposit - suite_deposit)
+                )
+    }
+
+    // A failed contract account is not created and its deposit is returned to the factory, so it is refunded to the deployer
+    // along with the unused part of the deposit. Contracts deployed successfully are kept
+    #[private]
+    pub fn on_suite_deployed(&mut self, suite: SuiteAccounts, deployer_id: AccountId, deposit: u128) -> bool {
+        let mut refund = deposit;
+        let mut is_deployed = true;
+        for (idx, kind) in SUITE_CONTRACTS.iter().enumerate() {
+            if !matches!(env::promise_result(idx as u64), near_sdk::PromiseResult::Successful(_)) {
+                refund += self.calc_contract_deposit(kind);
+                is_deployed = false;
+            };
+        }
+
+        if is_deployed {
+            log!("Suite is deployed: {}", serde_json::to_string(&suite).unwrap());
+        } else {
+            log!("Suite deployment failed: {}", serde_json::to_string(&suite).unwrap());
+        };
+
+        if refund > 0 {
+            Promise::new(deployer_id).transfer(refund);
+        };
+        is_deployed
+    }
+
+
+    fn create_contract_account(&self, kind: ContractKind, account_id: &AccountId) -> Promise {
+        let code = self.contracts_code.get(&kind).unwrap_or_else(|| ContractError::ContractCodeNotStored.panic());
+        let balance = self.calc_contract_deposit(&kind);
+        Promise::new(account_id.clone())
+            .create_account()
+            .transfer(balance)
+            .deploy_contract(code)
+    }
+
+    fn suite_accounts(&self, prefix: &String) -> SuiteAccounts {
+        let factory_id = env::current_account_id();
+        let sub_account = |name: &str| -> AccountId {
+            format!("{}-{}.{}", prefix, name, factory_id).parse().unwrap_or_else(|_| ContractError::InvalidSuiteAccountId.panic())
+        };
+        SuiteAccounts {
+            activity_ft: sub_account("ft"),
+            social_network: sub_account("social"),
+            nft: sub_account("nft"),
+            marketplace: sub_account("market"),
+            config: sub_account("config")
+        }
+    }
+
+    fn calc_contract_deposit(&self, kind: &ContractKind) -> Balance {
+        let code = self.contracts_code.get(kind).unwrap_or_else(|| ContractError::ContractCodeNotStored.panic());
+        let storage_size = code.len() as u64 + CONTRACT_STATE_STORAGE;
+        Balance::from(storage_size) * env::storage_byte_cost()
+    }
+
+    fn calc_suite_deposit(&self) -> Balance {
+        SUITE_CONTRACTS.iter().map(|kind| self.calc_contract_deposit(kind)).sum()
+    }
+
+    fn to_args(&self, args: Value) -> Vec<u8> {
+        serde_json::to_vec(&args).expect("Failed to serialize call arguments")
+    }
+
+    fn assert_prefix(&self, prefix: &str) {
+        if prefix.is_empty() || !prefix.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_') {
+            ContractError::InvalidPrefix.panic();
+        };
+    }
+
+}
+
+
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
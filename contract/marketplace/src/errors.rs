@@ -0,0 +1,53 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 2001 => "Already initialized",
+    NotOwner = 2002 => "This operation is restricted to the contract owner",
+    Paused = 2003 => "Marketplace is paused",
+    DepositLessThanRegistrationFee = 2004 => "Attached deposit must be greater than 0.00125 NEAR",
+    UnexpectedPromiseResult = 2005 => "Unexpected value result from promise",
+    UnexpectedMintedAmount = 2006 => "Unexpected amount of minted tokens",
+    ConfigContractNotSet = 2007 => "Config contract is not set",
+    ArtfansNftNotSet = 2008 => "Artfans NFT contract is not set",
+    ListingNotFound = 2009 => "Listing is not found",
+    ListingExpired = 2010 => "Listing is expired",
+    WrongListingPrice = 2011 => "Attached deposit must be equal to the listing price",
+    InvalidListingMsg = 2012 => "'msg' is not a valid listing",
+    NotListingOwner = 2013 => "This operation is restricted to the listing owner",
+    OwnListingPurchase = 2014 => "Listing owner cannot buy own listing",
+    OfferNotFound = 2015 => "Collection offer is not found",
+    OfferExpired = 2016 => "Collection offer is expired",
+    WrongOfferPrice = 2017 => "Attached deposit must be equal to the non-zero offer price",
+    NotOfferOwner = 2018 => "This operation is restricted to the offer owner",
+    OwnOfferAcceptance = 2019 => "Offer owner cannot accept own offer",
+    NotPaymentProcessor = 2020 => "This operation is restricted to the payment processor",
+    FiatPurchaseAlreadySettled = 2021 => "Fiat purchase with this external reference is already settled",
+    EmptyExternalRef = 2022 => "External reference is empty",
+    ExternalRefTooLong = 2023 => "External reference is too long",
+    NotActivityFt = 2024 => "This operation is restricted to the activity FT contract",
+    InvalidFtTransferMsg = 2025 => "'msg' must be 'sell_activity_ft'",
+    BuybackDisabled = 2026 => "Activity FT buyback is disabled",
+    BuybackAmountTooSmall = 2027 => "Amount of FT is too small to be bought back",
+    InsufficientBuybackPool = 2028 => "Buyback pool balance is insufficient",
+    BuybackDailyCapExceeded = 2029 => "Buyback daily cap is exceeded",
+    InvalidBuybackRate = 2030 => "Buyback rate must be greater than the purchase exchange rate",
+    InvalidFtBonusTiers = 2031 => "FT bonus tiers must be sorted by ascending amount, up to 10 tiers with bonus of up to 100%",
+    InvalidCart = 2032 => "Cart must contain from 1 to 5 items without repeated listings",
+    WrongCartTotal = 2033 => "Attached deposit must be equal to the cart total",
+    NotEnoughGas = 2034 => "More gas is required",
+    NoRefund = 2035 => "There is no refund to withdraw",
+    NotPendingOwner = 2036 => "This operation is restricted to the proposed contract owner",
+    InvalidLoyaltyPercent = 2037 => "Loyalty percent must be up to 100%",
+    InvalidRefundFeePercent = 2038 => "Refund fee percent must be up to 100%",
+    PrimarySaleNotFound = 2039 => "Escrowed primary sale of the token is not found",
+    NotPrimarySaleBuyer = 2040 => "Only the buyer of the primary sale can request its refund",
+    RefundWindowOpen = 2041 => "Refund window of the primary sale is not over yet",
+    RefundWindowClosed = 2042 => "Refund window of the primary sale is over",
+    PrimarySaleEscrowDisabled = 2043 => "Primary sale escrow is disabled",
+    InvalidGasSettings = 2044 => "Gas of every step must be non-zero and every purchase must fit into the transaction gas",
+    NotArtfansNft = 2045 => "This operation is restricted to the Artfans NFT contract",
+    NotSocialNetwork = 2046 => "This operation is restricted to the social network contract",
+    OldStateNotFound = 2047 => "Contract state to migrate is not found",
+    NoPendingLoyaltyPoints = 2048 => "There are no pending loyalty points to claim",
+}
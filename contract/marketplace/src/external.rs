@@ -1,22 +1,58 @@
 use near_sdk::{ext_contract, AccountId, Promise};
-use near_sdk::json_types::{U128};
+use std::collections::HashMap;
+use near_sdk::json_types::{U128, U64};
 use near_contract_standards::non_fungible_token::{Token};
-// use near_contract_standards::non_fungible_token::metadata::{TokenMetadata};
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::PromiseOrValue;
+use artfans_common::ConfigDTO;
+use crate::CartStep;
 
-pub const TGAS: u64 = 1_000_000_000_000;
+pub use artfans_common::TGAS;
+pub const GAS_FOR_NFT_BUY_MINT_APPROVE: u64 = 50*TGAS;
+pub const GAS_FOR_NFT_TRANSFER_PAYOUT: u64 = 15*TGAS;
+pub const GAS_FOR_NFT_TRANSFER: u64 = 15*TGAS;
+pub const GAS_FOR_RESOLVE_PURCHASE: u64 = 15*TGAS;
+pub const GAS_FOR_FT_MINT: u64 = 5*TGAS;
+pub const GAS_FOR_PURCHASE_RESOLVE: u64 = 5*TGAS;
+pub const GAS_FOR_CART_STEP_RESOLVE: u64 = 20*TGAS;
+pub const GAS_FOR_CHECKOUT: u64 = 10*TGAS;
+pub const GAS_FOR_STORAGE_BALANCE_OF: u64 = 5*TGAS;
+pub const GAS_FOR_ON_LOYALTY_REGISTRATION_CHECKED: u64 = 10*TGAS;
+pub const GAS_FOR_ON_LOYALTY_POINTS_MINTED: u64 = 3*TGAS;
+// Gas burnt by the purchase method itself, the reserve is kept for its execution after the step gas is calculated
+pub const GAS_FOR_PURCHASE_CALL: u64 = 15*TGAS;
+pub const GAS_FOR_PURCHASE_CALL_RESERVE: u64 = 5*TGAS;
+pub const MAX_TRANSACTION_GAS: u64 = 300*TGAS;
 
 #[ext_contract(ext_ft)]
-trait FungibleToken {
+pub trait FungibleToken {
     fn ft_mint(&mut self, account_id: AccountId, amount: U128, registration_fee: Option<U128>) -> U128;
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
 }
 
-// #[ext_contract(ext_nft)]
-// trait NonFungibleToken {
-//     fn nft_mint(&mut self, receiver_id: AccountId, metadata: Option<TokenMetadata>) -> Token;
-// }
+#[ext_contract(ext_nft)]
+pub trait NonFungibleToken {
+    fn nft_buy_mint_approve(&mut self, buyer_id: Option<AccountId>, approve_receiver_id: Option<AccountId>, approve_msg: Option<String>) -> Token;
+    fn nft_transfer(&mut self, receiver_id: AccountId, token_id: String, approval_id: Option<u64>, memo: Option<String>);
+    fn nft_transfer_payout(&mut self, receiver_id: AccountId, token_id: String, approval_id: u64, balance: U128, max_len_payout: u32) -> HashMap<AccountId, U128>;
+}
+
+#[ext_contract(ext_config)]
+pub trait Config {
+    fn get_config(&self) -> ConfigDTO;
+}
 
 #[ext_contract(ext_self)]
-trait ExtSelf {
-    fn on_activity_ft_purchased(&mut self, buyer_id: AccountId, ft_amount: u128) -> Promise;
-    fn on_artfans_nft_purchased(&mut self, buyer_id: AccountId) -> Option<Token>;
+pub trait ExtSelf {
+    fn on_activity_ft_purchased(&mut self, buyer_id: AccountId, payer_id: AccountId, near_amount: u128, ft_amount: u128, exchange_rate: u128) -> Promise;
+    fn on_config_refreshed(&mut self) -> bool;
+    fn on_artfans_nft_purchased(&mut self, buyer_id: AccountId, payer_id: AccountId, near_amount: U128) -> Option<Token>;
+    fn on_listing_purchased(&mut self, buyer_id: AccountId, owner_id: AccountId, price: U128, listing_id: U64, token_id: String, approval_id: U64) -> bool;
+    fn on_fiat_purchase_settled(&mut self, external_ref: String) -> bool;
+    fn on_cart_step_executed(&mut self, buyer_id: AccountId, steps: Vec<CartStep>, index: u32, results: Vec<bool>) -> PromiseOrValue<Vec<bool>>;
+    fn on_refund_withdrawn(&mut self, account_id: AccountId, amount: U128) -> bool;
+    fn on_primary_sale_refunded(&mut self, token_id: String, buyer_id: AccountId, amount: U128, refundable_until: U64) -> bool;
+    fn on_loyalty_registration_checked(&mut self, account_id: AccountId, payer_id: AccountId, points: U128, deposit: U128) -> bool;
+    fn on_loyalty_points_minted(&mut self, account_id: AccountId, payer_id: AccountId, points: U128, registration_cost: U128) -> bool;
+    fn on_collection_offer_accepted(&mut self, offer_id: U64, buyer_id: AccountId, owner_id: AccountId, price: U128, expires_at: Option<U64>) -> bool;
 }
\ No newline at end of file
@@ -1,26 +1,313 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, is_promise_success, promise_result_as_success, near_bindgen, log, AccountId, Gas, Promise, PanicOnDefault};
-use near_sdk::json_types::{U128};
-// use near_contract_standards::non_fungible_token::{Token};
+use near_sdk::{env, is_promise_success, promise_result_as_success, near_bindgen, AccountId, Balance, BorshStorageKey, Gas, Promise, PromiseOrValue, PanicOnDefault};
+use near_sdk::collections::{LookupMap, UnorderedMap};
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use artfans_common::{CachedConfig, CachedConfigDTO, ConfigDTO, Ownable, Ownership, Pausable, ACTIVITY_FT_EXCHANGE_RATE, NANOSECONDS_PER_DAY};
+use near_contract_standards::non_fungible_token::{Token};
+use near_contract_standards::storage_management::StorageBalance;
+use std::collections::HashMap;
 
 pub mod external;
 pub use crate::external::*;
+pub mod errors;
+pub use crate::errors::*;
+pub mod migration;
 
-pub const ACTIVITY_FT_EXCHANGE_RATE: u128 = 100;
 pub const ACTIVITY_FT_REGISTRATION_FEE: u128 = 1_250_000_000_000_000_000_000;
+pub const MAX_LEN_PAYOUT: u32 = 10;
+pub const CLEANUP_INCENTIVE_PERCENT: u128 = 10;
+pub const MAX_EXTERNAL_REF_LEN: usize = 128;
+pub const EVENT_STANDARD: &str = "artfans_marketplace";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
+pub const SELL_ACTIVITY_FT_MSG: &str = "sell_activity_ft";
+pub const MAX_FT_BONUS_TIERS: usize = 10;
+pub const MAX_FT_BONUS_PERCENT: u8 = 100;
+pub const ARTFANS_NFT_PRICE: u128 = 3_500_000_000_000_000_000_000_000;
+pub const MAX_CART_ITEMS: usize = 5;
+pub const MAX_LOYALTY_PERCENT: u8 = 100;
 
-// pub const ARTFANS_NFT_PRICE: u128 = 3_500_000_000_000_000_000_000_000;
-// pub const ARTFANS_NFT_REGISTRATION_FEE: u128 = 100_000_000_000_000_000_000_000;
 
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
-    owner: AccountId,
+    ownership: Ownership,
     activity_ft: AccountId,
     activity_ft_beneficiary: AccountId,
-    // artfans_nft: AccountId,
-    // artfans_nft_beneficiary: AccountId
+    config_contract: Option<AccountId>,
+    cached_config: CachedConfig,
+    artfans_nft: Option<AccountId>,
+    listings: UnorderedMap<u64, Listing>,
+    listings_by_token: LookupMap<String, u64>,
+    next_listing_id: u64,
+    collection_offers: UnorderedMap<u64, CollectionOffer>,
+    next_collection_offer_id: u64,
+    payment_processor: Option<AccountId>,
+    fiat_settlements: LookupMap<String, FiatSettlement>,
+    buyback: Buyback,
+    ft_bonus_tiers: Vec<FtBonusTier>,
+    refunds: LookupMap<AccountId, Balance>,
+    loyalty_percent: u8,
+    loyalty_stats: LookupMap<AccountId, LoyaltyStats>,
+    primary_sale_escrow: PrimarySaleEscrow,
+    primary_sales: LookupMap<String, PrimarySale>,
+    gas_settings: GasSettings,
+    social_network: Option<AccountId>,
+    accounts_identities: LookupMap<AccountId, AccountId>
+}
+
+#[derive(BorshStorageKey, BorshSerialize)]
+pub enum StorageKeys {
+    Listings,
+    ListingsByToken,
+    CollectionOffers,
+    FiatSettlements,
+    Refunds,
+    LoyaltyStats,
+    PrimarySales,
+    AccountsIdentities
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Listing {
+    token_id: String,
+    owner_id: AccountId,
+    approval_id: u64,
+    price: Balance,
+    listed_at: u64,
+    expires_at: Option<u64>,
+    auto_relist: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ListingArgs {
+    price: U128,
+    expires_at: Option<U64>,
+    auto_relist: Option<bool>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(untagged)]
+pub enum ApproveMsg {
+    AcceptCollectionOffer { offer_id: U64 },
+    RefundPrimarySale { refund_primary_sale: bool },
+    Listing(ListingArgs)
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CollectionOffer {
+    buyer_id: AccountId,
+    price: Balance,
+    expires_at: Option<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollectionOfferDTO {
+    offer_id: U64,
+    buyer_id: AccountId,
+    price: U128,
+    expires_at: Option<U64>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ListingDTO {
+    listing_id: U64,
+    token_id: String,
+    owner_id: AccountId,
+    price: U128,
+    expires_at: Option<U64>,
+    auto_relist: bool
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum FiatPurchaseItem {
+    ArtfansNft,
+    ActivityFt
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum FiatSettlementStatus {
+    Pending,
+    Completed,
+    Failed
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FiatSettlement {
+    buyer_id: AccountId,
+    item: FiatPurchaseItem,
+    near_amount: Balance,
+    settled_at: u64,
+    status: FiatSettlementStatus
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FiatSettlementDTO {
+    external_ref: String,
+    buyer_id: AccountId,
+    item: FiatPurchaseItem,
+    near_amount: U128,
+    settled_at: U64,
+    status: FiatSettlementStatus
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftApprovalDTO {
+    nft_contract_id: AccountId,
+    token_id: String,
+    owner_id: AccountId,
+    approval_id: U64,
+    msg: String
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CartItem {
+    ArtfansNft,
+    Listing { listing_id: U64 },
+    ActivityFt { near_amount: U128 }
+}
+
+// Cart item resolved at checkout, so the promise chain does not depend on the state changed in between
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CartStep {
+    ArtfansNft,
+    Listing { token_id: String, owner_id: AccountId, approval_id: u64, price: U128, listing_id: U64 },
+    ActivityFt { near_amount: U128, ft_amount: U128, exchange_rate: U128 }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct FtBonusTier {
+    min_near_amount: Balance,
+    bonus_percent: u8
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtBonusTierDTO {
+    min_near_amount: U128,
+    bonus_percent: u8
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Buyback {
+    pool: Balance,
+    rate: u128,
+    daily_cap: Balance,
+    day: u64,
+    paid_today: Balance,
+    bought_back_ft: u128
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BuybackDTO {
+    pool: U128,
+    rate: U128,
+    daily_cap: U128,
+    paid_today: U128,
+    bought_back_ft: U128
+}
+
+// Escrow is enabled when 'beneficiary' is set. The Artfans NFT contract must pay the sale proceeds
+// to the marketplace (its beneficiary), which holds them for 'period' nanoseconds before releasing to 'beneficiary'
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PrimarySaleEscrow {
+    beneficiary: Option<AccountId>,
+    period: u64,
+    refund_fee_percent: u8
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PrimarySaleEscrowDTO {
+    beneficiary: Option<AccountId>,
+    period: U64,
+    refund_fee_percent: u8
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PrimarySale {
+    buyer_id: AccountId,
+    amount: Balance,
+    refundable_until: u64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PrimarySaleDTO {
+    token_id: String,
+    buyer_id: AccountId,
+    amount: U128,
+    refundable_until: U64
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct LoyaltyStats {
+    purchases: u64,
+    near_spent: Balance,
+    points_credited: u128,
+    ft_registered: bool,
+    points_pending: u128
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LoyaltyStatsDTO {
+    purchases: U64,
+    near_spent: U128,
+    points_credited: U128,
+    points_pending: U128
+}
+
+// Static gas of every step of the purchase promise chains. 'ft_mint' is used for the loyalty points mint too
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct GasSettings {
+    ft_mint: u64,
+    nft_buy_mint_approve: u64,
+    nft_transfer_payout: u64,
+    nft_transfer: u64,
+    purchase_resolve: u64,
+    transfer_resolve: u64,
+    cart_step_resolve: u64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GasSettingsDTO {
+    ft_mint: U64,
+    nft_buy_mint_approve: U64,
+    nft_transfer_payout: U64,
+    nft_transfer: U64,
+    purchase_resolve: U64,
+    transfer_resolve: U64,
+    cart_step_resolve: U64
+}
+
+// '*_purchase_gas' is the gas to attach to the purchase methods
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MarketplaceConfigDTO {
+    activity_ft: AccountId,
+    artfans_nft: Option<AccountId>,
+    config_contract: Option<AccountId>,
+    social_network: Option<AccountId>,
+    cached_config: CachedConfigDTO,
+    gas_settings: GasSettingsDTO,
+    activity_ft_purchase_gas: U64,
+    artfans_nft_purchase_gas: U64,
+    listing_purchase_gas: U64
 }
 
 #[near_bindgen]
@@ -31,133 +318,1268 @@ impl Contract {
         owner: AccountId, 
         activity_ft: AccountId, 
         activity_ft_beneficiary: AccountId, 
-        // artfans_nft: AccountId, 
-        // artfans_nft_beneficiary: AccountId
     ) -> Self {
 
         if env::state_exists() == true {
-            env::panic_str("Already initialized");
+            ContractError::AlreadyInitialized.panic();
         }
 
         Self {
-            owner,
+            ownership: Ownership::new(owner),
             activity_ft,
             activity_ft_beneficiary,
-            // artfans_nft,
-            // artfans_nft_beneficiary
+            config_contract: None,
+            cached_config: CachedConfig::new(ACTIVITY_FT_EXCHANGE_RATE),
+            artfans_nft: None,
+            listings: UnorderedMap::new(StorageKeys::Listings),
+            listings_by_token: LookupMap::new(StorageKeys::ListingsByToken),
+            next_listing_id: 0,
+            collection_offers: UnorderedMap::new(StorageKeys::CollectionOffers),
+            next_collection_offer_id: 0,
+            payment_processor: None,
+            fiat_settlements: LookupMap::new(StorageKeys::FiatSettlements),
+            buyback: Buyback {
+                pool: 0,
+                rate: 0,
+                daily_cap: 0,
+                day: 0,
+                paid_today: 0,
+                bought_back_ft: 0
+            },
+            ft_bonus_tiers: Vec::new(),
+            refunds: LookupMap::new(StorageKeys::Refunds),
+            loyalty_percent: 0,
+            loyalty_stats: LookupMap::new(StorageKeys::LoyaltyStats),
+            primary_sale_escrow: PrimarySaleEscrow {
+                beneficiary: None,
+                period: 0,
+                refund_fee_percent: 0
+            },
+            primary_sales: LookupMap::new(StorageKeys::PrimarySales),
+            gas_settings: GasSettings {
+                ft_mint: GAS_FOR_FT_MINT,
+                nft_buy_mint_approve: GAS_FOR_NFT_BUY_MINT_APPROVE,
+                nft_transfer_payout: GAS_FOR_NFT_TRANSFER_PAYOUT,
+                nft_transfer: GAS_FOR_NFT_TRANSFER,
+                purchase_resolve: GAS_FOR_PURCHASE_RESOLVE,
+                transfer_resolve: GAS_FOR_RESOLVE_PURCHASE,
+                cart_step_resolve: GAS_FOR_CART_STEP_RESOLVE
+            },
+            social_network: None,
+            accounts_identities: LookupMap::new(StorageKeys::AccountsIdentities)
         }
     }
     
     #[payable]
     pub fn buy_activity_ft(&mut self) -> Promise {
+        self.assert_not_paused();
+
         let near_amount = env::attached_deposit();
         if near_amount < ACTIVITY_FT_REGISTRATION_FEE {
-            env::panic_str("Attached deposit must be greater than 0.00125 NEAR");
+            ContractError::DepositLessThanRegistrationFee.panic();
         };
 
         let buyer_id = env::predecessor_account_id();
-        let ft_amount = near_amount.saturating_mul(ACTIVITY_FT_EXCHANGE_RATE);
-        let ft_registration_fee = ACTIVITY_FT_REGISTRATION_FEE.saturating_mul(ACTIVITY_FT_EXCHANGE_RATE);
-        self.purchase_activity_ft(buyer_id, ft_amount, ft_registration_fee)
+        self.purchase_activity_ft(buyer_id.clone(), buyer_id, near_amount, 0)
     }
 
-    fn purchase_activity_ft(&mut self, buyer_id: AccountId, ft_amount: u128, ft_registration_fee: u128) -> Promise {
+    // 'reserved_gas' is the gas of the callbacks the caller attaches after the purchase
+    // A failed purchase is refunded to 'payer_id', the attached deposit of a fiat purchase comes from the payment processor
+    fn purchase_activity_ft(&mut self, buyer_id: AccountId, payer_id: AccountId, near_amount: u128, reserved_gas: u64) -> Promise {
+        let exchange_rate = self.cached_config.activity_ft_exchange_rate();
+        let ft_amount = self.calc_activity_ft_amount(near_amount, exchange_rate);
+        let ft_registration_fee = ACTIVITY_FT_REGISTRATION_FEE.saturating_mul(exchange_rate);
+        let callback_gas = self.gas_settings.purchase_resolve;
         ext_ft::ext(self.activity_ft.clone())
-            .with_static_gas(Gas(5*TGAS))
+            .with_static_gas(self.calc_step_gas(self.gas_settings.ft_mint, callback_gas + reserved_gas))
             .with_attached_deposit(ACTIVITY_FT_REGISTRATION_FEE)
             .ft_mint(buyer_id.clone(), U128::from(ft_amount), Some(U128::from(ft_registration_fee)))
                 .then(
                     ext_self::ext(env::current_account_id())
-                    .with_static_gas(Gas(5*TGAS))
-                    .on_activity_ft_purchased(buyer_id, ft_amount)
+                    .with_static_gas(Gas(callback_gas))
+                    .on_activity_ft_purchased(buyer_id, payer_id, near_amount, ft_amount, exchange_rate)
+                )
+    }
+
+    // The step receives all the gas left after the callbacks and the rest of the current call, but not less than its configured gas.
+    // The FT storage registration path of 'ft_mint' and the NFT mint take more gas than the default settings on congested storage
+    fn calc_step_gas(&self, step_gas: u64, callbacks_gas: u64) -> Gas {
+        let remaining_gas = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+        let available_gas = remaining_gas.saturating_sub(callbacks_gas + GAS_FOR_PURCHASE_CALL_RESERVE);
+        if available_gas < step_gas {
+            ContractError::NotEnoughGas.panic();
+        };
+        Gas(available_gas)
+    }
+
+    pub fn set_gas_settings(&mut self, gas_settings: GasSettingsDTO) {
+        self.assert_owner();
+        let gas_settings = GasSettings {
+            ft_mint: gas_settings.ft_mint.into(),
+            nft_buy_mint_approve: gas_settings.nft_buy_mint_approve.into(),
+            nft_transfer_payout: gas_settings.nft_transfer_payout.into(),
+            nft_transfer: gas_settings.nft_transfer.into(),
+            purchase_resolve: gas_settings.purchase_resolve.into(),
+            transfer_resolve: gas_settings.transfer_resolve.into(),
+            cart_step_resolve: gas_settings.cart_step_resolve.into()
+        };
+        let steps_gas = [
+            gas_settings.ft_mint,
+            gas_settings.nft_buy_mint_approve,
+            gas_settings.nft_transfer_payout,
+            gas_settings.nft_transfer,
+            gas_settings.purchase_resolve,
+            gas_settings.transfer_resolve,
+            gas_settings.cart_step_resolve
+        ];
+        // Fiat purchases are the longest chains: the purchase is followed by the settlement callback
+        let max_purchase_gas = std::cmp::max(calc_activity_ft_purchase_gas(&gas_settings), calc_artfans_nft_purchase_gas(&gas_settings))
+            + gas_settings.purchase_resolve;
+        if steps_gas.contains(&0) || max_purchase_gas > MAX_TRANSACTION_GAS || calc_listing_purchase_gas(&gas_settings) > MAX_TRANSACTION_GAS {
+            ContractError::InvalidGasSettings.panic();
+        };
+        self.gas_settings = gas_settings;
+    }
+
+    pub fn get_config(&self) -> MarketplaceConfigDTO {
+        MarketplaceConfigDTO {
+            activity_ft: self.activity_ft.clone(),
+            artfans_nft: self.artfans_nft.clone(),
+            config_contract: self.config_contract.clone(),
+            social_network: self.social_network.clone(),
+            cached_config: self.cached_config.to_dto(),
+            gas_settings: GasSettingsDTO {
+                ft_mint: U64(self.gas_settings.ft_mint),
+                nft_buy_mint_approve: U64(self.gas_settings.nft_buy_mint_approve),
+                nft_transfer_payout: U64(self.gas_settings.nft_transfer_payout),
+                nft_transfer: U64(self.gas_settings.nft_transfer),
+                purchase_resolve: U64(self.gas_settings.purchase_resolve),
+                transfer_resolve: U64(self.gas_settings.transfer_resolve),
+                cart_step_resolve: U64(self.gas_settings.cart_step_resolve)
+            },
+            activity_ft_purchase_gas: U64(calc_activity_ft_purchase_gas(&self.gas_settings)),
+            artfans_nft_purchase_gas: U64(calc_artfans_nft_purchase_gas(&self.gas_settings)),
+            listing_purchase_gas: U64(calc_listing_purchase_gas(&self.gas_settings))
+        }
+    }
+
+    // The bonus of the highest tier reached by 'near_amount' is added on top of the exchanged amount
+    fn calc_activity_ft_amount(&self, near_amount: u128, exchange_rate: u128) -> u128 {
+        let ft_amount = near_amount.saturating_mul(exchange_rate);
+        let bonus_percent = self.ft_bonus_tiers
+            .iter()
+            .rev()
+            .find(|tier| near_amount >= tier.min_near_amount)
+            .map_or(0, |tier| tier.bonus_percent);
+        ft_amount.saturating_add(ft_amount / 100 * u128::from(bonus_percent))
+    }
+
+    pub fn set_ft_bonus_tiers(&mut self, tiers: Vec<FtBonusTierDTO>) {
+        self.assert_owner();
+        if tiers.len() > MAX_FT_BONUS_TIERS {
+            ContractError::InvalidFtBonusTiers.panic();
+        };
+        let tiers: Vec<FtBonusTier> = tiers
+            .into_iter()
+            .map(|tier| FtBonusTier { min_near_amount: tier.min_near_amount.into(), bonus_percent: tier.bonus_percent })
+            .collect();
+        let is_ascending = tiers.windows(2).all(|pair| pair[0].min_near_amount < pair[1].min_near_amount);
+        if !is_ascending || tiers.iter().any(|tier| tier.bonus_percent > MAX_FT_BONUS_PERCENT) {
+            ContractError::InvalidFtBonusTiers.panic();
+        };
+        self.ft_bonus_tiers = tiers;
+    }
+
+    pub fn get_ft_bonus_tiers(&self) -> Vec<FtBonusTierDTO> {
+        self.ft_bonus_tiers
+            .iter()
+            .map(|tier| FtBonusTierDTO { min_near_amount: U128::from(tier.min_near_amount), bonus_percent: tier.bonus_percent })
+            .collect()
+    }
+
+    #[private]
+    pub fn on_activity_ft_purchased(&mut self, buyer_id: AccountId, payer_id: AccountId, near_amount: u128, ft_amount: u128, exchange_rate: u128) -> U128 {
+        if is_promise_success() {
+            let result = promise_result_as_success().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            let minted_ft_amount = self.pay_activity_ft_beneficiary(&result, near_amount, ft_amount, exchange_rate);
+            self.set_activity_ft_registered(&buyer_id);
+            U128(minted_ft_amount)
+        } else {
+            Promise::new(payer_id).transfer(near_amount);
+            U128(0)
+        }
+    }
+
+    fn pay_activity_ft_beneficiary(&self, result: &[u8], near_amount: u128, ft_amount: u128, exchange_rate: u128) -> u128 {
+        let beneficiary = self.cached_config.treasury().unwrap_or(&self.activity_ft_beneficiary).clone();
+        let minted_ft_amount = u128::from(near_sdk::serde_json::from_slice::<U128>(result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic()));
+
+        if minted_ft_amount == ft_amount {
+            Promise::new(beneficiary).transfer(near_amount);
+        } else {
+            let ft_registration_fee = ACTIVITY_FT_REGISTRATION_FEE.saturating_mul(exchange_rate);
+            if ft_amount.saturating_sub(minted_ft_amount) != ft_registration_fee {
+                ContractError::UnexpectedMintedAmount.panic();
+            };
+            let near_registration_fee = ft_registration_fee.saturating_div(exchange_rate);
+            let amount = near_amount - near_registration_fee;
+            Promise::new(beneficiary).transfer(amount);
+        };
+        minted_ft_amount
+    }
+
+    // Items are executed one by one, each callback starts the next item. The price of a failed item is added to the buyer's refunds
+    #[payable]
+    pub fn checkout(&mut self, items: Vec<CartItem>) -> Promise {
+        self.assert_not_paused();
+        if items.is_empty() || items.len() > MAX_CART_ITEMS {
+            ContractError::InvalidCart.panic();
+        };
+
+        let buyer_id = env::predecessor_account_id();
+        let mut listing_ids: Vec<u64> = Vec::new();
+        let mut total: Balance = 0;
+        let mut steps: Vec<CartStep> = Vec::new();
+        for item in items {
+            let step = match item {
+                CartItem::ArtfansNft => {
+                    self.artfans_nft.as_ref().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+                    total += ARTFANS_NFT_PRICE;
+                    CartStep::ArtfansNft
+                },
+                CartItem::Listing { listing_id } => {
+                    let listing_id = u64::from(listing_id);
+                    if listing_ids.contains(&listing_id) {
+                        ContractError::InvalidCart.panic();
+                    };
+                    listing_ids.push(listing_id);
+                    let listing = self.listings.get(&listing_id).unwrap_or_else(|| ContractError::ListingNotFound.panic());
+                    if self.is_listing_expired(&listing) {
+                        ContractError::ListingExpired.panic();
+                    };
+                    if buyer_id == listing.owner_id {
+                        ContractError::OwnListingPurchase.panic();
+                    };
+                    total += listing.price;
+                    CartStep::Listing { token_id: listing.token_id, owner_id: listing.owner_id, approval_id: listing.approval_id, price: U128(listing.price), listing_id: U64(listing_id) }
+                },
+                CartItem::ActivityFt { near_amount } => {
+                    let near_amount = u128::from(near_amount);
+                    if near_amount < ACTIVITY_FT_REGISTRATION_FEE {
+                        ContractError::DepositLessThanRegistrationFee.panic();
+                    };
+                    let exchange_rate = self.cached_config.activity_ft_exchange_rate();
+                    total += near_amount;
+                    CartStep::ActivityFt {
+                        near_amount: U128(near_amount),
+                        ft_amount: U128(self.calc_activity_ft_amount(near_amount, exchange_rate)),
+                        exchange_rate: U128(exchange_rate)
+                    }
+                }
+            };
+            steps.push(step);
+        }
+
+        if env::attached_deposit() != total {
+            ContractError::WrongCartTotal.panic();
+        };
+        if env::prepaid_gas() <= Gas(calc_cart_steps_gas(&steps, &self.gas_settings) + GAS_FOR_CHECKOUT) {
+            ContractError::NotEnoughGas.panic();
+        };
+
+        self.execute_cart_step(buyer_id, steps, 0, Vec::new())
+    }
+
+    fn execute_cart_step(&self, buyer_id: AccountId, steps: Vec<CartStep>, index: usize, results: Vec<bool>) -> Promise {
+        let step_promise = match &steps[index] {
+            CartStep::ArtfansNft => {
+                let artfans_nft = self.artfans_nft.clone().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+                ext_nft::ext(artfans_nft)
+                    .with_static_gas(Gas(self.gas_settings.nft_buy_mint_approve))
+                    .with_attached_deposit(ARTFANS_NFT_PRICE)
+                    .nft_buy_mint_approve(Some(buyer_id.clone()), None, None)
+            },
+            CartStep::Listing { token_id, approval_id, price, .. } => {
+                let artfans_nft = self.artfans_nft.clone().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+                ext_nft::ext(artfans_nft)
+                    .with_static_gas(Gas(self.gas_settings.nft_transfer_payout))
+                    .with_attached_deposit(1)
+                    .nft_transfer_payout(buyer_id.clone(), token_id.clone(), *approval_id, *price, MAX_LEN_PAYOUT)
+            },
+            CartStep::ActivityFt { ft_amount, exchange_rate, .. } => {
+                let ft_registration_fee = ACTIVITY_FT_REGISTRATION_FEE.saturating_mul((*exchange_rate).into());
+                ext_ft::ext(self.activity_ft.clone())
+                    .with_static_gas(Gas(self.gas_settings.ft_mint))
+                    .with_attached_deposit(ACTIVITY_FT_REGISTRATION_FEE)
+                    .ft_mint(buyer_id.clone(), *ft_amount, Some(U128::from(ft_registration_fee)))
+            }
+        };
+
+        let callback_gas = self.gas_settings.cart_step_resolve + calc_loyalty_mint_gas(&self.gas_settings) + calc_cart_steps_gas(&steps[index + 1..], &self.gas_settings);
+        step_promise.then(
+            ext_self::ext(env::current_account_id())
+            .with_static_gas(Gas(callback_gas))
+            .on_cart_step_executed(buyer_id, steps, index as u32, results)
+        )
+    }
+
+    #[private]
+    pub fn on_cart_step_executed(&mut self, buyer_id: AccountId, steps: Vec<CartStep>, index: u32, results: Vec<bool>) -> PromiseOrValue<Vec<bool>> {
+        let index = index as usize;
+        let mut results = results;
+        let result = promise_result_as_success();
+        let success = result.is_some();
+        match (&steps[index], result) {
+            (CartStep::ArtfansNft, None) => self.add_refund(&buyer_id, ARTFANS_NFT_PRICE),
+            (CartStep::Listing { token_id, owner_id, approval_id, price, listing_id }, Some(result)) => {
+                self.remove_sold_listing((*listing_id).into(), token_id, *approval_id);
+                self.settle_sale(&result, buyer_id.clone(), owner_id.clone(), (*price).into());
+            },
+            (CartStep::Listing { price, .. }, None) => self.add_refund(&buyer_id, (*price).into()),
+            (CartStep::ActivityFt { near_amount, ft_amount, exchange_rate }, Some(result)) => {
+                self.pay_activity_ft_beneficiary(&result, (*near_amount).into(), (*ft_amount).into(), (*exchange_rate).into());
+                self.set_activity_ft_registered(&buyer_id);
+            },
+            (CartStep::ActivityFt { near_amount, .. }, None) => self.add_refund(&buyer_id, (*near_amount).into()),
+            (CartStep::ArtfansNft, Some(_)) => self.credit_loyalty_points(buyer_id.clone(), ARTFANS_NFT_PRICE)
+        };
+        results.push(success);
+
+        if index + 1 < steps.len() {
+            PromiseOrValue::Promise(self.execute_cart_step(buyer_id, steps, index + 1, results))
+        } else {
+            PromiseOrValue::Value(results)
+        }
+    }
+
+    pub fn set_loyalty_percent(&mut self, loyalty_percent: u8) {
+        self.assert_owner();
+        if loyalty_percent > MAX_LOYALTY_PERCENT {
+            ContractError::InvalidLoyaltyPercent.panic();
+        };
+        self.loyalty_percent = loyalty_percent;
+    }
+
+    pub fn get_loyalty_percent(&self) -> u8 {
+        self.loyalty_percent
+    }
+
+    pub fn get_loyalty_stats(&self, account_id: AccountId) -> LoyaltyStatsDTO {
+        let loyalty_stats = self.loyalty_stats.get(&self.get_identity(account_id)).unwrap_or_default();
+        LoyaltyStatsDTO {
+            purchases: U64(loyalty_stats.purchases),
+            near_spent: U128(loyalty_stats.near_spent),
+            points_credited: U128(loyalty_stats.points_credited),
+            points_pending: U128(loyalty_stats.points_pending)
+        }
+    }
+
+    // Points are credited at the current exchange rate and minted as a detached promise, so a failed mint never reverts the purchase.
+    // The points of a buyer registered on the activity FT contract are minted right away, the others stay pending until the buyer
+    // claims them with the registration attached. Purchases of a linked account are credited to its identity
+    fn credit_loyalty_points(&mut self, buyer_id: AccountId, near_amount: Balance) {
+        let buyer_id = self.get_identity(buyer_id);
+        let exchange_rate = self.cached_config.activity_ft_exchange_rate();
+        let points = near_amount.saturating_mul(exchange_rate) / 100 * u128::from(self.loyalty_percent);
+
+        let mut loyalty_stats = self.loyalty_stats.get(&buyer_id).unwrap_or_default();
+        loyalty_stats.purchases += 1;
+        loyalty_stats.near_spent = loyalty_stats.near_spent.saturating_add(near_amount);
+        loyalty_stats.points_pending = loyalty_stats.points_pending.saturating_add(points);
+        let points = if loyalty_stats.ft_registered { std::mem::take(&mut loyalty_stats.points_pending) } else { 0 };
+        self.loyalty_stats.insert(&buyer_id, &loyalty_stats);
+
+        if points != 0 {
+            self.mint_loyalty_points(buyer_id.clone(), buyer_id, points, 0);
+        };
+    }
+
+    // Mints the pending points of the caller's identity. The activity FT registration of an unregistered identity is paid
+    // with the attached deposit, the rest of it is refunded
+    #[payable]
+    pub fn claim_loyalty_points(&mut self) -> Promise {
+        self.assert_not_paused();
+        let payer_id = env::predecessor_account_id();
+        let account_id = self.get_identity(payer_id.clone());
+        let mut loyalty_stats = self.loyalty_stats.get(&account_id).unwrap_or_default();
+        if loyalty_stats.points_pending == 0 {
+            ContractError::NoPendingLoyaltyPoints.panic();
+        };
+        let points = std::mem::take(&mut loyalty_stats.points_pending);
+        self.loyalty_stats.insert(&account_id, &loyalty_stats);
+
+        ext_ft::ext(self.activity_ft.clone())
+            .with_static_gas(Gas(GAS_FOR_STORAGE_BALANCE_OF))
+            .storage_balance_of(account_id.clone())
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(GAS_FOR_ON_LOYALTY_REGISTRATION_CHECKED + calc_loyalty_mint_gas(&self.gas_settings)))
+                    .on_loyalty_registration_checked(account_id, payer_id, U128(points), U128(env::attached_deposit()))
                 )
     }
 
+    // The points are restored as pending and the deposit is refunded when the identity is neither registered nor the registration is attached
     #[private]
-    pub fn on_activity_ft_purchased(&mut self, buyer_id: AccountId, ft_amount: u128) -> U128 {
-        let near_amount = ft_amount.saturating_div(ACTIVITY_FT_EXCHANGE_RATE);
+    pub fn on_loyalty_registration_checked(&mut self, account_id: AccountId, payer_id: AccountId, points: U128, deposit: U128) -> bool {
+        let deposit = Balance::from(deposit);
+        let is_registered = promise_result_as_success()
+            .and_then(|result| near_sdk::serde_json::from_slice::<Option<StorageBalance>>(&result).ok())
+            .map(|storage_balance| storage_balance.is_some());
+        let registration_cost = match is_registered {
+            Some(true) => 0,
+            Some(false) if deposit >= ACTIVITY_FT_REGISTRATION_FEE => ACTIVITY_FT_REGISTRATION_FEE,
+            _ => {
+                self.restore_pending_points(&account_id, points.into());
+                if deposit != 0 {
+                    Promise::new(payer_id).transfer(deposit);
+                };
+                return false
+            }
+        };
+        if deposit > registration_cost {
+            Promise::new(payer_id.clone()).transfer(deposit - registration_cost);
+        };
+        self.mint_loyalty_points(account_id, payer_id, points.into(), registration_cost);
+        true
+    }
+
+    // The points are out of the pending points while the mint is in flight, the callback credits or restores them
+    fn mint_loyalty_points(&self, account_id: AccountId, payer_id: AccountId, points: u128, registration_cost: Balance) {
+        ext_ft::ext(self.activity_ft.clone())
+            .with_static_gas(Gas(self.gas_settings.ft_mint))
+            .with_attached_deposit(registration_cost)
+            .ft_mint(account_id.clone(), U128(points), None)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(GAS_FOR_ON_LOYALTY_POINTS_MINTED))
+                    .on_loyalty_points_minted(account_id, payer_id, U128(points), U128(registration_cost))
+                );
+    }
 
+    // A failed mint returns the attached registration, which is refunded to the payer. The account is not considered
+    // registered anymore, since it may have left the activity FT contract; it registers again with 'claim_loyalty_points'
+    #[private]
+    pub fn on_loyalty_points_minted(&mut self, account_id: AccountId, payer_id: AccountId, points: U128, registration_cost: U128) -> bool {
         if is_promise_success() {
-            let result = promise_result_as_success().expect("Unexpected promise result");
-            let minted_ft_amount = u128::from(near_sdk::serde_json::from_slice::<U128>(&result).ok().expect("Unexpected value result from promise"));
+            let mut loyalty_stats = self.loyalty_stats.get(&account_id).unwrap_or_default();
+            loyalty_stats.points_credited = loyalty_stats.points_credited.saturating_add(points.into());
+            loyalty_stats.ft_registered = true;
+            self.loyalty_stats.insert(&account_id, &loyalty_stats);
+            true
+        } else {
+            self.restore_pending_points(&account_id, points.into());
+            if registration_cost.0 != 0 {
+                Promise::new(payer_id).transfer(registration_cost.into());
+            };
+            false
+        }
+    }
 
-            if minted_ft_amount == ft_amount {
-                Promise::new(self.activity_ft_beneficiary.clone()).transfer(near_amount);
+    fn restore_pending_points(&mut self, account_id: &AccountId, points: u128) {
+        let mut loyalty_stats = self.loyalty_stats.get(account_id).unwrap_or_default();
+        loyalty_stats.points_pending = loyalty_stats.points_pending.saturating_add(points);
+        loyalty_stats.ft_registered = false;
+        self.loyalty_stats.insert(account_id, &loyalty_stats);
+    }
+
+    // Activity FT purchases register the buyer on the activity FT contract and earn no loyalty points.
+    // The points of a linked account are minted to its identity, so its own registration is not tracked
+    fn set_activity_ft_registered(&mut self, buyer_id: &AccountId) {
+        if self.accounts_identities.contains_key(buyer_id) {
+            return;
+        };
+        let mut loyalty_stats = self.loyalty_stats.get(buyer_id).unwrap_or_default();
+        if !loyalty_stats.ft_registered {
+            loyalty_stats.ft_registered = true;
+            self.loyalty_stats.insert(buyer_id, &loyalty_stats);
+        };
+    }
+
+    pub fn set_social_network(&mut self, social_network: AccountId) {
+        self.assert_owner();
+        self.social_network = Some(social_network);
+    }
+
+    // Called by the social network on every account link and unlink. The loyalty stats of a linked account are merged into its identity
+    pub fn set_account_identity(&mut self, account_id: AccountId, identity_id: Option<AccountId>) {
+        if self.social_network.as_ref() != Some(&env::predecessor_account_id()) {
+            ContractError::NotSocialNetwork.panic();
+        };
+        match identity_id {
+            Some(identity_id) => {
+                if let Some(account_stats) = self.loyalty_stats.remove(&account_id) {
+                    let mut loyalty_stats = self.loyalty_stats.get(&identity_id).unwrap_or_default();
+                    loyalty_stats.purchases += account_stats.purchases;
+                    loyalty_stats.near_spent = loyalty_stats.near_spent.saturating_add(account_stats.near_spent);
+                    loyalty_stats.points_credited = loyalty_stats.points_credited.saturating_add(account_stats.points_credited);
+                    loyalty_stats.points_pending = loyalty_stats.points_pending.saturating_add(account_stats.points_pending);
+                    self.loyalty_stats.insert(&identity_id, &loyalty_stats);
+                };
+                self.accounts_identities.insert(&account_id, &identity_id);
+            },
+            None => {
+                self.accounts_identities.remove(&account_id);
+            }
+        };
+    }
+
+    pub fn get_identity(&self, account_id: AccountId) -> AccountId {
+        self.accounts_identities.get(&account_id).unwrap_or(account_id)
+    }
+
+    fn add_refund(&mut self, account_id: &AccountId, amount: Balance) {
+        let refund = self.refunds.get(account_id).unwrap_or(0);
+        self.refunds.insert(account_id, &(refund + amount));
+    }
+
+    pub fn get_refund(&self, account_id: AccountId) -> U128 {
+        U128(self.refunds.get(&account_id).unwrap_or(0))
+    }
+
+    pub fn withdraw_refund(&mut self) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = self.refunds.remove(&account_id).unwrap_or_else(|| ContractError::NoRefund.panic());
+        Promise::new(account_id.clone())
+            .transfer(amount)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .on_refund_withdrawn(account_id, U128(amount))
+                )
+    }
+
+    #[private]
+    pub fn on_refund_withdrawn(&mut self, account_id: AccountId, amount: U128) -> bool {
+        if is_promise_success() {
+            true
+        } else {
+            self.add_refund(&account_id, amount.into());
+            false
+        }
+    }
+
+    #[payable]
+    pub fn fund_buyback_pool(&mut self) -> U128 {
+        self.assert_owner();
+        self.buyback.pool += env::attached_deposit();
+        U128::from(self.buyback.pool)
+    }
+
+    pub fn withdraw_buyback_pool(&mut self, amount: U128) -> Promise {
+        self.assert_owner();
+        let amount = u128::from(amount);
+        if amount > self.buyback.pool {
+            ContractError::InsufficientBuybackPool.panic();
+        };
+        self.buyback.pool -= amount;
+        Promise::new(self.ownership.owner().clone()).transfer(amount)
+    }
+
+    // 'rate' is the amount of FT sold for 1 yoctoNEAR and must be greater than the purchase exchange rate, 0 disables the buyback.
+    // 'daily_cap' limits the NEAR paid out by the buyback per day, 0 means no limit
+    pub fn set_buyback_settings(&mut self, rate: U128, daily_cap: U128) {
+        self.assert_owner();
+        let rate = u128::from(rate);
+        if rate != 0 && rate <= self.cached_config.activity_ft_exchange_rate() {
+            ContractError::InvalidBuybackRate.panic();
+        };
+        self.buyback.rate = rate;
+        self.buyback.daily_cap = daily_cap.into();
+    }
+
+    pub fn get_buyback(&self) -> BuybackDTO {
+        let paid_today = if self.buyback.day == get_current_day() { self.buyback.paid_today } else { 0 };
+        BuybackDTO {
+            pool: U128::from(self.buyback.pool),
+            rate: U128::from(self.buyback.rate),
+            daily_cap: U128::from(self.buyback.daily_cap),
+            paid_today: U128::from(paid_today),
+            bought_back_ft: U128::from(self.buyback.bought_back_ft)
+        }
+    }
+
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        if env::predecessor_account_id() != self.activity_ft {
+            ContractError::NotActivityFt.panic();
+        };
+        if msg != SELL_ACTIVITY_FT_MSG {
+            ContractError::InvalidFtTransferMsg.panic();
+        };
+        self.sell_activity_ft(sender_id, amount.into());
+        PromiseOrValue::Value(U128(0))
+    }
+
+    // Sold FT stays on the marketplace account, the NEAR is paid from the buyback pool
+    fn sell_activity_ft(&mut self, seller_id: AccountId, ft_amount: u128) {
+        self.assert_not_paused();
+        if self.buyback.rate == 0 {
+            ContractError::BuybackDisabled.panic();
+        };
+
+        let near_amount = ft_amount / self.buyback.rate;
+        if near_amount == 0 {
+            ContractError::BuybackAmountTooSmall.panic();
+        };
+        if near_amount > self.buyback.pool {
+            ContractError::InsufficientBuybackPool.panic();
+        };
+
+        let current_day = get_current_day();
+        if self.buyback.day != current_day {
+            self.buyback.day = current_day;
+            self.buyback.paid_today = 0;
+        };
+        if self.buyback.daily_cap != 0 && self.buyback.paid_today + near_amount > self.buyback.daily_cap {
+            ContractError::BuybackDailyCapExceeded.panic();
+        };
+
+        self.buyback.pool -= near_amount;
+        self.buyback.paid_today += near_amount;
+        self.buyback.bought_back_ft += ft_amount;
+        Promise::new(seller_id).transfer(near_amount);
+    }
+
+    pub fn set_config_contract(&mut self, config_contract: AccountId) {
+        self.assert_owner();
+        self.config_contract = Some(config_contract);
+    }
+
+    pub fn refresh_config(&mut self) -> Promise {
+        let config_contract = self.config_contract.clone().unwrap_or_else(|| ContractError::ConfigContractNotSet.panic());
+        ext_config::ext(config_contract)
+            .with_static_gas(Gas(5*TGAS))
+            .get_config()
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .on_config_refreshed()
+                )
+    }
+
+    #[private]
+    pub fn on_config_refreshed(&mut self) -> bool {
+        if let Some(result) = promise_result_as_success() {
+            let config = near_sdk::serde_json::from_slice::<ConfigDTO>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            self.cached_config.update(&config, config.marketplace_paused);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_cached_config(&self) -> CachedConfigDTO {
+        self.cached_config.to_dto()
+    }
+
+    pub fn nft_on_approve(&mut self, token_id: String, owner_id: AccountId, approval_id: u64, msg: String) {
+        log_event("nft_approval", NftApprovalDTO {
+            nft_contract_id: env::predecessor_account_id(),
+            token_id: token_id.clone(),
+            owner_id: owner_id.clone(),
+            approval_id: U64(approval_id),
+            msg: msg.clone()
+        });
+        if msg.is_empty() || self.artfans_nft.as_ref() != Some(&env::predecessor_account_id()) {
+            return
+        }
+
+        let approve_msg = near_sdk::serde_json::from_str::<ApproveMsg>(&msg).ok().unwrap_or_else(|| ContractError::InvalidListingMsg.panic());
+        match approve_msg {
+            ApproveMsg::AcceptCollectionOffer { offer_id } => self.accept_collection_offer(offer_id.into(), token_id, owner_id, approval_id),
+            ApproveMsg::RefundPrimarySale { .. } => self.refund_primary_sale(token_id, owner_id, approval_id),
+            ApproveMsg::Listing(args) => self.create_listing(token_id, owner_id, approval_id, args)
+        };
+    }
+
+    fn create_listing(&mut self, token_id: String, owner_id: AccountId, approval_id: u64, args: ListingArgs) {
+        let listed_at = env::block_timestamp();
+        let expires_at = args.expires_at.map(u64::from);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= listed_at {
+                ContractError::InvalidListingMsg.panic();
+            };
+        };
+
+        if let Some(old_listing_id) = self.listings_by_token.get(&token_id) {
+            self.listings.remove(&old_listing_id);
+        };
+
+        let listing_id = self.next_listing_id;
+        self.next_listing_id += 1;
+        self.listings.insert(&listing_id, &Listing {
+            token_id: token_id.clone(),
+            owner_id,
+            approval_id,
+            price: args.price.into(),
+            listed_at,
+            expires_at,
+            auto_relist: args.auto_relist.unwrap_or(false)
+        });
+        self.listings_by_token.insert(&token_id, &listing_id);
+    }
+
+    #[payable]
+    pub fn buy_listing(&mut self, listing_id: U64) -> Promise {
+        self.assert_not_paused();
+
+        let listing_id = u64::from(listing_id);
+        let listing = self.listings.get(&listing_id).unwrap_or_else(|| ContractError::ListingNotFound.panic());
+        if self.is_listing_expired(&listing) {
+            ContractError::ListingExpired.panic();
+        };
+        if env::attached_deposit() != listing.price {
+            ContractError::WrongListingPrice.panic();
+        };
+        let buyer_id = env::predecessor_account_id();
+        if buyer_id == listing.owner_id {
+            ContractError::OwnListingPurchase.panic();
+        };
+
+        // The listing is kept until the transfer succeeds. A concurrent purchase of the same listing fails on the NFT contract, since the approval is already used
+        let artfans_nft = self.artfans_nft.clone().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+        ext_nft::ext(artfans_nft)
+            .with_static_gas(self.calc_step_gas(self.gas_settings.nft_transfer_payout, self.gas_settings.transfer_resolve))
+            .with_attached_deposit(1)
+            .nft_transfer_payout(buyer_id.clone(), listing.token_id.clone(), listing.approval_id, U128(listing.price), MAX_LEN_PAYOUT)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(self.gas_settings.transfer_resolve))
+                    .on_listing_purchased(buyer_id, listing.owner_id, U128(listing.price), U64(listing_id), listing.token_id, U64(listing.approval_id))
+                )
+    }
+
+    #[private]
+    pub fn on_listing_purchased(&mut self, buyer_id: AccountId, owner_id: AccountId, price: U128, listing_id: U64, token_id: String, approval_id: U64) -> bool {
+        let price = Balance::from(price);
+        if let Some(result) = promise_result_as_success() {
+            self.remove_sold_listing(listing_id.into(), &token_id, approval_id.into());
+            self.settle_sale(&result, buyer_id, owner_id, price);
+            true
+        } else {
+            Promise::new(buyer_id).transfer(price);
+            false
+        }
+    }
+
+    // Distributes the payout and credits the buyer's loyalty points
+    fn settle_sale(&mut self, result: &[u8], buyer_id: AccountId, owner_id: AccountId, price: Balance) {
+        let payout = near_sdk::serde_json::from_slice::<HashMap<AccountId, U128>>(result).ok()
+            .filter(|payout| payout.len() <= MAX_LEN_PAYOUT as usize)
+            .filter(|payout| payout.values().map(|amount| Balance::from(*amount)).sum::<Balance>() == price)
+            .unwrap_or_else(|| HashMap::from([(owner_id.clone(), U128(price))]));
+
+        self.credit_loyalty_points(buyer_id, price);
+        for (account_id, amount) in payout {
+            Promise::new(account_id).transfer(amount.into());
+        }
+    }
+
+    #[payable]
+    pub fn make_collection_offer(&mut self, price: U128, expiry: Option<U64>) -> U64 {
+        self.assert_not_paused();
+
+        let price = Balance::from(price);
+        if price == 0 || env::attached_deposit() != price {
+            ContractError::WrongOfferPrice.panic();
+        };
+        let expires_at = expiry.map(u64::from);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= env::block_timestamp() {
+                ContractError::OfferExpired.panic();
+            };
+        };
+
+        let offer_id = self.next_collection_offer_id;
+        self.next_collection_offer_id += 1;
+        self.collection_offers.insert(&offer_id, &CollectionOffer {
+            buyer_id: env::predecessor_account_id(),
+            price,
+            expires_at
+        });
+        U64(offer_id)
+    }
+
+    pub fn cancel_collection_offer(&mut self, offer_id: U64) -> Promise {
+        let offer_id = u64::from(offer_id);
+        let offer = self.collection_offers.get(&offer_id).unwrap_or_else(|| ContractError::OfferNotFound.panic());
+        if env::predecessor_account_id() != offer.buyer_id {
+            ContractError::NotOfferOwner.panic();
+        };
+        self.collection_offers.remove(&offer_id);
+        Promise::new(offer.buyer_id).transfer(offer.price)
+    }
+
+    // The offer is taken out of escrow while the transfer is in flight and restored if it fails
+    fn accept_collection_offer(&mut self, offer_id: u64, token_id: String, owner_id: AccountId, approval_id: u64) {
+        self.assert_not_paused();
+
+        let offer = self.collection_offers.get(&offer_id).unwrap_or_else(|| ContractError::OfferNotFound.panic());
+        if self.is_offer_expired(&offer) {
+            ContractError::OfferExpired.panic();
+        };
+        if offer.buyer_id == owner_id {
+            ContractError::OwnOfferAcceptance.panic();
+        };
+
+        self.collection_offers.remove(&offer_id);
+        if let Some(listing_id) = self.listings_by_token.get(&token_id) {
+            self.listings.remove(&listing_id);
+            self.listings_by_token.remove(&token_id);
+        };
+
+        ext_nft::ext(env::predecessor_account_id())
+            .with_static_gas(Gas(self.gas_settings.nft_transfer_payout))
+            .with_attached_deposit(1)
+            .nft_transfer_payout(offer.buyer_id.clone(), token_id, approval_id, U128(offer.price), MAX_LEN_PAYOUT)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(self.gas_settings.transfer_resolve))
+                    .on_collection_offer_accepted(U64(offer_id), offer.buyer_id, owner_id, U128(offer.price), offer.expires_at.map(U64))
+                );
+    }
+
+    #[private]
+    pub fn on_collection_offer_accepted(&mut self, offer_id: U64, buyer_id: AccountId, owner_id: AccountId, price: U128, expires_at: Option<U64>) -> bool {
+        let price = Balance::from(price);
+        if let Some(result) = promise_result_as_success() {
+            self.settle_sale(&result, buyer_id, owner_id, price);
+            true
+        } else {
+            self.collection_offers.insert(&offer_id.into(), &CollectionOffer {
+                buyer_id,
+                price,
+                expires_at: expires_at.map(u64::from)
+            });
+            false
+        }
+    }
+
+    pub fn get_collection_offer(&self, offer_id: U64) -> Option<CollectionOfferDTO> {
+        self.collection_offers.get(&offer_id.into())
+            .filter(|offer| !self.is_offer_expired(offer))
+            .map(|offer| to_collection_offer_dto(offer_id.into(), offer))
+    }
+
+    pub fn get_collection_offers(&self, from_index: U64, limit: U64) -> Vec<CollectionOfferDTO> {
+        self.collection_offers
+            .iter()
+            .filter(|(_, offer)| !self.is_offer_expired(offer))
+            .skip(u64::from(from_index) as usize)
+            .take(u64::from(limit) as usize)
+            .map(|(offer_id, offer)| to_collection_offer_dto(offer_id, offer))
+            .collect()
+    }
+
+    fn is_offer_expired(&self, offer: &CollectionOffer) -> bool {
+        match offer.expires_at {
+            Some(expires_at) => expires_at <= env::block_timestamp(),
+            None => false
+        }
+    }
+
+    pub fn remove_listing(&mut self, listing_id: U64) {
+        let listing_id = u64::from(listing_id);
+        let listing = self.listings.get(&listing_id).unwrap_or_else(|| ContractError::ListingNotFound.panic());
+        if env::predecessor_account_id() != listing.owner_id {
+            ContractError::NotListingOwner.panic();
+        };
+        self.remove_listing_storage(listing_id, &listing);
+    }
+
+    pub fn cleanup_expired_listings(&mut self, limit: U64) -> U64 {
+        let expired: Vec<(u64, Listing)> = self.listings
+            .iter()
+            .filter(|(_, listing)| self.is_listing_expired(listing))
+            .take(u64::from(limit) as usize)
+            .collect();
+
+        let initial_storage_usage = env::storage_usage();
+        for (listing_id, listing) in expired.iter() {
+            self.remove_listing_storage(*listing_id, listing);
+        }
+        let reclaimed_storage = initial_storage_usage.saturating_sub(env::storage_usage());
+
+        let incentive = Balance::from(reclaimed_storage) * env::storage_byte_cost() * CLEANUP_INCENTIVE_PERCENT / 100;
+        if incentive != 0 {
+            Promise::new(env::predecessor_account_id()).transfer(incentive);
+        };
+        U64(expired.len() as u64)
+    }
+
+    pub fn get_listing(&self, listing_id: U64) -> Option<ListingDTO> {
+        let listing_id = u64::from(listing_id);
+        self.listings.get(&listing_id)
+            .filter(|listing| !self.is_listing_expired(listing))
+            .map(|listing| self.to_listing_dto(listing_id, listing))
+    }
+
+    pub fn get_listings(&self, from_index: U64, limit: U64) -> Vec<ListingDTO> {
+        self.listings
+            .iter()
+            .filter(|(_, listing)| !self.is_listing_expired(listing))
+            .skip(u64::from(from_index) as usize)
+            .take(u64::from(limit) as usize)
+            .map(|(listing_id, listing)| self.to_listing_dto(listing_id, listing))
+            .collect()
+    }
+
+    fn remove_listing_storage(&mut self, listing_id: u64, listing: &Listing) {
+        self.listings.remove(&listing_id);
+        if self.listings_by_token.get(&listing.token_id) == Some(listing_id) {
+            self.listings_by_token.remove(&listing.token_id);
+        };
+    }
+
+    // The listing could be replaced while the transfer was in flight, so only the sold one is removed
+    fn remove_sold_listing(&mut self, listing_id: u64, token_id: &str, approval_id: u64) {
+        if let Some(listing) = self.listings.get(&listing_id) {
+            if listing.token_id == token_id && listing.approval_id == approval_id {
+                self.remove_listing_storage(listing_id, &listing);
+            };
+        };
+    }
+
+    fn is_listing_expired(&self, listing: &Listing) -> bool {
+        match listing.expires_at {
+            Some(expires_at) => !listing.auto_relist && expires_at <= env::block_timestamp(),
+            None => false
+        }
+    }
+
+    // Auto-relisted listings are extended by their original duration each time they expire
+    fn get_listing_expires_at(&self, listing: &Listing) -> Option<u64> {
+        listing.expires_at.map(|expires_at| {
+            let now = env::block_timestamp();
+            if !listing.auto_relist || expires_at > now {
+                expires_at
             } else {
-                let ft_registration_fee = ACTIVITY_FT_REGISTRATION_FEE.saturating_mul(ACTIVITY_FT_EXCHANGE_RATE);
-                assert_eq!(ft_amount.saturating_sub(minted_ft_amount), ft_registration_fee, "Unexpected amount of minted tokens");
-                let near_registration_fee = ft_registration_fee.saturating_div(ACTIVITY_FT_EXCHANGE_RATE);
-                let amount = near_amount - near_registration_fee;
-                Promise::new(self.activity_ft_beneficiary.clone()).transfer(amount);
+                let duration = expires_at - listing.listed_at;
+                expires_at + ((now - expires_at) / duration + 1) * duration
+            }
+        })
+    }
+
+    fn to_listing_dto(&self, listing_id: u64, listing: Listing) -> ListingDTO {
+        ListingDTO {
+            listing_id: U64(listing_id),
+            expires_at: self.get_listing_expires_at(&listing).map(U64),
+            token_id: listing.token_id,
+            owner_id: listing.owner_id,
+            price: U128(listing.price),
+            auto_relist: listing.auto_relist
+        }
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
+    }
+
+    pub fn set_artfans_nft(&mut self, artfans_nft: AccountId) {
+        self.assert_owner();
+        self.artfans_nft = Some(artfans_nft);
+    }
+
+    #[payable]
+    pub fn buy_artfans_nft(&mut self, approve_receiver_id: Option<AccountId>, approve_msg: Option<String>) -> Promise {
+        self.assert_not_paused();
+
+        let artfans_nft = self.artfans_nft.clone().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+        let buyer_id = env::predecessor_account_id();
+        let near_amount = env::attached_deposit();
+
+        let callback_gas = self.gas_settings.purchase_resolve + calc_loyalty_mint_gas(&self.gas_settings);
+        ext_nft::ext(artfans_nft)
+            .with_static_gas(self.calc_step_gas(self.gas_settings.nft_buy_mint_approve, callback_gas))
+            .with_attached_deposit(near_amount)
+            .nft_buy_mint_approve(Some(buyer_id.clone()), approve_receiver_id, approve_msg)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(callback_gas))
+                    .on_artfans_nft_purchased(buyer_id.clone(), buyer_id, U128(near_amount))
+                )
+    }
+
+    #[private]
+    pub fn on_artfans_nft_purchased(&mut self, buyer_id: AccountId, payer_id: AccountId, near_amount: U128) -> Option<Token> {
+        if let Some(result) = promise_result_as_success() {
+            let token = near_sdk::serde_json::from_slice::<Token>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            self.credit_loyalty_points(buyer_id, near_amount.into());
+            Some(token)
+        } else {
+            Promise::new(payer_id).transfer(near_amount.into());
+            None
+        }
+    }
+
+    // 'period' is in nanoseconds, 'refund_fee_percent' of the escrowed amount is kept on refund. 'beneficiary' set to null disables the escrow
+    pub fn set_primary_sale_escrow(&mut self, beneficiary: Option<AccountId>, period: U64, refund_fee_percent: u8) {
+        self.assert_owner();
+        if refund_fee_percent > 100 {
+            ContractError::InvalidRefundFeePercent.panic();
+        };
+        self.primary_sale_escrow = PrimarySaleEscrow {
+            beneficiary,
+            period: period.into(),
+            refund_fee_percent
+        };
+    }
+
+    pub fn get_primary_sale_escrow(&self) -> PrimarySaleEscrowDTO {
+        PrimarySaleEscrowDTO {
+            beneficiary: self.primary_sale_escrow.beneficiary.clone(),
+            period: U64(self.primary_sale_escrow.period),
+            refund_fee_percent: self.primary_sale_escrow.refund_fee_percent
+        }
+    }
+
+    pub fn get_primary_sale(&self, token_id: String) -> Option<PrimarySaleDTO> {
+        self.primary_sales.get(&token_id).map(|sale| PrimarySaleDTO {
+            token_id,
+            buyer_id: sale.buyer_id,
+            amount: U128(sale.amount),
+            refundable_until: U64(sale.refundable_until)
+        })
+    }
+
+    // The Artfans NFT contract pays out the proceeds of every sale (including sales outside the marketplace) with this call,
+    // the attached amount is escrowed. The call fails while the escrow is disabled, so the amount is returned to the NFT contract
+    #[payable]
+    pub fn add_primary_sale(&mut self, token_id: String, buyer_id: AccountId) {
+        if self.artfans_nft.as_ref() != Some(&env::predecessor_account_id()) {
+            ContractError::NotArtfansNft.panic();
+        };
+        if self.primary_sale_escrow.beneficiary.is_none() {
+            ContractError::PrimarySaleEscrowDisabled.panic();
+        };
+        self.primary_sales.insert(&token_id, &PrimarySale {
+            buyer_id,
+            amount: env::attached_deposit(),
+            refundable_until: env::block_timestamp() + self.primary_sale_escrow.period
+        });
+    }
+
+    // Anyone can release the escrowed amount to the beneficiary once the refund window is over
+    pub fn release_primary_sale(&mut self, token_id: String) -> Promise {
+        let sale = self.primary_sales.get(&token_id).unwrap_or_else(|| ContractError::PrimarySaleNotFound.panic());
+        if env::block_timestamp() < sale.refundable_until {
+            ContractError::RefundWindowOpen.panic();
+        };
+        let beneficiary = self.primary_sale_escrow.beneficiary.clone().unwrap_or_else(|| ContractError::PrimarySaleEscrowDisabled.panic());
+        self.primary_sales.remove(&token_id);
+        Promise::new(beneficiary).transfer(sale.amount)
+    }
+
+    // The buyer approves the token with the refund msg, the token is returned to the beneficiary and the escrowed
+    // amount without the refund fee is paid back. The sale is restored if the transfer fails
+    fn refund_primary_sale(&mut self, token_id: String, owner_id: AccountId, approval_id: u64) {
+        let sale = self.primary_sales.get(&token_id).unwrap_or_else(|| ContractError::PrimarySaleNotFound.panic());
+        if sale.buyer_id != owner_id {
+            ContractError::NotPrimarySaleBuyer.panic();
+        };
+        if env::block_timestamp() >= sale.refundable_until {
+            ContractError::RefundWindowClosed.panic();
+        };
+        let beneficiary = self.primary_sale_escrow.beneficiary.clone().unwrap_or_else(|| ContractError::PrimarySaleEscrowDisabled.panic());
+        self.primary_sales.remove(&token_id);
+
+        ext_nft::ext(env::predecessor_account_id())
+            .with_static_gas(Gas(self.gas_settings.nft_transfer))
+            .with_attached_deposit(1)
+            .nft_transfer(beneficiary, token_id.clone(), Some(approval_id), Some(String::from("Primary sale refund")))
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(self.gas_settings.transfer_resolve))
+                    .on_primary_sale_refunded(token_id, owner_id, U128(sale.amount), U64(sale.refundable_until))
+                );
+    }
+
+    #[private]
+    pub fn on_primary_sale_refunded(&mut self, token_id: String, buyer_id: AccountId, amount: U128, refundable_until: U64) -> bool {
+        let amount = Balance::from(amount);
+        if is_promise_success() {
+            let fee = amount / 100 * u128::from(self.primary_sale_escrow.refund_fee_percent);
+            Promise::new(buyer_id).transfer(amount - fee);
+            if fee != 0 {
+                if let Some(beneficiary) = self.primary_sale_escrow.beneficiary.clone() {
+                    Promise::new(beneficiary).transfer(fee);
+                };
             };
-            U128(minted_ft_amount)
+            true
         } else {
-            Promise::new(buyer_id.clone()).transfer(near_amount);
-            U128(0)
+            self.primary_sales.insert(&token_id, &PrimarySale {
+                buyer_id,
+                amount,
+                refundable_until: refundable_until.into()
+            });
+            false
         }
     }
 
-    // #[payable]
-    // pub fn mint_artfans_nft(&mut self) -> Promise {
-    //     let near_amount = env::attached_deposit();
-    //     if near_amount != ARTFANS_NFT_PRICE {
-    //         env::panic_str("Attached deposit must be equal to 3.5 NEAR");
-    //     };
+    pub fn set_payment_processor(&mut self, payment_processor: Option<AccountId>) {
+        self.assert_owner();
+        self.payment_processor = payment_processor;
+    }
+
+    pub fn get_payment_processor(&self) -> Option<AccountId> {
+        self.payment_processor.clone()
+    }
+
+    // Called by the payment processor after an off-chain card payment with the NEAR equivalent attached.
+    // A failed purchase is refunded to the buyer by the regular purchase callbacks, so 'external_ref' is never settled twice
+    #[payable]
+    pub fn settle_fiat_purchase(&mut self, buyer: AccountId, item: FiatPurchaseItem, external_ref: String) -> Promise {
+        self.assert_not_paused();
+        if self.payment_processor.as_ref() != Some(&env::predecessor_account_id()) {
+            ContractError::NotPaymentProcessor.panic();
+        };
+        if external_ref.is_empty() {
+            ContractError::EmptyExternalRef.panic();
+        };
+        if external_ref.len() > MAX_EXTERNAL_REF_LEN {
+            ContractError::ExternalRefTooLong.panic();
+        };
+        if self.fiat_settlements.contains_key(&external_ref) {
+            ContractError::FiatPurchaseAlreadySettled.panic();
+        };
+
+        let near_amount = env::attached_deposit();
+        let purchase = match item {
+            FiatPurchaseItem::ArtfansNft => {
+                let artfans_nft = self.artfans_nft.clone().unwrap_or_else(|| ContractError::ArtfansNftNotSet.panic());
+                let callback_gas = self.gas_settings.purchase_resolve + calc_loyalty_mint_gas(&self.gas_settings);
+                ext_nft::ext(artfans_nft)
+                    .with_static_gas(self.calc_step_gas(self.gas_settings.nft_buy_mint_approve, callback_gas + self.gas_settings.purchase_resolve))
+                    .with_attached_deposit(near_amount)
+                    .nft_buy_mint_approve(Some(buyer.clone()), None, None)
+                        .then(
+                            ext_self::ext(env::current_account_id())
+                            .with_static_gas(Gas(callback_gas))
+                            .on_artfans_nft_purchased(buyer.clone(), env::predecessor_account_id(), U128(near_amount))
+                        )
+            },
+            FiatPurchaseItem::ActivityFt => {
+                if near_amount < ACTIVITY_FT_REGISTRATION_FEE {
+                    ContractError::DepositLessThanRegistrationFee.panic();
+                };
+                self.purchase_activity_ft(buyer.clone(), env::predecessor_account_id(), near_amount, self.gas_settings.purchase_resolve)
+            }
+        };
+
+        let settlement = FiatSettlement {
+            buyer_id: buyer,
+            item,
+            near_amount,
+            settled_at: env::block_timestamp(),
+            status: FiatSettlementStatus::Pending
+        };
+        self.fiat_settlements.insert(&external_ref, &settlement);
+        log_event("fiat_purchase_settlement", to_fiat_settlement_dto(external_ref.clone(), settlement));
+
+        purchase.then(
+            ext_self::ext(env::current_account_id())
+            .with_static_gas(Gas(self.gas_settings.purchase_resolve))
+            .on_fiat_purchase_settled(external_ref)
+        )
+    }
+
+    #[private]
+    pub fn on_fiat_purchase_settled(&mut self, external_ref: String) -> bool {
+        let mut settlement = self.fiat_settlements.get(&external_ref).unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+        let result = promise_result_as_success();
+        let is_completed = match settlement.item {
+            FiatPurchaseItem::ArtfansNft => result
+                .and_then(|result| near_sdk::serde_json::from_slice::<Option<Token>>(&result).ok())
+                .flatten()
+                .is_some(),
+            FiatPurchaseItem::ActivityFt => result
+                .and_then(|result| near_sdk::serde_json::from_slice::<U128>(&result).ok())
+                .map(|minted_ft_amount| minted_ft_amount.0 != 0)
+                .unwrap_or(false)
+        };
+
+        settlement.status = if is_completed { FiatSettlementStatus::Completed } else { FiatSettlementStatus::Failed };
+        self.fiat_settlements.insert(&external_ref, &settlement);
+        log_event("fiat_purchase_resolution", to_fiat_settlement_dto(external_ref, settlement));
+        is_completed
+    }
+
+    pub fn get_fiat_settlement(&self, external_ref: String) -> Option<FiatSettlementDTO> {
+        self.fiat_settlements.get(&external_ref)
+            .map(|settlement| to_fiat_settlement_dto(external_ref, settlement))
+    }
 
-    //     let buyer_id = env::predecessor_account_id();
-    //     self.purchase_artfans_nft(buyer_id)
-    // }
-    
-    // fn purchase_artfans_nft(&mut self, buyer_id: AccountId) -> Promise {
-    //     ext_nft::ext(self.artfans_nft.clone())
-    //         .with_static_gas(Gas(5*TGAS))
-    //         .with_attached_deposit(ARTFANS_NFT_REGISTRATION_FEE)
-    //         .nft_mint(buyer_id.clone(), None)
-    //             .then(
-    //                 ext_self::ext(env::current_account_id())
-    //                 .with_static_gas(Gas(5*TGAS))
-    //                 .on_artfans_nft_purchased(buyer_id)
-    //             )
-    // }
-
-    // #[private]
-    // pub fn on_artfans_nft_purchased(&mut self, buyer_id: AccountId) -> Option<Token> {
-    //     let near_amount = ARTFANS_NFT_PRICE - ARTFANS_NFT_REGISTRATION_FEE;
-        
-    //     if is_promise_success() {
-    //         let result = promise_result_as_success().expect("Unexpected promise result");
-    //         let token = near_sdk::serde_json::from_slice::<Token>(&result).ok().expect("Unexpected value result from promise");
-    //         Promise::new(self.artfans_nft_beneficiary.clone()).transfer(near_amount);
-    //         Some(token)
-    //     } else {
-    //         Promise::new(buyer_id.clone()).transfer(near_amount);
-    //         None
-    //     }
-    // }
-    
 }
 
 
-pub trait Ownable {
-    fn assert_owner(&self) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.get_owner(),
-            "This operation is restricted to the contract owner."
-        );
+fn to_fiat_settlement_dto(external_ref: String, settlement: FiatSettlement) -> FiatSettlementDTO {
+    FiatSettlementDTO {
+        external_ref,
+        buyer_id: settlement.buyer_id,
+        item: settlement.item,
+        near_amount: U128(settlement.near_amount),
+        settled_at: U64(settlement.settled_at),
+        status: settlement.status
     }
-    fn get_owner(&self) -> AccountId;
-    fn set_owner(&mut self, owner: AccountId);
 }
 
-#[near_bindgen]
-impl Ownable for Contract {
-    fn get_owner(&self) -> AccountId {
-        self.owner.clone()
+fn calc_cart_steps_gas(steps: &[CartStep], gas_settings: &GasSettings) -> u64 {
+    steps
+        .iter()
+        .map(|step| {
+            let step_gas = match step {
+                CartStep::ArtfansNft => gas_settings.nft_buy_mint_approve,
+                CartStep::Listing { .. } => gas_settings.nft_transfer_payout,
+                CartStep::ActivityFt { .. } => gas_settings.ft_mint
+            };
+            step_gas + gas_settings.cart_step_resolve + calc_loyalty_mint_gas(gas_settings)
+        })
+        .sum()
+}
+
+// The Artfans NFT purchase callback mints the loyalty points, so it carries the gas of one more FT mint and its callback.
+// The listing purchase callback has enough gas for the mint in 'transfer_resolve', activity FT purchases earn no points
+fn calc_activity_ft_purchase_gas(gas_settings: &GasSettings) -> u64 {
+    gas_settings.ft_mint + gas_settings.purchase_resolve + GAS_FOR_PURCHASE_CALL
+}
+
+fn calc_artfans_nft_purchase_gas(gas_settings: &GasSettings) -> u64 {
+    gas_settings.nft_buy_mint_approve + gas_settings.purchase_resolve + calc_loyalty_mint_gas(gas_settings) + GAS_FOR_PURCHASE_CALL
+}
+
+fn calc_loyalty_mint_gas(gas_settings: &GasSettings) -> u64 {
+    gas_settings.ft_mint + GAS_FOR_ON_LOYALTY_POINTS_MINTED
+}
+
+fn calc_listing_purchase_gas(gas_settings: &GasSettings) -> u64 {
+    gas_settings.nft_transfer_payout + gas_settings.transfer_resolve + GAS_FOR_PURCHASE_CALL
+}
+
+fn get_current_day() -> u64 {
+    env::block_timestamp() / NANOSECONDS_PER_DAY
+}
+
+fn log_event<T: Serialize>(event: &str, data: T) {
+    artfans_common::log_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, data);
+}
+
+fn to_collection_offer_dto(offer_id: u64, offer: CollectionOffer) -> CollectionOfferDTO {
+    CollectionOfferDTO {
+        offer_id: U64(offer_id),
+        buyer_id: offer.buyer_id,
+        price: U128(offer.price),
+        expires_at: offer.expires_at.map(U64)
     }
+}
 
-    fn set_owner(&mut self, owner: AccountId) {
-        self.assert_owner();
-        self.owner = owner;
+impl Pausable for Contract {
+    fn is_paused(&self) -> bool {
+        self.cached_config.is_paused()
+    }
+
+    fn panic_paused(&self) -> ! {
+        ContractError::Paused.panic()
     }
-}
\ No newline at end of file
+}
+
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
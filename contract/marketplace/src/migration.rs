@@ -0,0 +1,22 @@
+use crate::*;
+
+// Contract state before the upgrade
+#[derive(BorshDeserialize)]
+pub struct OldContract {
+    owner: AccountId,
+    activity_ft: AccountId,
+    activity_ft_beneficiary: AccountId
+}
+
+#[near_bindgen]
+impl Contract {
+
+    // The old state is removed, so 'new' initializes the collections and settings added since with their defaults
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_state: OldContract = env::state_read().unwrap_or_else(|| ContractError::OldStateNotFound.panic());
+        env::storage_remove(b"STATE");
+        Self::new(old_state.owner, old_state.activity_ft, old_state.activity_ft_beneficiary)
+    }
+}
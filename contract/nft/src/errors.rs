@@ -0,0 +1,48 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 3001 => "Already initialized",
+    NotOwner = 3002 => "This operation is restricted to token owner",
+    NotTokenMetadataAdmin = 3003 => "This operation is restricted to token metadata admin",
+    TokenMetadataAdminAlreadyRegistered = 3004 => "The account is already registered as a token metadata admin",
+    TokenMetadataAdminNotRegistered = 3005 => "The account is not registered as a token metadata admin",
+    WrongNftPrice = 3006 => "Attached deposit must be equal to 3.5 NEAR",
+    ApproveReceiverNotSpecified = 3007 => "'approve_receiver_id' must be specified for provided 'approve_msg'",
+    DefaultTokenMetadataNotSet = 3008 => "Default Token Metadata is not set",
+    MaxSupplyReached = 3009 => "Max Supply is reached",
+    TokenNotFound = 3010 => "Token id does not exist",
+    TokenMetadataExtensionNotSet = 3011 => "Token Metadata extension is not set",
+    DepositRequired = 3012 => "Requires attached deposit of at least 1 yoctoNEAR",
+    SelfOperator = 3013 => "Owner can not be approved as own operator",
+    OperatorAlreadyApproved = 3014 => "The account is already approved as an operator",
+    OperatorNotApproved = 3015 => "The account is not approved as an operator",
+    NotEnoughGas = 3016 => "More gas is required",
+    ApprovalExtensionNotSet = 3017 => "Approval extension is not set",
+    PurchaseRestrictedToMarketplace = 3018 => "Tokens can be bought only through the marketplace",
+    BuyerNotSpecified = 3019 => "'buyer_id' must be specified by the marketplace",
+    SocialNetworkNotSet = 3020 => "Social network contract is not set",
+    NotWhitelisted = 3021 => "Only whitelisted accounts can buy tokens during the presale",
+    UnexpectedPromiseResult = 3022 => "Unexpected promise result",
+    NoRetainedFees = 3023 => "There are no retained registration fees to sweep",
+    MetadataHashMismatch = 3024 => "'media' and 'reference' must be set together with 'media_hash' and 'reference_hash'",
+    InvalidMetadataHash = 3025 => "Metadata hash must be a base64-encoded sha256 hash (32 bytes)",
+    InvalidMetadataUrl = 3026 => "Metadata url is empty or exceeds 512 bytes",
+    ReferenceHashNotSet = 3027 => "Token metadata has no 'reference_hash'",
+    InvalidTransferLock = 3028 => "Transfer lock must end in the future",
+    TokenTransferLocked = 3029 => "Token transfers are locked",
+    InvalidProposalTitle = 3030 => "Proposal title is empty or exceeds 256 bytes",
+    InvalidProposalOptions = 3031 => "Proposal must have from 2 to 10 non-empty options of up to 256 bytes",
+    InvalidProposalEnd = 3032 => "Proposal must end in the future",
+    ProposalNotFound = 3033 => "Proposal is not found",
+    VotingClosed = 3034 => "Voting on the proposal is closed",
+    InvalidVoteOption = 3035 => "Vote option does not exist",
+    InvalidVoteTokens = 3036 => "From 1 to 50 tokens can vote in one call",
+    NotTokenHolder = 3037 => "Only the token holder can vote with it",
+    TokenNotInSnapshot = 3038 => "Token was minted after the proposal snapshot",
+    TokenAlreadyVoted = 3039 => "Token has already voted on the proposal",
+    OldStateNotFound = 3040 => "Contract state to migrate is not found",
+    NotPendingOwner = 3041 => "This operation is restricted to the proposed contract owner",
+    ConfigContractNotSet = 3042 => "Config contract is not set",
+    Paused = 3043 => "NFT sales are paused",
+}
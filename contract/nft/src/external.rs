@@ -0,0 +1,61 @@
+use near_sdk::{ext_contract, AccountId, PromiseOrValue};
+use near_sdk::json_types::{U64, U128};
+use crate::AccountsPageDTO;
+use near_contract_standards::non_fungible_token::{Token, TokenId};
+use std::collections::HashMap;
+use artfans_common::ConfigDTO;
+
+pub use artfans_common::TGAS;
+pub const GAS_FOR_RESOLVE_TRANSFER: u64 = 5*TGAS;
+pub const GAS_FOR_NFT_TRANSFER_CALL: u64 = 25*TGAS + GAS_FOR_RESOLVE_TRANSFER;
+pub const GAS_FOR_NFT_ON_APPROVE: u64 = 10*TGAS;
+pub const GAS_FOR_RESOLVE_BUY_MINT_APPROVE: u64 = 5*TGAS;
+pub const GAS_FOR_GET_ACCOUNTS_WITH_MIN_FRIENDS: u64 = 50*TGAS;
+pub const GAS_FOR_ON_WHITELIST_PAGE_RECEIVED: u64 = 50*TGAS;
+pub const GAS_FOR_ON_RETAINED_FEES_SWEPT: u64 = 5*TGAS;
+pub const GAS_FOR_NFT_ON_HOLDER_CHANGED: u64 = 10*TGAS;
+pub const GAS_FOR_ON_TRANSFER_HOOK_NOTIFIED: u64 = 3*TGAS;
+pub const GAS_FOR_ADD_PRIMARY_SALE: u64 = 5*TGAS;
+pub const GAS_FOR_ON_PRIMARY_SALE_ADDED: u64 = 3*TGAS;
+
+
+#[ext_contract(ext_nft_receiver)]
+pub trait NonFungibleTokenReceiver {
+    fn nft_on_transfer(&mut self, sender_id: AccountId, previous_owner_id: AccountId, token_id: TokenId, msg: String) -> PromiseOrValue<bool>;
+}
+
+#[ext_contract(ext_nft_approval_receiver)]
+pub trait NonFungibleTokenApprovalReceiver {
+    fn nft_on_approve(&mut self, token_id: TokenId, owner_id: AccountId, approval_id: u64, msg: String);
+}
+
+#[ext_contract(ext_social_network)]
+pub trait SocialNetwork {
+    fn get_accounts_with_min_friends(&self, min_friends: U64, from_index: U64, limit: U64) -> AccountsPageDTO;
+}
+
+#[ext_contract(ext_transfer_hook_receiver)]
+pub trait TransferHookReceiver {
+    fn nft_on_holder_changed(&mut self, token_id: TokenId, old_owner_id: AccountId, new_owner_id: AccountId);
+}
+
+#[ext_contract(ext_marketplace)]
+pub trait Marketplace {
+    fn add_primary_sale(&mut self, token_id: TokenId, buyer_id: AccountId);
+}
+
+#[ext_contract(ext_config)]
+pub trait Config {
+    fn get_config(&self) -> ConfigDTO;
+}
+
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn nft_resolve_transfer(&mut self, previous_owner_id: AccountId, receiver_id: AccountId, token_id: TokenId, approved_account_ids: Option<HashMap<AccountId, u64>>) -> bool;
+    fn nft_resolve_buy_mint_approve(&mut self, token: Token) -> Token;
+    fn on_whitelist_page_received(&mut self) -> Option<U64>;
+    fn on_retained_fees_swept(&mut self, amount: U128) -> U128;
+    fn on_transfer_hook_notified(&mut self, token_id: TokenId) -> bool;
+    fn on_primary_sale_added(&mut self, amount: U128) -> bool;
+    fn on_config_refreshed(&mut self) -> bool;
+}
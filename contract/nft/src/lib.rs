@@ -1,20 +1,33 @@
 use near_contract_standards::non_fungible_token::metadata::{
     NFTContractMetadata, NonFungibleTokenMetadataProvider, TokenMetadata,
 };
-use near_contract_standards::non_fungible_token::{Token, TokenId, NonFungibleToken};
+use near_contract_standards::non_fungible_token::{Token, TokenId, NonFungibleToken, refund_deposit};
+use near_contract_standards::non_fungible_token::core::{NonFungibleTokenCore, NonFungibleTokenResolver};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupSet};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedSet, Vector};
+use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    assert_one_yocto,
-    env, near_bindgen, AccountId, BorshStorageKey, PanicOnDefault, Promise, PromiseOrValue,
+    assert_one_yocto, is_promise_success, promise_result_as_success, serde_json,
+    env, log, near_bindgen, AccountId, BorshStorageKey, Gas, PanicOnDefault, Promise, PromiseOrValue,
 };
-use near_sdk::json_types::U128;
+use near_sdk::json_types::{U128, U64, Base64VecU8};
+use artfans_common::{CachedConfig, CachedConfigDTO, ConfigDTO, Ownable, Ownership, Pausable, ACTIVITY_FT_EXCHANGE_RATE};
 use std::collections::HashMap;
 
+pub mod external;
+pub use crate::external::*;
+pub mod errors;
+pub use crate::errors::*;
+pub mod migration;
+
 
 pub const NFT_MAX_SUPPLY: u128 = 26_000;
 pub const NFT_PRICE: u128 = 3_500_000_000_000_000_000_000_000;
 pub const NFT_REGISTRATION_FEE: u128 = 100_000_000_000_000_000_000_000;
+pub const MAX_METADATA_URL_LEN: usize = 512;
+pub const MAX_PROPOSAL_TITLE_LEN: usize = 256;
+pub const MAX_PROPOSAL_OPTIONS: usize = 10;
+pub const MAX_VOTE_TOKENS: usize = 50;
 
 
 #[near_bindgen]
@@ -24,7 +37,23 @@ pub struct Contract {
     metadata: LazyOption<NFTContractMetadata>,
     default_token_metadata: LazyOption<TokenMetadata>,
     token_metadata_admins: LookupSet<AccountId>,
-    beneficiary: AccountId
+    operators: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    beneficiary: AccountId,
+    marketplace: Option<AccountId>,
+    marketplace_only: bool,
+    social_network: Option<AccountId>,
+    presale_whitelist: UnorderedSet<AccountId>,
+    presale_active: bool,
+    retained_fees: u128,
+    transfer_hook_receiver: Option<AccountId>,
+    transfer_locks: LookupMap<TokenId, u64>,
+    proposals: Vector<Proposal>,
+    proposals_votes: LookupSet<(u64, TokenId)>,
+    mint_transfer_lock: Option<u64>,
+    ownership: Ownership,
+    config_contract: Option<AccountId>,
+    cached_config: CachedConfig,
+    snapshot_owners: LookupMap<TokenId, Vec<(u64, AccountId)>>
 }
 
 
@@ -36,19 +65,68 @@ enum StorageKey {
     Enumeration,
     Approval,
     DefaultTokenMetadata,
-    TokenMetadataAdmins
+    TokenMetadataAdmins,
+    Operators,
+    AccountOperators { account_id: Vec<u8> },
+    PresaleWhitelist,
+    TransferLocks,
+    Proposals,
+    ProposalsVotes,
+    SnapshotOwners
+}
+
+// Tokens are minted with sequential ids, so the ones minted before the snapshot have ids up to 'snapshot_supply'
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Proposal {
+    title: String,
+    options: Vec<String>,
+    snapshot_block: u64,
+    snapshot_supply: u64,
+    ends_at: u64,
+    votes: Vec<u64>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalDTO {
+    proposal_id: U64,
+    title: String,
+    options: Vec<String>,
+    snapshot_block: U64,
+    snapshot_supply: U64,
+    ends_at: U64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProposalTallyDTO {
+    votes: Vec<U64>,
+    total_votes: U64,
+    leading_option: Option<u8>,
+    finished: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountsPageDTO {
+    accounts: Vec<AccountId>,
+    next_index: Option<U64>
 }
 
 #[near_bindgen]
 impl Contract {
+    // 'marketplace' is registered without the 'marketplace_only' restriction, so the suite factory can wire it on deployment
     #[init]
     pub fn new(
         owner: AccountId, 
         contract_metadata: NFTContractMetadata, 
         default_token_metadata: TokenMetadata,
-        beneficiary: AccountId
+        beneficiary: AccountId,
+        marketplace: Option<AccountId>
     ) -> Self {
-        assert!(!env::state_exists(), "Already initialized");
+        if env::state_exists() {
+            ContractError::AlreadyInitialized.panic();
+        };
         
         contract_metadata.assert_valid();
         default_token_metadata.assert_valid();
@@ -64,49 +142,373 @@ impl Contract {
             metadata: LazyOption::new(StorageKey::Metadata, Some(&contract_metadata)),
             default_token_metadata: LazyOption::new(StorageKey::DefaultTokenMetadata, Some(&default_token_metadata)),
             token_metadata_admins: LookupSet::new(StorageKey::TokenMetadataAdmins),
-            beneficiary
+            operators: LookupMap::new(StorageKey::Operators),
+            beneficiary,
+            marketplace,
+            marketplace_only: false,
+            social_network: None,
+            presale_whitelist: UnorderedSet::new(StorageKey::PresaleWhitelist),
+            presale_active: false,
+            retained_fees: 0,
+            transfer_hook_receiver: None,
+            transfer_locks: LookupMap::new(StorageKey::TransferLocks),
+            proposals: Vector::new(StorageKey::Proposals),
+            proposals_votes: LookupSet::new(StorageKey::ProposalsVotes),
+            mint_transfer_lock: None,
+            ownership: Ownership::new(owner.clone()),
+            config_contract: None,
+            cached_config: CachedConfig::new(ACTIVITY_FT_EXCHANGE_RATE),
+            snapshot_owners: LookupMap::new(StorageKey::SnapshotOwners)
         };
         this.token_metadata_admins.insert(&owner);
         this
     }
 
 
+    // Tokens bought while the mint transfer lock is set are locked until it ends
     #[payable]
-    pub fn nft_buy_mint_approve(&mut self, approve_receiver_id: Option<AccountId>, approve_msg: Option<String>) -> Token {
-        
+    pub fn nft_buy_mint_approve(&mut self, buyer_id: Option<AccountId>, approve_receiver_id: Option<AccountId>, approve_msg: Option<String>) -> PromiseOrValue<Token> {
+        self.assert_not_paused();
+
         if env::attached_deposit() != NFT_PRICE {
-            env::panic_str("Attached deposit must be equal to 3.5 NEAR");
+            ContractError::WrongNftPrice.panic();
         };
 
         if approve_receiver_id.is_none() && approve_msg.is_some() {
-            env::panic_str("'approve_receiver_id' must be specified for provided 'approve_msg'");
+            ContractError::ApproveReceiverNotSpecified.panic();
         };
 
-        let buyer_id = env::predecessor_account_id();
+        let buyer_id = self.get_buyer_id(buyer_id);
+        if self.presale_active && !self.presale_whitelist.contains(&buyer_id) {
+            ContractError::NotWhitelisted.panic();
+        };
         let total_supply: u128 = self.tokens.owner_by_id.len() as u128;
         if total_supply < NFT_MAX_SUPPLY {
+            let initial_storage_usage = env::storage_usage();
             let token_id: TokenId = format!("{}", total_supply + 1);
-            let token_metadata = self.default_token_metadata.get().expect("Default Token Metadata is not set");
+            let token_metadata = self.default_token_metadata.get().unwrap_or_else(|| ContractError::DefaultTokenMetadataNotSet.panic());
             let token = self.tokens.internal_mint_with_refund(
                 token_id.clone(), 
-                buyer_id, 
+                buyer_id.clone(), 
                 Some(token_metadata), 
                 None
             );
-            
-            if let Some(account_id) = approve_receiver_id {
-                self.tokens.nft_approve(token_id, account_id, approve_msg);
-            };
 
             let near_amount = NFT_PRICE - NFT_REGISTRATION_FEE;
-            Promise::new(self.beneficiary.clone()).transfer(near_amount); // send funds to beneficiary
-            token
+            self.pay_beneficiary(&token_id, &buyer_id, near_amount);
+
+            // Approval storage is covered by NFT_REGISTRATION_FEE, so 'nft_approve' (which refunds the attached deposit) is not used here
+            let approval = approve_receiver_id.map(|account_id| {
+                let approval_id = self.internal_approve(&token_id, &account_id);
+                (account_id, approval_id)
+            });
+            if let Some(locked_until) = self.get_mint_transfer_lock() {
+                self.transfer_locks.insert(&token_id, &u64::from(locked_until));
+            };
+            self.retain_registration_fee(env::storage_usage() - initial_storage_usage);
+
+            match (approval, approve_msg) {
+                (Some((account_id, approval_id)), Some(msg)) => {
+                    if env::prepaid_gas() <= Gas(GAS_FOR_NFT_ON_APPROVE + GAS_FOR_RESOLVE_BUY_MINT_APPROVE) {
+                        ContractError::NotEnoughGas.panic();
+                    };
+                    ext_nft_approval_receiver::ext(account_id)
+                        .with_static_gas(Gas(GAS_FOR_NFT_ON_APPROVE))
+                        .nft_on_approve(token_id, buyer_id, approval_id, msg)
+                            .then(
+                                ext_self::ext(env::current_account_id())
+                                .with_static_gas(Gas(GAS_FOR_RESOLVE_BUY_MINT_APPROVE))
+                                .nft_resolve_buy_mint_approve(token)
+                            )
+                            .into()
+                },
+                _ => PromiseOrValue::Value(token)
+            }
         } else {
-            env::panic_str("Max Supply is reached");
+            ContractError::MaxSupplyReached.panic();
         }
     }
 
 
+    // Proceeds paid to the marketplace are passed with the sale, so the marketplace can escrow them.
+    // Proceeds the marketplace rejects are returned to this contract and retained until they are swept
+    fn pay_beneficiary(&self, token_id: &TokenId, buyer_id: &AccountId, near_amount: u128) {
+        if self.marketplace.as_ref() == Some(&self.beneficiary) {
+            ext_marketplace::ext(self.beneficiary.clone())
+                .with_static_gas(Gas(GAS_FOR_ADD_PRIMARY_SALE))
+                .with_attached_deposit(near_amount)
+                .add_primary_sale(token_id.clone(), buyer_id.clone())
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                        .with_static_gas(Gas(GAS_FOR_ON_PRIMARY_SALE_ADDED))
+                        .on_primary_sale_added(U128::from(near_amount))
+                    );
+        } else {
+            Promise::new(self.beneficiary.clone()).transfer(near_amount);
+        };
+    }
+
+    // Only the part of NFT_REGISTRATION_FEE which is not locked by the token (and its approval) storage can be swept
+    fn retain_registration_fee(&mut self, storage_usage: u64) {
+        let storage_cost = env::storage_byte_cost() * u128::from(storage_usage);
+        self.retained_fees += NFT_REGISTRATION_FEE.saturating_sub(storage_cost);
+    }
+
+    #[private]
+    pub fn on_primary_sale_added(&mut self, amount: U128) -> bool {
+        if is_promise_success() {
+            true
+        } else {
+            self.retained_fees += u128::from(amount);
+            false
+        }
+    }
+
+    pub fn get_retained_fees(&self) -> U128 {
+        U128::from(self.retained_fees)
+    }
+
+    pub fn sweep_retained_fees(&mut self, to: AccountId) -> Promise {
+        self.assert_owner();
+        let amount = self.retained_fees;
+        if amount == 0 {
+            ContractError::NoRetainedFees.panic();
+        };
+        self.retained_fees = 0;
+        Promise::new(to)
+            .transfer(amount)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(GAS_FOR_ON_RETAINED_FEES_SWEPT))
+                    .on_retained_fees_swept(U128::from(amount))
+                )
+    }
+
+    #[private]
+    pub fn on_retained_fees_swept(&mut self, amount: U128) -> U128 {
+        if is_promise_success() {
+            amount
+        } else {
+            self.retained_fees += u128::from(amount);
+            U128::from(0)
+        }
+    }
+
+    pub fn set_transfer_hook_receiver(&mut self, transfer_hook_receiver: Option<AccountId>) {
+        self.assert_owner();
+        self.transfer_hook_receiver = transfer_hook_receiver;
+    }
+
+    pub fn get_transfer_hook_receiver(&self) -> Option<AccountId> {
+        self.transfer_hook_receiver.clone()
+    }
+
+    fn get_transfer_hook_gas(&self) -> u64 {
+        if self.transfer_hook_receiver.is_some() {
+            GAS_FOR_NFT_ON_HOLDER_CHANGED + GAS_FOR_ON_TRANSFER_HOOK_NOTIFIED
+        } else {
+            0
+        }
+    }
+
+    // Called before the token leaves 'old_owner_id'. If a proposal was created since the last recorded transfer,
+    // 'old_owner_id' held the token at its snapshot (and at every earlier one not recorded yet), so one entry per proposal is enough
+    fn record_snapshot_owner(&mut self, token_id: &TokenId, old_owner_id: &AccountId) {
+        let latest_proposal_id = match self.proposals.len() {
+            0 => return,
+            len => len - 1
+        };
+        let snapshot_supply = self.proposals.get(latest_proposal_id).unwrap().snapshot_supply;
+        match token_id.parse::<u64>() {
+            Ok(token_number) if token_number <= snapshot_supply => {},
+            _ => return
+        };
+        let mut owners = self.snapshot_owners.get(token_id).unwrap_or_default();
+        if !matches!(owners.last(), Some((proposal_id, _)) if *proposal_id >= latest_proposal_id) {
+            owners.push((latest_proposal_id, old_owner_id.clone()));
+            self.snapshot_owners.insert(token_id, &owners);
+        };
+    }
+
+    // The holder at the snapshot is the one recorded on the first transfer after it, or the current owner if the token has not moved since
+    fn internal_snapshot_owner(&self, proposal_id: u64, token_id: &TokenId) -> Option<AccountId> {
+        let owners = self.snapshot_owners.get(token_id).unwrap_or_default();
+        let index = owners.partition_point(|(recorded_proposal_id, _)| *recorded_proposal_id < proposal_id);
+        match owners.into_iter().nth(index) {
+            Some((_, owner_id)) => Some(owner_id),
+            None => self.tokens.owner_by_id.get(token_id)
+        }
+    }
+
+    // The hook never fails the transfer: it is skipped if there is not enough gas left and its failure is only logged
+    fn notify_transfer_hook(&self, token_id: TokenId, old_owner_id: AccountId, new_owner_id: AccountId) {
+        if let Some(receiver_id) = self.transfer_hook_receiver.clone() {
+            if env::prepaid_gas() - env::used_gas() <= Gas(self.get_transfer_hook_gas()) {
+                log!("Transfer hook for token {} is skipped: not enough gas", token_id);
+                return
+            };
+            ext_transfer_hook_receiver::ext(receiver_id)
+                .with_static_gas(Gas(GAS_FOR_NFT_ON_HOLDER_CHANGED))
+                .nft_on_holder_changed(token_id.clone(), old_owner_id, new_owner_id)
+                    .then(
+                        ext_self::ext(env::current_account_id())
+                        .with_static_gas(Gas(GAS_FOR_ON_TRANSFER_HOOK_NOTIFIED))
+                        .on_transfer_hook_notified(token_id)
+                    );
+        };
+    }
+
+    #[private]
+    pub fn on_transfer_hook_notified(&mut self, token_id: TokenId) -> bool {
+        let notified = is_promise_success();
+        if !notified {
+            log!("Transfer hook for token {} failed", token_id);
+        };
+        notified
+    }
+
+    fn get_buyer_id(&self, buyer_id: Option<AccountId>) -> AccountId {
+        let predecessor_id = env::predecessor_account_id();
+        if self.marketplace.as_ref() == Some(&predecessor_id) {
+            buyer_id.unwrap_or_else(|| ContractError::BuyerNotSpecified.panic())
+        } else if self.marketplace_only {
+            ContractError::PurchaseRestrictedToMarketplace.panic();
+        } else {
+            predecessor_id
+        }
+    }
+
+    pub fn set_config_contract(&mut self, config_contract: AccountId) {
+        self.assert_owner();
+        self.config_contract = Some(config_contract);
+    }
+
+    pub fn refresh_config(&mut self) -> Promise {
+        let config_contract = self.config_contract.clone().unwrap_or_else(|| ContractError::ConfigContractNotSet.panic());
+        ext_config::ext(config_contract)
+            .with_static_gas(Gas(5*TGAS))
+            .get_config()
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .on_config_refreshed()
+                )
+    }
+
+    #[private]
+    pub fn on_config_refreshed(&mut self) -> bool {
+        if let Some(result) = promise_result_as_success() {
+            let config = serde_json::from_slice::<ConfigDTO>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            self.cached_config.update(&config, config.nft_paused);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_cached_config(&self) -> CachedConfigDTO {
+        self.cached_config.to_dto()
+    }
+
+    pub fn set_marketplace(&mut self, marketplace: Option<AccountId>, marketplace_only: bool) {
+        self.assert_owner();
+        self.marketplace = marketplace;
+        self.marketplace_only = marketplace_only;
+    }
+
+    pub fn get_marketplace(&self) -> (Option<AccountId>, bool) {
+        (self.marketplace.clone(), self.marketplace_only)
+    }
+
+    pub fn set_social_network(&mut self, social_network: Option<AccountId>) {
+        self.assert_owner();
+        self.social_network = social_network;
+    }
+
+    pub fn get_social_network(&self) -> Option<AccountId> {
+        self.social_network.clone()
+    }
+
+    pub fn set_presale_active(&mut self, presale_active: bool) {
+        self.assert_owner();
+        self.presale_active = presale_active;
+    }
+
+    pub fn is_presale_active(&self) -> bool {
+        self.presale_active
+    }
+
+    pub fn add_to_whitelist(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_owner();
+        for account_id in account_ids.iter() {
+            self.presale_whitelist.insert(account_id);
+        }
+    }
+
+    pub fn remove_from_whitelist(&mut self, account_ids: Vec<AccountId>) {
+        self.assert_owner();
+        for account_id in account_ids.iter() {
+            self.presale_whitelist.remove(account_id);
+        }
+    }
+
+    pub fn is_whitelisted(&self, account_id: AccountId) -> bool {
+        self.presale_whitelist.contains(&account_id)
+    }
+
+    pub fn get_whitelist(&self, from_index: U64, limit: U64) -> Vec<AccountId> {
+        self.presale_whitelist
+            .iter()
+            .skip(u64::from(from_index) as usize)
+            .take(u64::from(limit) as usize)
+            .collect()
+    }
+
+    // Pulls one page of the social network accounts having at least 'min_friends' friends into the presale whitelist.
+    // The callback returns the index of the next page, or None when the last page is synced
+    pub fn sync_whitelist_from_social(&mut self, min_friends: U64, from_index: U64, limit: U64) -> Promise {
+        self.assert_owner();
+        let social_network = self.social_network.clone().unwrap_or_else(|| ContractError::SocialNetworkNotSet.panic());
+        ext_social_network::ext(social_network)
+            .with_static_gas(Gas(GAS_FOR_GET_ACCOUNTS_WITH_MIN_FRIENDS))
+            .get_accounts_with_min_friends(min_friends, from_index, limit)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(GAS_FOR_ON_WHITELIST_PAGE_RECEIVED))
+                    .on_whitelist_page_received()
+                )
+    }
+
+    #[private]
+    pub fn on_whitelist_page_received(&mut self) -> Option<U64> {
+        let result = promise_result_as_success().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+        let page = serde_json::from_slice::<AccountsPageDTO>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+        for account_id in page.accounts.iter() {
+            self.presale_whitelist.insert(account_id);
+        }
+        page.next_index
+    }
+
+    #[private]
+    pub fn nft_resolve_buy_mint_approve(&mut self, token: Token) -> Token {
+        // The token stays approved even if the receiver failed, as with 'nft_approve'
+        token
+    }
+
+    fn internal_approve(&mut self, token_id: &TokenId, account_id: &AccountId) -> u64 {
+        let approvals_by_id = self.tokens.approvals_by_id.as_mut().unwrap_or_else(|| ContractError::ApprovalExtensionNotSet.panic());
+        let next_approval_id_by_id = self.tokens.next_approval_id_by_id.as_mut().unwrap_or_else(|| ContractError::ApprovalExtensionNotSet.panic());
+
+        let approval_id = next_approval_id_by_id.get(token_id).unwrap_or(1u64);
+        let mut approved_account_ids = approvals_by_id.get(token_id).unwrap_or_default();
+        approved_account_ids.insert(account_id.clone(), approval_id);
+
+        approvals_by_id.insert(token_id, &approved_account_ids);
+        next_approval_id_by_id.insert(token_id, &(approval_id + 1));
+        approval_id
+    }
+
+
     #[payable]
     pub fn nft_set_metadata(
         &mut self,
@@ -115,12 +517,13 @@ impl Contract {
     ) {
         self.assert_token_metadata_admin();
         if self.tokens.owner_by_id.get(&token_id).is_none() {
-            env::panic_str("Token id does not exist");
+            ContractError::TokenNotFound.panic();
         };
+        assert_metadata_integrity(&token_metadata);
         if let Some(token_metadata_by_id) = &mut self.tokens.token_metadata_by_id {
             token_metadata_by_id.insert(&token_id, &token_metadata);
         } else {
-            env::panic_str("Token Metadata extension is not set");
+            ContractError::TokenMetadataExtensionNotSet.panic();
         };
     }
 
@@ -131,18 +534,29 @@ impl Contract {
         default_token_metadata: TokenMetadata
     ) {
         self.assert_token_metadata_admin();
-        default_token_metadata.assert_valid();
+        assert_metadata_integrity(&default_token_metadata);
         self.default_token_metadata.set(&default_token_metadata);
     }
 
 
+    // Collectors can check that the content behind 'reference' is the one the token was issued with
+    pub fn verify_token_reference(&self, token_id: TokenId, bytes: Base64VecU8) -> bool {
+        let token_metadata_by_id = self.tokens.token_metadata_by_id.as_ref().unwrap_or_else(|| ContractError::TokenMetadataExtensionNotSet.panic());
+        let token_metadata = token_metadata_by_id.get(&token_id).unwrap_or_else(|| ContractError::TokenNotFound.panic());
+        match token_metadata.reference_hash {
+            Some(reference_hash) => env::sha256(&bytes.0) == reference_hash.0,
+            None => ContractError::ReferenceHashNotSet.panic()
+        }
+    }
+
+
     pub fn nft_payout(
         &self, 
         token_id: String,
         balance: U128, 
         max_len_payout: u32
     ) -> HashMap<AccountId, U128> {
-        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("Token id does not exist");
+        let owner_id = self.tokens.owner_by_id.get(&token_id).unwrap_or_else(|| ContractError::TokenNotFound.panic());
         let mut result: HashMap<AccountId, U128> = HashMap::new();
         result.insert(owner_id, balance);
         result
@@ -159,48 +573,371 @@ impl Contract {
         max_len_payout: u32,
     ) -> HashMap<AccountId, U128> {
         assert_one_yocto();
-        let owner_id = self.tokens.owner_by_id.get(&token_id).expect("Token id does not exist");
-        self.tokens.nft_transfer(receiver_id, token_id, Some(approval_id), None);
+        self.assert_transfer_unlocked(&token_id);
+        let sender_id = self.get_transfer_sender_id(&token_id);
+        let (owner_id, _) = self.tokens.internal_transfer(&sender_id, &receiver_id, &token_id, Some(approval_id), None);
+        self.record_snapshot_owner(&token_id, &owner_id);
+        self.notify_transfer_hook(token_id, owner_id.clone(), receiver_id);
         let mut result: HashMap<AccountId, U128> = HashMap::new();
         result.insert(owner_id, balance);
         result
     }
     
-    fn assert_owner(&self) {
-        assert_eq!(env::predecessor_account_id(), self.tokens.owner_id,
-            "This operation is restricted to token owner"
-        );
+
+    #[payable]
+    pub fn approve_for_all(&mut self, operator_id: AccountId) {
+        if env::attached_deposit() == 0 {
+            ContractError::DepositRequired.panic();
+        };
+
+        let owner_id = env::predecessor_account_id();
+        if owner_id == operator_id {
+            ContractError::SelfOperator.panic();
+        };
+
+        let initial_storage_usage = env::storage_usage();
+
+        let mut owner_operators = self.operators.get(&owner_id).unwrap_or_else(|| {
+            UnorderedSet::new(
+                StorageKey::AccountOperators {
+                    account_id: env::sha256(owner_id.as_bytes())
+                }
+            )
+        });
+        if !owner_operators.insert(&operator_id) {
+            ContractError::OperatorAlreadyApproved.panic();
+        };
+        self.operators.insert(&owner_id, &owner_operators);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+    }
+
+
+    #[payable]
+    pub fn revoke_for_all(&mut self, operator_id: AccountId) {
+        assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let mut owner_operators = self.operators.get(&owner_id).unwrap_or_else(|| ContractError::OperatorNotApproved.panic());
+        if !owner_operators.remove(&operator_id) {
+            ContractError::OperatorNotApproved.panic();
+        };
+
+        if owner_operators.is_empty() {
+            self.operators.remove(&owner_id);
+        } else {
+            self.operators.insert(&owner_id, &owner_operators);
+        };
+    }
+
+
+    pub fn is_approved_for_all(&self, owner_id: AccountId, operator_id: AccountId) -> bool {
+        match self.operators.get(&owner_id) {
+            Some(owner_operators) => owner_operators.contains(&operator_id),
+            None => false
+        }
+    }
+
+
+    // 'locked_until' (timestamp in nanoseconds) locks transfers of the tokens bought until then, e.g. for a giveaway batch.
+    // Set to null to mint unlocked tokens
+    pub fn set_mint_transfer_lock(&mut self, locked_until: Option<U64>) {
+        self.assert_owner();
+        if let Some(locked_until) = locked_until {
+            if u64::from(locked_until) <= env::block_timestamp() {
+                ContractError::InvalidTransferLock.panic();
+            };
+        };
+        self.mint_transfer_lock = locked_until.map(u64::from);
+    }
+
+    // Returns None when the lock is not set or has ended
+    pub fn get_mint_transfer_lock(&self) -> Option<U64> {
+        self.mint_transfer_lock
+            .filter(|locked_until| *locked_until > env::block_timestamp())
+            .map(U64)
+    }
+
+    // Returns None when the token is not locked or the lock has expired
+    pub fn get_transfer_lock(&self, token_id: TokenId) -> Option<U64> {
+        self.transfer_locks
+            .get(&token_id)
+            .filter(|locked_until| *locked_until > env::block_timestamp())
+            .map(U64)
+    }
+
+
+    // An expired lock is removed on the first transfer after its expiry
+    fn assert_transfer_unlocked(&mut self, token_id: &TokenId) {
+        if let Some(locked_until) = self.transfer_locks.get(token_id) {
+            if locked_until > env::block_timestamp() {
+                ContractError::TokenTransferLocked.panic();
+            };
+            self.transfer_locks.remove(token_id);
+        };
+    }
+
+
+    // Operators act on behalf of the token owner, so the transfer is authorized as if it was sent by the owner
+    fn get_transfer_sender_id(&self, token_id: &TokenId) -> AccountId {
+        let sender_id = env::predecessor_account_id();
+        if let Some(owner_id) = self.tokens.owner_by_id.get(token_id) {
+            if self.is_approved_for_all(owner_id.clone(), sender_id.clone()) {
+                return owner_id
+            };
+        };
+        sender_id
+    }
+
+    // The supply snapshot is taken at creation, 'ends_at' is a timestamp in nanoseconds
+    pub fn create_proposal(&mut self, title: String, options: Vec<String>, ends_at: U64) -> U64 {
+        self.assert_owner();
+        if title.trim().is_empty() || title.len() > MAX_PROPOSAL_TITLE_LEN {
+            ContractError::InvalidProposalTitle.panic();
+        };
+        if options.len() < 2 || options.len() > MAX_PROPOSAL_OPTIONS || options.iter().any(|option| option.trim().is_empty() || option.len() > MAX_PROPOSAL_TITLE_LEN) {
+            ContractError::InvalidProposalOptions.panic();
+        };
+        let ends_at = u64::from(ends_at);
+        if ends_at <= env::block_timestamp() {
+            ContractError::InvalidProposalEnd.panic();
+        };
+
+        let proposal_id = self.proposals.len();
+        self.proposals.push(&Proposal {
+            votes: vec![0; options.len()],
+            title,
+            options,
+            snapshot_block: env::block_height(),
+            snapshot_supply: self.tokens.owner_by_id.len(),
+            ends_at
+        });
+        U64(proposal_id)
+    }
+
+    pub fn get_proposal(&self, proposal_id: U64) -> Option<ProposalDTO> {
+        self.proposals.get(u64::from(proposal_id)).map(|proposal| ProposalDTO {
+            proposal_id,
+            title: proposal.title,
+            options: proposal.options,
+            snapshot_block: U64(proposal.snapshot_block),
+            snapshot_supply: U64(proposal.snapshot_supply),
+            ends_at: U64(proposal.ends_at)
+        })
+    }
+
+    pub fn get_proposals_count(&self) -> U64 {
+        U64(self.proposals.len())
+    }
+
+    // Every token minted before the snapshot votes once, the account that held it at the snapshot casts the vote.
+    // The attached deposit covers the votes storage, the rest is refunded
+    #[payable]
+    pub fn vote(&mut self, proposal_id: U64, option: u8, token_ids: Vec<TokenId>) -> U64 {
+        let proposal_id = u64::from(proposal_id);
+        let mut proposal = self.proposals.get(proposal_id).unwrap_or_else(|| ContractError::ProposalNotFound.panic());
+        if env::block_timestamp() >= proposal.ends_at {
+            ContractError::VotingClosed.panic();
+        };
+        if usize::from(option) >= proposal.options.len() {
+            ContractError::InvalidVoteOption.panic();
+        };
+        if token_ids.is_empty() || token_ids.len() > MAX_VOTE_TOKENS {
+            ContractError::InvalidVoteTokens.panic();
+        };
+
+        let initial_storage_usage = env::storage_usage();
+        let voter_id = env::predecessor_account_id();
+        for token_id in token_ids.iter() {
+            match token_id.parse::<u64>() {
+                Ok(token_number) if token_number <= proposal.snapshot_supply => {},
+                _ => ContractError::TokenNotInSnapshot.panic()
+            };
+            if self.internal_snapshot_owner(proposal_id, token_id).as_ref() != Some(&voter_id) {
+                ContractError::NotTokenHolder.panic();
+            };
+            if !self.proposals_votes.insert(&(proposal_id, token_id.clone())) {
+                ContractError::TokenAlreadyVoted.panic();
+            };
+        }
+
+        let weight = token_ids.len() as u64;
+        proposal.votes[usize::from(option)] += weight;
+        self.proposals.replace(proposal_id, &proposal);
+
+        refund_deposit(env::storage_usage() - initial_storage_usage);
+        U64(weight)
+    }
+
+    pub fn get_snapshot_owner(&self, proposal_id: U64, token_id: TokenId) -> Option<AccountId> {
+        let proposal = self.proposals.get(u64::from(proposal_id)).unwrap_or_else(|| ContractError::ProposalNotFound.panic());
+        match token_id.parse::<u64>() {
+            Ok(token_number) if token_number <= proposal.snapshot_supply => self.internal_snapshot_owner(u64::from(proposal_id), &token_id),
+            _ => None
+        }
+    }
+
+    pub fn has_token_voted(&self, proposal_id: U64, token_id: TokenId) -> bool {
+        self.proposals_votes.contains(&(u64::from(proposal_id), token_id))
+    }
+
+    // 'leading_option' is None when no votes are cast or the top options are tied
+    pub fn tally(&self, proposal_id: U64) -> ProposalTallyDTO {
+        let proposal = self.proposals.get(u64::from(proposal_id)).unwrap_or_else(|| ContractError::ProposalNotFound.panic());
+        let max_votes = proposal.votes.iter().copied().max().unwrap_or(0);
+        let leaders: Vec<usize> = proposal.votes.iter()
+            .enumerate()
+            .filter(|(_, votes)| **votes == max_votes)
+            .map(|(option, _)| option)
+            .collect();
+
+        ProposalTallyDTO {
+            total_votes: U64(proposal.votes.iter().sum()),
+            leading_option: if max_votes > 0 && leaders.len() == 1 { Some(leaders[0] as u8) } else { None },
+            votes: proposal.votes.into_iter().map(U64).collect(),
+            finished: env::block_timestamp() >= proposal.ends_at
+        }
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
     }
 
     fn assert_token_metadata_admin(&self) {
-        assert!(self.token_metadata_admins.contains(&env::predecessor_account_id()),
-            "This operation is restricted to token token metadata admin"
-        );
+        if !self.token_metadata_admins.contains(&env::predecessor_account_id()) {
+            ContractError::NotTokenMetadataAdmin.panic();
+        };
     }
 
     pub fn add_token_metadata_admin(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.token_metadata_admins.insert(&account_id) {
-            env::panic_str("The account is already registered as a token metadata admin");
+            ContractError::TokenMetadataAdminAlreadyRegistered.panic();
         }
     }
 
     pub fn remove_token_metadata_admin(&mut self, account_id: AccountId) {
         self.assert_owner();
         if !self.token_metadata_admins.remove(&account_id) {
-            env::panic_str("The account is not registered as a token metadata admin");
+            ContractError::TokenMetadataAdminNotRegistered.panic();
         }
     }
 
 }
 
-near_contract_standards::impl_non_fungible_token_core!(Contract, tokens);
+#[near_bindgen]
+impl NonFungibleTokenCore for Contract {
+    #[payable]
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        assert_one_yocto();
+        self.assert_transfer_unlocked(&token_id);
+        let sender_id = self.get_transfer_sender_id(&token_id);
+        let (old_owner, _) = self.tokens.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        self.record_snapshot_owner(&token_id, &old_owner);
+        self.notify_transfer_hook(token_id, old_owner, receiver_id);
+    }
+
+    #[payable]
+    fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        assert_one_yocto();
+        // Gas is reserved for the transfer hook and for the one sent from 'nft_resolve_transfer' if the token is returned
+        let transfer_hook_gas = self.get_transfer_hook_gas();
+        if env::prepaid_gas() <= Gas(GAS_FOR_NFT_TRANSFER_CALL + 2 * transfer_hook_gas) {
+            ContractError::NotEnoughGas.panic();
+        };
+        self.assert_transfer_unlocked(&token_id);
+        let sender_id = self.get_transfer_sender_id(&token_id);
+        let (old_owner, old_approvals) = self.tokens.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+        self.record_snapshot_owner(&token_id, &old_owner);
+        self.notify_transfer_hook(token_id.clone(), old_owner.clone(), receiver_id.clone());
+
+        ext_nft_receiver::ext(receiver_id.clone())
+            .with_static_gas(env::prepaid_gas() - Gas(GAS_FOR_NFT_TRANSFER_CALL + 2 * transfer_hook_gas))
+            .nft_on_transfer(env::predecessor_account_id(), old_owner.clone(), token_id.clone(), msg)
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(GAS_FOR_RESOLVE_TRANSFER + transfer_hook_gas))
+                    .nft_resolve_transfer(old_owner, receiver_id, token_id, old_approvals)
+                )
+                .into()
+    }
+
+    fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        self.tokens.nft_token(token_id)
+    }
+}
+
+#[near_bindgen]
+impl NonFungibleTokenResolver for Contract {
+    #[private]
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let transferred = self.tokens.nft_resolve_transfer(previous_owner_id.clone(), receiver_id.clone(), token_id.clone(), approved_account_ids);
+        if !transferred {
+            self.record_snapshot_owner(&token_id, &receiver_id);
+            self.notify_transfer_hook(token_id, receiver_id, previous_owner_id);
+        };
+        transferred
+    }
+}
+
 near_contract_standards::impl_non_fungible_token_approval!(Contract, tokens);
 near_contract_standards::impl_non_fungible_token_enumeration!(Contract, tokens);
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
+
+impl Pausable for Contract {
+    fn is_paused(&self) -> bool {
+        self.cached_config.is_paused()
+    }
+
+    fn panic_paused(&self) -> ! {
+        ContractError::Paused.panic()
+    }
+}
 
 #[near_bindgen]
 impl NonFungibleTokenMetadataProvider for Contract {
     fn nft_metadata(&self) -> NFTContractMetadata {
         self.metadata.get().unwrap()
     }
-}
\ No newline at end of file
+}
+
+
+// Every 'media' / 'reference' link has to come with the sha256 hash of its content
+fn assert_metadata_integrity(token_metadata: &TokenMetadata) {
+    assert_metadata_link(&token_metadata.media, &token_metadata.media_hash);
+    assert_metadata_link(&token_metadata.reference, &token_metadata.reference_hash);
+}
+
+fn assert_metadata_link(url: &Option<String>, hash: &Option<Base64VecU8>) {
+    match (url, hash) {
+        (Some(url), Some(hash)) => {
+            if url.trim().is_empty() || url.len() > MAX_METADATA_URL_LEN {
+                ContractError::InvalidMetadataUrl.panic();
+            };
+            if hash.0.len() != 32 {
+                ContractError::InvalidMetadataHash.panic();
+            };
+        },
+        (None, None) => {},
+        _ => ContractError::MetadataHashMismatch.panic()
+    }
+}
@@ -0,0 +1,45 @@
+use crate::*;
+
+// Contract state before the upgrade
+#[derive(BorshDeserialize)]
+pub struct OldContract {
+    tokens: NonFungibleToken,
+    metadata: LazyOption<NFTContractMetadata>,
+    default_token_metadata: LazyOption<TokenMetadata>,
+    token_metadata_admins: LookupSet<AccountId>,
+    beneficiary: AccountId
+}
+
+#[near_bindgen]
+impl Contract {
+
+    // Tokens and metadata are carried over, the collections and settings added since start with their defaults
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_state: OldContract = env::state_read().unwrap_or_else(|| ContractError::OldStateNotFound.panic());
+        Self {
+            ownership: Ownership::new(old_state.tokens.owner_id.clone()),
+            tokens: old_state.tokens,
+            metadata: old_state.metadata,
+            default_token_metadata: old_state.default_token_metadata,
+            token_metadata_admins: old_state.token_metadata_admins,
+            operators: LookupMap::new(StorageKey::Operators),
+            beneficiary: old_state.beneficiary,
+            marketplace: None,
+            marketplace_only: false,
+            social_network: None,
+            presale_whitelist: UnorderedSet::new(StorageKey::PresaleWhitelist),
+            presale_active: false,
+            retained_fees: 0,
+            transfer_hook_receiver: None,
+            transfer_locks: LookupMap::new(StorageKey::TransferLocks),
+            proposals: Vector::new(StorageKey::Proposals),
+            proposals_votes: LookupSet::new(StorageKey::ProposalsVotes),
+            mint_transfer_lock: None,
+            config_contract: None,
+            cached_config: CachedConfig::new(ACTIVITY_FT_EXCHANGE_RATE),
+            snapshot_owners: LookupMap::new(StorageKey::SnapshotOwners)
+        }
+    }
+}
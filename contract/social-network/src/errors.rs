@@ -0,0 +1,125 @@
+use artfans_common::contract_errors;
+pub use artfans_common::ErrorCodeDTO;
+
+contract_errors! {
+    AlreadyInitialized = 4001 => "Already initialized",
+    NotOwner = 4002 => "This operation is restricted to the contract owner",
+    Paused = 4003 => "Social network is paused",
+    FeeNotCharged = 4004 => "Fee was not charged",
+    ConfigContractNotSet = 4005 => "Config contract is not set",
+    UnexpectedPromiseResult = 4006 => "Unexpected value result from promise",
+    UsizeConversionFailed = 4007 => "'usize' conversion failed",
+    StorageNotFound = 4008 => "Storage collection is not found",
+    StorageMeasurementLeak = 4009 => "Measurement of storage aborted due to data leak",
+    EmptyPostId = 4010 => "'post_id' is empty or whitespace",
+    PostIdTooShort = 4011 => "'post_id' length is too small",
+    PostIdTooLong = 4012 => "'post_id' cannot exceed 100 bytes",
+    EmptyText = 4013 => "'text' is empty or whitespace",
+    TextTooShort = 4014 => "'text' length is too small",
+    InvalidProfileMetadata = 4015 => "Metadata is not a valid json string",
+    PostNotFound = 4016 => "Post is not found",
+    MessageNotFound = 4017 => "Message is not found",
+    ParentMessageNotFound = 4018 => "Parent message does not exist",
+    PostAlreadyLiked = 4019 => "Post is liked already",
+    PostNotLiked = 4020 => "Post is not liked",
+    MessageAlreadyLiked = 4021 => "Message is liked already",
+    MessageNotLiked = 4022 => "Message is not liked",
+    FriendAlreadyAdded = 4023 => "Friend is added already",
+    FriendNotAdded = 4024 => "Friend is not added",
+    CannotLinkSelf = 4025 => "Account cannot be linked to itself",
+    PrimaryAccountIsLinked = 4026 => "Primary account is linked to another identity",
+    AccountAlreadyLinked = 4027 => "Account is linked already",
+    LinkRequestNotFound = 4028 => "Account link request is not found",
+    AccountLinkNotFound = 4029 => "Account link is not found",
+    EmptyArchiveCid = 4030 => "'cid' is empty or whitespace",
+    ArchiveCidTooLong = 4031 => "'cid' cannot exceed 100 bytes",
+    AccountLikesArePrivate = 4032 => "Account likes are private",
+    EmptyListingId = 4033 => "'listing_id' is empty or whitespace",
+    ListingIdTooLong = 4034 => "'listing_id' cannot exceed 100 bytes",
+    AppKeyScopeViolation = 4035 => "Access key is not allowed to call this method",
+    AppKeyNotRegistered = 4036 => "App key is not registered",
+    EmptyAppScopes = 4037 => "'scopes' is empty",
+    NotMessageAuthor = 4038 => "Only the message author can edit it",
+    MessageNotEditable = 4039 => "Only text messages can be edited",
+    MessageEditWindowExpired = 4040 => "Message edit window is expired",
+    FriendsImportRootNotSet = 4041 => "Friends import merkle root is not set",
+    InvalidFriendsImportProof = 4042 => "Friends import proof is invalid",
+    EmptyImportedFriends = 4043 => "'friends' is empty",
+    TooManyImportedFriends = 4044 => "'friends' cannot exceed 100 accounts",
+    NotPostingDelegate = 4045 => "Caller is not a posting delegate of the account",
+    CannotDelegateToSelf = 4046 => "Account cannot delegate posting to itself",
+    PostingDelegateAlreadyGranted = 4047 => "Posting delegate is granted already",
+    PostingDelegateNotFound = 4048 => "Posting delegate is not found",
+    InvalidLanguageTag = 4049 => "'lang' is not a valid BCP-47 language tag",
+    WallPostingRestricted = 4050 => "Account wall does not accept messages from the caller",
+    ReversalLimitExceeded = 4051 => "Action on this target cannot be reversed more times today",
+    NotPendingOwner = 4052 => "This operation is restricted to the proposed contract owner",
+    PostMessagesLimitReached = 4053 => "Post reached its messages limit, reply in its continuation post",
+    MessageDeleted = 4054 => "Message is deleted",
+    PendingOperationNotFound = 4055 => "Pending operation is not found",
+    NotOperationCaller = 4056 => "Only the operation caller or the contract owner can replay it",
+    PendingOperationInFlight = 4057 => "Fee collection of the operation may still be in progress",
+    SensitiveMarkForced = 4058 => "Sensitive mark set by a moderator cannot be removed",
+    CannotFriendSelf = 4059 => "Account cannot send a friend request to itself",
+    FriendRequestAlreadySent = 4060 => "Friend request is sent already",
+    IncomingFriendRequestPending = 4061 => "The account has already sent a friend request, accept it instead",
+    FriendRequestNotFound = 4062 => "Friend request is not found",
+    CannotBlockSelf = 4063 => "Account cannot block itself",
+    AccountAlreadyBlocked = 4064 => "The account is blocked already",
+    AccountNotBlocked = 4065 => "The account is not blocked",
+    BlockedByAccount = 4066 => "The action is blocked by the account",
+    CannotMuteSelf = 4067 => "Account cannot mute itself",
+    AccountAlreadyMuted = 4068 => "The account is muted already",
+    AccountNotMuted = 4069 => "The account is not muted",
+    CannotReportOwnContent = 4070 => "Account cannot report its own content",
+    ContentAlreadyReported = 4071 => "The content is reported by the account already",
+    NotModerator = 4072 => "This operation is restricted to moderators",
+    ModeratorAlreadyAdded = 4073 => "The account is a moderator already",
+    ModeratorNotFound = 4074 => "The account is not a moderator",
+    InvalidBanExpiry = 4075 => "Ban expiry must be in the future",
+    AccountNotBanned = 4076 => "The account is not banned",
+    AccountBanned = 4077 => "The account is banned",
+    CredentialNftNotAllowed = 4078 => "NFT contract is not an allowed verification credential",
+    CredentialNftNotOwned = 4079 => "Credential token is not owned by the account",
+    InvalidVerificationTier = 4080 => "Verification tier must be greater than 0",
+    InvalidTokenId = 4081 => "'token_id' length is invalid",
+    InvalidAuditSamples = 4082 => "'samples' must be from 1 to 5",
+    NotPostAuthor = 4083 => "This operation is restricted to the post owner and moderators",
+    PostBeingDeleted = 4084 => "Post is being deleted",
+    PostAlreadyReposted = 4085 => "Post is reposted by the account already",
+    InvalidAnnouncementExpiry = 4086 => "Announcement expiry must be in the future",
+    AnnouncementsLimitReached = 4087 => "Announcements limit is reached, remove an announcement first",
+    AnnouncementNotFound = 4088 => "Announcement is not found",
+    QuotedContentNotFound = 4089 => "Quoted post or message is not found",
+    LikesHistoryAlreadyEnabled = 4090 => "Likes history is already enabled",
+    LikesHistoryNotEnabled = 4091 => "Likes history is not enabled",
+    NotPostOwner = 4092 => "This operation is restricted to the post owner",
+    PostTransferNotFound = 4093 => "There is no pending transfer of the post to the account",
+    InvalidPostTransfer = 4094 => "Post cannot be transferred to its owner",
+    InvalidProbationPolicy = 4095 => "'fee_multiplier_percent' must be at least 100",
+    ProbationActionsLimitExceeded = 4096 => "Daily actions limit of accounts on probation is reached",
+    InvalidPageCursor = 4097 => "Page cursor is invalid",
+    InvalidBatchSize = 4098 => "'calls' must contain from 1 to 10 calls",
+    DuplicateBatchLike = 4099 => "Post or message is liked more than once in the batch",
+    ReactionAlreadyAdded = 4100 => "The reaction is added by the account already",
+    ReactionNotFound = 4101 => "The account has no reaction to the post or message",
+    SuperLikesDisabled = 4102 => "Super likes are disabled",
+    InvalidTipAmount = 4103 => "Tip amount must be greater than 0",
+    CannotTipSelf = 4104 => "Account cannot tip its own message",
+    InvalidNearTipCut = 4105 => "NEAR tip cut must not exceed 100 percent",
+    InvalidWithdrawAmount = 4106 => "Withdraw amount exceeds the NEAR tips cut balance",
+    MediaDisabled = 4107 => "Media messages are disabled",
+    InvalidMediaCid = 4108 => "Media CID is invalid",
+    InvalidMediaMimeType = 4109 => "Media MIME type is invalid",
+    InvalidLinkUrl = 4110 => "Link URL is invalid",
+    InvalidLinkPreview = 4111 => "Link title or description is empty or too long",
+    InvalidLeaderboardSize = 4112 => "Leaderboard size exceeds the maximum",
+    MessageNotPinned = 4113 => "Post has no pinned message",
+    PostCommentsLocked = 4114 => "Post comments are locked",
+    InvalidDirectMessage = 4115 => "Direct message payload must be from 1 to 4096 bytes",
+    CannotMessageSelf = 4116 => "Account cannot send a direct message to itself",
+    DirectMessagesRestricted = 4117 => "Recipient does not accept direct messages from the caller",
+    PendingOperationFeeNotCharged = 4118 => "Fee of the pending operation was not charged",
+    PostDeleted = 4119 => "Post is deleted, its id cannot be reused",
+    OldStateNotFound = 4120 => "Contract state to migrate is not found",
+}
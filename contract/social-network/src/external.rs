@@ -1,17 +1,37 @@
 use near_sdk::{ext_contract, AccountId};
-use near_sdk::json_types::{U128};
-use crate::Call;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::{Promise, PromiseOrValue};
+use artfans_common::ConfigDTO;
 
-pub const TGAS: u64 = 1_000_000_000_000;
-pub const ACTIVITY_FT_EXCHANGE_RATE: u128 = 100;
+pub use artfans_common::{TGAS, ACTIVITY_FT_EXCHANGE_RATE};
 
 
 #[ext_contract(ext_ft)]
-trait FungibleToken {
-    fn ft_collect_fee(&mut self, amount: U128);
+pub trait FungibleToken {
+    fn ft_collect_fee(&mut self, payer_id: AccountId, amount: U128);
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_nft)]
+pub trait NonFungibleToken {
+    fn nft_token(&self, token_id: String);
+}
+
+#[ext_contract(ext_marketplace)]
+pub trait Marketplace {
+    fn set_account_identity(&mut self, account_id: AccountId, identity_id: Option<AccountId>);
+}
+
+#[ext_contract(ext_config)]
+pub trait Config {
+    fn get_config(&self) -> ConfigDTO;
 }
 
 #[ext_contract(ext_self)]
-trait ExtSelf {
-    fn on_fee_collected(&mut self, caller_id: AccountId, call: Call) -> Option<String>;
+pub trait ExtSelf {
+    fn on_fee_collected(&mut self, operation_id: U64) -> PromiseOrValue<Option<String>>;
+    fn on_fee_not_charged(&mut self);
+    fn execute_pending_operation(&mut self, operation_id: U64) -> Option<String>;
+    fn on_config_refreshed(&mut self) -> bool;
+    fn on_credential_nft_checked(&mut self, account_id: AccountId, caller_id: AccountId, contract_id: AccountId, token_id: String) -> Promise;
 }
\ No newline at end of file
@@ -1,33 +1,134 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{env, is_promise_success, near_bindgen, log, Balance, AccountId, Gas, Promise, PanicOnDefault, StorageUsage, BorshStorageKey};
+use near_sdk::{env, is_promise_success, promise_result_as_success, near_bindgen, Balance, AccountId, Gas, Promise, PromiseOrValue, PanicOnDefault, PublicKey, StorageUsage, BorshStorageKey};
 use near_sdk::json_types::{U128, U64, Base64VecU8};
-use near_sdk::collections::{LookupMap, Vector, UnorderedSet, LazyOption};
+use near_sdk::collections::{LookupMap, LookupSet, Vector, UnorderedSet, UnorderedMap, LazyOption};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::serde_json;
 use near_sdk::serde_json::{Result, Value};
 use std::convert::{From, TryFrom};
+use std::collections::HashMap;
+use artfans_common::{CachedConfig, CachedConfigDTO, ConfigDTO, Ownable, Ownership, Pausable, NANOSECONDS_PER_DAY};
 
 pub mod external;
 pub use crate::external::*;
+pub mod errors;
+pub use crate::errors::*;
+pub mod migration;
 
 const MIN_ACCOUNT_ID_LEN : usize = 2;
 const MIN_POST_ID_LEN : usize = 1;
 const MAX_POST_ID_LEN : usize = 100;
 const MIN_POST_MESSAGE_LEN : usize = 1;
+const MAX_ARCHIVE_CID_LEN : usize = 100;
+const MAX_LISTING_ID_LEN : usize = 100;
+const MAX_CONTINUATION_SUFFIX_LEN : usize = 11;
+const PENDING_OPERATION_REPLAY_DELAY : u64 = 100;
+const STREAK_BADGE_THRESHOLDS : [u32; 4] = [3, 7, 30, 100];
+const MAX_IMPORTED_FRIENDS : usize = 100;
+const MAX_TRENDING_POSTS : usize = 50;
+const MAX_LANG_TAG_LEN : usize = 16;
+const TRENDING_SCORE_HALF_LIFE : u64 = 12 * 3_600_000_000_000;
+const TRENDING_SCORE_PRECISION : u128 = 1_000_000;
+const TRENDING_WINDOW : u64 = 6 * 3_600_000_000_000;
+const LIKE_ENGAGEMENT_WEIGHT : u128 = 1;
+const COMMENT_ENGAGEMENT_WEIGHT : u128 = 2;
+const REPOST_ENGAGEMENT_WEIGHT : u128 = 3;
+const MIN_TOKEN_ID_LEN : usize = 1;
+const MAX_TOKEN_ID_LEN : usize = 100;
+const MAX_STORAGE_AUDIT_SAMPLES : u8 = 5;
+const DELETE_POST_GAS_RESERVE : u64 = 20 * TGAS;
+const MAX_ANNOUNCEMENTS : u64 = 10;
+const MAX_MESSAGE_MENTIONS : usize = 10;
+const MAX_THREAD_DEPTH : u32 = 20;
+const MAX_THREAD_MESSAGES : u64 = 100;
+const MAX_BATCH_CALLS : usize = 10;
+const MIN_MEDIA_CID_LEN : usize = 46;
+const MEDIA_MIME_TYPES : [&str; 2] = ["image/", "video/"];
+const MAX_LEADERBOARD_SIZE : u8 = 100;
+const MAX_LINK_URL_LEN : usize = 512;
+const MAX_LINK_TITLE_LEN : usize = 200;
+const MAX_LINK_DESCRIPTION_LEN : usize = 500;
+const LINK_URL_SCHEMES : [&str; 2] = ["https://", "http://"];
+const MAX_NOTIFICATIONS : u64 = 50;
+const MIN_DIRECT_MESSAGE_LEN : usize = 1;
+const MAX_DIRECT_MESSAGE_LEN : usize = 4096;
+
+pub const EVENT_STANDARD: &str = "artfans_social";
+pub const EVENT_STANDARD_VERSION: &str = "1.0.0";
 
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
 pub struct Contract {
-    owner: AccountId,
+    ownership: Ownership,
     fee_ft: AccountId,
+    config_contract: Option<AccountId>,
+    cached_config: CachedConfig,
     admin_settings: AdminSettings,
     storage_usage_settings: StorageUsageSettings,
     posts_messages: LookupMap<PostId, Vector<Message>>,
+    posts_authors_messages: LookupMap<PostAuthorId, Vector<u64>>,
     posts_likes: LookupMap<PostId, UnorderedSet<AccountId>>,
     posts_messages_likes: LookupMap<MessageId, UnorderedSet<AccountId>>,
     accounts_friends: LookupMap<AccountId, UnorderedSet<AccountId>>,
     accounts_profiles: LookupMap<AccountId, AccountProfile>,
     accounts_stats: LookupMap<AccountId, AccountStats>,
+    accounts_identities: LookupMap<AccountId, AccountId>,
+    accounts_links: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    accounts_link_requests: LookupMap<AccountId, AccountId>,
+    moderation_log: Vector<ModerationRecord>,
+    accounts_app_keys: LookupMap<AccountAppKey, Vec<AppScope>>,
+    accounts_badges: LookupMap<AccountId, UnorderedSet<Badge>>,
+    friends_import_root: Option<Vec<u8>>,
+    posts_scores: LookupMap<PostId, PostScore>,
+    trending_posts: LazyOption<Vec<(PostId, PostScore)>>,
+    posting_delegates: LookupMap<PostingDelegate, u64>,
+    accounts_walls: LookupMap<AccountId, Vector<WallMessage>>,
+    reversal_counters: LookupMap<Vec<u8>, ReversalCounter>,
+    accounts_with_friends: UnorderedSet<AccountId>,
+    posts_continuations: LookupMap<PostId, PostId>,
+    pending_operations: LookupMap<u64, PendingOperation>,
+    next_operation_id: u64,
+    accounts_reports: LookupMap<AccountId, u32>,
+    sensitive_posts: LookupSet<PostId>,
+    incoming_friend_requests: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    outgoing_friend_requests: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    blocked_accounts: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    muted_accounts: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    content_reports: LookupMap<ReportedContent, UnorderedMap<AccountId, ReportReason>>,
+    reported_contents: UnorderedSet<ReportedContent>,
+    moderators: LookupSet<AccountId>,
+    banned_accounts: LookupMap<AccountId, Option<u64>>,
+    credential_nfts: UnorderedMap<AccountId, u8>,
+    accounts_verifications: LookupMap<AccountId, AccountVerification>,
+    deleting_posts: LookupSet<PostId>,
+    posts_reposts: LookupMap<PostId, UnorderedSet<AccountId>>,
+    announcements: UnorderedMap<u64, Announcement>,
+    next_announcement_id: u64,
+    accounts_mentions: LookupMap<AccountId, Vector<MessageId>>,
+    all_posts: UnorderedSet<PostId>,
+    accounts_messages: LookupMap<AccountId, Vector<MessageId>>,
+    posts_message_likes_counts: LookupMap<PostId, u64>,
+    accounts_likes_history: LookupMap<AccountId, Vector<AccountLike>>,
+    posts_owners: LookupMap<PostId, AccountId>,
+    posts_pending_owners: LookupMap<PostId, AccountId>,
+    posts_messages_replies: LookupMap<MessageId, Vector<u64>>,
+    probation_policy: ProbationPolicy,
+    posts_reactions: LookupMap<PostId, Reactions>,
+    posts_messages_reactions: LookupMap<MessageId, Reactions>,
+    posts_super_likes: LookupMap<PostId, SuperLikes>,
+    messages_tips: LookupMap<MessageId, u128>,
+    messages_near_tips: LookupMap<MessageId, u128>,
+    accounts_near_tips: LookupMap<AccountId, NearTips>,
+    near_tip_cut_percent: u8,
+    near_tips_cut_balance: u128,
+    posts_stats: LookupMap<PostId, PostStats>,
+    leaderboards: LookupMap<LeaderboardKind, Vec<(AccountId, u64)>>,
+    leaderboard_size: u8,
+    accounts_notifications: LookupMap<AccountId, NotificationsLog>,
+    conversations: LookupMap<ConversationId, Vector<DirectMessage>>,
+    accounts_conversations: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    deleted_posts: LookupSet<PostId>,
+    marketplace: Option<AccountId>,
 }
 
 #[derive(BorshStorageKey, BorshSerialize)]
@@ -44,6 +145,76 @@ pub enum StorageKeys {
     AccountFriends { account_id: Vec<u8> },
     AccountsProfiles,
     AccountProfileImage { account_id: Vec<u8> },
+    AccountsIdentities,
+    AccountsLinks,
+    AccountLinks { account_id: Vec<u8> },
+    AccountsLinkRequests,
+    PostsAuthorsMessages,
+    PostAuthorMessages { post_id: Vec<u8>, account_id: Vec<u8> },
+    ModerationLog,
+    AccountsAppKeys,
+    AccountsBadges,
+    AccountBadges { account_id: Vec<u8> },
+    PostsScores,
+    TrendingPosts,
+    PostingDelegates,
+    AccountsWalls,
+    AccountWall { account_id: Vec<u8> },
+    ReversalCounters,
+    AccountsWithFriends,
+    PostsContinuations,
+    PendingOperations,
+    AccountsReports,
+    SensitivePosts,
+    IncomingFriendRequests,
+    AccountIncomingFriendRequests { account_id: Vec<u8> },
+    OutgoingFriendRequests,
+    AccountOutgoingFriendRequests { account_id: Vec<u8> },
+    BlockedAccounts,
+    AccountBlockedAccounts { account_id: Vec<u8> },
+    MutedAccounts,
+    AccountMutedAccounts { account_id: Vec<u8> },
+    ContentReports,
+    ContentReporters { content_hash: Vec<u8> },
+    ReportedContents,
+    Moderators,
+    BannedAccounts,
+    CredentialNfts,
+    AccountsVerifications,
+    DeletingPosts,
+    PostsReposts,
+    PostReposts { post_id: Vec<u8> },
+    Announcements,
+    AccountsMentions,
+    AccountMentions { account_id: Vec<u8> },
+    AllPosts,
+    AccountsMessages,
+    AccountMessages { account_id: Vec<u8> },
+    PostsMessageLikesCounts,
+    AccountsLikesHistory,
+    AccountLikesHistory { account_id: Vec<u8> },
+    PostsOwners,
+    PostsPendingOwners,
+    PostsMessagesReplies,
+    PostMessageReplies { post_id: Vec<u8>, msg_idx: u64 },
+    PostsReactions,
+    PostReactions { post_id: Vec<u8> },
+    PostsMessagesReactions,
+    PostMessageReactions { post_id: Vec<u8>, msg_idx: u64 },
+    PostsSuperLikes,
+    PostSuperLikes { post_id: Vec<u8> },
+    MessagesTips,
+    MessagesNearTips,
+    AccountsNearTips,
+    PostsStats,
+    Leaderboards,
+    AccountsNotifications,
+    AccountNotifications { account_id: Vec<u8> },
+    Conversations,
+    Conversation { conversation_hash: Vec<u8> },
+    AccountsConversations,
+    AccountConversations { account_id: Vec<u8> },
+    DeletedPosts,
 }
 
 
@@ -60,7 +231,47 @@ pub struct StorageUsageSettings {
     account_friends_collection_size: StorageUsage,
     min_account_profile_size: StorageUsage,
     min_account_recent_like_size: StorageUsage,
-    account_recent_likes_collection_size: StorageUsage
+    account_recent_likes_collection_size: StorageUsage,
+    min_account_link_request_size: StorageUsage,
+    min_account_link_size: StorageUsage,
+    account_links_collection_size: StorageUsage,
+    author_messages_collection_size: StorageUsage,
+    min_app_key_size: StorageUsage,
+    min_account_badge_size: StorageUsage,
+    account_badges_collection_size: StorageUsage,
+    min_post_score_size: StorageUsage,
+    min_posting_delegate_size: StorageUsage,
+    min_wall_message_size: StorageUsage,
+    wall_messages_collection_size: StorageUsage,
+    min_reversal_counter_size: StorageUsage,
+    min_post_continuation_size: StorageUsage,
+    min_friend_request_size: StorageUsage,
+    friend_requests_collection_size: StorageUsage,
+    min_content_report_size: StorageUsage,
+    content_reports_collection_size: StorageUsage,
+    min_account_verification_size: StorageUsage,
+    min_account_mention_size: StorageUsage,
+    account_mentions_collection_size: StorageUsage,
+    account_messages_collection_size: StorageUsage,
+    min_post_message_likes_count_size: StorageUsage,
+    min_account_like_history_size: StorageUsage,
+    account_likes_history_collection_size: StorageUsage,
+    min_post_owner_size: StorageUsage,
+    min_message_reply_size: StorageUsage,
+    message_replies_collection_size: StorageUsage,
+    min_post_reaction_size: StorageUsage,
+    post_reactions_collection_size: StorageUsage,
+    min_message_reaction_size: StorageUsage,
+    message_reactions_collection_size: StorageUsage,
+    min_post_super_like_size: StorageUsage,
+    post_super_likes_collection_size: StorageUsage,
+    min_message_tips_size: StorageUsage,
+    min_message_near_tips_size: StorageUsage,
+    min_account_near_tips_size: StorageUsage,
+    min_notification_size: StorageUsage,
+    notifications_collection_size: StorageUsage,
+    min_direct_message_size: StorageUsage,
+    conversation_collection_size: StorageUsage
 }
 
 type PostId = String;
@@ -71,9 +282,29 @@ pub struct MessageId {
     msg_idx: u64
 }
 
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PostAuthorId {
+    post_id: PostId,
+    account_id: AccountId
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub enum MessagePayload {
-    Text { text: String }
+    Text { text: String },
+    Archived { cid: String, text_hash: Vec<u8> },
+    Listing { marketplace: AccountId, listing_id: String },
+    Deleted,
+    Quote { quoted_post_id: PostId, quoted_msg_idx: Option<u64>, text: String },
+    Media { text: String, media_cid: String, mime_type: String },
+    Link { url: String, title: Option<String>, description: Option<String> },
+    HiddenByAuthor
+}
+
+impl MessagePayload {
+    // Message removed by its author, a moderator or the post owner ('HiddenByAuthor')
+    fn is_deleted(&self) -> bool {
+        matches!(self, MessagePayload::Deleted | MessagePayload::HiddenByAuthor)
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -82,11 +313,184 @@ pub struct Message {
     parent_idx: Option<u64>,
     payload: MessagePayload,
     timestamp: u64,
+    edited_at: Option<u64>,
+    delegate: Option<AccountId>,
+    lang: Option<String>,
+    sensitive: Option<SensitiveMarker>
+}
+
+// Who marked the message as sensitive. Only the author's own mark can be removed by the author
+#[derive(BorshDeserialize, BorshSerialize, PartialEq)]
+pub enum SensitiveMarker {
+    Author,
+    Moderator
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct WallMessage {
+    account: AccountId,
+    text: String,
+    timestamp: u64
+}
+
+#[derive(BorshSerialize)]
+pub enum ReversibleAction {
+    PostLike { account_id: AccountId, post_id: PostId },
+    MessageLike { account_id: AccountId, msg_id: MessageId },
+    Friend { account_id: AccountId, friend_id: AccountId }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ReversalCounter {
+    day: u64,
+    count: u8
+}
+
+// Absolute index of the next item, so items appended between pages do not shift the next page
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PageCursor {
+    next_index: u64,
+    reverse: bool
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct AccountStats {
-    recent_likes: Vec<AccountLike>
+    recent_likes: Vec<AccountLike>,
+    current_streak: u32,
+    longest_streak: u32,
+    last_active_day: u64,
+    first_seen_at: u64,
+    day_actions_count: u32,
+    messages_count: u64,
+    likes_given: u64,
+    likes_received: u64,
+    tips_received: u128
+}
+
+// 'Like' is the like of the post or message, the other reactions are stored in 'Reactions'
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Reaction {
+    Like,
+    Fire,
+    Laugh,
+    Wow,
+    Sad,
+    Clap
+}
+
+impl Reaction {
+    const ALL: [Reaction; 6] = [Reaction::Like, Reaction::Fire, Reaction::Laugh, Reaction::Wow, Reaction::Sad, Reaction::Clap];
+
+    fn index(&self) -> usize {
+        Reaction::ALL.iter().position(|reaction| reaction == self).unwrap()
+    }
+
+    fn emoji(&self) -> &'static str {
+        match self {
+            Reaction::Like => "\u{2764}",
+            Reaction::Fire => "\u{1f525}",
+            Reaction::Laugh => "\u{1f602}",
+            Reaction::Wow => "\u{1f62e}",
+            Reaction::Sad => "\u{1f622}",
+            Reaction::Clap => "\u{1f44f}"
+        }
+    }
+}
+
+// Reaction of every account with the number of accounts per reaction (indexed as 'Reaction::ALL')
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Reactions {
+    accounts: UnorderedMap<AccountId, Reaction>,
+    counts: Vec<u64>
+}
+
+impl Reactions {
+    fn add(&mut self, account_id: &AccountId, reaction: Reaction) {
+        if let Some(previous) = self.accounts.insert(account_id, &reaction) {
+            self.counts[previous.index()] -= 1;
+        };
+        self.counts[reaction.index()] += 1;
+    }
+
+    fn remove(&mut self, account_id: &AccountId) -> Option<Reaction> {
+        let reaction = self.accounts.remove(account_id)?;
+        self.counts[reaction.index()] -= 1;
+        Some(reaction)
+    }
+}
+
+// Weight of every super like of a post, the weight is the fee multiplier at the time of the like
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SuperLikes {
+    accounts: UnorderedMap<AccountId, u8>,
+    total_weight: u64
+}
+
+// Counters kept up to date by the execute paths. 'messages_count' excludes deleted messages,
+// 'unique_commenters' counts every account that has added a message to the post
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct PostStats {
+    messages_count: u64,
+    likes_count: u64,
+    unique_commenters: u64,
+    pinned_msg_idx: Option<u64>,
+    comments_locked: bool
+}
+
+// NEAR tips sent and received by an account
+#[derive(BorshDeserialize, BorshSerialize, Default)]
+pub struct NearTips {
+    sent: u128,
+    received: u128
+}
+
+// New accounts ('days' since the first action or without a profile) pay 'fee_multiplier_percent' of every fee
+// and are limited to 'max_daily_actions' paid calls a day. 'days' = 0 disables the probation
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProbationPolicy {
+    days: u32,
+    fee_multiplier_percent: u16,
+    max_daily_actions: u32
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Copy, Clone)]
+pub struct PostScore {
+    score: u128,
+    updated_at: u64
+}
+
+// 'MostLiked' ranks accounts by the likes received, 'MostActive' by the messages authored
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum LeaderboardKind {
+    MostLiked,
+    MostActive
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Badge {
+    Streak { days: u32 }
+}
+
+// Verification is granted by holding a token of an allowlisted credential NFT contract
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountVerification {
+    tier: u8,
+    contract_id: AccountId,
+    token_id: String,
+    verified_at: u64
+}
+
+// Announcements are capped by MAX_ANNOUNCEMENTS, so their storage is covered by the contract
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Announcement {
+    text: String,
+    created_at: u64,
+    expires_at: Option<u64>,
+    pinned: bool
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -95,12 +499,141 @@ pub enum AccountLike {
     MessageLike { msg_id: MessageId }
 }
 
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum NotificationKind {
+    PostComment { msg_id: MessageID },
+    MessageReply { msg_id: MessageID },
+    PostLike { post_id: PostId },
+    MessageLike { msg_id: MessageID },
+    FriendAdded
+}
+
+impl NotificationKind {
+    fn get_post_id(&self) -> Option<&PostId> {
+        match self {
+            NotificationKind::PostComment { msg_id } | NotificationKind::MessageReply { msg_id } | NotificationKind::MessageLike { msg_id } => Some(&msg_id.post_id),
+            NotificationKind::PostLike { post_id } => Some(post_id),
+            NotificationKind::FriendAdded => None
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Notification {
+    kind: NotificationKind,
+    actor: AccountId,
+    timestamp: u64
+}
+
+// Ring buffer of the last 'MAX_NOTIFICATIONS' notifications. 'total' counts all notifications
+// ever added, the one with sequence number 'n' is stored at 'n % MAX_NOTIFICATIONS'
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct NotificationsLog {
+    items: Vector<Notification>,
+    total: u64,
+    read: u64
+}
+
+// Accounts of the conversation are stored in ascending order, so both participants get the same key
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ConversationId {
+    account_a: AccountId,
+    account_b: AccountId
+}
+
+impl ConversationId {
+    fn new(account_id: &AccountId, other_id: &AccountId) -> Self {
+        if account_id < other_id {
+            ConversationId { account_a: account_id.clone(), account_b: other_id.clone() }
+        } else {
+            ConversationId { account_a: other_id.clone(), account_b: account_id.clone() }
+        }
+    }
+}
+
+// 'payload' is the ciphertext encrypted by the sender's client, the contract never reads it
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct DirectMessage {
+    sender: AccountId,
+    payload: Vec<u8>,
+    timestamp: u64
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct AccountProfile {
     json_metadata: String,
     image: LazyOption<Vec<u8>>,
     current_image_len: u64,
-    image_url: String
+    image_url: String,
+    likes_visibility: LikesVisibility,
+    wall_policy: WallPolicy
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum LikesVisibility {
+    Public,
+    Private
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum WallPolicy {
+    Everyone,
+    Friends,
+    Nobody
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PostingDelegate {
+    account_id: AccountId,
+    delegate_id: AccountId
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountAppKey {
+    account_id: AccountId,
+    public_key: PublicKey
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum AppScope {
+    Messages,
+    Likes,
+    Friends,
+    Profile,
+    Identity
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ModerationRecord {
+    actor: AccountId,
+    action: ModerationAction,
+    target: ModerationTarget,
+    reason_hash: Option<Vec<u8>>,
+    timestamp: u64
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ModerationAction {
+    Archive,
+    MarkSensitive,
+    RemoveMessage,
+    ClearPost,
+    Ban,
+    Unban,
+    DeletePost
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ModerationTarget {
+    Post { post_id: PostId },
+    Message { msg_id: MessageID },
+    Account { account_id: AccountId }
 }
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
@@ -112,7 +645,15 @@ pub struct AdminSettings {
     like_message_extra_fee_percent: u8,
     add_friend_extra_fee_percent: u8,
     update_profile_extra_fee_percent: u8,
-    account_recent_like_extra_fee_percent: u8
+    account_recent_like_extra_fee_percent: u8,
+    archive_refund_percent: u8,
+    message_edit_window_sec: u32,
+    max_daily_reversals: u8,
+    max_post_messages: u32,
+    sensitive_reports_threshold: u32,
+    super_like_fee_multiplier: u8,
+    max_media_cid_len: u8,
+    max_media_mime_type_len: u8
 }
 
 impl PartialEq for AccountLike {
@@ -141,11 +682,14 @@ impl PartialEq for MessageId {
 
 impl Eq for MessageId {}
 
-#[derive(Serialize, Deserialize)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Call {
-    AddMessageToPost { post_id: PostId, text: String },
-    AddMessageToMessage { parent_msg_id: MessageID, text: String },
+    AddMessageToPost { post_id: PostId, text: String, delegate: Option<AccountId>, lang: Option<String>, sensitive: bool },
+    AddListingToPost { post_id: PostId, marketplace: AccountId, listing_id: String },
+    AddQuoteToPost { post_id: PostId, quoted_post_id: PostId, quoted_msg_idx: Option<U64>, text: String },
+    AddMessageToMessage { parent_msg_id: MessageID, text: String, delegate: Option<AccountId>, lang: Option<String>, sensitive: bool },
+    EditMessage { msg_id: MessageID, text: String },
     AddFriend { friend_id: AccountId },
     LikePost { post_id: PostId },
     UnlikePost { post_id: PostId },
@@ -153,15 +697,218 @@ pub enum Call {
     UnlikeMessage { msg_id: MessageID },
     UpdateProfile { profile: AccountProfileData },
     RemoveFriend { friend_id: AccountId },
+    ImportFriends { friends: Vec<AccountId> },
+    RequestAccountLink { account_id: AccountId },
+    AcceptAccountLink { primary_id: AccountId },
+    UnlinkAccount { account_id: AccountId },
+    RegisterAppKey { public_key: PublicKey, scopes: Vec<AppScope> },
+    UnregisterAppKey { public_key: PublicKey },
+    GrantPostingDelegate { delegate_id: AccountId },
+    RevokePostingDelegate { delegate_id: AccountId },
+    PostOnWall { target_account: AccountId, text: String },
+    DeleteMessage { msg_id: MessageID },
+    SetMessageSensitive { msg_id: MessageID, sensitive: bool },
+    SendFriendRequest { friend_id: AccountId },
+    AcceptFriendRequest { requester_id: AccountId },
+    DeclineFriendRequest { requester_id: AccountId },
+    BlockAccount { blocked_id: AccountId },
+    UnblockAccount { blocked_id: AccountId },
+    MuteAccount { muted_id: AccountId },
+    UnmuteAccount { muted_id: AccountId },
+    ReportMessage { msg_id: MessageID, reason: ReportReason },
+    ReportPost { post_id: PostId, reason: ReportReason },
+    VerifyAccount { contract_id: AccountId, token_id: String, tier: u8 },
+    Repost { post_id: PostId },
+    EnableLikesHistory,
+    DisableLikesHistory,
+    TransferPost { post_id: PostId, new_owner: AccountId },
+    AcceptPostTransfer { post_id: PostId },
+    CancelPostTransfer { post_id: PostId },
+    Batch { calls: Vec<BatchCall> },
+    ReactToPost { post_id: PostId, reaction: Reaction },
+    ReactToMessage { msg_id: MessageID, reaction: Reaction },
+    RemovePostReaction { post_id: PostId },
+    RemoveMessageReaction { msg_id: MessageID },
+    SuperLikePost { post_id: PostId },
+    TipMessage { msg_id: MessageID, amount: U128 },
+    TipMessageNear { msg_id: MessageID, amount: U128 },
+    AddMediaToPost { post_id: PostId, text: String, media_cid: String, mime_type: String },
+    AddLinkToPost { post_id: PostId, url: String, title: Option<String>, description: Option<String> },
+    RemoveMessageFromOwnPost { msg_id: MessageID },
+    MarkNotificationsRead,
+    SendDirectMessage { recipient_id: AccountId, payload: Base64VecU8 },
+}
+
+// Actions which can be combined into one 'batch' call. Messages are written by the caller itself
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BatchCall {
+    AddMessageToPost { post_id: PostId, text: String, lang: Option<String>, sensitive: Option<bool> },
+    AddMessageToMessage { parent_msg_id: MessageID, text: String, lang: Option<String>, sensitive: Option<bool> },
+    LikePost { post_id: PostId },
+    LikeMessage { msg_id: MessageID },
+}
+
+impl From<BatchCall> for Call {
+    fn from(batch_call: BatchCall) -> Self {
+        match batch_call {
+            BatchCall::AddMessageToPost { post_id, text, lang, sensitive } => {
+                Call::AddMessageToPost { post_id, text, delegate: None, lang, sensitive: sensitive.unwrap_or(false) }
+            },
+            BatchCall::AddMessageToMessage { parent_msg_id, text, lang, sensitive } => {
+                Call::AddMessageToMessage { parent_msg_id, text, delegate: None, lang, sensitive: sensitive.unwrap_or(false) }
+            },
+            BatchCall::LikePost { post_id } => Call::LikePost { post_id },
+            BatchCall::LikeMessage { msg_id } => Call::LikeMessage { msg_id }
+        }
+    }
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct MessageID {
     post_id: PostId,
     msg_idx: U64
 }
 
+// Data of 'message_added' event, 'parent_msg_id' is set for replies
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageAddedEventDTO {
+    account_id: AccountId,
+    msg_id: MessageID,
+    parent_msg_id: Option<MessageID>
+}
+
+// Data of 'post_liked' and 'post_unliked' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostLikeEventDTO {
+    account_id: AccountId,
+    post_id: PostId
+}
+
+// Data of 'message_liked' and 'message_unliked' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageLikeEventDTO {
+    account_id: AccountId,
+    msg_id: MessageID
+}
+
+// Data of 'post_reposted' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostRepostedEventDTO {
+    account_id: AccountId,
+    post_id: PostId
+}
+
+// Data of 'post_transferred' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostTransferredEventDTO {
+    post_id: PostId,
+    old_owner: AccountId,
+    new_owner: AccountId
+}
+
+// Data of 'message_pinned' and 'message_unpinned' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessagePinnedEventDTO {
+    account_id: AccountId,
+    post_id: PostId,
+    msg_idx: U64
+}
+
+// Data of 'post_comments_locked' and 'post_comments_unlocked' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostCommentsLockedEventDTO {
+    account_id: AccountId,
+    post_id: PostId
+}
+
+// Data of 'post_super_liked' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostSuperLikeEventDTO {
+    account_id: AccountId,
+    post_id: PostId,
+    weight: u8
+}
+
+// Data of 'message_tipped' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageTippedEventDTO {
+    account_id: AccountId,
+    msg_id: MessageID,
+    author_id: AccountId,
+    amount: U128
+}
+
+// Data of 'message_tipped_near' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageTippedNearEventDTO {
+    account_id: AccountId,
+    msg_id: MessageID,
+    author_id: AccountId,
+    amount: U128,
+    platform_cut: U128
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NearTipsDTO {
+    sent: U128,
+    received: U128
+}
+
+// Data of 'post_reacted' and 'post_reaction_removed' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostReactionEventDTO {
+    account_id: AccountId,
+    post_id: PostId,
+    reaction: Reaction
+}
+
+// Data of 'message_reacted' and 'message_reaction_removed' events
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageReactionEventDTO {
+    account_id: AccountId,
+    msg_id: MessageID,
+    reaction: Reaction
+}
+
+// Data of 'friend_added' event, emitted for every added direction of the friendship
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FriendAddedEventDTO {
+    account_id: AccountId,
+    friend_id: AccountId
+}
+
+// Data of 'direct_message_sent' event, the payload is not logged
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DirectMessageSentEventDTO {
+    account_id: AccountId,
+    recipient_id: AccountId,
+    msg_idx: U64
+}
+
+// Data of 'profile_updated' event
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProfileUpdatedEventDTO {
+    account_id: AccountId
+}
+
 impl From<MessageID> for MessageId {
     fn from(v: MessageID) -> Self {
         MessageId {
@@ -198,12 +945,54 @@ impl From<&MessageId> for MessageID {
     }
 }
 
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum ReportedContent {
+    Post { post_id: PostId },
+    Message { msg_id: MessageId }
+}
+
+impl From<ReportedContent> for ModerationTarget {
+    fn from(v: ReportedContent) -> Self {
+        match v {
+            ReportedContent::Post { post_id } => ModerationTarget::Post { post_id },
+            ReportedContent::Message { msg_id } => ModerationTarget::Message { msg_id: msg_id.into() }
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Copy, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReportReason {
+    Spam,
+    Harassment,
+    Violence,
+    Nudity,
+    Copyright,
+    Other
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReportedContentDTO {
+    target: ModerationTarget,
+    reports_count: U64
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
+pub struct ContentReportDTO {
+    reporter: AccountId,
+    reason: ReportReason
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
 pub struct AccountProfileData {
     json_metadata: Option<String>,
     image: Option<Base64VecU8>,
-    image_url: Option<String>
+    image_url: Option<String>,
+    likes_visibility: Option<LikesVisibility>,
+    wall_policy: Option<WallPolicy>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -215,7 +1004,15 @@ pub struct AdminSettingsData {
     like_message_extra_fee_percent: Option<u8>,
     add_friend_extra_fee_percent: Option<u8>,
     update_profile_extra_fee_percent: Option<u8>,
-    account_recent_like_extra_fee_percent: Option<u8>
+    account_recent_like_extra_fee_percent: Option<u8>,
+    archive_refund_percent: Option<u8>,
+    message_edit_window_sec: Option<u32>,
+    max_daily_reversals: Option<u8>,
+    max_post_messages: Option<u32>,
+    sensitive_reports_threshold: Option<u32>,
+    super_like_fee_multiplier: Option<u8>,
+    max_media_cid_len: Option<u8>,
+    max_media_mime_type_len: Option<u8>
 }
 
 #[derive(Serialize, Deserialize)]
@@ -224,1121 +1021,6648 @@ pub struct MessageDTO {
     msg_idx: U64,
     parent_idx: Option<U64>,
     account: AccountId,
+    delegate: Option<AccountId>,
+    lang: Option<String>,
     text: Option<String>,
+    archive: Option<MessageArchiveDTO>,
+    listing: Option<ListingDTO>,
+    quote: Option<QuoteDTO>,
+    media: Option<MediaDTO>,
+    link: Option<LinkDTO>,
+    sensitive: bool,
     timestamp: U64,
+    edited: bool,
+    edited_at: Option<U64>,
+    deleted: bool,
+    hidden_by_author: bool,
     likes_count: U64
 }
 
 #[derive(Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
-pub struct LikesInfoDTO {
-    likes_count: U64,
-    is_liked: bool
+pub struct ThreadMessageDTO {
+    depth: u32,
+    message: MessageDTO
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PageDTO<T> {
+    items: Vec<T>,
+    next_cursor: Option<Base64VecU8>
+}
 
-#[near_bindgen]
-impl Contract {
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ModerationRecordDTO {
+    actor: AccountId,
+    action: ModerationAction,
+    target: ModerationTarget,
+    reason_hash: Option<Base64VecU8>,
+    timestamp: U64
+}
 
-    #[init]
-    pub fn new(owner: AccountId, fee_ft: AccountId, settings: AdminSettingsData) -> Self {
-        if env::state_exists() == true {
-            env::panic_str("Already initialized");
-        }
-        let mut this = Self {
-            owner,
-            fee_ft,
-            admin_settings: AdminSettings {
-                account_recent_likes_limit: match settings.account_recent_likes_limit {
-                    Some(account_recent_likes_limit) => account_recent_likes_limit,
-                    None => 0
-                },
-                add_message_extra_fee_percent: match settings.add_message_extra_fee_percent {
-                    Some(add_message_extra_fee_percent) => add_message_extra_fee_percent,
-                    None => 0
-                },
-                like_post_extra_fee_percent: match settings.like_post_extra_fee_percent {
-                    Some(like_post_extra_fee_percent) => like_post_extra_fee_percent,
-                    None => 0
-                },
-                like_message_extra_fee_percent: match settings.like_message_extra_fee_percent {
-                    Some(like_message_extra_fee_percent) => like_message_extra_fee_percent,
-                    None => 0
-                },
-                add_friend_extra_fee_percent: match settings.add_friend_extra_fee_percent {
-                    Some(add_friend_extra_fee_percent) => add_friend_extra_fee_percent,
-                    None => 0
-                },
-                update_profile_extra_fee_percent: match settings.update_profile_extra_fee_percent {
-                    Some(update_profile_extra_fee_percent) => update_profile_extra_fee_percent,
-                    None => 0
-                },
-                account_recent_like_extra_fee_percent: match settings.account_recent_like_extra_fee_percent {
-                    Some(account_recent_like_extra_fee_percent) => account_recent_like_extra_fee_percent,
-                    None => 0
-                }
-            },
-            storage_usage_settings: StorageUsageSettings {
-                min_message_size: 0,
-                messages_collection_size: 0,
-                min_post_like_size: 0,
-                post_likes_collection_size: 0,
-                min_message_like_size: 0,
-                message_likes_collection_size: 0,
-                min_account_friend_size: 0,
-                account_friends_collection_size: 0,
-                min_account_profile_size: 0,
-                min_account_recent_like_size: 0,
-                account_recent_likes_collection_size: 0
-            },
-            posts_messages: LookupMap::new(StorageKeys::PostsMessages),
-            posts_likes: LookupMap::new(StorageKeys::PostsLikes),
-            posts_messages_likes: LookupMap::new(StorageKeys::PostsMessagesLikes),
-            accounts_friends: LookupMap::new(StorageKeys::AccountsFriends),
-            accounts_profiles: LookupMap::new(StorageKeys::AccountsProfiles),
-            accounts_stats: LookupMap::new(StorageKeys::AccountsStats)
-        };
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ListingDTO {
+    marketplace: AccountId,
+    listing_id: String
+}
 
-        this.update_storage_usage_settings();
+// Quoted message, or the whole post without 'msg_idx'
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuoteDTO {
+    post_id: PostId,
+    msg_idx: Option<U64>
+}
 
-        this
-    }
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MediaDTO {
+    media_cid: String,
+    mime_type: String
+}
 
-    pub fn add_message_to_post(&mut self, post_id: PostId, text: String) -> Promise {
-        let account_id = env::predecessor_account_id();
-        // log!("add_message_to_post: predecessor_account_id is {}", env::predecessor_account_id());
-        self.assert_add_message_to_post_call(&post_id, &text);
-        let fee = self.calc_add_message_to_post_fee(&account_id, &post_id, &text);
-        // log!("add_message_to_post fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::AddMessageToPost { post_id, text })
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LinkDTO {
+    url: String,
+    title: Option<String>,
+    description: Option<String>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageArchiveDTO {
+    cid: String,
+    text_hash: Base64VecU8
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WallMessageDTO {
+    msg_idx: U64,
+    account: AccountId,
+    text: String,
+    timestamp: U64,
+    pinned: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AnnouncementDTO {
+    announcement_id: U64,
+    text: String,
+    created_at: U64,
+    expires_at: Option<U64>,
+    pinned: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeaderboardEntryDTO {
+    account_id: AccountId,
+    value: U64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TrendingPostDTO {
+    post_id: PostId,
+    score: U128
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountStreakDTO {
+    current_streak: u32,
+    longest_streak: u32,
+    last_active_day: U64,
+    first_seen_at: U64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountStatsDTO {
+    messages_count: U64,
+    likes_given: U64,
+    likes_received: U64,
+    friends_count: U64,
+    tips_received: U128
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LikesInfoDTO {
+    likes_count: U64,
+    is_liked: bool,
+    weighted_likes_count: U64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RepostsInfoDTO {
+    reposts_count: U64,
+    is_reposted: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountsPageDTO {
+    accounts: Vec<AccountId>,
+    next_index: Option<U64>
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageUsageDriftDTO {
+    setting: String,
+    stored: U64,
+    measured: U64,
+    delta: i64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountVerificationDTO {
+    tier: u8,
+    contract_id: AccountId,
+    token_id: String,
+    verified_at: U64
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CredentialNftDTO {
+    contract_id: AccountId,
+    tier: u8
+}
+
+// Only the owner of the 'nft_token' result is read, other token fields are ignored
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CredentialTokenDTO {
+    owner_id: AccountId
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeConversionDTO {
+    fee_ft_amount: U128,
+    near_amount: U128,
+    exchange_rate: U128
+}
+
+// Call which fee is being collected. It is removed once the call is executed, an entry left
+// behind means the execution failed (e.g. ran out of gas) after the fee was charged
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PendingOperation {
+    caller_id: AccountId,
+    payer_id: AccountId,
+    fee: u128,
+    call: Call,
+    block_height: u64,
+    fee_charged: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingOperationDTO {
+    operation_id: U64,
+    caller_id: AccountId,
+    payer_id: AccountId,
+    fee: U128,
+    call: Call,
+    block_height: U64,
+    fee_charged: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostSummaryDTO {
+    post_id: PostId,
+    messages_count: U64,
+    likes_count: U64,
+    message_likes_count: U64,
+    continuation: Option<PostId>,
+    sensitive: bool
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostStatsDTO {
+    messages_count: U64,
+    likes_count: U64,
+    unique_commenters: U64,
+    pinned_msg_idx: Option<U64>,
+    comments_locked: bool
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DirectMessageDTO {
+    msg_idx: U64,
+    sender: AccountId,
+    payload: Base64VecU8,
+    timestamp: U64
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConversationDTO {
+    account_id: AccountId,
+    messages_count: U64,
+    last_message_at: Option<U64>
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NotificationDTO {
+    idx: U64,
+    kind: NotificationKind,
+    actor: AccountId,
+    timestamp: U64,
+    is_read: bool
+}
+
+
+#[near_bindgen]
+impl Contract {
+
+    #[init]
+    pub fn new(owner: AccountId, fee_ft: AccountId, settings: AdminSettingsData) -> Self {
+        if env::state_exists() {
+            ContractError::AlreadyInitialized.panic();
+        }
+        let mut this = Self {
+            ownership: Ownership::new(owner),
+            fee_ft,
+            config_contract: None,
+            cached_config: CachedConfig::new(ACTIVITY_FT_EXCHANGE_RATE),
+            admin_settings: AdminSettings {
+                account_recent_likes_limit: settings.account_recent_likes_limit.unwrap_or_default(),
+                add_message_extra_fee_percent: settings.add_message_extra_fee_percent.unwrap_or_default(),
+                like_post_extra_fee_percent: settings.like_post_extra_fee_percent.unwrap_or_default(),
+                like_message_extra_fee_percent: settings.like_message_extra_fee_percent.unwrap_or_default(),
+                add_friend_extra_fee_percent: settings.add_friend_extra_fee_percent.unwrap_or_default(),
+                update_profile_extra_fee_percent: settings.update_profile_extra_fee_percent.unwrap_or_default(),
+                account_recent_like_extra_fee_percent: settings.account_recent_like_extra_fee_percent.unwrap_or_default(),
+                archive_refund_percent: settings.archive_refund_percent.unwrap_or_default(),
+                message_edit_window_sec: settings.message_edit_window_sec.unwrap_or_default(),
+                max_daily_reversals: settings.max_daily_reversals.unwrap_or_default(),
+                // Applied after the storage measurement, which adds several messages to one post
+                max_post_messages: 0,
+                sensitive_reports_threshold: settings.sensitive_reports_threshold.unwrap_or_default(),
+                super_like_fee_multiplier: settings.super_like_fee_multiplier.unwrap_or_default(),
+                max_media_cid_len: settings.max_media_cid_len.unwrap_or_default(),
+                max_media_mime_type_len: settings.max_media_mime_type_len.unwrap_or_default()
+            },
+            storage_usage_settings: StorageUsageSettings {
+                min_message_size: 0,
+                messages_collection_size: 0,
+                min_post_like_size: 0,
+                post_likes_collection_size: 0,
+                min_message_like_size: 0,
+                message_likes_collection_size: 0,
+                min_account_friend_size: 0,
+                account_friends_collection_size: 0,
+                min_account_profile_size: 0,
+                min_account_recent_like_size: 0,
+                account_recent_likes_collection_size: 0,
+                min_account_link_request_size: 0,
+                min_account_link_size: 0,
+                account_links_collection_size: 0,
+                author_messages_collection_size: 0,
+                min_app_key_size: 0,
+                min_account_badge_size: 0,
+                account_badges_collection_size: 0,
+                min_post_score_size: 0,
+                min_posting_delegate_size: 0,
+                min_wall_message_size: 0,
+                wall_messages_collection_size: 0,
+                min_reversal_counter_size: 0,
+                min_post_continuation_size: 0,
+                min_friend_request_size: 0,
+                friend_requests_collection_size: 0,
+                min_content_report_size: 0,
+                content_reports_collection_size: 0,
+                min_account_verification_size: 0,
+                min_account_mention_size: 0,
+                account_mentions_collection_size: 0,
+                account_messages_collection_size: 0,
+                min_post_message_likes_count_size: 0,
+                min_account_like_history_size: 0,
+                account_likes_history_collection_size: 0,
+                min_post_owner_size: 0,
+                min_message_reply_size: 0,
+                message_replies_collection_size: 0,
+                min_post_reaction_size: 0,
+                post_reactions_collection_size: 0,
+                min_message_reaction_size: 0,
+                message_reactions_collection_size: 0,
+                min_post_super_like_size: 0,
+                post_super_likes_collection_size: 0,
+                min_message_tips_size: 0,
+                min_message_near_tips_size: 0,
+                min_account_near_tips_size: 0,
+                min_notification_size: 0,
+                notifications_collection_size: 0,
+                min_direct_message_size: 0,
+                conversation_collection_size: 0
+            },
+            posts_messages: LookupMap::new(StorageKeys::PostsMessages),
+            posts_authors_messages: LookupMap::new(StorageKeys::PostsAuthorsMessages),
+            posts_likes: LookupMap::new(StorageKeys::PostsLikes),
+            posts_messages_likes: LookupMap::new(StorageKeys::PostsMessagesLikes),
+            accounts_friends: LookupMap::new(StorageKeys::AccountsFriends),
+            accounts_profiles: LookupMap::new(StorageKeys::AccountsProfiles),
+            accounts_stats: LookupMap::new(StorageKeys::AccountsStats),
+            accounts_identities: LookupMap::new(StorageKeys::AccountsIdentities),
+            accounts_links: LookupMap::new(StorageKeys::AccountsLinks),
+            accounts_link_requests: LookupMap::new(StorageKeys::AccountsLinkRequests),
+            moderation_log: Vector::new(StorageKeys::ModerationLog),
+            accounts_app_keys: LookupMap::new(StorageKeys::AccountsAppKeys),
+            accounts_badges: LookupMap::new(StorageKeys::AccountsBadges),
+            friends_import_root: None,
+            posts_scores: LookupMap::new(StorageKeys::PostsScores),
+            trending_posts: LazyOption::new(StorageKeys::TrendingPosts, Some(&Vec::new())),
+            posting_delegates: LookupMap::new(StorageKeys::PostingDelegates),
+            accounts_walls: LookupMap::new(StorageKeys::AccountsWalls),
+            reversal_counters: LookupMap::new(StorageKeys::ReversalCounters),
+            accounts_with_friends: UnorderedSet::new(StorageKeys::AccountsWithFriends),
+            posts_continuations: LookupMap::new(StorageKeys::PostsContinuations),
+            pending_operations: LookupMap::new(StorageKeys::PendingOperations),
+            next_operation_id: 0,
+            accounts_reports: LookupMap::new(StorageKeys::AccountsReports),
+            sensitive_posts: LookupSet::new(StorageKeys::SensitivePosts),
+            incoming_friend_requests: LookupMap::new(StorageKeys::IncomingFriendRequests),
+            outgoing_friend_requests: LookupMap::new(StorageKeys::OutgoingFriendRequests),
+            blocked_accounts: LookupMap::new(StorageKeys::BlockedAccounts),
+            muted_accounts: LookupMap::new(StorageKeys::MutedAccounts),
+            content_reports: LookupMap::new(StorageKeys::ContentReports),
+            reported_contents: UnorderedSet::new(StorageKeys::ReportedContents),
+            moderators: LookupSet::new(StorageKeys::Moderators),
+            banned_accounts: LookupMap::new(StorageKeys::BannedAccounts),
+            credential_nfts: UnorderedMap::new(StorageKeys::CredentialNfts),
+            accounts_verifications: LookupMap::new(StorageKeys::AccountsVerifications),
+            deleting_posts: LookupSet::new(StorageKeys::DeletingPosts),
+            posts_reposts: LookupMap::new(StorageKeys::PostsReposts),
+            announcements: UnorderedMap::new(StorageKeys::Announcements),
+            next_announcement_id: 0,
+            accounts_mentions: LookupMap::new(StorageKeys::AccountsMentions),
+            all_posts: UnorderedSet::new(StorageKeys::AllPosts),
+            accounts_messages: LookupMap::new(StorageKeys::AccountsMessages),
+            posts_message_likes_counts: LookupMap::new(StorageKeys::PostsMessageLikesCounts),
+            accounts_likes_history: LookupMap::new(StorageKeys::AccountsLikesHistory),
+            posts_owners: LookupMap::new(StorageKeys::PostsOwners),
+            posts_pending_owners: LookupMap::new(StorageKeys::PostsPendingOwners),
+            posts_messages_replies: LookupMap::new(StorageKeys::PostsMessagesReplies),
+            probation_policy: ProbationPolicy {
+                days: 0,
+                fee_multiplier_percent: 100,
+                max_daily_actions: 0
+            },
+            posts_reactions: LookupMap::new(StorageKeys::PostsReactions),
+            posts_messages_reactions: LookupMap::new(StorageKeys::PostsMessagesReactions),
+            posts_super_likes: LookupMap::new(StorageKeys::PostsSuperLikes),
+            messages_tips: LookupMap::new(StorageKeys::MessagesTips),
+            messages_near_tips: LookupMap::new(StorageKeys::MessagesNearTips),
+            accounts_near_tips: LookupMap::new(StorageKeys::AccountsNearTips),
+            near_tip_cut_percent: 0,
+            near_tips_cut_balance: 0,
+            posts_stats: LookupMap::new(StorageKeys::PostsStats),
+            leaderboards: LookupMap::new(StorageKeys::Leaderboards),
+            leaderboard_size: 0,
+            accounts_notifications: LookupMap::new(StorageKeys::AccountsNotifications),
+            conversations: LookupMap::new(StorageKeys::Conversations),
+            accounts_conversations: LookupMap::new(StorageKeys::AccountsConversations),
+            deleted_posts: LookupSet::new(StorageKeys::DeletedPosts),
+            marketplace: None
+        };
+
+        this.update_storage_usage_settings();
+        if let Some(max_post_messages) = settings.max_post_messages {
+            this.admin_settings.max_post_messages = max_post_messages;
+        }
+
+        this
+    }
+
+    pub fn add_message_to_post(&mut self, post_id: PostId, text: String, on_behalf_of: Option<AccountId>, lang: Option<String>, sensitive: Option<bool>) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let (account_id, delegate) = self.get_message_author(on_behalf_of);
+        // log!("add_message_to_post: predecessor_account_id is {}", env::predecessor_account_id());
+        self.assert_add_message_to_post_call(&post_id, &text);
+        let lang = self.normalize_lang(lang);
+        let sensitive = sensitive.unwrap_or(false);
+        let payload_len = text.len() + calc_delegate_bytes(&delegate) + calc_lang_bytes(&lang)
+            + calc_sensitive_bytes(&self.get_sensitive_marker(&account_id, sensitive));
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id)
+            + self.calc_mentions_fee(&account_id, &self.get_message_post_id(&post_id).0, &text);
+        // log!("add_message_to_post fee {}", fee);
+        let payer_id = get_message_fee_payer(&account_id, &delegate);
+        self.collect_fee_from_and_execute_call(fee, payer_id, account_id, Call::AddMessageToPost { post_id, text, delegate, lang, sensitive })
+    }
+
+    pub fn add_listing_to_post(&mut self, post_id: PostId, marketplace: AccountId, listing_id: String) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_add_listing_to_post_call(&post_id, &listing_id);
+        // Listing payload stores two strings instead of one
+        let payload_len = marketplace.as_str().len() + listing_id.len() + 4;
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id);
+        // log!("add_listing_to_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AddListingToPost { post_id, marketplace, listing_id })
+    }
+
+    pub fn add_quote_to_post(&mut self, post_id: PostId, quoted_post_id: PostId, quoted_msg_idx: Option<U64>, text: String) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_add_quote_to_post_call(&account_id, &post_id, &quoted_post_id, &quoted_msg_idx, &text);
+        // Quote payload stores the quoted post id and the optional message index in addition to the text
+        let payload_len = text.len() + quoted_post_id.len() + 4 + 1 + if quoted_msg_idx.is_some() { 8 } else { 0 };
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id)
+            + self.calc_mentions_fee(&account_id, &self.get_message_post_id(&post_id).0, &text);
+        // log!("add_quote_to_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AddQuoteToPost { post_id, quoted_post_id, quoted_msg_idx, text })
+    }
+
+    pub fn add_media_to_post(&mut self, post_id: PostId, text: String, media_cid: String, mime_type: String) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_add_media_to_post_call(&post_id, &text, &media_cid, &mime_type);
+        // Media payload stores the CID and the MIME type in addition to the text
+        let payload_len = text.len() + media_cid.len() + mime_type.len() + 8;
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id)
+            + self.calc_mentions_fee(&account_id, &self.get_message_post_id(&post_id).0, &text);
+        // log!("add_media_to_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AddMediaToPost { post_id, text, media_cid, mime_type })
+    }
+
+    pub fn add_link_to_post(&mut self, post_id: PostId, url: String, title: Option<String>, description: Option<String>) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_add_link_to_post_call(&post_id, &url, &title, &description);
+        // Link payload stores the URL and the optional title and description
+        let payload_len = url.len() + 2
+            + title.as_ref().map_or(0, |title| title.len() + 4)
+            + description.as_ref().map_or(0, |description| description.len() + 4);
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id);
+        // log!("add_link_to_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AddLinkToPost { post_id, url, title, description })
+    }
+
+    pub fn add_message_to_message(&mut self, parent_msg_id: MessageID, text: String, on_behalf_of: Option<AccountId>, lang: Option<String>, sensitive: Option<bool>) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let (account_id, delegate) = self.get_message_author(on_behalf_of);
+        self.assert_add_message_to_message_call(&account_id, &parent_msg_id, &text);
+        let lang = self.normalize_lang(lang);
+        let sensitive = sensitive.unwrap_or(false);
+        let payload_len = text.len() + calc_delegate_bytes(&delegate) + calc_lang_bytes(&lang)
+            + calc_sensitive_bytes(&self.get_sensitive_marker(&account_id, sensitive));
+        let fee = self.calc_add_message_to_message_fee(&account_id, &parent_msg_id, payload_len)
+            + self.calc_post_score_fee(&parent_msg_id.post_id)
+            + self.calc_mentions_fee(&account_id, &parent_msg_id.post_id, &text);
+        // log!("add_message_to_message fee {}", fee);
+        let payer_id = get_message_fee_payer(&account_id, &delegate);
+        self.collect_fee_from_and_execute_call(fee, payer_id, account_id, Call::AddMessageToMessage { parent_msg_id, text, delegate, lang, sensitive })
+    }
+
+    pub fn post_on_wall(&mut self, target_account: AccountId, text: String) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let target_account = self.get_identity(target_account);
+        self.assert_post_on_wall_call(&account_id, &target_account, &text);
+        let fee = self.calc_post_on_wall_fee(&account_id, &target_account, &text);
+        // log!("post_on_wall fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::PostOnWall { target_account, text })
+    }
+
+    // Messages of the accounts muted by 'viewer_id' are skipped, so the page can be shorter than 'limit'.
+    // The first page starts with the pinned announcements, which are not counted in 'limit'
+    pub fn get_wall(&self, account_id: AccountId, from_index: U64, limit: U64, viewer_id: Option<AccountId>) -> Vec<WallMessageDTO> {
+        let from = u64::from(from_index);
+        let lim = u64::from(limit);
+        let mut wall_messages = if from == 0 {
+            self.get_pinned_wall_announcements()
+        } else {
+            Vec::new()
+        };
+
+        if let Some(account_wall) = self.accounts_walls.get(&self.get_identity(account_id)) {
+            let muted_accounts = self.get_viewer_muted_accounts(viewer_id);
+
+            wall_messages.extend((from..std::cmp::min(from + lim, account_wall.len()))
+                .map(|idx| (idx, account_wall.get(idx).unwrap()))
+                .filter(|(_, msg)| !is_muted_author(&muted_accounts, &msg.account))
+                .map(|(idx, msg)| {
+                    WallMessageDTO {
+                        msg_idx: U64(idx),
+                        account: msg.account,
+                        text: msg.text,
+                        timestamp: U64(msg.timestamp),
+                        pinned: false
+                    }
+                }));
+        };
+        wall_messages
+    }
+
+    pub fn send_direct_message(&mut self, recipient_id: AccountId, encrypted_payload: Base64VecU8) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let recipient_id = self.get_identity(recipient_id);
+        self.assert_send_direct_message_call(&account_id, &recipient_id, &encrypted_payload);
+        let fee = self.calc_send_direct_message_fee(&account_id, &recipient_id, encrypted_payload.0.len());
+        // log!("send_direct_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::SendDirectMessage { recipient_id, payload: encrypted_payload })
+    }
+
+    // Messages are returned in the order they were sent. Contract state is public, so the payloads
+    // are readable by anyone and only the client-side encryption keeps them private
+    pub fn get_conversation(&self, account_a: AccountId, account_b: AccountId, from_index: U64, limit: U64) -> Vec<DirectMessageDTO> {
+        let conversation_id = ConversationId::new(&self.get_identity(account_a), &self.get_identity(account_b));
+        match self.conversations.get(&conversation_id) {
+            Some(conversation) => {
+                let from = u64::from(from_index);
+                let lim = u64::from(limit);
+                (from..std::cmp::min(from + lim, conversation.len()))
+                    .map(|idx| {
+                        let msg = conversation.get(idx).unwrap();
+                        DirectMessageDTO {
+                            msg_idx: U64(idx),
+                            sender: msg.sender,
+                            payload: Base64VecU8(msg.payload),
+                            timestamp: U64(msg.timestamp)
+                        }
+                    })
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    // Conversations are listed in the order they were started
+    pub fn get_conversations(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<ConversationDTO> {
+        let account_id = self.get_identity(account_id);
+        get_accounts_set_page(self.accounts_conversations.get(&account_id), from_index, limit, false)
+            .into_iter()
+            .map(|other_id| {
+                let conversation = self.conversations.get(&ConversationId::new(&account_id, &other_id));
+                ConversationDTO {
+                    messages_count: U64(conversation.as_ref().map_or(0, |conversation| conversation.len())),
+                    last_message_at: conversation
+                        .and_then(|conversation| conversation.get(conversation.len().checked_sub(1)?))
+                        .map(|msg| U64(msg.timestamp)),
+                    account_id: other_id
+                }
+            })
+            .collect()
+    }
+
+    // Pinned announcement is posted by the contract account, its 'msg_idx' is the announcement id
+    fn get_pinned_wall_announcements(&self) -> Vec<WallMessageDTO> {
+        self.announcements.iter()
+            .filter(|(_, announcement)| announcement.pinned && is_announcement_active(announcement))
+            .map(|(announcement_id, announcement)| WallMessageDTO {
+                msg_idx: U64(announcement_id),
+                account: env::current_account_id(),
+                text: announcement.text,
+                timestamp: U64(announcement.created_at),
+                pinned: true
+            })
+            .collect()
+    }
+
+    // 'expires_at' is a timestamp in nanoseconds, the announcement stays active until removed without it.
+    // Expired announcements are removed to make room for the new one
+    pub fn create_announcement(&mut self, text: String, expires_at: Option<U64>, pinned: Option<bool>) -> U64 {
+        self.assert_owner();
+        self.assert_text(&text);
+        let expires_at = expires_at.map(u64::from);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= env::block_timestamp() {
+                ContractError::InvalidAnnouncementExpiry.panic();
+            };
+        };
+
+        let expired_ids: Vec<u64> = self.announcements.iter()
+            .filter(|(_, announcement)| !is_announcement_active(announcement))
+            .map(|(announcement_id, _)| announcement_id)
+            .collect();
+        for announcement_id in expired_ids {
+            self.announcements.remove(&announcement_id);
+        }
+        if self.announcements.len() >= MAX_ANNOUNCEMENTS {
+            ContractError::AnnouncementsLimitReached.panic();
+        };
+
+        let announcement_id = self.next_announcement_id;
+        self.next_announcement_id += 1;
+        self.announcements.insert(&announcement_id, &Announcement {
+            text,
+            created_at: env::block_timestamp(),
+            expires_at,
+            pinned: pinned.unwrap_or(false)
+        });
+        U64(announcement_id)
+    }
+
+    pub fn remove_announcement(&mut self, announcement_id: U64) {
+        self.assert_owner();
+        if self.announcements.remove(&u64::from(announcement_id)).is_none() {
+            ContractError::AnnouncementNotFound.panic();
+        };
+    }
+
+    pub fn get_active_announcements(&self) -> Vec<AnnouncementDTO> {
+        self.announcements.iter()
+            .filter(|(_, announcement)| is_announcement_active(announcement))
+            .map(|(announcement_id, announcement)| AnnouncementDTO {
+                announcement_id: U64(announcement_id),
+                text: announcement.text,
+                created_at: U64(announcement.created_at),
+                expires_at: announcement.expires_at.map(U64),
+                pinned: announcement.pinned
+            })
+            .collect()
+    }
+
+    pub fn grant_posting_delegate(&mut self, delegate_id: AccountId) -> Promise {
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_grant_posting_delegate_call(&account_id, &delegate_id);
+        let fee = self.calc_grant_posting_delegate_fee(&account_id, &delegate_id);
+        // log!("grant_posting_delegate fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::GrantPostingDelegate { delegate_id })
+    }
+
+    pub fn revoke_posting_delegate(&mut self, delegate_id: AccountId) -> Promise {
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_revoke_posting_delegate_call(&account_id, &delegate_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::RevokePostingDelegate { delegate_id })
+    }
+
+    pub fn is_posting_delegate(&self, account_id: AccountId, delegate_id: AccountId) -> bool {
+        self.posting_delegates.contains_key(&PostingDelegate { account_id: self.get_identity(account_id), delegate_id })
+    }
+
+    // Delegated messages are attributed to the granting account, which also pays for them
+    fn get_message_author(&self, on_behalf_of: Option<AccountId>) -> (AccountId, Option<AccountId>) {
+        match on_behalf_of {
+            Some(account_id) => {
+                let account_id = self.get_identity(account_id);
+                let delegate_id = env::predecessor_account_id();
+                if !self.posting_delegates.contains_key(&PostingDelegate { account_id: account_id.clone(), delegate_id: delegate_id.clone() }) {
+                    ContractError::NotPostingDelegate.panic();
+                };
+                (account_id, Some(delegate_id))
+            },
+            None => (self.get_identity(env::predecessor_account_id()), None)
+        }
+    }
+
+    // Asserts every call as its own method does and sums their fees. A post or message can be liked once per batch,
+    // since the likes are asserted against the state before the batch
+    fn prepare_batch_calls(&self, account_id: &AccountId, batch_calls: Vec<BatchCall>) -> (u128, Vec<BatchCall>) {
+        if batch_calls.is_empty() || batch_calls.len() > MAX_BATCH_CALLS {
+            ContractError::InvalidBatchSize.panic();
+        };
+
+        let mut fee = 0u128;
+        let mut calls = Vec::with_capacity(batch_calls.len());
+        let mut liked_posts: Vec<PostId> = Vec::new();
+        let mut liked_messages: Vec<(PostId, u64)> = Vec::new();
+        for batch_call in batch_calls {
+            match batch_call {
+                BatchCall::AddMessageToPost { post_id, text, lang, sensitive } => {
+                    self.assert_app_scope(AppScope::Messages);
+                    self.assert_add_message_to_post_call(&post_id, &text);
+                    let lang = self.normalize_lang(lang);
+                    let sensitive = sensitive.unwrap_or(false);
+                    let payload_len = text.len() + calc_lang_bytes(&lang)
+                        + calc_sensitive_bytes(&self.get_sensitive_marker(account_id, sensitive));
+                    fee += self.calc_add_payload_to_post_fee(account_id, &post_id, payload_len)
+                        + self.calc_post_score_fee(&post_id)
+                        + self.calc_mentions_fee(account_id, &self.get_message_post_id(&post_id).0, &text);
+                    calls.push(BatchCall::AddMessageToPost { post_id, text, lang, sensitive: Some(sensitive) });
+                },
+                BatchCall::AddMessageToMessage { parent_msg_id, text, lang, sensitive } => {
+                    self.assert_app_scope(AppScope::Messages);
+                    self.assert_add_message_to_message_call(account_id, &parent_msg_id, &text);
+                    let lang = self.normalize_lang(lang);
+                    let sensitive = sensitive.unwrap_or(false);
+                    let payload_len = text.len() + calc_lang_bytes(&lang)
+                        + calc_sensitive_bytes(&self.get_sensitive_marker(account_id, sensitive));
+                    fee += self.calc_add_message_to_message_fee(account_id, &parent_msg_id, payload_len)
+                        + self.calc_post_score_fee(&parent_msg_id.post_id)
+                        + self.calc_mentions_fee(account_id, &parent_msg_id.post_id, &text);
+                    calls.push(BatchCall::AddMessageToMessage { parent_msg_id, text, lang, sensitive: Some(sensitive) });
+                },
+                BatchCall::LikePost { post_id } => {
+                    self.assert_app_scope(AppScope::Likes);
+                    self.assert_like_post_call(account_id, &post_id);
+                    if liked_posts.contains(&post_id) {
+                        ContractError::DuplicateBatchLike.panic();
+                    };
+                    fee += self.calc_like_post_fee(account_id, &post_id)
+                        + self.calc_account_recent_likes_fee(account_id, &post_id, false)
+                        + self.calc_account_likes_history_fee(account_id, &post_id, false)
+                        + self.calc_post_score_fee(&post_id);
+                    liked_posts.push(post_id.clone());
+                    calls.push(BatchCall::LikePost { post_id });
+                },
+                BatchCall::LikeMessage { msg_id } => {
+                    self.assert_app_scope(AppScope::Likes);
+                    self.assert_like_message_call(account_id, &msg_id);
+                    let liked_message = (msg_id.post_id.clone(), u64::from(msg_id.msg_idx));
+                    if liked_messages.contains(&liked_message) {
+                        ContractError::DuplicateBatchLike.panic();
+                    };
+                    fee += self.calc_like_message_fee(account_id, &msg_id)
+                        + self.calc_account_recent_likes_fee(account_id, &msg_id.post_id, true)
+                        + self.calc_account_likes_history_fee(account_id, &msg_id.post_id, true)
+                        + self.calc_post_message_likes_count_fee(&msg_id.post_id);
+                    liked_messages.push(liked_message);
+                    calls.push(BatchCall::LikeMessage { msg_id });
+                }
+            }
+        }
+        (fee, calls)
+    }
+
+    // New messages go to the last post of the continuation chain. Once it reaches 'max_post_messages'
+    // a new continuation post is started; the last post is returned as the one to link it from
+    fn get_message_post_id(&self, post_id: &PostId) -> (PostId, Option<PostId>) {
+        let mut last_post_id = post_id.clone();
+        while let Some(next_post_id) = self.posts_continuations.get(&last_post_id) {
+            last_post_id = next_post_id;
+        }
+
+        match self.posts_messages.get(&last_post_id) {
+            Some(post_messages) if self.is_post_full(&post_messages) => {
+                (self.get_continuation_post_id(post_id), Some(last_post_id))
+            },
+            _ => (last_post_id, None)
+        }
+    }
+
+    fn get_continuation_post_id(&self, post_id: &PostId) -> PostId {
+        // Continuation ids are '<post_id>~<n>' with the original id shortened to keep them within 'MAX_POST_ID_LEN'
+        let mut prefix = String::new();
+        for ch in post_id.chars() {
+            if prefix.len() + ch.len_utf8() > MAX_POST_ID_LEN - MAX_CONTINUATION_SUFFIX_LEN {
+                break;
+            }
+            prefix.push(ch);
+        }
+
+        let mut n = 1u32;
+        loop {
+            let continuation_id = format!("{}~{}", prefix, n);
+            if !self.posts_messages.contains_key(&continuation_id) && !self.posts_continuations.contains_key(&continuation_id) {
+                return continuation_id
+            }
+            n += 1;
+        }
+    }
+
+    fn is_post_full(&self, post_messages: &Vector<Message>) -> bool {
+        let max_post_messages = u64::from(self.admin_settings.max_post_messages);
+        max_post_messages != 0 && post_messages.len() >= max_post_messages
+    }
+
+    pub fn edit_message(&mut self, msg_id: MessageID, text: String) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_edit_message_call(&account_id, &msg_id, &text);
+        let fee = self.calc_edit_message_fee(&msg_id, &text);
+        // log!("edit_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::EditMessage { msg_id, text })
+    }
+
+    pub fn delete_message(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_delete_message_call(&account_id, &msg_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::DeleteMessage { msg_id })
+    }
+
+    // Post owner removes a comment under the post, its storage is refunded to the message author
+    pub fn remove_message_from_own_post(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_remove_message_from_own_post_call(&account_id, &msg_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::RemoveMessageFromOwnPost { msg_id })
+    }
+
+    pub fn set_message_sensitive(&mut self, msg_id: MessageID, sensitive: bool) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_set_message_sensitive_call(&account_id, &msg_id, sensitive);
+        self.collect_fee_and_execute_call(1, account_id, Call::SetMessageSensitive { msg_id, sensitive })
+    }
+
+    pub fn mark_sensitive(&mut self, target: ModerationTarget, reason_hash: Option<Base64VecU8>) {
+        self.assert_owner();
+        match &target {
+            ModerationTarget::Post { post_id } => {
+                self.assert_post_id(post_id);
+                self.sensitive_posts.insert(post_id);
+            },
+            ModerationTarget::Message { msg_id } => {
+                self.assert_message_id(msg_id);
+                let mut post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+                let msg_idx = u64::from(msg_id.msg_idx);
+                let mut msg = post_messages.get(msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+                if msg.sensitive != Some(SensitiveMarker::Moderator) {
+                    msg.sensitive = Some(SensitiveMarker::Moderator);
+                    post_messages.replace(msg_idx, &msg);
+                    self.add_account_report(&msg.account);
+                };
+            },
+            ModerationTarget::Account { account_id } => {
+                let account_id = self.get_identity(account_id.clone());
+                self.add_account_report(&account_id);
+            }
+        };
+        self.add_moderation_record(ModerationAction::MarkSensitive, target, reason_hash);
+    }
+
+    pub fn add_moderator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if !self.moderators.insert(&account_id) {
+            ContractError::ModeratorAlreadyAdded.panic();
+        };
+    }
+
+    pub fn remove_moderator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        if !self.moderators.remove(&account_id) {
+            ContractError::ModeratorNotFound.panic();
+        };
+    }
+
+    pub fn is_moderator(&self, account_id: AccountId) -> bool {
+        self.moderators.contains(&account_id)
+    }
+
+    // Post is deleted with its likes, messages and their likes in as many calls as the gas allows.
+    // Returns the number of entries left, the post is deleted when it is 0. Storage is not refunded
+    pub fn delete_post(&mut self, post_id: PostId) -> U64 {
+        self.assert_not_paused();
+        self.assert_post_id(&post_id);
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+
+        // The first message and the owner entry are removed last, so the owner is known until the post is deleted
+        let owner_id = self.get_post_owner_id(&post_id, &post_messages);
+        let caller_id = env::predecessor_account_id();
+        let is_owner = owner_id == Some(self.get_identity(caller_id.clone()));
+        if !is_owner && caller_id != *self.ownership.owner() && !self.moderators.contains(&caller_id) {
+            ContractError::NotPostAuthor.panic();
+        };
+
+        if self.deleting_posts.insert(&post_id) && !is_owner {
+            self.add_moderation_record(ModerationAction::DeletePost, ModerationTarget::Post { post_id: post_id.clone() }, None);
+        };
+
+        U64(self.delete_post_entries(&post_id))
+    }
+
+    // Restricted to the post owner, moderators and the contract owner. The pin replaces the previous one,
+    // its 8 bytes are covered by the contract
+    pub fn pin_message(&mut self, post_id: PostId, msg_idx: U64) {
+        self.assert_not_paused();
+        let msg_id = MessageID { post_id: post_id.clone(), msg_idx };
+        self.assert_message_id(&msg_id);
+        self.assert_message_existence(&msg_id);
+        self.assert_message_not_deleted(&msg_id);
+        self.assert_post_not_being_deleted(&post_id);
+        let account_id = self.assert_post_owner_or_moderator(&post_id);
+
+        let mut post_stats = self.posts_stats.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        post_stats.pinned_msg_idx = Some(msg_idx.into());
+        self.posts_stats.insert(&post_id, &post_stats);
+        log_event("message_pinned", MessagePinnedEventDTO { account_id, post_id, msg_idx });
+    }
+
+    pub fn unpin_message(&mut self, post_id: PostId) {
+        self.assert_not_paused();
+        self.assert_post_id(&post_id);
+        let account_id = self.assert_post_owner_or_moderator(&post_id);
+
+        let mut post_stats = self.posts_stats.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg_idx = post_stats.pinned_msg_idx.take().unwrap_or_else(|| ContractError::MessageNotPinned.panic());
+        self.posts_stats.insert(&post_id, &post_stats);
+        log_event("message_unpinned", MessagePinnedEventDTO { account_id, post_id, msg_idx: U64(msg_idx) });
+    }
+
+    // Restricted to the post owner, moderators and the contract owner. Likes are still allowed
+    pub fn lock_post_comments(&mut self, post_id: PostId) {
+        self.assert_not_paused();
+        self.assert_post_id(&post_id);
+        let account_id = self.assert_post_owner_or_moderator(&post_id);
+        self.set_post_comments_locked(&post_id, true);
+        log_event("post_comments_locked", PostCommentsLockedEventDTO { account_id, post_id });
+    }
+
+    pub fn unlock_post_comments(&mut self, post_id: PostId) {
+        self.assert_not_paused();
+        self.assert_post_id(&post_id);
+        let account_id = self.assert_post_owner_or_moderator(&post_id);
+        self.set_post_comments_locked(&post_id, false);
+        log_event("post_comments_unlocked", PostCommentsLockedEventDTO { account_id, post_id });
+    }
+
+    fn set_post_comments_locked(&mut self, post_id: &PostId, locked: bool) {
+        let mut post_stats = self.posts_stats.get(post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        post_stats.comments_locked = locked;
+        self.posts_stats.insert(post_id, &post_stats);
+    }
+
+    pub fn is_post_being_deleted(&self, post_id: PostId) -> bool {
+        self.deleting_posts.contains(&post_id)
+    }
+
+    // The post is owned by the author of its first message until it is transferred.
+    // The transfer is completed when the new owner accepts it, the owner can cancel it before that
+    pub fn transfer_post(&mut self, post_id: PostId, new_owner: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let new_owner = self.get_identity(new_owner);
+        self.assert_transfer_post_call(&account_id, &post_id, &new_owner);
+        let fee = self.calc_transfer_post_fee(&post_id, &new_owner);
+        // log!("transfer_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::TransferPost { post_id, new_owner })
+    }
+
+    pub fn accept_post_transfer(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_accept_post_transfer_call(&account_id, &post_id);
+        let fee = self.calc_accept_post_transfer_fee(&post_id, &account_id);
+        // log!("accept_post_transfer fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AcceptPostTransfer { post_id })
+    }
+
+    pub fn cancel_post_transfer(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_cancel_post_transfer_call(&account_id, &post_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::CancelPostTransfer { post_id })
+    }
+
+    pub fn get_post_owner(&self, post_id: PostId) -> Option<AccountId> {
+        let post_messages = self.posts_messages.get(&post_id)?;
+        self.get_post_owner_id(&post_id, &post_messages)
+    }
+
+    pub fn get_pending_post_owner(&self, post_id: PostId) -> Option<AccountId> {
+        self.posts_pending_owners.get(&post_id)
+    }
+
+    fn get_post_owner_id(&self, post_id: &PostId, post_messages: &Vector<Message>) -> Option<AccountId> {
+        self.posts_owners.get(post_id).or_else(|| post_messages.get(0).map(|msg| msg.account))
+    }
+
+    // Storage of the removed content is not refunded to its author
+    pub fn moderate_remove_message(&mut self, msg_id: MessageID, reason_hash: Option<Base64VecU8>) {
+        self.assert_moderator();
+        self.assert_message_id(&msg_id);
+        self.assert_message_existence(&msg_id);
+        self.assert_message_not_deleted(&msg_id);
+        self.remove_message_content(&(&msg_id).into());
+        self.add_moderation_record(ModerationAction::RemoveMessage, ModerationTarget::Message { msg_id }, reason_hash);
+    }
+
+    // Every message of the post is removed, the post itself and its likes stay
+    pub fn moderate_clear_post(&mut self, post_id: PostId, reason_hash: Option<Base64VecU8>) {
+        self.assert_moderator();
+        self.assert_post_id(&post_id);
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        for msg_idx in 0..post_messages.len() {
+            if !post_messages.get(msg_idx).unwrap().payload.is_deleted() {
+                self.remove_message_content(&MessageId { post_id: post_id.clone(), msg_idx });
+            };
+        }
+        self.add_moderation_record(ModerationAction::ClearPost, ModerationTarget::Post { post_id }, reason_hash);
+    }
+
+    // 'expires_at' is a timestamp in nanoseconds, the ban is permanent without it
+    pub fn ban_account(&mut self, account_id: AccountId, expires_at: Option<U64>, reason_hash: Option<Base64VecU8>) {
+        self.assert_moderator();
+        let account_id = self.get_identity(account_id);
+        let expires_at = expires_at.map(u64::from);
+        if let Some(expires_at) = expires_at {
+            if expires_at <= env::block_timestamp() {
+                ContractError::InvalidBanExpiry.panic();
+            };
+        };
+        self.banned_accounts.insert(&account_id, &expires_at);
+        self.add_moderation_record(ModerationAction::Ban, ModerationTarget::Account { account_id }, reason_hash);
+    }
+
+    pub fn unban_account(&mut self, account_id: AccountId, reason_hash: Option<Base64VecU8>) {
+        self.assert_moderator();
+        let account_id = self.get_identity(account_id);
+        if self.banned_accounts.remove(&account_id).is_none() {
+            ContractError::AccountNotBanned.panic();
+        };
+        self.add_moderation_record(ModerationAction::Unban, ModerationTarget::Account { account_id }, reason_hash);
+    }
+
+    pub fn set_credential_nft(&mut self, contract_id: AccountId, tier: u8) {
+        self.assert_owner();
+        if tier == 0 {
+            ContractError::InvalidVerificationTier.panic();
+        };
+        self.credential_nfts.insert(&contract_id, &tier);
+    }
+
+    // Verifications granted by the contract are kept, they are replaced by the next verification of the account
+    pub fn remove_credential_nft(&mut self, contract_id: AccountId) {
+        self.assert_owner();
+        if self.credential_nfts.remove(&contract_id).is_none() {
+            ContractError::CredentialNftNotAllowed.panic();
+        };
+    }
+
+    pub fn set_probation_policy(&mut self, policy: ProbationPolicy) {
+        self.assert_owner();
+        if policy.fee_multiplier_percent < 100 {
+            ContractError::InvalidProbationPolicy.panic();
+        };
+        self.probation_policy = policy;
+    }
+
+    pub fn get_probation_policy(&self) -> ProbationPolicy {
+        self.probation_policy
+    }
+
+    pub fn set_near_tip_cut_percent(&mut self, percent: u8) {
+        self.assert_owner();
+        if percent > 100 {
+            ContractError::InvalidNearTipCut.panic();
+        };
+        self.near_tip_cut_percent = percent;
+    }
+
+    pub fn get_near_tip_cut_percent(&self) -> u8 {
+        self.near_tip_cut_percent
+    }
+
+    pub fn get_near_tips_cut_balance(&self) -> U128 {
+        U128(self.near_tips_cut_balance)
+    }
+
+    pub fn withdraw_near_tips_cut(&mut self, amount: U128) -> Promise {
+        self.assert_owner();
+        let amount = u128::from(amount);
+        if amount == 0 || amount > self.near_tips_cut_balance {
+            ContractError::InvalidWithdrawAmount.panic();
+        };
+        self.near_tips_cut_balance -= amount;
+        Promise::new(self.ownership.owner().clone()).transfer(amount)
+    }
+
+    pub fn is_on_probation(&self, account_id: AccountId) -> bool {
+        let account_id = self.get_identity(account_id);
+        self.is_account_on_probation(&account_id)
+    }
+
+    pub fn get_credential_nfts(&self) -> Vec<CredentialNftDTO> {
+        self.credential_nfts.iter()
+            .map(|(contract_id, tier)| CredentialNftDTO { contract_id, tier })
+            .collect()
+    }
+
+    pub fn get_account_verification(&self, account_id: AccountId) -> Option<AccountVerificationDTO> {
+        let account_id = self.get_identity(account_id);
+        self.accounts_verifications.get(&account_id).map(|verification| AccountVerificationDTO {
+            tier: verification.tier,
+            contract_id: verification.contract_id,
+            token_id: verification.token_id,
+            verified_at: U64(verification.verified_at)
+        })
+    }
+
+    pub fn is_banned(&self, account_id: AccountId) -> bool {
+        self.is_account_banned(&self.get_identity(account_id))
+    }
+
+    // Expired ban is treated as absent, its entry is removed by 'unban_account' or replaced by the next ban
+    fn is_account_banned(&self, account_id: &AccountId) -> bool {
+        match self.banned_accounts.get(account_id) {
+            Some(Some(expires_at)) => expires_at > env::block_timestamp(),
+            Some(None) => true,
+            None => false
+        }
+    }
+
+    fn assert_not_banned(&self, account_id: &AccountId) {
+        if self.is_account_banned(account_id) {
+            ContractError::AccountBanned.panic();
+        };
+    }
+
+    fn assert_moderator(&self) {
+        let account_id = env::predecessor_account_id();
+        if account_id != *self.ownership.owner() && !self.moderators.contains(&account_id) {
+            ContractError::NotModerator.panic();
+        };
+    }
+
+    pub fn get_account_reports(&self, account_id: AccountId) -> u32 {
+        self.accounts_reports.get(&self.get_identity(account_id)).unwrap_or(0)
+    }
+
+    fn add_account_report(&mut self, account_id: &AccountId) {
+        let reports = self.accounts_reports.get(account_id).unwrap_or(0);
+        self.accounts_reports.insert(account_id, &reports.saturating_add(1));
+    }
+
+    // Messages of accounts reported at least 'sensitive_reports_threshold' times are always marked
+    fn get_sensitive_marker(&self, account_id: &AccountId, sensitive: bool) -> Option<SensitiveMarker> {
+        let threshold = self.admin_settings.sensitive_reports_threshold;
+        if threshold != 0 && self.accounts_reports.get(account_id).unwrap_or(0) >= threshold {
+            Some(SensitiveMarker::Moderator)
+        } else if sensitive {
+            Some(SensitiveMarker::Author)
+        } else {
+            None
+        }
+    }
+
+    pub fn like_post(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_like_post_call(&account_id, &post_id);
+        let fee = self.calc_like_post_fee(&account_id, &post_id) 
+            + self.calc_account_recent_likes_fee(&account_id, &post_id, false)
+            + self.calc_account_likes_history_fee(&account_id, &post_id, false)
+            + self.calc_post_score_fee(&post_id);
+        // log!("like_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::LikePost { post_id })
+    }
+
+    // Like charged 'super_like_fee_multiplier' times the like fee, it is counted with the multiplier as its weight
+    pub fn super_like_post(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_super_like_post_call(&account_id, &post_id);
+        let fee = self.calc_super_like_post_fee(&account_id, &post_id);
+        // log!("super_like_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::SuperLikePost { post_id })
+    }
+
+    // 'amount' of activity FT is collected with the fee and transferred to the message author
+    pub fn tip_message(&mut self, msg_id: MessageID, amount: U128) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_tip_message_call(&account_id, &msg_id, amount);
+        let fee = self.calc_message_tips_fee(&msg_id) + u128::from(amount);
+        // log!("tip_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::TipMessage { msg_id, amount })
+    }
+
+    // The attached NEAR without the platform cut is transferred to the message author, the storage fee is charged in activity FT
+    #[payable]
+    pub fn tip_message_near(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let amount = env::attached_deposit();
+        if amount == 0 {
+            ContractError::InvalidTipAmount.panic();
+        };
+        self.assert_tip_message_call(&account_id, &msg_id, U128(amount));
+        let fee = self.calc_message_near_tips_fee(&account_id, &msg_id);
+        // log!("tip_message_near fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::TipMessageNear { msg_id, amount: U128(amount) })
+    }
+
+    pub fn unlike_post(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_unlike_post_call(&account_id, &post_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::PostLike { account_id: account_id.clone(), post_id: post_id.clone() });
+        self.collect_fee_and_execute_call(fee, account_id, Call::UnlikePost { post_id })
+    }
+
+    pub fn repost(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_repost_call(&account_id, &post_id);
+        let fee = self.calc_repost_fee(&account_id, &post_id)
+            + self.calc_post_score_fee(&post_id);
+        // log!("repost fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::Repost { post_id })
+    }
+
+    pub fn like_message(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_like_message_call(&account_id, &msg_id);
+        let fee = self.calc_like_message_fee(&account_id, &msg_id)
+            + self.calc_account_recent_likes_fee(&account_id, &msg_id.post_id, true)
+            + self.calc_account_likes_history_fee(&account_id, &msg_id.post_id, true)
+            + self.calc_post_message_likes_count_fee(&msg_id.post_id);
+        // log!("like_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::LikeMessage { msg_id })
+    }
+
+    // 'Like' reaction is the like of the post. Another reaction replaces the previous reaction of the account
+    pub fn react_to_post(&mut self, post_id: PostId, reaction: Reaction) -> Promise {
+        if reaction == Reaction::Like {
+            return self.like_post(post_id)
+        }
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_react_to_post_call(&account_id, &post_id, reaction);
+        let fee = self.calc_react_to_post_fee(&account_id, &post_id);
+        // log!("react_to_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::ReactToPost { post_id, reaction })
+    }
+
+    pub fn remove_post_reaction(&mut self, post_id: PostId) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_remove_post_reaction_call(&account_id, &post_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::RemovePostReaction { post_id })
+    }
+
+    // 'Like' reaction is the like of the message. Another reaction replaces the previous reaction of the account
+    pub fn react_to_message(&mut self, msg_id: MessageID, reaction: Reaction) -> Promise {
+        if reaction == Reaction::Like {
+            return self.like_message(msg_id)
+        }
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_react_to_message_call(&account_id, &msg_id, reaction);
+        let fee = self.calc_react_to_message_fee(&account_id, &msg_id);
+        // log!("react_to_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::ReactToMessage { msg_id, reaction })
+    }
+
+    pub fn remove_message_reaction(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_remove_message_reaction_call(&account_id, &msg_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::RemoveMessageReaction { msg_id })
+    }
+
+    pub fn unlike_message(&mut self, msg_id: MessageID) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_unlike_message_call(&account_id, &msg_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::MessageLike { account_id: account_id.clone(), msg_id: (&msg_id).into() });
+        self.collect_fee_and_execute_call(fee, account_id, Call::UnlikeMessage { msg_id })
+    }
+
+    // Calls are validated against the state before the batch, their fees are collected at once and
+    // they are executed one by one. Every call result is returned in the order of 'calls'
+    pub fn batch(&mut self, calls: Vec<BatchCall>) -> Promise {
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let (fee, calls) = self.prepare_batch_calls(&account_id, calls);
+        // log!("batch fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::Batch { calls })
+    }
+
+    pub fn add_friend(&mut self, friend_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_add_friend_call(&account_id, &friend_id);
+        let fee = self.calc_add_friend_fee(&account_id, &friend_id)
+            + self.calc_notification_fee(&account_id, &Some(friend_id.clone()), None);
+        // log!("add_friend fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AddFriend { friend_id })
+    }
+
+    pub fn send_friend_request(&mut self, friend_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_send_friend_request_call(&account_id, &friend_id);
+        let fee = self.calc_send_friend_request_fee(&account_id, &friend_id);
+        // log!("send_friend_request fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::SendFriendRequest { friend_id })
+    }
+
+    // Accepting account pays for both friendship entries
+    pub fn accept_friend_request(&mut self, requester_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_friend_request_exists(&requester_id, &account_id);
+        let fee = self.calc_accept_friend_request_fee(&account_id, &requester_id);
+        // log!("accept_friend_request fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AcceptFriendRequest { requester_id })
+    }
+
+    pub fn decline_friend_request(&mut self, requester_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_friend_request_exists(&requester_id, &account_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::DeclineFriendRequest { requester_id })
+    }
+
+    pub fn get_incoming_friend_requests(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        get_accounts_set_page(self.incoming_friend_requests.get(&self.get_identity(account_id)), from_index, limit, false)
+    }
+
+    pub fn get_outgoing_friend_requests(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        get_accounts_set_page(self.outgoing_friend_requests.get(&self.get_identity(account_id)), from_index, limit, false)
+    }
+
+    // Blocked account cannot add the account as a friend, reply to its messages or like its content
+    pub fn block_account(&mut self, blocked_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let blocked_id = self.get_identity(blocked_id);
+        self.assert_block_account_call(&account_id, &blocked_id);
+        let fee = self.calc_block_account_fee(&account_id, &blocked_id);
+        // log!("block_account fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::BlockAccount { blocked_id })
+    }
+
+    pub fn unblock_account(&mut self, blocked_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let blocked_id = self.get_identity(blocked_id);
+        self.assert_unblock_account_call(&account_id, &blocked_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::UnblockAccount { blocked_id })
+    }
+
+    pub fn get_blocked_accounts(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        get_accounts_set_page(self.blocked_accounts.get(&self.get_identity(account_id)), from_index, limit, false)
+    }
+
+    pub fn is_blocked(&self, account_id: AccountId, blocked_id: AccountId) -> bool {
+        self.blocked_accounts
+            .get(&self.get_identity(account_id))
+            .is_some_and(|blocked_accounts| blocked_accounts.contains(&self.get_identity(blocked_id)))
+    }
+
+    // Unlike blocking, muting only hides the messages of the muted account from the views called with 'viewer_id'
+    pub fn mute_account(&mut self, muted_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let muted_id = self.get_identity(muted_id);
+        self.assert_mute_account_call(&account_id, &muted_id);
+        let fee = self.calc_mute_account_fee(&account_id, &muted_id);
+        // log!("mute_account fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::MuteAccount { muted_id })
+    }
+
+    pub fn unmute_account(&mut self, muted_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let muted_id = self.get_identity(muted_id);
+        self.assert_unmute_account_call(&account_id, &muted_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::UnmuteAccount { muted_id })
+    }
+
+    pub fn get_muted_accounts(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        get_accounts_set_page(self.muted_accounts.get(&self.get_identity(account_id)), from_index, limit, false)
+    }
+
+    pub fn is_muted(&self, account_id: AccountId, muted_id: AccountId) -> bool {
+        self.muted_accounts
+            .get(&self.get_identity(account_id))
+            .is_some_and(|muted_accounts| muted_accounts.contains(&self.get_identity(muted_id)))
+    }
+
+    fn get_viewer_muted_accounts(&self, viewer_id: Option<AccountId>) -> Option<UnorderedSet<AccountId>> {
+        viewer_id.and_then(|viewer_id| self.muted_accounts.get(&self.get_identity(viewer_id)))
+    }
+
+    pub fn report_message(&mut self, msg_id: MessageID, reason: ReportReason) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_message_id(&msg_id);
+        let content = ReportedContent::Message { msg_id: (&msg_id).into() };
+        self.assert_report_content_call(&account_id, &content);
+        let fee = self.calc_report_content_fee(&account_id, &content);
+        // log!("report_message fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::ReportMessage { msg_id, reason })
+    }
+
+    pub fn report_post(&mut self, post_id: PostId, reason: ReportReason) -> Promise {
+        self.assert_app_scope(AppScope::Messages);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        let content = ReportedContent::Post { post_id: post_id.clone() };
+        self.assert_report_content_call(&account_id, &content);
+        let fee = self.calc_report_content_fee(&account_id, &content);
+        // log!("report_post fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::ReportPost { post_id, reason })
+    }
+
+    pub fn get_reported_contents(&self, from_index: U64, limit: U64) -> Vec<ReportedContentDTO> {
+        let from = u64::from(from_index);
+        let lim = u64::from(limit);
+        let reported_contents = self.reported_contents.as_vector();
+
+        (from..std::cmp::min(from + lim, reported_contents.len()))
+            .map(|idx| {
+                let content = reported_contents.get(idx).unwrap();
+                let reports_count = self.content_reports.get(&content).map_or(0, |content_reports| content_reports.len());
+                ReportedContentDTO {
+                    target: content.into(),
+                    reports_count: U64(reports_count)
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_reported_contents_count(&self) -> U64 {
+        U64(self.reported_contents.len())
+    }
+
+    pub fn get_content_reports(&self, target: ModerationTarget, from_index: U64, limit: U64) -> Vec<ContentReportDTO> {
+        let content = match target {
+            ModerationTarget::Post { post_id } => ReportedContent::Post { post_id },
+            ModerationTarget::Message { msg_id } => ReportedContent::Message { msg_id: msg_id.into() },
+            ModerationTarget::Account { .. } => return Vec::new()
+        };
+        match self.content_reports.get(&content) {
+            Some(content_reports) => {
+                let from = u64::from(from_index);
+                let lim = u64::from(limit);
+                let reporters = content_reports.keys_as_vector();
+                let reasons = content_reports.values_as_vector();
+                (from..std::cmp::min(from + lim, content_reports.len()))
+                    .map(|idx| ContentReportDTO {
+                        reporter: reporters.get(idx).unwrap(),
+                        reason: reasons.get(idx).unwrap()
+                    })
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    pub fn remove_friend(&mut self, friend_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_remove_friend_call(&account_id, &friend_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::Friend { account_id: account_id.clone(), friend_id: friend_id.clone() });
+        self.collect_fee_and_execute_call(fee, account_id, Call::RemoveFriend { friend_id })
+    }
+
+    pub fn claim_imported_friends(&mut self, proof: Vec<Base64VecU8>, friends: Vec<AccountId>) -> Promise {
+        self.assert_app_scope(AppScope::Friends);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_claim_imported_friends_call(&account_id, &proof, &friends);
+        let fee = self.calc_import_friends_fee(&account_id, &friends);
+        // log!("claim_imported_friends fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::ImportFriends { friends })
+    }
+
+    pub fn set_friends_import_root(&mut self, root: Option<Base64VecU8>) {
+        self.assert_owner();
+        self.friends_import_root = root.map(|root| root.into());
+    }
+
+    pub fn get_friends_import_root(&self) -> Option<Base64VecU8> {
+        self.friends_import_root.clone().map(Base64VecU8::from)
+    }
+
+    pub fn update_profile(&mut self, profile: AccountProfileData) -> Promise {
+        self.assert_app_scope(AppScope::Profile);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_update_profile_call(&profile);
+        let update_profile_fee = self.calc_update_profile_fee(&account_id, &profile);
+        let fee: u128 = if update_profile_fee != 0 {
+            update_profile_fee
+        } else {
+            1
+        };
+        // log!("update_profile fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::UpdateProfile { profile })
+    }
+
+    pub fn request_account_link(&mut self, account_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Identity);
+        let primary_id = env::predecessor_account_id();
+        self.assert_request_account_link_call(&primary_id, &account_id);
+        let fee = self.calc_request_account_link_fee(&primary_id, &account_id);
+        // log!("request_account_link fee {}", fee);
+        self.collect_fee_and_execute_call(fee, primary_id, Call::RequestAccountLink { account_id })
+    }
+
+    pub fn accept_account_link(&mut self, primary_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Identity);
+        let account_id = env::predecessor_account_id();
+        self.assert_accept_account_link_call(&account_id, &primary_id);
+        let fee = self.calc_accept_account_link_fee(&account_id, &primary_id);
+        // log!("accept_account_link fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::AcceptAccountLink { primary_id })
+    }
+
+    pub fn unlink_account(&mut self, account_id: AccountId) -> Promise {
+        self.assert_app_scope(AppScope::Identity);
+        let caller_id = env::predecessor_account_id();
+        self.assert_unlink_account_call(&caller_id, &account_id);
+        self.collect_fee_and_execute_call(1, caller_id, Call::UnlinkAccount { account_id })
+    }
+
+    pub fn verify_via_nft(&mut self, contract_id: AccountId, token_id: String) -> Promise {
+        self.assert_app_scope(AppScope::Profile);
+        self.assert_not_paused();
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_verify_via_nft_call(&account_id, &contract_id, &token_id);
+        ext_nft::ext(contract_id.clone())
+            .with_static_gas(Gas(5*TGAS))
+            .nft_token(token_id.clone())
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(20*TGAS))
+                    .on_credential_nft_checked(account_id, env::predecessor_account_id(), contract_id, token_id)
+                )
+    }
+
+    // The token may be held by the identity or by the calling linked account, the fee is collected from the caller
+    #[private]
+    pub fn on_credential_nft_checked(&mut self, account_id: AccountId, caller_id: AccountId, contract_id: AccountId, token_id: String) -> Promise {
+        let owner_id = promise_result_as_success()
+            .and_then(|result| serde_json::from_slice::<Option<CredentialTokenDTO>>(&result).ok())
+            .flatten()
+            .map(|token| token.owner_id);
+        if owner_id.as_ref() != Some(&account_id) && owner_id.as_ref() != Some(&caller_id) {
+            ContractError::CredentialNftNotOwned.panic();
+        };
+        // The allowlist may have changed while the token was checked
+        let tier = self.credential_nfts.get(&contract_id).unwrap_or_else(|| ContractError::CredentialNftNotAllowed.panic());
+        let fee = self.calc_verify_account_fee(&account_id, &contract_id, &token_id);
+        // log!("verify_via_nft fee {}", fee);
+        self.collect_fee_from_and_execute_call(fee, caller_id, account_id, Call::VerifyAccount { contract_id, token_id, tier })
+    }
+
+    pub fn archive_post_messages(&mut self, post_id: PostId, cid: String, from_index: U64, limit: U64, reason_hash: Option<Base64VecU8>) -> U64 {
+        self.assert_owner();
+        self.assert_post_id(&post_id);
+        self.assert_archive_cid(&cid);
+
+        let mut post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let from = u64::from(from_index);
+        let lim = u64::from(limit);
+
+        let mut refunds: HashMap<AccountId, u128> = HashMap::new();
+        let mut archived_count = 0u64;
+
+        for idx in from..std::cmp::min(from + lim, post_messages.len()) {
+            let mut msg = post_messages.get(idx).unwrap();
+            if let MessagePayload::Text { text } = &msg.payload {
+                let initial_storage_usage = env::storage_usage();
+
+                msg.payload = MessagePayload::Archived {
+                    cid: cid.clone(),
+                    text_hash: env::sha256(text.as_bytes())
+                };
+                post_messages.replace(idx, &msg);
+                archived_count += 1;
+
+                let reclaimed_storage = initial_storage_usage.saturating_sub(env::storage_usage());
+                let refund = self.calc_storage_refund(reclaimed_storage);
+                if refund != 0 {
+                    *refunds.entry(msg.account).or_insert(0) += refund;
+                }
+            };
+        }
+
+        for (account_id, amount) in refunds {
+            ext_ft::ext(self.fee_ft.clone())
+                .with_static_gas(Gas(5*TGAS))
+                .with_attached_deposit(1)
+                .ft_transfer(account_id, U128::from(amount), Some(String::from("Archived messages storage refund")));
+        }
+
+        if archived_count != 0 {
+            self.add_moderation_record(ModerationAction::Archive, ModerationTarget::Post { post_id }, reason_hash);
+        };
+
+        U64(archived_count)
+    }
+
+    pub fn register_app_key(&mut self, public_key: PublicKey, scopes: Vec<AppScope>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        self.assert_register_app_key_call(&scopes);
+        let fee = self.calc_register_app_key_fee(&account_id, &public_key, &scopes);
+        // log!("register_app_key fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::RegisterAppKey { public_key, scopes })
+    }
+
+    pub fn unregister_app_key(&mut self, public_key: PublicKey) -> Promise {
+        let account_id = env::predecessor_account_id();
+        self.assert_unregister_app_key_call(&account_id, &public_key);
+        self.collect_fee_and_execute_call(1, account_id, Call::UnregisterAppKey { public_key })
+    }
+
+    pub fn update_admin_settings(&mut self, settings: AdminSettingsData) {
+        self.assert_owner();
+        if let Some(account_recent_likes_limit) = settings.account_recent_likes_limit {
+            self.admin_settings.account_recent_likes_limit = account_recent_likes_limit;
+        }
+        if let Some(add_message_extra_fee_percent) = settings.add_message_extra_fee_percent {
+            self.admin_settings.add_message_extra_fee_percent = add_message_extra_fee_percent;
+        }
+        if let Some(like_post_extra_fee_percent) = settings.like_post_extra_fee_percent {
+            self.admin_settings.like_post_extra_fee_percent = like_post_extra_fee_percent;
+        }
+        if let Some(like_message_extra_fee_percent) = settings.like_message_extra_fee_percent {
+            self.admin_settings.like_message_extra_fee_percent = like_message_extra_fee_percent;
+        }
+        if let Some(add_friend_extra_fee_percent) = settings.add_friend_extra_fee_percent {
+            self.admin_settings.add_friend_extra_fee_percent = add_friend_extra_fee_percent;
+        }
+        if let Some(update_profile_extra_fee_percent) = settings.update_profile_extra_fee_percent {
+            self.admin_settings.update_profile_extra_fee_percent = update_profile_extra_fee_percent;
+        }
+        if let Some(account_recent_like_extra_fee_percent) = settings.account_recent_like_extra_fee_percent {
+            self.admin_settings.account_recent_like_extra_fee_percent = account_recent_like_extra_fee_percent;
+        }
+        if let Some(archive_refund_percent) = settings.archive_refund_percent {
+            self.admin_settings.archive_refund_percent = archive_refund_percent;
+        }
+        if let Some(message_edit_window_sec) = settings.message_edit_window_sec {
+            self.admin_settings.message_edit_window_sec = message_edit_window_sec;
+        }
+        if let Some(max_daily_reversals) = settings.max_daily_reversals {
+            self.admin_settings.max_daily_reversals = max_daily_reversals;
+        }
+        if let Some(max_post_messages) = settings.max_post_messages {
+            self.admin_settings.max_post_messages = max_post_messages;
+        }
+        if let Some(sensitive_reports_threshold) = settings.sensitive_reports_threshold {
+            self.admin_settings.sensitive_reports_threshold = sensitive_reports_threshold;
+        }
+        if let Some(super_like_fee_multiplier) = settings.super_like_fee_multiplier {
+            self.admin_settings.super_like_fee_multiplier = super_like_fee_multiplier;
+        }
+        if let Some(max_media_cid_len) = settings.max_media_cid_len {
+            self.admin_settings.max_media_cid_len = max_media_cid_len;
+        }
+        if let Some(max_media_mime_type_len) = settings.max_media_mime_type_len {
+            self.admin_settings.max_media_mime_type_len = max_media_mime_type_len;
+        }
+    }
+
+    // Re-runs the storage measurements 'samples' times and reports the settings whose largest measured size
+    // differs from the stored one. The stored settings are restored, so fees are not changed by the audit
+    pub fn audit_storage_fees(&mut self, samples: u8) -> Vec<StorageUsageDriftDTO> {
+        self.assert_owner();
+        if samples == 0 || samples > MAX_STORAGE_AUDIT_SAMPLES {
+            ContractError::InvalidAuditSamples.panic();
+        };
+
+        let stored_settings = self.storage_usage_settings;
+        let mut measured_sizes: HashMap<String, u64> = HashMap::new();
+        for _ in 0..samples {
+            self.update_storage_usage_settings();
+            for (setting, size) in get_storage_settings_entries(&self.storage_usage_settings) {
+                let measured_size = measured_sizes.entry(setting).or_insert(0);
+                *measured_size = std::cmp::max(*measured_size, size);
+            }
+        }
+        self.storage_usage_settings = stored_settings;
+
+        get_storage_settings_entries(&stored_settings)
+            .into_iter()
+            .filter_map(|(setting, stored)| {
+                let measured = measured_sizes.get(&setting).copied().unwrap_or(0);
+                if measured == stored {
+                    return None
+                }
+                Some(StorageUsageDriftDTO {
+                    delta: measured as i64 - stored as i64,
+                    setting,
+                    stored: U64(stored),
+                    measured: U64(measured)
+                })
+            })
+            .collect()
+    }
+    
+    // Messages of the accounts muted by 'viewer_id' are skipped, so the page can be shorter than 'limit'
+    pub fn get_post_messages(&self, post_id: PostId, from_index: U64, limit: U64, viewer_id: Option<AccountId>, reverse: Option<bool>) -> Vec<MessageDTO> {
+        if let Some(post_messages) = self.posts_messages.get(&post_id) {
+            let muted_accounts = self.get_viewer_muted_accounts(viewer_id);
+            
+            get_page_indexes(post_messages.len(), from_index, limit, reverse.unwrap_or(false))
+                .map(|idx| (idx, post_messages.get(idx).unwrap()))
+                .filter(|(_, msg)| !is_muted_author(&muted_accounts, &msg.account))
+                .map(|(idx, msg)| {
+                    let msg_id = MessageId { post_id: post_id.clone(), msg_idx: idx };
+                    self.to_message_dto(&msg_id, msg)
+                })
+                .collect()
+        } else {
+            ContractError::PostNotFound.panic();
+        }
+    }
+
+    pub fn get_post_messages_page(&self, post_id: PostId, cursor: Option<Base64VecU8>, limit: U64, viewer_id: Option<AccountId>, reverse: Option<bool>) -> PageDTO<MessageDTO> {
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let muted_accounts = self.get_viewer_muted_accounts(viewer_id);
+        let (indexes, next_cursor) = get_cursor_page_indexes(post_messages.len(), cursor, limit, reverse);
+
+        let items = indexes
+            .into_iter()
+            .map(|idx| (idx, post_messages.get(idx).unwrap()))
+            .filter(|(_, msg)| !is_muted_author(&muted_accounts, &msg.account))
+            .map(|(idx, msg)| {
+                let msg_id = MessageId { post_id: post_id.clone(), msg_idx: idx };
+                self.to_message_dto(&msg_id, msg)
+            })
+            .collect();
+        PageDTO { items, next_cursor }
+    }
+
+    // 'lang' matches the message tag itself and its subtags, e.g. "en" matches "en" and "en-gb"
+    pub fn get_post_messages_filtered(&self, post_id: PostId, lang: String, from_index: U64, limit: U64, viewer_id: Option<AccountId>) -> Vec<MessageDTO> {
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let lang = lang.to_ascii_lowercase();
+        let lang_prefix = format!("{}-", lang);
+        let muted_accounts = self.get_viewer_muted_accounts(viewer_id);
+
+        post_messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| match &msg.lang {
+                Some(msg_lang) => *msg_lang == lang || msg_lang.starts_with(&lang_prefix),
+                None => false
+            })
+            .filter(|(_, msg)| !is_muted_author(&muted_accounts, &msg.account))
+            .skip(usize::try_from(u64::from(from_index)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic()))
+            .take(usize::try_from(u64::from(limit)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic()))
+            .map(|(idx, msg)| {
+                let msg_id = MessageId { post_id: post_id.clone(), msg_idx: u64::try_from(idx).unwrap() };
+                self.to_message_dto(&msg_id, msg)
+            })
+            .collect()
+    }
+
+    // Continuation posts are listed as separate posts. Deleting a post moves the last listed post to its place
+    pub fn get_all_posts(&self, from_index: U64, limit: U64) -> Vec<PostId> {
+        let from = u64::from(from_index);
+        let lim = u64::from(limit);
+        (from..std::cmp::min(from + lim, self.all_posts.len()))
+            .map(|idx| self.all_posts.as_vector().get(idx).unwrap())
+            .collect()
+    }
+
+    pub fn get_posts_count(&self) -> U64 {
+        U64(self.all_posts.len())
+    }
+
+    pub fn get_post_summary(&self, post_id: PostId) -> PostSummaryDTO {
+        let messages_count = self.posts_messages.get(&post_id).map_or(0, |post_messages| post_messages.len());
+        let likes_count = self.posts_likes.get(&post_id).map_or(0, |post_likes| post_likes.len());
+        let message_likes_count = self.posts_message_likes_counts.get(&post_id).unwrap_or(0);
+        let continuation = self.posts_continuations.get(&post_id);
+        let sensitive = self.sensitive_posts.contains(&post_id);
+
+        PostSummaryDTO {
+            post_id,
+            messages_count: U64(messages_count),
+            likes_count: U64(likes_count),
+            message_likes_count: U64(message_likes_count),
+            continuation,
+            sensitive
+        }
+    }
+
+    // Posts created before the counters were introduced have no stats
+    pub fn get_post_stats(&self, post_id: PostId) -> Option<PostStatsDTO> {
+        self.posts_stats.get(&post_id).map(|post_stats| PostStatsDTO {
+            messages_count: U64(post_stats.messages_count),
+            likes_count: U64(post_stats.likes_count),
+            unique_commenters: U64(post_stats.unique_commenters),
+            pinned_msg_idx: post_stats.pinned_msg_idx.map(U64),
+            comments_locked: post_stats.comments_locked
+        })
+    }
+
+    pub fn get_post_message(&self, msg_id: MessageID) -> Option<MessageDTO> {
+        if let Some(post_messages) = self.posts_messages.get(&msg_id.post_id) {
+            let id : MessageId = msg_id.into();
+            if let Some(msg) = post_messages.get(id.msg_idx) {
+                Some(self.to_message_dto(&id, msg))
+            } else {
+                ContractError::MessageNotFound.panic();
+            }
+        } else {
+            ContractError::PostNotFound.panic();
+        }
+    }
+
+    pub fn get_post_messages_by_author(&self, post_id: PostId, author: AccountId, from_index: U64, limit: U64) -> Vec<MessageDTO> {
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let post_author_id = PostAuthorId { post_id: post_id.clone(), account_id: self.get_identity(author) };
+
+        if let Some(author_messages) = self.posts_authors_messages.get(&post_author_id) {
+            let from = u64::from(from_index);
+            let lim = u64::from(limit);
+
+            (from..std::cmp::min(from + lim, author_messages.len()))
+                .map(|idx| {
+                    let msg_idx = author_messages.get(idx).unwrap();
+                    let msg = post_messages.get(msg_idx).unwrap();
+                    let msg_id = MessageId { post_id: post_id.clone(), msg_idx };
+                    self.to_message_dto(&msg_id, msg)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_message_replies(&self, msg_id: MessageID, from_index: U64, limit: U64) -> Vec<MessageDTO> {
+        let msg_id: MessageId = msg_id.into();
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+
+        if let Some(message_replies) = self.posts_messages_replies.get(&msg_id) {
+            let from = u64::from(from_index);
+            let lim = u64::from(limit);
+
+            (from..std::cmp::min(from + lim, message_replies.len()))
+                .map(|idx| {
+                    let msg_idx = message_replies.get(idx).unwrap();
+                    let msg = post_messages.get(msg_idx).unwrap();
+                    let reply_id = MessageId { post_id: msg_id.post_id.clone(), msg_idx };
+                    self.to_message_dto(&reply_id, msg)
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    // Depth-first traversal of the replies tree, the root message has depth 0. 'depth' and 'limit'
+    // are capped, so a deep or wide thread is returned partially
+    pub fn get_post_thread(&self, post_id: PostId, root_msg_idx: U64, depth: u32, limit: U64) -> Vec<ThreadMessageDTO> {
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let root_msg_idx = u64::from(root_msg_idx);
+        let root_msg = post_messages.get(root_msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+        let depth = std::cmp::min(depth, MAX_THREAD_DEPTH);
+        let lim = std::cmp::min(u64::from(limit), MAX_THREAD_MESSAGES);
+        if lim == 0 {
+            return Vec::new()
+        }
+
+        let root_id = MessageId { post_id: post_id.clone(), msg_idx: root_msg_idx };
+        let mut thread = vec![ThreadMessageDTO { depth: 0, message: self.to_message_dto(&root_id, root_msg) }];
+
+        // Replies of every message on the current path with the index of the next reply to visit
+        let mut path: Vec<(Vector<u64>, u64, u32)> = Vec::new();
+        if depth > 0 {
+            if let Some(root_replies) = self.posts_messages_replies.get(&root_id) {
+                path.push((root_replies, 0, 1));
+            }
+        }
+
+        while u64::try_from(thread.len()).unwrap() < lim {
+            let (msg_idx, msg_depth) = match path.last_mut() {
+                Some((replies, next_idx, replies_depth)) => {
+                    if *next_idx >= replies.len() {
+                        path.pop();
+                        continue
+                    }
+                    *next_idx += 1;
+                    (replies.get(*next_idx - 1).unwrap(), *replies_depth)
+                },
+                None => break
+            };
+
+            let msg_id = MessageId { post_id: post_id.clone(), msg_idx };
+            let msg = post_messages.get(msg_idx).unwrap();
+            thread.push(ThreadMessageDTO { depth: msg_depth, message: self.to_message_dto(&msg_id, msg) });
+
+            if msg_depth < depth {
+                if let Some(replies) = self.posts_messages_replies.get(&msg_id) {
+                    path.push((replies, 0, msg_depth + 1));
+                }
+            }
+        }
+        thread
+    }
+
+    pub fn get_message_replies_count(&self, msg_id: MessageID) -> U64 {
+        let count = self.posts_messages_replies
+            .get(&msg_id.into())
+            .map_or(0, |message_replies| message_replies.len());
+        U64(count)
+    }
+
+    // Messages of deleted posts are skipped, so a page can be shorter than 'limit'
+    pub fn get_account_messages(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<MessageDTO> {
+        let account_id = self.get_identity(account_id);
+        match self.accounts_messages.get(&account_id) {
+            Some(account_messages) => {
+                let from = u64::from(from_index);
+                let lim = u64::from(limit);
+                (from..std::cmp::min(from + lim, account_messages.len()))
+                    .filter_map(|idx| self.get_account_message_dto(account_messages.get(idx).unwrap()))
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    pub fn get_account_messages_page(&self, account_id: AccountId, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> PageDTO<MessageDTO> {
+        let account_id = self.get_identity(account_id);
+        match self.accounts_messages.get(&account_id) {
+            Some(account_messages) => {
+                let (indexes, next_cursor) = get_cursor_page_indexes(account_messages.len(), cursor, limit, reverse);
+                let items = indexes
+                    .into_iter()
+                    .filter_map(|idx| self.get_account_message_dto(account_messages.get(idx).unwrap()))
+                    .collect();
+                PageDTO { items, next_cursor }
+            },
+            None => PageDTO { items: Vec::new(), next_cursor: None }
+        }
+    }
+
+    // Ids of deleted posts are not reused, so an entry of a deleted post does not resolve to a message
+    fn get_account_message_dto(&self, msg_id: MessageId) -> Option<MessageDTO> {
+        let msg = self.posts_messages.get(&msg_id.post_id)?.get(msg_id.msg_idx)?;
+        Some(self.to_message_dto(&msg_id, msg))
+    }
+
+    // Messages of deleted posts are subtracted from the stats as the posts are deleted
+    pub fn get_account_messages_count(&self, account_id: AccountId) -> U64 {
+        let count = self.accounts_stats
+            .get(&self.get_identity(account_id))
+            .map_or(0, |account_stats| account_stats.messages_count);
+        U64(count)
+    }
+
+    fn to_message_dto(&self, msg_id: &MessageId, msg: Message) -> MessageDTO {
+        let deleted = msg.payload.is_deleted();
+        let hidden_by_author = matches!(msg.payload, MessagePayload::HiddenByAuthor);
+        let (text, archive, listing, quote, media, link) = match msg.payload {
+            MessagePayload::Text { text } => {
+                (Some(text), None, None, None, None, None)
+            },
+            MessagePayload::Archived { cid, text_hash } => {
+                (None, Some(MessageArchiveDTO { cid, text_hash: Base64VecU8::from(text_hash) }), None, None, None, None)
+            },
+            MessagePayload::Listing { marketplace, listing_id } => {
+                (None, None, Some(ListingDTO { marketplace, listing_id }), None, None, None)
+            },
+            MessagePayload::Quote { quoted_post_id, quoted_msg_idx, text } => {
+                (Some(text), None, None, Some(QuoteDTO { post_id: quoted_post_id, msg_idx: quoted_msg_idx.map(U64) }), None, None)
+            },
+            MessagePayload::Media { text, media_cid, mime_type } => {
+                (Some(text), None, None, None, Some(MediaDTO { media_cid, mime_type }), None)
+            },
+            MessagePayload::Link { url, title, description } => {
+                (None, None, None, None, None, Some(LinkDTO { url, title, description }))
+            },
+            MessagePayload::Deleted | MessagePayload::HiddenByAuthor => (None, None, None, None, None, None)
+        };
+        MessageDTO {
+            msg_idx: U64(msg_id.msg_idx),
+            parent_idx: msg.parent_idx.map(U64),
+            account: msg.account,
+            delegate: msg.delegate,
+            lang: msg.lang,
+            text,
+            archive,
+            listing,
+            quote,
+            media,
+            link,
+            sensitive: msg.sensitive.is_some(),
+            timestamp: U64(msg.timestamp),
+            edited: msg.edited_at.is_some(),
+            edited_at: msg.edited_at.map(U64),
+            deleted,
+            hidden_by_author,
+            likes_count: match self.posts_messages_likes.get(msg_id) {
+                Some(post_message_likes) => U64(post_message_likes.len()),
+                None => U64(0)
+            }
+        }
+    }
+
+    pub fn get_post_likes(&self, post_id: PostId, from_index: U64, limit: U64, reverse: Option<bool>) -> Vec<AccountId> {
+        get_accounts_set_page(self.posts_likes.get(&post_id), from_index, limit, reverse.unwrap_or(false))
+    }
+
+    pub fn get_post_likes_page(&self, post_id: PostId, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> PageDTO<AccountId> {
+        get_accounts_set_cursor_page(self.posts_likes.get(&post_id), cursor, limit, reverse)
+    }
+
+    // Number of accounts per reaction keyed by the reaction emoji, likes are counted as the heart reaction. Reactions without accounts are omitted
+    pub fn get_post_reactions(&self, post_id: PostId) -> HashMap<String, U64> {
+        let likes_count = self.posts_likes.get(&post_id).map_or(0, |post_likes| post_likes.len());
+        get_reactions_counts(likes_count, self.posts_reactions.get(&post_id))
+    }
+
+    pub fn get_message_reactions(&self, msg_id: MessageID) -> HashMap<String, U64> {
+        let msg_id: MessageId = msg_id.into();
+        let likes_count = self.posts_messages_likes.get(&msg_id).map_or(0, |post_message_likes| post_message_likes.len());
+        get_reactions_counts(likes_count, self.posts_messages_reactions.get(&msg_id))
+    }
+
+    // Reaction of the account other than the like
+    pub fn get_post_reaction(&self, post_id: PostId, account_id: AccountId) -> Option<Reaction> {
+        self.posts_reactions.get(&post_id)?.accounts.get(&self.get_identity(account_id))
+    }
+
+    pub fn get_message_reaction(&self, msg_id: MessageID, account_id: AccountId) -> Option<Reaction> {
+        self.posts_messages_reactions.get(&msg_id.into())?.accounts.get(&self.get_identity(account_id))
+    }
+
+    pub fn get_message_tips(&self, msg_id: MessageID) -> U128 {
+        U128(self.messages_tips.get(&msg_id.into()).unwrap_or(0))
+    }
+
+    // Total of the NEAR tips of the message, including the platform cut
+    pub fn get_message_near_tips(&self, msg_id: MessageID) -> U128 {
+        U128(self.messages_near_tips.get(&msg_id.into()).unwrap_or(0))
+    }
+
+    pub fn get_account_near_tips(&self, account_id: AccountId) -> NearTipsDTO {
+        let near_tips = self.accounts_near_tips.get(&account_id).unwrap_or_default();
+        NearTipsDTO {
+            sent: U128(near_tips.sent),
+            received: U128(near_tips.received)
+        }
+    }
+
+    pub fn get_post_likes_info(&self, post_id: PostId, account_id: AccountId) -> LikesInfoDTO {
+        if let Some(post_likes) = self.posts_likes.get(&post_id) {
+            // A super like counts with its weight instead of 1
+            let (super_likes_count, super_likes_weight) = self.posts_super_likes.get(&post_id)
+                .map_or((0, 0), |post_super_likes| (post_super_likes.accounts.len(), post_super_likes.total_weight));
+            LikesInfoDTO {
+                likes_count: U64(post_likes.len()),
+                is_liked: post_likes.contains(&account_id),
+                weighted_likes_count: U64(post_likes.len().saturating_sub(super_likes_count) + super_likes_weight)
+            }
+        } else {
+            LikesInfoDTO {
+                likes_count: U64(0),
+                is_liked: false,
+                weighted_likes_count: U64(0)
+            }
+        }
+    }
+
+    pub fn get_post_reposts(&self, post_id: PostId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        if let Some(post_reposts) = self.posts_reposts.get(&post_id) {
+            use std::convert::TryFrom;
+            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
+                post_reposts
+                    .iter()
+                    .skip(from)
+                    .take(lim)
+                    .collect()
+            } else {
+                ContractError::UsizeConversionFailed.panic();
+            }
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_post_reposts_info(&self, post_id: PostId, account_id: AccountId) -> RepostsInfoDTO {
+        if let Some(post_reposts) = self.posts_reposts.get(&post_id) {
+            RepostsInfoDTO {
+                reposts_count: U64(post_reposts.len()),
+                is_reposted: post_reposts.contains(&account_id)
+            }
+        } else {
+            RepostsInfoDTO {
+                reposts_count: U64(0),
+                is_reposted: false
+            }
+        }
+    }
+
+    // Mentions in deleted posts are skipped, so a page can be shorter than 'limit'
+    pub fn get_account_mentions(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<MessageID> {
+        match self.accounts_mentions.get(&self.get_identity(account_id)) {
+            Some(account_mentions) => {
+                let from = u64::from(from_index);
+                let lim = u64::from(limit);
+                (from..std::cmp::min(from + lim, account_mentions.len()))
+                    .map(|idx| account_mentions.get(idx).unwrap())
+                    .filter(|msg_id| !self.deleted_posts.contains(&msg_id.post_id))
+                    .map(MessageID::from)
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    pub fn get_account_mentions_page(&self, account_id: AccountId, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> PageDTO<MessageID> {
+        match self.accounts_mentions.get(&self.get_identity(account_id)) {
+            Some(account_mentions) => {
+                let (indexes, next_cursor) = get_cursor_page_indexes(account_mentions.len(), cursor, limit, reverse);
+                let items = indexes
+                    .into_iter()
+                    .map(|idx| account_mentions.get(idx).unwrap())
+                    .filter(|msg_id| !self.deleted_posts.contains(&msg_id.post_id))
+                    .map(MessageID::from)
+                    .collect();
+                PageDTO { items, next_cursor }
+            },
+            None => PageDTO { items: Vec::new(), next_cursor: None }
+        }
+    }
+
+    pub fn get_account_mentions_count(&self, account_id: AccountId) -> U64 {
+        let count = self.accounts_mentions
+            .get(&self.get_identity(account_id))
+            .map_or(0, |account_mentions| account_mentions.len());
+        U64(count)
+    }
+
+    pub fn mark_notifications_read(&mut self) -> Promise {
+        self.assert_app_scope(AppScope::Profile);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.collect_fee_and_execute_call(1, account_id, Call::MarkNotificationsRead)
+    }
+
+    // Newest notifications go first, 'from_index' is counted from the newest one. Notifications about deleted posts
+    // are skipped, so a page can be shorter than 'limit'
+    pub fn get_notifications(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<NotificationDTO> {
+        match self.accounts_notifications.get(&self.get_identity(account_id)) {
+            Some(notifications_log) => {
+                let from = std::cmp::min(u64::from(from_index), notifications_log.items.len());
+                let count = std::cmp::min(u64::from(limit), notifications_log.items.len() - from);
+                (0..count)
+                    .map(|offset| {
+                        let idx = notifications_log.total - 1 - from - offset;
+                        (idx, notifications_log.items.get(idx % MAX_NOTIFICATIONS).unwrap())
+                    })
+                    .filter(|(_, notification)| !notification.kind.get_post_id().is_some_and(|post_id| self.deleted_posts.contains(post_id)))
+                    .map(|(idx, notification)| {
+                        NotificationDTO {
+                            idx: U64(idx),
+                            kind: notification.kind,
+                            actor: notification.actor,
+                            timestamp: U64(notification.timestamp),
+                            is_read: idx < notifications_log.read
+                        }
+                    })
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    pub fn get_unread_notifications_count(&self, account_id: AccountId) -> U64 {
+        let count = self.accounts_notifications
+            .get(&self.get_identity(account_id))
+            .map_or(0, |notifications_log| std::cmp::min(notifications_log.total - notifications_log.read, notifications_log.items.len()));
+        U64(count)
+    }
+
+    pub fn get_message_likes(&self, msg_id: MessageID, from_index: U64, limit: U64) -> Vec<AccountId> {
+        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
+            use std::convert::TryFrom;
+            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
+                post_message_likes
+                    .iter()
+                    .skip(from)
+                    .take(lim)
+                    .collect()
+            } else {
+                ContractError::UsizeConversionFailed.panic();
+            }
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_message_likes_info(&self, msg_id: MessageID, account_id: AccountId) -> LikesInfoDTO {
+        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
+            LikesInfoDTO {
+                likes_count: U64(post_message_likes.len()),
+                is_liked: post_message_likes.contains(&account_id),
+                weighted_likes_count: U64(post_message_likes.len())
+            }
+        } else {
+            LikesInfoDTO {
+                likes_count: U64(0),
+                is_liked: false,
+                weighted_likes_count: U64(0)
+            }
+        }
+    }
+    
+    pub fn get_account_last_likes(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<(PostId, Option<U64>)> {
+        let account_id = self.get_identity(account_id);
+        if self.get_likes_visibility(&account_id) == LikesVisibility::Private {
+            ContractError::AccountLikesArePrivate.panic();
+        };
+        self.internal_get_account_last_likes(account_id, from_index, limit)
+    }
+
+    // Views can't authenticate the caller, so the account reads its own private likes with a call.
+    // The predecessor isn't available in view calls, so this method can't be called as a view
+    pub fn get_own_last_likes(&self, from_index: U64, limit: U64) -> Vec<(PostId, Option<U64>)> {
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.internal_get_account_last_likes(account_id, from_index, limit)
+    }
+
+    // Unlike the recent likes, the history keeps every like of the account, including the unliked ones.
+    // Every like recorded to the history is paid by the account
+    pub fn enable_likes_history(&mut self) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_enable_likes_history_call(&account_id);
+        let fee = self.calc_enable_likes_history_fee(&account_id);
+        // log!("enable_likes_history fee {}", fee);
+        self.collect_fee_and_execute_call(fee, account_id, Call::EnableLikesHistory)
+    }
+
+    // The recorded history is removed
+    pub fn disable_likes_history(&mut self) -> Promise {
+        self.assert_app_scope(AppScope::Likes);
+        let account_id = self.get_identity(env::predecessor_account_id());
+        self.assert_disable_likes_history_call(&account_id);
+        self.collect_fee_and_execute_call(1, account_id, Call::DisableLikesHistory)
+    }
+
+    pub fn is_likes_history_enabled(&self, account_id: AccountId) -> bool {
+        self.accounts_likes_history.contains_key(&self.get_identity(account_id))
+    }
+
+    pub fn get_account_likes_count(&self, account_id: AccountId) -> U64 {
+        let count = self.accounts_likes_history
+            .get(&self.get_identity(account_id))
+            .map_or(0, |likes_history| likes_history.len());
+        U64(count)
+    }
+
+    // Likes of deleted posts are skipped, so a page can be shorter than 'limit'
+    pub fn get_account_likes_history(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<(PostId, Option<U64>)> {
+        let account_id = self.get_identity(account_id);
+        if self.get_likes_visibility(&account_id) == LikesVisibility::Private {
+            ContractError::AccountLikesArePrivate.panic();
+        };
+        match self.accounts_likes_history.get(&account_id) {
+            Some(likes_history) => {
+                let from = u64::from(from_index);
+                let lim = u64::from(limit);
+                (from..std::cmp::min(from + lim, likes_history.len()))
+                    .map(|idx| match likes_history.get(idx).unwrap() {
+                        AccountLike::PostLike { post_id } => (post_id, None),
+                        AccountLike::MessageLike { msg_id } => (msg_id.post_id, Some(U64(msg_id.msg_idx)))
+                    })
+                    .filter(|(post_id, _)| !self.deleted_posts.contains(post_id))
+                    .collect()
+            },
+            None => Vec::new()
+        }
+    }
+
+    fn get_likes_visibility(&self, account_id: &AccountId) -> LikesVisibility {
+        match self.accounts_profiles.get(account_id) {
+            Some(account_profile) => account_profile.likes_visibility,
+            None => LikesVisibility::Public
+        }
+    }
+
+    fn internal_get_account_last_likes(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<(PostId, Option<U64>)> {
+        if let Some(accounts_stats) = self.accounts_stats.get(&account_id) {
+            accounts_stats.recent_likes
+                .into_iter()
+                .skip(usize::try_from(u64::from(from_index)).ok().unwrap())
+                .take(usize::try_from(u64::from(limit)).ok().unwrap())
+                .map(|item| {
+                    match item {
+                        AccountLike::PostLike { post_id } => {
+                            (post_id, None)
+                        },
+                        AccountLike::MessageLike { msg_id } => {
+                            (msg_id.post_id, Some(U64(msg_id.msg_idx)))
+                        }
+                    }
+                })
+                .collect()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_account_friends(&self, account_id: AccountId, from_index: U64, limit: U64, reverse: Option<bool>) -> Vec<AccountId> {
+        get_accounts_set_page(self.accounts_friends.get(&self.get_identity(account_id)), from_index, limit, reverse.unwrap_or(false))
+    }
+
+    pub fn get_account_friends_page(&self, account_id: AccountId, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> PageDTO<AccountId> {
+        get_accounts_set_cursor_page(self.accounts_friends.get(&self.get_identity(account_id)), cursor, limit, reverse)
+    }
+
+    // Friendship is reciprocal when both accounts added each other
+    pub fn are_friends(&self, a: AccountId, b: AccountId) -> bool {
+        let has_friend = |account_id: &AccountId, friend_id: &AccountId| {
+            self.accounts_friends.get(account_id).is_some_and(|account_friends| account_friends.contains(friend_id))
+        };
+        let (a, b) = (self.get_identity(a), self.get_identity(b));
+        has_friend(&a, &b) && has_friend(&b, &a)
+    }
+
+    // Iterates the smaller of the two friend sets and stops as soon as 'limit' mutual friends are found
+    // 'from_index' and 'limit' are applied to the mutual friends, not to the friends of either account
+    pub fn get_mutual_friends(&self, account_a: AccountId, account_b: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        let (account_a, account_b) = (self.get_identity(account_a), self.get_identity(account_b));
+        let (a_friends, b_friends) = match (self.accounts_friends.get(&account_a), self.accounts_friends.get(&account_b)) {
+            (Some(a_friends), Some(b_friends)) => (a_friends, b_friends),
+            _ => return Vec::new()
+        };
+        let from = usize::try_from(u64::from(from_index)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic());
+        let lim = usize::try_from(u64::from(limit)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic());
+        let (smaller, larger) = if a_friends.len() <= b_friends.len() { (a_friends, b_friends) } else { (b_friends, a_friends) };
+        smaller
+            .iter()
+            .filter(|friend_id| larger.contains(friend_id))
+            .skip(from)
+            .take(lim)
+            .collect()
+    }
+
+    // The friends collection keeps its length, so no separate counter is stored
+    pub fn get_friends_count(&self, account_id: AccountId) -> U64 {
+        U64(self.accounts_friends.get(&self.get_identity(account_id)).map_or(0, |account_friends| account_friends.len()))
+    }
+
+    // Scans a page of accounts that have ever added a friend and returns those with at least 'min_friends' friends.
+    // 'next_index' is None when the last page is reached
+    pub fn get_accounts_with_min_friends(&self, min_friends: U64, from_index: U64, limit: U64) -> AccountsPageDTO {
+        use std::convert::TryFrom;
+        if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
+            let accounts = self.accounts_with_friends
+                .iter()
+                .skip(from)
+                .take(lim)
+                .filter(|account_id| {
+                    self.accounts_friends.get(account_id).map_or(0, |account_friends| account_friends.len()) >= u64::from(min_friends)
+                })
+                .collect();
+            let next_index = from.saturating_add(lim);
+            AccountsPageDTO {
+                accounts,
+                next_index: if (next_index as u64) < self.accounts_with_friends.len() { Some(U64::from(next_index as u64)) } else { None }
+            }
+        } else {
+            ContractError::UsizeConversionFailed.panic();
+        }
+    }
+
+    // Posts engaged with in the current window, ordered by the decayed score
+    pub fn get_trending_posts(&self, limit: U64) -> Vec<TrendingPostDTO> {
+        let mut trending_posts: Vec<TrendingPostDTO> = self.trending_posts
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(_, post_score)| is_current_trending_window(post_score))
+            .map(|(post_id, post_score)| TrendingPostDTO {
+                post_id,
+                score: U128(self.calc_decayed_score(&post_score))
+            })
+            .collect();
+
+        trending_posts.sort_by_key(|post| std::cmp::Reverse(post.score.0));
+        trending_posts.truncate(usize::try_from(u64::from(limit)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic()));
+        trending_posts
+    }
+
+    // Removes the trending posts not engaged with in the current window. Anyone can call it, e.g. a cron job
+    pub fn prune_trending_posts(&mut self) -> U64 {
+        let mut trending_posts = self.trending_posts.get().unwrap_or_default();
+        let trending_posts_count = trending_posts.len();
+        trending_posts.retain(|(_, post_score)| is_current_trending_window(post_score));
+        let pruned = trending_posts_count - trending_posts.len();
+        if pruned != 0 {
+            self.trending_posts.set(&trending_posts);
+        };
+        U64(pruned as u64)
+    }
+
+    pub fn get_leaderboard(&self, kind: LeaderboardKind, limit: U64) -> Vec<LeaderboardEntryDTO> {
+        let mut leaderboard = self.leaderboards.get(&kind).unwrap_or_default();
+        leaderboard.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+        leaderboard
+            .into_iter()
+            .take(usize::try_from(u64::from(limit)).unwrap_or_else(|_| ContractError::UsizeConversionFailed.panic()))
+            .map(|(account_id, value)| LeaderboardEntryDTO { account_id, value: U64(value) })
+            .collect()
+    }
+
+    pub fn get_leaderboard_size(&self) -> u8 {
+        self.leaderboard_size
+    }
+
+    // Leaderboards are filled incrementally as the stats change, 0 disables them. Shrinking keeps the top entries
+    pub fn set_leaderboard_size(&mut self, size: u8) {
+        self.assert_owner();
+        if size > MAX_LEADERBOARD_SIZE {
+            ContractError::InvalidLeaderboardSize.panic();
+        };
+        self.leaderboard_size = size;
+        for kind in [LeaderboardKind::MostLiked, LeaderboardKind::MostActive] {
+            if let Some(mut leaderboard) = self.leaderboards.get(&kind) {
+                leaderboard.sort_by_key(|entry| std::cmp::Reverse(entry.1));
+                leaderboard.truncate(usize::from(size));
+                self.leaderboards.insert(&kind, &leaderboard);
+            };
+        }
+    }
+
+    pub fn get_account_streak(&self, account_id: AccountId) -> AccountStreakDTO {
+        let account_id = self.get_identity(account_id);
+        match self.accounts_stats.get(&account_id) {
+            Some(account_stats) => AccountStreakDTO {
+                current_streak: self.calc_current_streak(&account_stats),
+                longest_streak: account_stats.longest_streak,
+                last_active_day: U64(account_stats.last_active_day),
+                first_seen_at: U64(account_stats.first_seen_at)
+            },
+            None => AccountStreakDTO {
+                current_streak: 0,
+                longest_streak: 0,
+                last_active_day: U64(0),
+                first_seen_at: U64(0)
+            }
+        }
+    }
+
+    // Likes counters are decreased by unlikes, likes removed with deleted content are kept
+    pub fn get_account_stats(&self, account_id: AccountId) -> AccountStatsDTO {
+        let account_id = self.get_identity(account_id);
+        let friends_count = self.get_friends_count(account_id.clone());
+        match self.accounts_stats.get(&account_id) {
+            Some(account_stats) => AccountStatsDTO {
+                messages_count: U64(account_stats.messages_count),
+                likes_given: U64(account_stats.likes_given),
+                likes_received: U64(account_stats.likes_received),
+                friends_count,
+                tips_received: U128(account_stats.tips_received)
+            },
+            None => AccountStatsDTO {
+                messages_count: U64(0),
+                likes_given: U64(0),
+                likes_received: U64(0),
+                friends_count,
+                tips_received: U128(0)
+            }
+        }
+    }
+
+    pub fn get_account_badges(&self, account_id: AccountId) -> Vec<Badge> {
+        let account_id = self.get_identity(account_id);
+        if let Some(account_badges) = self.accounts_badges.get(&account_id) {
+            account_badges.to_vec()
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_profile(&self, account_id: AccountId) -> Option<AccountProfileData> {
+        if let Some(account_profile) = self.accounts_profiles.get(&account_id) {
+            Some(AccountProfileData {
+              json_metadata: Some(account_profile.json_metadata),
+              image: account_profile.image.get().map(Base64VecU8::from),
+              image_url: Some(account_profile.image_url),
+              likes_visibility: Some(account_profile.likes_visibility),
+              wall_policy: Some(account_profile.wall_policy)
+            })
+        } else {
+            None
+        }
+    }
+
+    pub fn get_moderation_log(&self, from_index: U64, limit: U64) -> Vec<ModerationRecordDTO> {
+        let from = u64::from(from_index);
+        let lim = u64::from(limit);
+
+        (from..std::cmp::min(from + lim, self.moderation_log.len()))
+            .map(|idx| {
+                let record = self.moderation_log.get(idx).unwrap();
+                ModerationRecordDTO {
+                    actor: record.actor,
+                    action: record.action,
+                    target: record.target,
+                    reason_hash: record.reason_hash.map(Base64VecU8::from),
+                    timestamp: U64(record.timestamp)
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_moderation_log_len(&self) -> U64 {
+        U64(self.moderation_log.len())
+    }
+
+    fn add_moderation_record(&mut self, action: ModerationAction, target: ModerationTarget, reason_hash: Option<Base64VecU8>) {
+        let record = ModerationRecord {
+            actor: env::predecessor_account_id(),
+            action,
+            target,
+            reason_hash: reason_hash.map(|hash| hash.into()),
+            timestamp: env::block_timestamp()
+        };
+        self.moderation_log.push(&record);
+    }
+
+    pub fn get_app_key_scopes(&self, account_id: AccountId, public_key: PublicKey) -> Option<Vec<AppScope>> {
+        self.accounts_app_keys.get(&AccountAppKey { account_id, public_key })
+    }
+
+    pub fn get_identity(&self, account_id: AccountId) -> AccountId {
+        self.accounts_identities.get(&account_id).unwrap_or(account_id)
+    }
+
+    pub fn get_linked_accounts(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
+        if let Some(account_links) = self.accounts_links.get(&account_id) {
+            use std::convert::TryFrom;
+            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
+                account_links
+                    .iter()
+                    .skip(from)
+                    .take(lim)
+                    .collect()
+            } else {
+                ContractError::UsizeConversionFailed.panic();
+            }
+        } else {
+            Vec::new()
+        }
+    }
+
+    pub fn get_account_link_request(&self, account_id: AccountId) -> Option<AccountId> {
+        self.accounts_link_requests.get(&account_id)
+    }
+
+    pub fn set_config_contract(&mut self, config_contract: AccountId) {
+        self.assert_owner();
+        self.config_contract = Some(config_contract);
+    }
+
+    // Account links are pushed to the marketplace, so purchases of linked accounts aggregate to the identity
+    pub fn set_marketplace(&mut self, marketplace: AccountId) {
+        self.assert_owner();
+        self.marketplace = Some(marketplace);
+    }
+
+    pub fn refresh_config(&mut self) -> Promise {
+        let config_contract = self.config_contract.clone().unwrap_or_else(|| ContractError::ConfigContractNotSet.panic());
+        ext_config::ext(config_contract)
+            .with_static_gas(Gas(5*TGAS))
+            .get_config()
+                .then(
+                    ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .on_config_refreshed()
+                )
+    }
+
+    #[private]
+    pub fn on_config_refreshed(&mut self) -> bool {
+        if let Some(result) = promise_result_as_success() {
+            let config = serde_json::from_slice::<ConfigDTO>(&result).ok().unwrap_or_else(|| ContractError::UnexpectedPromiseResult.panic());
+            self.cached_config.update(&config, config.social_network_paused);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn get_cached_config(&self) -> CachedConfigDTO {
+        self.cached_config.to_dto()
+    }
+
+    pub fn get_exchange_rate(&self) -> U128 {
+        U128(self.cached_config.activity_ft_exchange_rate())
+    }
+
+    pub fn convert_fee(&self, fee_ft_amount: U128) -> FeeConversionDTO {
+        let exchange_rate = self.cached_config.activity_ft_exchange_rate();
+        FeeConversionDTO {
+            fee_ft_amount,
+            near_amount: U128(u128::from(fee_ft_amount).checked_div(exchange_rate).unwrap_or(0)),
+            exchange_rate: U128(exchange_rate)
+        }
+    }
+
+    pub fn get_error_codes(&self) -> Vec<ErrorCodeDTO> {
+        error_codes()
+    }
+
+    pub fn get_admin_settings(&self) -> AdminSettings {
+        self.admin_settings
+    }
+
+    pub fn get_storage_settings(&self) -> StorageUsageSettings {
+        self.storage_usage_settings
+    }
+
+
+    // Fee quotes (activity FT amount the call collects, including the account activity fee)
+
+    pub fn get_add_message_fee(&self, account_id: AccountId, post_id: PostId, text: String, lang: Option<String>, delegate_id: Option<AccountId>, sensitive: Option<bool>) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_add_message_to_post_call(&post_id, &text);
+        let lang = self.normalize_lang(lang);
+        let payload_len = text.len() + calc_delegate_bytes(&delegate_id) + calc_lang_bytes(&lang)
+            + calc_sensitive_bytes(&self.get_sensitive_marker(&account_id, sensitive.unwrap_or(false)));
+        let fee = self.calc_add_payload_to_post_fee(&account_id, &post_id, payload_len)
+            + self.calc_post_score_fee(&post_id)
+            + self.calc_mentions_fee(&account_id, &self.get_message_post_id(&post_id).0, &text);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_add_reply_fee(&self, account_id: AccountId, parent_msg_id: MessageID, text: String, lang: Option<String>, delegate_id: Option<AccountId>, sensitive: Option<bool>) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_add_message_to_message_call(&account_id, &parent_msg_id, &text);
+        let lang = self.normalize_lang(lang);
+        let payload_len = text.len() + calc_delegate_bytes(&delegate_id) + calc_lang_bytes(&lang)
+            + calc_sensitive_bytes(&self.get_sensitive_marker(&account_id, sensitive.unwrap_or(false)));
+        let fee = self.calc_add_message_to_message_fee(&account_id, &parent_msg_id, payload_len)
+            + self.calc_post_score_fee(&parent_msg_id.post_id)
+            + self.calc_mentions_fee(&account_id, &parent_msg_id.post_id, &text);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_post_on_wall_fee(&self, account_id: AccountId, target_account: AccountId, text: String) -> U128 {
+        let account_id = self.get_identity(account_id);
+        let target_account = self.get_identity(target_account);
+        self.assert_post_on_wall_call(&account_id, &target_account, &text);
+        let fee = self.calc_post_on_wall_fee(&account_id, &target_account, &text);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_send_direct_message_fee(&self, account_id: AccountId, recipient_id: AccountId, encrypted_payload: Base64VecU8) -> U128 {
+        let account_id = self.get_identity(account_id);
+        let recipient_id = self.get_identity(recipient_id);
+        self.assert_send_direct_message_call(&account_id, &recipient_id, &encrypted_payload);
+        let fee = self.calc_send_direct_message_fee(&account_id, &recipient_id, encrypted_payload.0.len());
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_edit_message_fee(&self, account_id: AccountId, msg_id: MessageID, text: String) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_edit_message_call(&account_id, &msg_id, &text);
+        let fee = self.calc_edit_message_fee(&msg_id, &text);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_like_post_fee(&self, account_id: AccountId, post_id: PostId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_like_post_call(&account_id, &post_id);
+        let fee = self.calc_like_post_fee(&account_id, &post_id)
+            + self.calc_account_recent_likes_fee(&account_id, &post_id, false)
+            + self.calc_account_likes_history_fee(&account_id, &post_id, false)
+            + self.calc_post_score_fee(&post_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_super_like_post_fee(&self, account_id: AccountId, post_id: PostId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_super_like_post_call(&account_id, &post_id);
+        let fee = self.calc_super_like_post_fee(&account_id, &post_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_repost_fee(&self, account_id: AccountId, post_id: PostId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_repost_call(&account_id, &post_id);
+        let fee = self.calc_repost_fee(&account_id, &post_id)
+            + self.calc_post_score_fee(&post_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_like_message_fee(&self, account_id: AccountId, msg_id: MessageID) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_like_message_call(&account_id, &msg_id);
+        let fee = self.calc_like_message_fee(&account_id, &msg_id)
+            + self.calc_account_recent_likes_fee(&account_id, &msg_id.post_id, true)
+            + self.calc_account_likes_history_fee(&account_id, &msg_id.post_id, true)
+            + self.calc_post_message_likes_count_fee(&msg_id.post_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_batch_fee(&self, account_id: AccountId, calls: Vec<BatchCall>) -> U128 {
+        let account_id = self.get_identity(account_id);
+        let (fee, _) = self.prepare_batch_calls(&account_id, calls);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_add_friend_fee(&self, account_id: AccountId, friend_id: AccountId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_add_friend_call(&account_id, &friend_id);
+        let fee = self.calc_add_friend_fee(&account_id, &friend_id)
+            + self.calc_notification_fee(&account_id, &Some(friend_id.clone()), None);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_verify_via_nft_fee(&self, account_id: AccountId, contract_id: AccountId, token_id: String) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_verify_via_nft_call(&account_id, &contract_id, &token_id);
+        let fee = self.calc_verify_account_fee(&account_id, &contract_id, &token_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_send_friend_request_fee(&self, account_id: AccountId, friend_id: AccountId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_send_friend_request_call(&account_id, &friend_id);
+        let fee = self.calc_send_friend_request_fee(&account_id, &friend_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_accept_friend_request_fee(&self, account_id: AccountId, requester_id: AccountId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_friend_request_exists(&requester_id, &account_id);
+        let fee = self.calc_accept_friend_request_fee(&account_id, &requester_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_import_friends_fee(&self, account_id: AccountId, friends: Vec<AccountId>) -> U128 {
+        let account_id = self.get_identity(account_id);
+        let fee = self.calc_import_friends_fee(&account_id, &friends);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_update_profile_fee(&self, account_id: AccountId, profile: AccountProfileData) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_update_profile_call(&profile);
+        let fee = std::cmp::max(self.calc_update_profile_fee(&account_id, &profile), 1);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_unlike_post_fee(&self, account_id: AccountId, post_id: PostId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_unlike_post_call(&account_id, &post_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::PostLike { account_id: account_id.clone(), post_id });
+        self.quote_fee(fee, &account_id)
+    }
+
+    // Includes the tip 'amount'
+    pub fn get_tip_message_fee(&self, account_id: AccountId, msg_id: MessageID, amount: U128) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_tip_message_call(&account_id, &msg_id, amount);
+        let fee = self.calc_message_tips_fee(&msg_id);
+        U128(u128::from(self.quote_fee(fee, &account_id)) + u128::from(amount))
+    }
+
+    // Activity FT fee, the NEAR tip is attached to the call
+    pub fn get_tip_message_near_fee(&self, account_id: AccountId, msg_id: MessageID) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_tip_message_call(&account_id, &msg_id, U128(1));
+        let fee = self.calc_message_near_tips_fee(&account_id, &msg_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_react_to_post_fee(&self, account_id: AccountId, post_id: PostId, reaction: Reaction) -> U128 {
+        if reaction == Reaction::Like {
+            return self.get_like_post_fee(account_id, post_id)
+        }
+        let account_id = self.get_identity(account_id);
+        self.assert_react_to_post_call(&account_id, &post_id, reaction);
+        let fee = self.calc_react_to_post_fee(&account_id, &post_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_react_to_message_fee(&self, account_id: AccountId, msg_id: MessageID, reaction: Reaction) -> U128 {
+        if reaction == Reaction::Like {
+            return self.get_like_message_fee(account_id, msg_id)
+        }
+        let account_id = self.get_identity(account_id);
+        self.assert_react_to_message_call(&account_id, &msg_id, reaction);
+        let fee = self.calc_react_to_message_fee(&account_id, &msg_id);
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_unlike_message_fee(&self, account_id: AccountId, msg_id: MessageID) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_unlike_message_call(&account_id, &msg_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::MessageLike { account_id: account_id.clone(), msg_id: (&msg_id).into() });
+        self.quote_fee(fee, &account_id)
+    }
+
+    pub fn get_remove_friend_fee(&self, account_id: AccountId, friend_id: AccountId) -> U128 {
+        let account_id = self.get_identity(account_id);
+        self.assert_remove_friend_call(&account_id, &friend_id);
+        let fee = self.calc_reversal_fee(&ReversibleAction::Friend { account_id: account_id.clone(), friend_id });
+        self.quote_fee(fee, &account_id)
+    }
+
+    fn quote_fee(&self, fee: u128, account_id: &AccountId) -> U128 {
+        U128::from(self.calc_probation_fee(account_id, fee + self.calc_account_activity_fee(account_id)))
+    }
+
+
+    // Assert incoming call
+
+    fn assert_add_message_to_post_call(&self, post_id: &PostId, text: &String) {
+        self.assert_text(text);
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+        self.assert_post_comments_not_locked(post_id);
+    }
+
+    fn assert_post_comments_not_locked(&self, post_id: &PostId) {
+        if self.posts_stats.get(post_id).is_some_and(|post_stats| post_stats.comments_locked) {
+            ContractError::PostCommentsLocked.panic();
+        };
+    }
+
+    fn assert_add_listing_to_post_call(&self, post_id: &PostId, listing_id: &str) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+        self.assert_post_comments_not_locked(post_id);
+
+        if listing_id.trim().is_empty() {
+            ContractError::EmptyListingId.panic();
+        };
+
+        if listing_id.len() > MAX_LISTING_ID_LEN {
+            ContractError::ListingIdTooLong.panic();
+        };
+    }
+
+    // Quoted post (or its message) must exist and must not be deleted
+    fn assert_add_quote_to_post_call(&self, account_id: &AccountId, post_id: &PostId, quoted_post_id: &PostId, quoted_msg_idx: &Option<U64>, text: &String) {
+        self.assert_add_message_to_post_call(post_id, text);
+        self.assert_post_id(quoted_post_id);
+
+        let quoted_post_messages = self.posts_messages.get(quoted_post_id).unwrap_or_else(|| ContractError::QuotedContentNotFound.panic());
+        let quoted_msg_idx = quoted_msg_idx.map_or(0, u64::from);
+        let quoted_msg = quoted_post_messages.get(quoted_msg_idx).unwrap_or_else(|| ContractError::QuotedContentNotFound.panic());
+        if quoted_msg.payload.is_deleted() {
+            ContractError::QuotedContentNotFound.panic();
+        };
+        self.assert_not_blocked_by(account_id, &quoted_msg.account);
+    }
+
+    // Text is an optional caption. CID is a base32 (CIDv1) or base58 (CIDv0) string, only images and videos are allowed
+    fn assert_add_media_to_post_call(&self, post_id: &PostId, text: &str, media_cid: &str, mime_type: &str) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+        self.assert_post_comments_not_locked(post_id);
+
+        let max_media_cid_len = usize::from(self.admin_settings.max_media_cid_len);
+        if max_media_cid_len == 0 {
+            ContractError::MediaDisabled.panic();
+        };
+
+        if !text.is_empty() && text.trim().is_empty() {
+            ContractError::EmptyText.panic();
+        };
+
+        if media_cid.len() < MIN_MEDIA_CID_LEN || media_cid.len() > max_media_cid_len || !media_cid.chars().all(|c| c.is_ascii_alphanumeric()) {
+            ContractError::InvalidMediaCid.panic();
+        };
+
+        let mime_type_valid = mime_type.len() <= usize::from(self.admin_settings.max_media_mime_type_len)
+            && MEDIA_MIME_TYPES.iter().any(|prefix| mime_type.len() > prefix.len() && mime_type.starts_with(prefix))
+            && mime_type.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || "/.+-".contains(c));
+        if !mime_type_valid {
+            ContractError::InvalidMediaMimeType.panic();
+        };
+    }
+
+    // URL must be http(s) with a host and without whitespace or control characters
+    fn assert_add_link_to_post_call(&self, post_id: &PostId, url: &str, title: &Option<String>, description: &Option<String>) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+        self.assert_post_comments_not_locked(post_id);
+
+        if url.len() > MAX_LINK_URL_LEN {
+            ContractError::InvalidLinkUrl.panic();
+        };
+        let host = LINK_URL_SCHEMES.iter()
+            .find_map(|scheme| url.strip_prefix(scheme))
+            .and_then(|rest| rest.split(&['/', '?', '#'][..]).next())
+            .unwrap_or("");
+        if host.is_empty() || !url.chars().all(|c| c.is_ascii_graphic()) {
+            ContractError::InvalidLinkUrl.panic();
+        };
+
+        if title.as_ref().is_some_and(|title| title.trim().is_empty() || title.len() > MAX_LINK_TITLE_LEN) {
+            ContractError::InvalidLinkPreview.panic();
+        };
+        if description.as_ref().is_some_and(|description| description.trim().is_empty() || description.len() > MAX_LINK_DESCRIPTION_LEN) {
+            ContractError::InvalidLinkPreview.panic();
+        };
+    }
+
+    fn assert_add_message_to_message_call(&self, account_id: &AccountId, parent_msg_id: &MessageID, text: &String) {
+        self.assert_text(text);
+        self.assert_message_id(parent_msg_id);
+
+        let post_id = &parent_msg_id.post_id;
+        let msg_idx: u64 = parent_msg_id.msg_idx.into();
+        self.assert_post_not_being_deleted(post_id);
+        self.assert_post_comments_not_locked(post_id);
+        
+        if let Some(post_messages) = self.posts_messages.get(post_id) {
+            let parent_msg = post_messages.get(msg_idx).unwrap_or_else(|| ContractError::ParentMessageNotFound.panic());
+            self.assert_not_blocked_by(account_id, &parent_msg.account);
+            if self.is_post_full(&post_messages) {
+                ContractError::PostMessagesLimitReached.panic();
+            };
+        } else {
+            ContractError::PostNotFound.panic();
+        };
+    }
+
+    fn assert_edit_message_call(&self, account_id: &AccountId, msg_id: &MessageID, text: &String) {
+        self.assert_text(text);
+        self.assert_message_id(msg_id);
+
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg = post_messages.get(msg_id.msg_idx.into()).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        if &msg.account != account_id {
+            ContractError::NotMessageAuthor.panic();
+        };
+
+        if !matches!(msg.payload, MessagePayload::Text { .. }) {
+            ContractError::MessageNotEditable.panic();
+        };
+
+        let edit_window = u64::from(self.admin_settings.message_edit_window_sec) * 1_000_000_000;
+        if env::block_timestamp().saturating_sub(msg.timestamp) > edit_window {
+            ContractError::MessageEditWindowExpired.panic();
+        };
+    }
+
+    fn assert_like_post_call(&self, account_id: &AccountId, post_id: &PostId) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+
+        if let Some(first_msg) = self.posts_messages.get(post_id).and_then(|post_messages| post_messages.get(0)) {
+            self.assert_not_blocked_by(account_id, &first_msg.account);
+        };
+
+        if let Some(post_likes) = self.posts_likes.get(post_id) {
+            if post_likes.contains(account_id) {
+                ContractError::PostAlreadyLiked.panic();
+            };
+        };
+    }
+
+    fn assert_tip_message_call(&self, account_id: &AccountId, msg_id: &MessageID, amount: U128) {
+        if u128::from(amount) == 0 {
+            ContractError::InvalidTipAmount.panic();
+        };
+        self.assert_message_id(msg_id);
+        self.assert_message_existence(msg_id);
+        self.assert_message_not_deleted(msg_id);
+        self.assert_post_not_being_deleted(&msg_id.post_id);
+
+        let msg = self.posts_messages.get(&msg_id.post_id).and_then(|post_messages| post_messages.get(msg_id.msg_idx.into())).unwrap();
+        if msg.account == *account_id {
+            ContractError::CannotTipSelf.panic();
+        };
+        self.assert_not_blocked_by(account_id, &msg.account);
+    }
+
+    fn assert_super_like_post_call(&self, account_id: &AccountId, post_id: &PostId) {
+        if self.admin_settings.super_like_fee_multiplier == 0 {
+            ContractError::SuperLikesDisabled.panic();
+        };
+        self.assert_like_post_call(account_id, post_id);
+    }
+
+    fn assert_repost_call(&self, account_id: &AccountId, post_id: &PostId) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+
+        let first_msg = self.posts_messages.get(post_id)
+            .and_then(|post_messages| post_messages.get(0))
+            .unwrap_or_else(|| ContractError::PostNotFound.panic());
+        self.assert_not_blocked_by(account_id, &first_msg.account);
+
+        if let Some(post_reposts) = self.posts_reposts.get(post_id) {
+            if post_reposts.contains(account_id) {
+                ContractError::PostAlreadyReposted.panic();
+            };
+        };
+    }
+
+    fn assert_unlike_post_call(&self, account_id: &AccountId, post_id: &PostId) {
+        self.assert_post_id(post_id);
+
+        if let Some(post_likes) = self.posts_likes.get(post_id) {
+            if !post_likes.contains(account_id) {
+                ContractError::PostNotLiked.panic();
+            };
+        } else {
+            ContractError::PostNotLiked.panic();
+        };
+
+        self.assert_reversal_allowed(&ReversibleAction::PostLike { account_id: account_id.clone(), post_id: post_id.clone() });
+    }
+
+    fn assert_delete_message_call(&self, account_id: &AccountId, msg_id: &MessageID) {
+        self.assert_message_id(msg_id);
+
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg = post_messages.get(msg_id.msg_idx.into()).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        if &msg.account != account_id {
+            ContractError::NotMessageAuthor.panic();
+        };
+
+        if msg.payload.is_deleted() {
+            ContractError::MessageDeleted.panic();
+        };
+    }
+
+    fn assert_set_message_sensitive_call(&self, account_id: &AccountId, msg_id: &MessageID, sensitive: bool) {
+        self.assert_message_id(msg_id);
+
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg = post_messages.get(msg_id.msg_idx.into()).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        if &msg.account != account_id {
+            ContractError::NotMessageAuthor.panic();
+        };
+
+        if !sensitive && msg.sensitive == Some(SensitiveMarker::Moderator) {
+            ContractError::SensitiveMarkForced.panic();
+        };
+    }
+
+    fn assert_like_message_call(&self, account_id: &AccountId, msg_id: &MessageID) {        
+        self.assert_message_id(msg_id);
+        self.assert_message_existence(msg_id);
+        self.assert_message_not_deleted(msg_id);
+        self.assert_post_not_being_deleted(&msg_id.post_id);
+
+        let msg = self.posts_messages.get(&msg_id.post_id).and_then(|post_messages| post_messages.get(msg_id.msg_idx.into())).unwrap();
+        self.assert_not_blocked_by(account_id, &msg.account);
+
+        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
+            if post_message_likes.contains(account_id) {
+                ContractError::MessageAlreadyLiked.panic();
+            };
+        };
+    }
+
+    fn assert_unlike_message_call(&self, account_id: &AccountId, msg_id: &MessageID) {        
+        self.assert_message_id(msg_id);
+        self.assert_message_existence(msg_id);
+
+        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
+            if !post_message_likes.contains(account_id) {
+                ContractError::MessageNotLiked.panic();
+            };
+        } else {
+            ContractError::MessageNotLiked.panic();
+        };
+
+        self.assert_reversal_allowed(&ReversibleAction::MessageLike { account_id: account_id.clone(), msg_id: msg_id.into() });
+    }
+
+    fn assert_send_friend_request_call(&self, account_id: &AccountId, friend_id: &AccountId) {
+        if account_id == friend_id {
+            ContractError::CannotFriendSelf.panic();
+        };
+
+        self.assert_not_blocked_by(account_id, friend_id);
+
+        if self.are_friends(account_id.clone(), friend_id.clone()) {
+            ContractError::FriendAlreadyAdded.panic();
+        };
+
+        if has_friend_request(&self.outgoing_friend_requests, account_id, friend_id) {
+            ContractError::FriendRequestAlreadySent.panic();
+        };
+
+        if has_friend_request(&self.outgoing_friend_requests, friend_id, account_id) {
+            ContractError::IncomingFriendRequestPending.panic();
+        };
+    }
+
+    fn assert_report_content_call(&self, account_id: &AccountId, content: &ReportedContent) {
+        let author_id = match content {
+            ReportedContent::Post { post_id } => {
+                self.assert_post_id(post_id);
+                let post_messages = self.posts_messages.get(post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+                post_messages.get(0).unwrap_or_else(|| ContractError::PostNotFound.panic()).account
+            },
+            ReportedContent::Message { msg_id } => {
+                let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+                post_messages.get(msg_id.msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic()).account
+            }
+        };
+
+        if &author_id == account_id {
+            ContractError::CannotReportOwnContent.panic();
+        };
+
+        if let Some(content_reports) = self.content_reports.get(content) {
+            if content_reports.get(account_id).is_some() {
+                ContractError::ContentAlreadyReported.panic();
+            };
+        };
+    }
+
+    fn assert_mute_account_call(&self, account_id: &AccountId, muted_id: &AccountId) {
+        if account_id == muted_id {
+            ContractError::CannotMuteSelf.panic();
+        };
+
+        if self.is_muted(account_id.clone(), muted_id.clone()) {
+            ContractError::AccountAlreadyMuted.panic();
+        };
+    }
+
+    fn assert_block_account_call(&self, account_id: &AccountId, blocked_id: &AccountId) {
+        if account_id == blocked_id {
+            ContractError::CannotBlockSelf.panic();
+        };
+
+        if self.is_blocked(account_id.clone(), blocked_id.clone()) {
+            ContractError::AccountAlreadyBlocked.panic();
+        };
+    }
+
+    fn assert_not_blocked_by(&self, account_id: &AccountId, other_id: &AccountId) {
+        if let Some(blocked_accounts) = self.blocked_accounts.get(other_id) {
+            if blocked_accounts.contains(account_id) {
+                ContractError::BlockedByAccount.panic();
+            };
+        };
+    }
+
+    fn assert_friend_request_exists(&self, requester_id: &AccountId, account_id: &AccountId) {
+        if !has_friend_request(&self.outgoing_friend_requests, requester_id, account_id) {
+            ContractError::FriendRequestNotFound.panic();
+        };
+    }
+
+    fn assert_add_friend_call(&self, account_id: &AccountId, friend_id: &AccountId) {
+        self.assert_not_blocked_by(account_id, friend_id);
+
+        if let Some(account_friends) = self.accounts_friends.get(account_id) {
+            if account_friends.contains(friend_id) {
+                ContractError::FriendAlreadyAdded.panic();
+            };
+        };
+    }
+
+    fn assert_remove_friend_call(&self, account_id: &AccountId, friend_id: &AccountId) {
+        if let Some(account_friends) = self.accounts_friends.get(account_id) {
+            if !account_friends.contains(friend_id) {
+                ContractError::FriendNotAdded.panic();
+            };
+        } else {
+            ContractError::FriendNotAdded.panic();
+        };
+
+        self.assert_reversal_allowed(&ReversibleAction::Friend { account_id: account_id.clone(), friend_id: friend_id.clone() });
+    }
+
+    fn assert_react_to_post_call(&self, account_id: &AccountId, post_id: &PostId, reaction: Reaction) {
+        self.assert_post_id(post_id);
+        self.assert_post_not_being_deleted(post_id);
+
+        let first_msg = self.posts_messages.get(post_id)
+            .and_then(|post_messages| post_messages.get(0))
+            .unwrap_or_else(|| ContractError::PostNotFound.panic());
+        self.assert_not_blocked_by(account_id, &first_msg.account);
+
+        if let Some(post_reactions) = self.posts_reactions.get(post_id) {
+            if post_reactions.accounts.get(account_id) == Some(reaction) {
+                ContractError::ReactionAlreadyAdded.panic();
+            };
+        };
+    }
+
+    fn assert_remove_post_reaction_call(&self, account_id: &AccountId, post_id: &PostId) {
+        self.assert_post_id(post_id);
+        let has_reaction = self.posts_reactions.get(post_id)
+            .is_some_and(|post_reactions| post_reactions.accounts.get(account_id).is_some());
+        if !has_reaction {
+            ContractError::ReactionNotFound.panic();
+        };
+    }
+
+    fn assert_react_to_message_call(&self, account_id: &AccountId, msg_id: &MessageID, reaction: Reaction) {
+        self.assert_message_id(msg_id);
+        self.assert_message_existence(msg_id);
+        self.assert_message_not_deleted(msg_id);
+        self.assert_post_not_being_deleted(&msg_id.post_id);
+
+        let msg = self.posts_messages.get(&msg_id.post_id).and_then(|post_messages| post_messages.get(msg_id.msg_idx.into())).unwrap();
+        self.assert_not_blocked_by(account_id, &msg.account);
+
+        if let Some(message_reactions) = self.posts_messages_reactions.get(&msg_id.into()) {
+            if message_reactions.accounts.get(account_id) == Some(reaction) {
+                ContractError::ReactionAlreadyAdded.panic();
+            };
+        };
+    }
+
+    fn assert_remove_message_reaction_call(&self, account_id: &AccountId, msg_id: &MessageID) {
+        self.assert_message_id(msg_id);
+        let has_reaction = self.posts_messages_reactions.get(&msg_id.into())
+            .is_some_and(|message_reactions| message_reactions.accounts.get(account_id).is_some());
+        if !has_reaction {
+            ContractError::ReactionNotFound.panic();
+        };
+    }
+
+    fn assert_reversal_allowed(&self, action: &ReversibleAction) {
+        if self.admin_settings.max_daily_reversals == 0 {
+            return
+        }
+
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        if let Some(counter) = self.reversal_counters.get(&get_reversal_key(action)) {
+            if counter.day == today && counter.count >= self.admin_settings.max_daily_reversals {
+                ContractError::ReversalLimitExceeded.panic();
+            };
+        };
+    }
+
+    fn assert_probation_actions_allowed(&self, account_id: &AccountId) {
+        if self.probation_policy.max_daily_actions == 0 || !self.is_account_on_probation(account_id) {
+            return
+        }
+
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        if let Some(account_stats) = self.accounts_stats.get(account_id) {
+            if account_stats.last_active_day == today && account_stats.day_actions_count >= self.probation_policy.max_daily_actions {
+                ContractError::ProbationActionsLimitExceeded.panic();
+            };
+        };
+    }
+
+    fn assert_claim_imported_friends_call(&self, account_id: &AccountId, proof: &[Base64VecU8], friends: &[AccountId]) {
+        if friends.is_empty() {
+            ContractError::EmptyImportedFriends.panic();
+        };
+
+        if friends.len() > MAX_IMPORTED_FRIENDS {
+            ContractError::TooManyImportedFriends.panic();
+        };
+
+        let root = self.friends_import_root.as_ref().unwrap_or_else(|| ContractError::FriendsImportRootNotSet.panic());
+
+        // Leaf commits to the account and its whole imported friends list, inner nodes hash sorted pairs
+        let leaf = env::sha256(&(account_id, friends).try_to_vec().unwrap());
+        let computed_root = proof.iter().fold(leaf, |hash, sibling| {
+            let sibling: &Vec<u8> = &sibling.0;
+            if &hash <= sibling {
+                env::sha256(&[hash.as_slice(), sibling.as_slice()].concat())
+            } else {
+                env::sha256(&[sibling.as_slice(), hash.as_slice()].concat())
+            }
+        });
+
+        if &computed_root != root {
+            ContractError::InvalidFriendsImportProof.panic();
+        };
+    }
+
+    fn assert_verify_via_nft_call(&self, account_id: &AccountId, contract_id: &AccountId, token_id: &str) {
+        if token_id.len() < MIN_TOKEN_ID_LEN || token_id.len() > MAX_TOKEN_ID_LEN {
+            ContractError::InvalidTokenId.panic();
+        };
+
+        if self.credential_nfts.get(contract_id).is_none() {
+            ContractError::CredentialNftNotAllowed.panic();
+        };
+
+        self.assert_not_banned(account_id);
+    }
+
+    fn assert_request_account_link_call(&self, primary_id: &AccountId, account_id: &AccountId) {
+        if primary_id == account_id {
+            ContractError::CannotLinkSelf.panic();
+        };
+
+        if self.accounts_identities.contains_key(primary_id) {
+            ContractError::PrimaryAccountIsLinked.panic();
+        };
+
+        if self.accounts_identities.contains_key(account_id) || self.accounts_links.contains_key(account_id) {
+            ContractError::AccountAlreadyLinked.panic();
+        };
+    }
+
+    fn assert_accept_account_link_call(&self, account_id: &AccountId, primary_id: &AccountId) {
+        if self.accounts_link_requests.get(account_id).as_ref() != Some(primary_id) {
+            ContractError::LinkRequestNotFound.panic();
+        };
+
+        if self.accounts_identities.contains_key(primary_id) {
+            ContractError::PrimaryAccountIsLinked.panic();
+        };
+
+        if self.accounts_identities.contains_key(account_id) || self.accounts_links.contains_key(account_id) {
+            ContractError::AccountAlreadyLinked.panic();
+        };
+    }
+
+    fn assert_unlink_account_call(&self, caller_id: &AccountId, account_id: &AccountId) {
+        match self.accounts_identities.get(account_id) {
+            Some(primary_id) => {
+                if caller_id != &primary_id && caller_id != account_id {
+                    ContractError::AccountLinkNotFound.panic();
+                };
+            },
+            None => {
+                ContractError::AccountLinkNotFound.panic();
+            }
+        };
+    }
+
+    fn assert_app_scope(&self, scope: AppScope) {
+        let account_id = env::predecessor_account_id();
+        if env::signer_account_id() != account_id {
+            return
+        }
+        let app_key = AccountAppKey { account_id, public_key: env::signer_account_pk() };
+        if let Some(scopes) = self.accounts_app_keys.get(&app_key) {
+            if !scopes.contains(&scope) {
+                ContractError::AppKeyScopeViolation.panic();
+            };
+        };
+    }
+
+    fn assert_not_app_key(&self) {
+        let account_id = env::predecessor_account_id();
+        if env::signer_account_id() != account_id {
+            return
+        }
+        if self.accounts_app_keys.contains_key(&AccountAppKey { account_id, public_key: env::signer_account_pk() }) {
+            ContractError::AppKeyScopeViolation.panic();
+        };
+    }
+
+    fn assert_register_app_key_call(&self, scopes: &[AppScope]) {
+        self.assert_not_app_key();
+
+        if scopes.is_empty() {
+            ContractError::EmptyAppScopes.panic();
+        };
+    }
+
+    // Accounts that blocked the sender do not receive its direct messages
+    fn assert_send_direct_message_call(&self, account_id: &AccountId, recipient_id: &AccountId, payload: &Base64VecU8) {
+        if payload.0.len() < MIN_DIRECT_MESSAGE_LEN || payload.0.len() > MAX_DIRECT_MESSAGE_LEN {
+            ContractError::InvalidDirectMessage.panic();
+        };
+
+        if account_id == recipient_id {
+            ContractError::CannotMessageSelf.panic();
+        };
+
+        let is_blocked = self.blocked_accounts
+            .get(recipient_id)
+            .is_some_and(|blocked_accounts| blocked_accounts.contains(account_id));
+        if is_blocked {
+            ContractError::DirectMessagesRestricted.panic();
+        };
+    }
+
+    fn assert_post_on_wall_call(&self, account_id: &AccountId, target_account: &AccountId, text: &String) {
+        self.assert_text(text);
+
+        if account_id == target_account {
+            return
+        }
+
+        let wall_policy = match self.accounts_profiles.get(target_account) {
+            Some(account_profile) => account_profile.wall_policy,
+            None => WallPolicy::Everyone
+        };
+
+        let is_allowed = match wall_policy {
+            WallPolicy::Everyone => true,
+            WallPolicy::Friends => self.accounts_friends.get(target_account).is_some_and(|friends| friends.contains(account_id)),
+            WallPolicy::Nobody => false
+        };
+
+        if !is_allowed {
+            ContractError::WallPostingRestricted.panic();
+        };
+    }
+
+    fn assert_grant_posting_delegate_call(&self, account_id: &AccountId, delegate_id: &AccountId) {
+        self.assert_not_app_key();
+
+        if account_id == delegate_id {
+            ContractError::CannotDelegateToSelf.panic();
+        };
+
+        if self.posting_delegates.contains_key(&PostingDelegate { account_id: account_id.clone(), delegate_id: delegate_id.clone() }) {
+            ContractError::PostingDelegateAlreadyGranted.panic();
+        };
+    }
+
+    fn assert_revoke_posting_delegate_call(&self, account_id: &AccountId, delegate_id: &AccountId) {
+        self.assert_not_app_key();
+
+        if !self.posting_delegates.contains_key(&PostingDelegate { account_id: account_id.clone(), delegate_id: delegate_id.clone() }) {
+            ContractError::PostingDelegateNotFound.panic();
+        };
+    }
+
+    fn assert_unregister_app_key_call(&self, account_id: &AccountId, public_key: &PublicKey) {
+        self.assert_not_app_key();
+
+        if !self.accounts_app_keys.contains_key(&AccountAppKey { account_id: account_id.clone(), public_key: public_key.clone() }) {
+            ContractError::AppKeyNotRegistered.panic();
+        };
+    }
+
+    fn assert_update_profile_call(&self, profile: &AccountProfileData) {
+        if let Some(json_metadata) = &profile.json_metadata {
+            let result : Result<Value> = serde_json::from_str(json_metadata);
+            if result.is_err() {
+                ContractError::InvalidProfileMetadata.panic();
+            };
+        };
+    }
+    
+    // Returns the caller identity
+    fn assert_post_owner_or_moderator(&self, post_id: &PostId) -> AccountId {
+        let post_messages = self.posts_messages.get(post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let caller_id = env::predecessor_account_id();
+        let account_id = self.get_identity(caller_id.clone());
+        let is_owner = self.get_post_owner_id(post_id, &post_messages).as_ref() == Some(&account_id);
+        if !is_owner && caller_id != *self.ownership.owner() && !self.moderators.contains(&caller_id) {
+            ContractError::NotPostAuthor.panic();
+        };
+        account_id
+    }
+
+    fn assert_post_owner(&self, account_id: &AccountId, post_id: &PostId) {
+        let post_messages = self.posts_messages.get(post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        if self.get_post_owner_id(post_id, &post_messages).as_ref() != Some(account_id) {
+            ContractError::NotPostOwner.panic();
+        };
+    }
+
+    fn assert_transfer_post_call(&self, account_id: &AccountId, post_id: &PostId, new_owner: &AccountId) {
+        self.assert_post_id(post_id);
+        self.assert_post_owner(account_id, post_id);
+        self.assert_post_not_being_deleted(post_id);
+        if account_id == new_owner {
+            ContractError::InvalidPostTransfer.panic();
+        };
+    }
+
+    fn assert_accept_post_transfer_call(&self, account_id: &AccountId, post_id: &PostId) {
+        if self.posts_pending_owners.get(post_id).as_ref() != Some(account_id) {
+            ContractError::PostTransferNotFound.panic();
+        };
+        self.assert_post_not_being_deleted(post_id);
+    }
+
+    fn assert_cancel_post_transfer_call(&self, account_id: &AccountId, post_id: &PostId) {
+        self.assert_post_owner(account_id, post_id);
+        if !self.posts_pending_owners.contains_key(post_id) {
+            ContractError::PostTransferNotFound.panic();
+        };
+    }
+
+    fn assert_remove_message_from_own_post_call(&self, account_id: &AccountId, msg_id: &MessageID) {
+        self.assert_message_id(msg_id);
+        self.assert_message_existence(msg_id);
+        self.assert_message_not_deleted(msg_id);
+        self.assert_post_not_being_deleted(&msg_id.post_id);
+        self.assert_post_owner(account_id, &msg_id.post_id);
+    }
+
+    fn assert_unblock_account_call(&self, account_id: &AccountId, blocked_id: &AccountId) {
+        if !self.is_blocked(account_id.clone(), blocked_id.clone()) {
+            ContractError::AccountNotBlocked.panic();
+        };
+    }
+
+    fn assert_unmute_account_call(&self, account_id: &AccountId, muted_id: &AccountId) {
+        if !self.is_muted(account_id.clone(), muted_id.clone()) {
+            ContractError::AccountNotMuted.panic();
+        };
+    }
+
+    fn assert_enable_likes_history_call(&self, account_id: &AccountId) {
+        if self.accounts_likes_history.contains_key(account_id) {
+            ContractError::LikesHistoryAlreadyEnabled.panic();
+        };
+    }
+
+    fn assert_disable_likes_history_call(&self, account_id: &AccountId) {
+        if !self.accounts_likes_history.contains_key(account_id) {
+            ContractError::LikesHistoryNotEnabled.panic();
+        };
+    }
+
+    // Asserts a stored call as its own method did when the call was created. The friends import
+    // proof is not stored, it is checked only once
+    fn assert_call(&self, caller_id: &AccountId, call: &Call) {
+        match call {
+            Call::AddMessageToPost { post_id, text, delegate, .. } => {
+                self.assert_posting_delegate(caller_id, delegate);
+                self.assert_add_message_to_post_call(post_id, text);
+            },
+            Call::AddListingToPost { post_id, listing_id, .. } => self.assert_add_listing_to_post_call(post_id, listing_id),
+            Call::AddQuoteToPost { post_id, quoted_post_id, quoted_msg_idx, text } => self.assert_add_quote_to_post_call(caller_id, post_id, quoted_post_id, quoted_msg_idx, text),
+            Call::AddMessageToMessage { parent_msg_id, text, delegate, .. } => {
+                self.assert_posting_delegate(caller_id, delegate);
+                self.assert_add_message_to_message_call(caller_id, parent_msg_id, text);
+            },
+            Call::EditMessage { msg_id, text } => self.assert_edit_message_call(caller_id, msg_id, text),
+            Call::AddFriend { friend_id } => self.assert_add_friend_call(caller_id, friend_id),
+            Call::LikePost { post_id } => self.assert_like_post_call(caller_id, post_id),
+            Call::UnlikePost { post_id } => self.assert_unlike_post_call(caller_id, post_id),
+            Call::LikeMessage { msg_id } => self.assert_like_message_call(caller_id, msg_id),
+            Call::UnlikeMessage { msg_id } => self.assert_unlike_message_call(caller_id, msg_id),
+            Call::UpdateProfile { profile } => self.assert_update_profile_call(profile),
+            Call::RemoveFriend { friend_id } => self.assert_remove_friend_call(caller_id, friend_id),
+            Call::ImportFriends { .. } => (),
+            Call::RequestAccountLink { account_id } => self.assert_request_account_link_call(caller_id, account_id),
+            Call::AcceptAccountLink { primary_id } => self.assert_accept_account_link_call(caller_id, primary_id),
+            Call::UnlinkAccount { account_id } => self.assert_unlink_account_call(caller_id, account_id),
+            Call::RegisterAppKey { scopes, .. } => self.assert_register_app_key_call(scopes),
+            Call::UnregisterAppKey { public_key } => self.assert_unregister_app_key_call(caller_id, public_key),
+            Call::GrantPostingDelegate { delegate_id } => self.assert_grant_posting_delegate_call(caller_id, delegate_id),
+            Call::RevokePostingDelegate { delegate_id } => self.assert_revoke_posting_delegate_call(caller_id, delegate_id),
+            Call::PostOnWall { target_account, text } => self.assert_post_on_wall_call(caller_id, target_account, text),
+            Call::DeleteMessage { msg_id } => self.assert_delete_message_call(caller_id, msg_id),
+            Call::SetMessageSensitive { msg_id, sensitive } => self.assert_set_message_sensitive_call(caller_id, msg_id, *sensitive),
+            Call::SendFriendRequest { friend_id } => self.assert_send_friend_request_call(caller_id, friend_id),
+            Call::AcceptFriendRequest { requester_id } | Call::DeclineFriendRequest { requester_id } => self.assert_friend_request_exists(requester_id, caller_id),
+            Call::BlockAccount { blocked_id } => self.assert_block_account_call(caller_id, blocked_id),
+            Call::UnblockAccount { blocked_id } => self.assert_unblock_account_call(caller_id, blocked_id),
+            Call::MuteAccount { muted_id } => self.assert_mute_account_call(caller_id, muted_id),
+            Call::UnmuteAccount { muted_id } => self.assert_unmute_account_call(caller_id, muted_id),
+            Call::ReportMessage { msg_id, .. } => {
+                self.assert_message_id(msg_id);
+                self.assert_report_content_call(caller_id, &ReportedContent::Message { msg_id: msg_id.into() });
+            },
+            Call::ReportPost { post_id, .. } => self.assert_report_content_call(caller_id, &ReportedContent::Post { post_id: post_id.clone() }),
+            Call::VerifyAccount { contract_id, token_id, .. } => self.assert_verify_via_nft_call(caller_id, contract_id, token_id),
+            Call::Repost { post_id } => self.assert_repost_call(caller_id, post_id),
+            Call::EnableLikesHistory => self.assert_enable_likes_history_call(caller_id),
+            Call::DisableLikesHistory => self.assert_disable_likes_history_call(caller_id),
+            Call::TransferPost { post_id, new_owner } => self.assert_transfer_post_call(caller_id, post_id, new_owner),
+            Call::AcceptPostTransfer { post_id } => self.assert_accept_post_transfer_call(caller_id, post_id),
+            Call::CancelPostTransfer { post_id } => self.assert_cancel_post_transfer_call(caller_id, post_id),
+            Call::Batch { calls } => self.assert_batch_calls(caller_id, calls),
+            Call::ReactToPost { post_id, reaction } => self.assert_react_to_post_call(caller_id, post_id, *reaction),
+            Call::ReactToMessage { msg_id, reaction } => self.assert_react_to_message_call(caller_id, msg_id, *reaction),
+            Call::RemovePostReaction { post_id } => self.assert_remove_post_reaction_call(caller_id, post_id),
+            Call::RemoveMessageReaction { msg_id } => self.assert_remove_message_reaction_call(caller_id, msg_id),
+            Call::SuperLikePost { post_id } => self.assert_super_like_post_call(caller_id, post_id),
+            Call::TipMessage { msg_id, amount } | Call::TipMessageNear { msg_id, amount } => self.assert_tip_message_call(caller_id, msg_id, *amount),
+            Call::AddMediaToPost { post_id, text, media_cid, mime_type } => self.assert_add_media_to_post_call(post_id, text, media_cid, mime_type),
+            Call::AddLinkToPost { post_id, url, title, description } => self.assert_add_link_to_post_call(post_id, url, title, description),
+            Call::RemoveMessageFromOwnPost { msg_id } => self.assert_remove_message_from_own_post_call(caller_id, msg_id),
+            Call::MarkNotificationsRead => (),
+            Call::SendDirectMessage { recipient_id, payload } => self.assert_send_direct_message_call(caller_id, recipient_id, payload)
+        }
+    }
+
+    fn assert_posting_delegate(&self, account_id: &AccountId, delegate: &Option<AccountId>) {
+        if let Some(delegate_id) = delegate {
+            if !self.posting_delegates.contains_key(&PostingDelegate { account_id: account_id.clone(), delegate_id: delegate_id.clone() }) {
+                ContractError::NotPostingDelegate.panic();
+            };
+        };
+    }
+
+    fn assert_batch_calls(&self, account_id: &AccountId, calls: &[BatchCall]) {
+        let mut liked_posts: Vec<&PostId> = Vec::new();
+        let mut liked_messages: Vec<&MessageID> = Vec::new();
+        for call in calls {
+            match call {
+                BatchCall::AddMessageToPost { post_id, text, .. } => self.assert_add_message_to_post_call(post_id, text),
+                BatchCall::AddMessageToMessage { parent_msg_id, text, .. } => self.assert_add_message_to_message_call(account_id, parent_msg_id, text),
+                BatchCall::LikePost { post_id } => {
+                    self.assert_like_post_call(account_id, post_id);
+                    if liked_posts.contains(&post_id) {
+                        ContractError::DuplicateBatchLike.panic();
+                    };
+                    liked_posts.push(post_id);
+                },
+                BatchCall::LikeMessage { msg_id } => {
+                    self.assert_like_message_call(account_id, msg_id);
+                    if liked_messages.iter().any(|liked| liked.post_id == msg_id.post_id && u64::from(liked.msg_idx) == u64::from(msg_id.msg_idx)) {
+                        ContractError::DuplicateBatchLike.panic();
+                    };
+                    liked_messages.push(msg_id);
+                }
+            }
+        }
+    }
+
+    fn assert_post_not_being_deleted(&self, post_id: &PostId) {
+        if self.deleting_posts.contains(post_id) {
+            ContractError::PostBeingDeleted.panic();
+        };
+
+        if self.deleted_posts.contains(post_id) {
+            ContractError::PostDeleted.panic();
+        };
+    }
+
+    fn assert_post_id(&self, post_id: &PostId) {
+        // TODO: Add validation for post_id limit length
+        if post_id.trim().is_empty() {
+            ContractError::EmptyPostId.panic();
+        };
+
+        if post_id.len() < MIN_POST_ID_LEN {
+            ContractError::PostIdTooShort.panic();
+        }
+
+        if post_id.len() > MAX_POST_ID_LEN {
+            ContractError::PostIdTooLong.panic();
+        }
+    }
+
+    fn assert_text(&self, text: &String) {
+        if text.trim().is_empty() {
+            ContractError::EmptyText.panic();
+        };
+
+        // TODO: Add validation for text limit length
+        if text.len() < MIN_POST_MESSAGE_LEN {
+            ContractError::TextTooShort.panic();
+        };
+    }
+
+    // Language tags are stored lowercased: primary subtag of 2-8 letters followed by subtags of 1-8 letters or digits
+    fn normalize_lang(&self, lang: Option<String>) -> Option<String> {
+        lang.map(|lang| {
+            if lang.len() > MAX_LANG_TAG_LEN {
+                ContractError::InvalidLanguageTag.panic();
+            };
+            let mut subtags = lang.split('-');
+            let is_primary_valid = subtags
+                .next()
+                .is_some_and(|primary| primary.len() >= 2 && primary.len() <= 8 && primary.chars().all(|c| c.is_ascii_alphabetic()));
+            let are_subtags_valid = subtags
+                .all(|subtag| !subtag.is_empty() && subtag.len() <= 8 && subtag.chars().all(|c| c.is_ascii_alphanumeric()));
+            if !is_primary_valid || !are_subtags_valid {
+                ContractError::InvalidLanguageTag.panic();
+            };
+            lang.to_ascii_lowercase()
+        })
+    }
+
+    fn assert_archive_cid(&self, cid: &str) {
+        if cid.trim().is_empty() {
+            ContractError::EmptyArchiveCid.panic();
+        };
+
+        if cid.len() > MAX_ARCHIVE_CID_LEN {
+            ContractError::ArchiveCidTooLong.panic();
+        };
+    }
+
+    fn assert_message_existence(&self, msg_id: &MessageID) {
+        if let Some(post_messages) = self.posts_messages.get(&msg_id.post_id) {
+            if post_messages.get(u64::from(msg_id.msg_idx)).is_none() {
+                ContractError::MessageNotFound.panic();
+            };
+
+        } else {
+            ContractError::MessageNotFound.panic();
+        };
+    }
+
+    fn assert_message_not_deleted(&self, msg_id: &MessageID) {
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg = post_messages.get(msg_id.msg_idx.into()).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        if msg.payload.is_deleted() {
+            ContractError::MessageDeleted.panic();
+        };
+    }
+
+    fn assert_message_id(&self, msg_id: &MessageID) {
+        let post_id = &msg_id.post_id;
+        self.assert_post_id(post_id);
+    }
+
+
+    // Calculate call fee
+
+    fn calc_add_message_to_post_fee(&self, account_id: &AccountId, post_id: &PostId, payload_len: usize) -> u128 {
+        let is_first = !self.posts_messages.contains_key(post_id);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let post_id_extra_bytes = if is_first { 
+            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+        let text_extra_bytes = u64::try_from(payload_len - MIN_POST_MESSAGE_LEN).unwrap();
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.messages_collection_size
+        } else {
+            0u64
+        };
+        let author_index_bytes = self.calc_author_messages_index_bytes(account_id, post_id, is_first);
+        let account_index_bytes = self.calc_account_messages_index_bytes(account_id, post_id);
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
+        // log!("text_extra_bytes bytes {}", text_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+        // log!("author_index_bytes bytes {}", author_index_bytes);
+
+        let storage_size = self.storage_usage_settings.min_message_size 
+            + account_extra_bytes 
+            + (post_id_extra_bytes * 3) // Post id is also stored twice in 'all_posts: UnorderedSet<PostId>'
+            + text_extra_bytes 
+            + collection_bytes
+            + author_index_bytes
+            + account_index_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    }
+
+    fn calc_add_payload_to_post_fee(&self, account_id: &AccountId, post_id: &PostId, payload_len: usize) -> u128 {
+        let (target_post_id, continued_post_id) = self.get_message_post_id(post_id);
+        let continuation_fee = match continued_post_id {
+            Some(continued_post_id) => self.calc_post_continuation_fee(&continued_post_id, &target_post_id),
+            None => 0
+        };
+        let notification_fee = self.calc_notification_fee(account_id, &self.get_post_author_id(post_id), Some(&target_post_id));
+        self.calc_add_message_to_post_fee(account_id, &target_post_id, payload_len) + continuation_fee + notification_fee
+    }
+
+    fn calc_post_continuation_fee(&self, post_id: &PostId, continuation_id: &PostId) -> u128 {
+        let storage_size = self.storage_usage_settings.min_post_continuation_size
+            + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+            + u64::try_from(continuation_id.len() - MIN_POST_ID_LEN).unwrap();
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    }
+
+    fn calc_add_message_to_message_fee(&self, account_id: &AccountId, parent_msg_id: &MessageID, payload_len: usize) -> u128 {
+        let post_id = &parent_msg_id.post_id;
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let text_extra_bytes = u64::try_from(payload_len - MIN_POST_MESSAGE_LEN).unwrap();
+        let msg_idx_bytes = 8u64;
+        let author_index_bytes = self.calc_author_messages_index_bytes(account_id, post_id, false);
+        let account_index_bytes = self.calc_account_messages_index_bytes(account_id, post_id);
+        let replies_index_bytes = self.calc_message_replies_index_bytes(parent_msg_id);
+        
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("text_extra_bytes bytes {}", text_extra_bytes);
+        // log!("msg_idx_bytes bytes {}", msg_idx_bytes);
+        // log!("author_index_bytes bytes {}", author_index_bytes);
+
+        let storage_size = self.storage_usage_settings.min_message_size 
+            + account_extra_bytes 
+            + text_extra_bytes 
+            + msg_idx_bytes
+            + author_index_bytes
+            + account_index_bytes
+            + replies_index_bytes;
+
+        let notification_fee = self.calc_notification_fee(account_id, &self.get_message_author_id(&parent_msg_id.clone().into()), Some(post_id));
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent) + notification_fee
+    }
+
+    fn calc_edit_message_fee(&self, msg_id: &MessageID, text: &str) -> u128 {
+        let post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let msg = post_messages.get(msg_id.msg_idx.into()).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        let current_text_len = match msg.payload {
+            MessagePayload::Text { text } => text.len(),
+            _ => ContractError::MessageNotEditable.panic()
+        };
+        let text_extra_bytes = u64::try_from(text.len().saturating_sub(current_text_len)).unwrap();
+        // 'edited_at' grows from None to Some(u64) on the first edit
+        let edited_at_bytes = if msg.edited_at.is_none() { 8u64 } else { 0u64 };
+
+        let storage_size = text_extra_bytes + edited_at_bytes;
+        let fee = self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent);
+        std::cmp::max(fee, 1)
+    }
+
+    fn calc_message_replies_index_bytes(&self, parent_msg_id: &MessageID) -> u64 {
+        let collection_bytes = if self.posts_messages_replies.contains_key(&parent_msg_id.clone().into()) {
+            0u64
+        } else {
+            self.storage_usage_settings.message_replies_collection_size
+                + u64::try_from(parent_msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap()
+        };
+
+        self.storage_usage_settings.min_message_reply_size + collection_bytes
+    }
+
+    // 'min_message_size' includes the account index entry with minimal post id, the index collection is measured separately
+    fn calc_account_messages_index_bytes(&self, account_id: &AccountId, post_id: &PostId) -> u64 {
+        let post_id_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let collection_bytes = if self.accounts_messages.contains_key(account_id) {
+            0u64
+        } else {
+            self.storage_usage_settings.account_messages_collection_size
+                + u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        };
+
+        post_id_extra_bytes + collection_bytes
+    }
+
+    fn calc_author_messages_index_bytes(&self, account_id: &AccountId, post_id: &PostId, is_first_post_message: bool) -> u64 {
+        let post_author_id = PostAuthorId { post_id: post_id.clone(), account_id: account_id.clone() };
+        if self.posts_authors_messages.contains_key(&post_author_id) {
+            return 0
+        }
+
+        // 'min_message_size' and 'messages_collection_size' already include the index entry and,
+        // for the first post message, the index collection created for minimal ids
+        let key_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+            + u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if is_first_post_message {
+            0u64
+        } else {
+            self.storage_usage_settings.author_messages_collection_size
+        };
+
+        key_extra_bytes + collection_bytes
+    }
+
+    fn calc_like_post_fee(&self, account_id: &AccountId, post_id: &PostId) -> u128 {
+        let is_first = !self.posts_likes.contains_key(post_id);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let post_id_extra_bytes = if is_first {
+            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() 
+        } else {
+            0u64
+        };
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.post_likes_collection_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+        
+        let storage_size = self.storage_usage_settings.min_post_like_size 
+            + (account_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + post_id_extra_bytes
+            + collection_bytes;
+
+        let notification_fee = self.calc_notification_fee(account_id, &self.get_post_author_id(post_id), Some(post_id));
+        self.calc_storage_fee(storage_size, self.admin_settings.like_post_extra_fee_percent) + notification_fee
+    }
+
+    // Once the log is full the oldest notification is overwritten, so the notification is not charged
+    fn calc_notification_fee(&self, account_id: &AccountId, recipient_id: &Option<AccountId>, post_id: Option<&PostId>) -> u128 {
+        let recipient_id = match recipient_id {
+            Some(recipient_id) if self.is_notified(recipient_id, account_id) => recipient_id,
+            _ => return 0
+        };
+        let notification_bytes = match self.accounts_notifications.get(recipient_id) {
+            Some(notifications_log) if notifications_log.items.len() >= MAX_NOTIFICATIONS => return 0,
+            Some(_) => self.storage_usage_settings.min_notification_size,
+            None => self.storage_usage_settings.min_notification_size
+                + self.storage_usage_settings.notifications_collection_size
+                + u64::try_from(recipient_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        };
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let post_id_extra_bytes = post_id.map_or(0, |post_id| u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap());
+
+        let storage_size = notification_bytes + account_extra_bytes + post_id_extra_bytes;
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_mentions_fee(&self, account_id: &AccountId, post_id: &PostId, text: &str) -> u128 {
+        let post_id_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let storage_size: u64 = self.get_message_mentions(account_id, text)
+            .iter()
+            .map(|mentioned_id| {
+                let collection_bytes = if self.accounts_mentions.contains_key(mentioned_id) {
+                    0u64
+                } else {
+                    self.storage_usage_settings.account_mentions_collection_size
+                        + u64::try_from(mentioned_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+                };
+                self.storage_usage_settings.min_account_mention_size + post_id_extra_bytes + collection_bytes
+            })
+            .sum();
+
+        if storage_size == 0 {
+            return 0
+        }
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    }
+
+    // Reposts have the same layout as the post likes, so their storage measurement is reused
+    fn calc_repost_fee(&self, account_id: &AccountId, post_id: &PostId) -> u128 {
+        let is_first = !self.posts_reposts.contains_key(post_id);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.post_likes_collection_size
+                + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+
+        let storage_size = self.storage_usage_settings.min_post_like_size
+            + (account_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.like_post_extra_fee_percent)
+    }
+
+    fn calc_like_message_fee(&self, account_id: &AccountId, msg_id: &MessageID) -> u128 {
+        let is_first = !self.posts_messages_likes.contains_key(&msg_id.clone().into());
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let post_id_extra_bytes = if is_first { 
+            u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap() 
+        } else {
+            0u64
+        };
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.message_likes_collection_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+        
+        let storage_size = self.storage_usage_settings.min_message_like_size 
+            + (account_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + post_id_extra_bytes
+            + collection_bytes;
+
+        let notification_fee = self.calc_notification_fee(account_id, &self.get_message_author_id(&msg_id.clone().into()), Some(&msg_id.post_id));
+        self.calc_storage_fee(storage_size, self.admin_settings.like_message_extra_fee_percent) + notification_fee
+    }
+
+
+    fn calc_super_like_post_fee(&self, account_id: &AccountId, post_id: &PostId) -> u128 {
+        let like_fee = self.calc_like_post_fee(account_id, post_id)
+            + self.calc_account_recent_likes_fee(account_id, post_id, false)
+            + self.calc_account_likes_history_fee(account_id, post_id, false)
+            + self.calc_post_score_fee(post_id);
+
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if self.posts_super_likes.contains_key(post_id) {
+            0u64
+        } else {
+            self.storage_usage_settings.post_super_likes_collection_size
+                + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+        };
+        let storage_size = self.storage_usage_settings.min_post_super_like_size
+            + (account_extra_bytes * 2) // UnorderedMap stores additional key in its 'keys: Vector<K>'
+            + collection_bytes;
+
+        like_fee.saturating_mul(self.admin_settings.super_like_fee_multiplier.into())
+            + self.calc_storage_fee(storage_size, self.admin_settings.like_post_extra_fee_percent)
+    }
+
+    // Replacing the reaction of the account takes no storage
+    fn calc_react_to_post_fee(&self, account_id: &AccountId, post_id: &PostId) -> u128 {
+        let post_reactions = self.posts_reactions.get(post_id);
+        if post_reactions.as_ref().is_some_and(|post_reactions| post_reactions.accounts.get(account_id).is_some()) {
+            return 1
+        }
+
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if post_reactions.is_none() {
+            self.storage_usage_settings.post_reactions_collection_size
+                + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+
+        let storage_size = self.storage_usage_settings.min_post_reaction_size
+            + (account_extra_bytes * 2) // UnorderedMap stores additional key in its 'keys: Vector<K>'
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.like_post_extra_fee_percent)
+    }
+
+    fn calc_react_to_message_fee(&self, account_id: &AccountId, msg_id: &MessageID) -> u128 {
+        let message_reactions = self.posts_messages_reactions.get(&msg_id.into());
+        if message_reactions.as_ref().is_some_and(|message_reactions| message_reactions.accounts.get(account_id).is_some()) {
+            return 1
+        }
+
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if message_reactions.is_none() {
+            self.storage_usage_settings.message_reactions_collection_size
+                + u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+
+        let storage_size = self.storage_usage_settings.min_message_reaction_size
+            + (account_extra_bytes * 2) // UnorderedMap stores additional key in its 'keys: Vector<K>'
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.like_message_extra_fee_percent)
+    }
+
+    fn calc_account_recent_likes_fee(&self, account_id: &AccountId, post_id: &PostId, is_msg: bool) -> u128 {
+        if self.admin_settings.account_recent_likes_limit == 0 {
+            return 0
+        }
+        
+        // Account stats entry itself is paid by the account activity fee
+        let existing_account_stats = self.accounts_stats.get(account_id);
+
+        let msg_idx_bytes = if is_msg {
+            8u64
+        } else {
+            0u64
+        };
+
+        let like_extra_bytes = if existing_account_stats.is_some() {
+            let account_stats = existing_account_stats.as_ref().unwrap();
+            let recent_likes_len = u64::try_from(account_stats.recent_likes.len()).unwrap();
+            let recent_likes_limit = u64::from(self.admin_settings.account_recent_likes_limit);
+            
+            if recent_likes_len == recent_likes_limit {
+                let like_to_delete = account_stats.recent_likes.first().unwrap_or_else(|| ContractError::StorageNotFound.panic());
+                match like_to_delete {
+                    AccountLike::PostLike { post_id: post_id_to_delete } => {
+                        if post_id.len() > post_id_to_delete.len() {
+                            u64::try_from(post_id.len() - post_id_to_delete.len()).unwrap() + msg_idx_bytes
+                        } else {
+                            msg_idx_bytes
+                        }
+                    },
+                    AccountLike::MessageLike { msg_id: msg_id_to_delete } => {
+                        if post_id.len() > msg_id_to_delete.post_id.len() {
+                            u64::try_from(post_id.len() - msg_id_to_delete.post_id.len()).unwrap()
+                        } else {
+                            0u64
+                        }
+                    }
+                }
+
+            } else if recent_likes_len < recent_likes_limit {
+                u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() + msg_idx_bytes
+            } else {
+                0u64
+            }
+        } else  {
+            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() + msg_idx_bytes
+        };
+
+        let min_account_recent_like_size = if existing_account_stats.is_some() {
+            let account_stats = existing_account_stats.as_ref().unwrap();
+            let recent_likes_len = u64::try_from(account_stats.recent_likes.len()).unwrap();
+            let recent_likes_limit = u64::from(self.admin_settings.account_recent_likes_limit);
+
+            if recent_likes_len < recent_likes_limit {
+                self.storage_usage_settings.min_account_recent_like_size
+            } else {
+                0u64
+            }
+
+        } else {
+            self.storage_usage_settings.min_account_recent_like_size
+        };
+
+        // log!("like_extra_bytes bytes {}", like_extra_bytes);
+        // log!("min_account_recent_like_size bytes {}", min_account_recent_like_size);
+
+        let storage_size = min_account_recent_like_size
+            + like_extra_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.account_recent_like_extra_fee_percent)
+    }
+
+    fn calc_account_activity_fee(&self, account_id: &AccountId) -> u128 {
+        let existing_account_stats = self.accounts_stats.get(account_id);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+
+        let stats_bytes = if existing_account_stats.is_none() {
+            self.storage_usage_settings.account_recent_likes_collection_size + account_extra_bytes
+        } else {
+            0u64
+        };
+
+        let next_streak = match &existing_account_stats {
+            Some(account_stats) => self.calc_next_streak(account_stats),
+            None => 1
+        };
+
+        let badge_bytes = match self.get_streak_badge(account_id, next_streak) {
+            Some(_) => {
+                if self.accounts_badges.contains_key(account_id) {
+                    self.storage_usage_settings.min_account_badge_size
+                } else {
+                    self.storage_usage_settings.min_account_badge_size
+                        + self.storage_usage_settings.account_badges_collection_size
+                        + account_extra_bytes
+                }
+            },
+            None => 0u64
+        };
+
+        // log!("stats_bytes bytes {}", stats_bytes);
+        // log!("badge_bytes bytes {}", badge_bytes);
+
+        let storage_size = stats_bytes + badge_bytes;
+        if storage_size == 0 {
+            return 0
+        }
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_add_friend_fee(&self, account_id: &AccountId, friend_id: &AccountId) -> u128 {
+        let is_first = !self.accounts_friends.contains_key(account_id);
+        let account_extra_bytes = if is_first { 
+            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap() * 3 // the key is also stored twice in 'accounts_with_friends'
+        } else {
+            0u64
+        };
+        let friend_id_extra_bytes = u64::try_from(friend_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.account_friends_collection_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("friend_id_extra_bytes bytes {}", friend_id_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+
+        let storage_size = self.storage_usage_settings.min_account_friend_size 
+            + account_extra_bytes 
+            + (friend_id_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_friend_extra_fee_percent)
+    }
+
+    fn calc_send_friend_request_fee(&self, account_id: &AccountId, friend_id: &AccountId) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let friend_id_extra_bytes = u64::try_from(friend_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+
+        // The request is stored in the outgoing requests of the account and in the incoming requests of the friend
+        let storage_size = self.calc_friend_request_bytes(self.outgoing_friend_requests.contains_key(account_id), account_extra_bytes, friend_id_extra_bytes)
+            + self.calc_friend_request_bytes(self.incoming_friend_requests.contains_key(friend_id), friend_id_extra_bytes, account_extra_bytes);
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_friend_extra_fee_percent)
+    }
+
+    fn calc_friend_request_bytes(&self, has_collection: bool, key_extra_bytes: u64, element_extra_bytes: u64) -> u64 {
+        let collection_bytes = if has_collection {
+            0u64
+        } else {
+            self.storage_usage_settings.friend_requests_collection_size + key_extra_bytes
+        };
+
+        self.storage_usage_settings.min_friend_request_size
+            + (element_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + collection_bytes
+    }
+
+    // Block list has the same layout as the friend requests, so their storage measurement is reused
+    fn calc_block_account_fee(&self, account_id: &AccountId, blocked_id: &AccountId) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let blocked_id_extra_bytes = u64::try_from(blocked_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let storage_size = self.calc_friend_request_bytes(self.blocked_accounts.contains_key(account_id), account_extra_bytes, blocked_id_extra_bytes);
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_report_content_fee(&self, account_id: &AccountId, content: &ReportedContent) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        // UnorderedMap stores additional key in its 'keys: Vector<K>'
+        let mut storage_size = self.storage_usage_settings.min_content_report_size + account_extra_bytes * 2;
+
+        if !self.content_reports.contains_key(content) {
+            // Content is stored as the key of 'content_reports' and twice in 'reported_contents'.
+            // Reports are measured for a post, message key additionally stores 'msg_idx'
+            let content_extra_bytes = match content {
+                ReportedContent::Post { post_id } => u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap(),
+                ReportedContent::Message { msg_id } => u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap() + 8
+            };
+            storage_size += self.storage_usage_settings.content_reports_collection_size + content_extra_bytes * 3;
+        };
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_mute_account_fee(&self, account_id: &AccountId, muted_id: &AccountId) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let muted_id_extra_bytes = u64::try_from(muted_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let storage_size = self.calc_friend_request_bytes(self.muted_accounts.contains_key(account_id), account_extra_bytes, muted_id_extra_bytes);
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_accept_friend_request_fee(&self, account_id: &AccountId, requester_id: &AccountId) -> u128 {
+        let mut fee = 0;
+        if !self.has_friend(account_id, requester_id) {
+            fee += self.calc_add_friend_fee(account_id, requester_id)
+                + self.calc_notification_fee(account_id, &Some(requester_id.clone()), None);
+        };
+        if !self.has_friend(requester_id, account_id) {
+            fee += self.calc_add_friend_fee(requester_id, account_id);
+        };
+        std::cmp::max(fee, 1)
+    }
+
+    fn has_friend(&self, account_id: &AccountId, friend_id: &AccountId) -> bool {
+        self.accounts_friends.get(account_id).is_some_and(|account_friends| account_friends.contains(friend_id))
+    }
+
+    fn calc_reversal_fee(&self, action: &ReversibleAction) -> u128 {
+        if self.admin_settings.max_daily_reversals == 0 || self.reversal_counters.contains_key(&get_reversal_key(action)) {
+            return 1
+        }
+        self.calc_storage_fee(self.storage_usage_settings.min_reversal_counter_size, 0)
+    }
+
+    fn calc_post_score_fee(&self, post_id: &PostId) -> u128 {
+        if self.posts_scores.contains_key(post_id) {
+            return 0
+        }
+        let post_id_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let storage_size = self.storage_usage_settings.min_post_score_size + post_id_extra_bytes;
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    // Pending owners have the same layout as the owners, so their storage measurement is reused
+    fn calc_transfer_post_fee(&self, post_id: &PostId, new_owner: &AccountId) -> u128 {
+        let storage_size = match self.posts_pending_owners.get(post_id) {
+            Some(pending_owner) => u64::try_from(new_owner.as_str().len().saturating_sub(pending_owner.as_str().len())).unwrap(),
+            None => self.storage_usage_settings.min_post_owner_size
+                + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+                + u64::try_from(new_owner.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        };
+        std::cmp::max(self.calc_storage_fee(storage_size, 0), 1)
+    }
+
+    fn calc_accept_post_transfer_fee(&self, post_id: &PostId, account_id: &AccountId) -> u128 {
+        let storage_size = match self.posts_owners.get(post_id) {
+            Some(owner_id) => u64::try_from(account_id.as_str().len().saturating_sub(owner_id.as_str().len())).unwrap(),
+            None => self.storage_usage_settings.min_post_owner_size
+                + u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
+                + u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        };
+        std::cmp::max(self.calc_storage_fee(storage_size, 0), 1)
+    }
+
+    fn calc_enable_likes_history_fee(&self, account_id: &AccountId) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let storage_size = self.storage_usage_settings.account_likes_history_collection_size + account_extra_bytes;
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_account_likes_history_fee(&self, account_id: &AccountId, post_id: &PostId, is_msg: bool) -> u128 {
+        if !self.accounts_likes_history.contains_key(account_id) {
+            return 0
+        }
+        let post_id_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let msg_idx_bytes = if is_msg { 8u64 } else { 0u64 };
+        let storage_size = self.storage_usage_settings.min_account_like_history_size + post_id_extra_bytes + msg_idx_bytes;
+        self.calc_storage_fee(storage_size, self.admin_settings.account_recent_like_extra_fee_percent)
+    }
+
+    fn calc_message_tips_fee(&self, msg_id: &MessageID) -> u128 {
+        if self.messages_tips.contains_key(&msg_id.into()) {
+            return 1
+        }
+        let post_id_extra_bytes = u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let storage_size = self.storage_usage_settings.min_message_tips_size + post_id_extra_bytes;
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_message_near_tips_fee(&self, account_id: &AccountId, msg_id: &MessageID) -> u128 {
+        let author_id = self.posts_messages.get(&msg_id.post_id).and_then(|post_messages| post_messages.get(msg_id.msg_idx.into())).unwrap().account;
+
+        let mut storage_size = 0u64;
+        if !self.messages_near_tips.contains_key(&msg_id.into()) {
+            storage_size += self.storage_usage_settings.min_message_near_tips_size
+                + u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap();
+        };
+        for id in [account_id, &author_id] {
+            if !self.accounts_near_tips.contains_key(id) {
+                storage_size += self.storage_usage_settings.min_account_near_tips_size
+                    + u64::try_from(id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+            };
+        }
+        if storage_size == 0 {
+            return 1
+        }
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_post_message_likes_count_fee(&self, post_id: &PostId) -> u128 {
+        if self.posts_message_likes_counts.contains_key(post_id) {
+            return 0
+        }
+        let post_id_extra_bytes = u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap();
+        let storage_size = self.storage_usage_settings.min_post_message_likes_count_size + post_id_extra_bytes;
+        self.calc_storage_fee(storage_size, self.admin_settings.like_message_extra_fee_percent)
+    }
+
+    fn calc_import_friends_fee(&self, account_id: &AccountId, friends: &[AccountId]) -> u128 {
+        let account_friends = self.accounts_friends.get(account_id);
+        let is_first = account_friends.is_none();
+        let account_extra_bytes = if is_first {
+            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap() * 3 // the key is also stored twice in 'accounts_with_friends'
+        } else {
+            0u64
+        };
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.account_friends_collection_size
+        } else {
+            0u64
+        };
+
+        let mut new_friends: Vec<&AccountId> = friends
+            .iter()
+            .filter(|friend_id| !account_friends.as_ref().is_some_and(|account_friends| account_friends.contains(friend_id)))
+            .collect();
+        new_friends.sort();
+        new_friends.dedup();
+
+        let friends_bytes: u64 = new_friends
+            .iter()
+            .map(|friend_id| {
+                let friend_id_extra_bytes = u64::try_from(friend_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+                self.storage_usage_settings.min_account_friend_size + (friend_id_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
+            })
+            .sum();
+
+        if friends_bytes == 0 {
+            return 1
+        }
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("friends_bytes bytes {}", friends_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+
+        let storage_size = friends_bytes
+            + account_extra_bytes
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_friend_extra_fee_percent)
+    }
+
+    fn calc_update_profile_fee(&self, account_id: &AccountId, profile_update: &AccountProfileData) -> u128 {
+        let existing_profile = self.accounts_profiles.get(account_id);
+        let account_extra_bytes = if existing_profile.is_none() {
+            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+        let json_metadata_extra_bytes = match &profile_update.json_metadata {
+            Some(metadata) => {
+              if let Some(p) = &existing_profile {
+                  match u64::try_from(metadata.len()).unwrap().checked_sub(u64::try_from(p.json_metadata.len()).unwrap()) {
+                      Some(diff) => diff,
+                      None => 0u64
+                  }
+              } else {
+                  u64::try_from(metadata.len()).unwrap()
+              }
+            },
+            None => 0u64
+        };
+        let image_extra_bytes = match &profile_update.image {
+            Some(bytes) => {
+                let v : Vec<u8> = bytes.clone().into();
+                if let Some(p) = &existing_profile {
+                    match u64::try_from(v.len()).unwrap().checked_sub(p.current_image_len) {
+                        Some(diff) => diff,
+                        None => 0u64
+                    }
+                } else {
+                    u64::try_from(v.len()).unwrap()
+                }
+            },
+            None => 0u64
+        };
+
+        let image_url_extra_bytes = match &profile_update.image_url {
+            Some(image_url) => {
+              if let Some(p) = &existing_profile {
+                  match u64::try_from(image_url.len()).unwrap().checked_sub(u64::try_from(p.image_url.len()).unwrap()) {
+                      Some(diff) => diff,
+                      None => 0u64
+                  }
+              } else {
+                  u64::try_from(image_url.len()).unwrap()
+              }
+            },
+            None => 0u64
+        };
+
+        let min_account_profile_size = if existing_profile.is_none() {
+            self.storage_usage_settings.min_account_profile_size 
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("json_metadata_extra_bytes bytes {}", json_metadata_extra_bytes);
+        // log!("image_url_extra_bytes bytes {}", image_url_extra_bytes);
+        // log!("image_extra_bytes bytes {}", image_extra_bytes);
+        // log!("min_account_profile_size bytes {}", min_account_profile_size);
+
+        let storage_size = min_account_profile_size
+            + account_extra_bytes 
+            + json_metadata_extra_bytes
+            + image_extra_bytes
+            + image_url_extra_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.update_profile_extra_fee_percent)
+    }
+
+    fn calc_verify_account_fee(&self, account_id: &AccountId, contract_id: &AccountId, token_id: &str) -> u128 {
+        let credential_bytes = u64::try_from(contract_id.as_str().len() - MIN_ACCOUNT_ID_LEN + token_id.len() - MIN_TOKEN_ID_LEN).unwrap();
+        let storage_size = match self.accounts_verifications.get(account_id) {
+            // Replaced verification is charged only for the grown credential
+            Some(existing) => {
+                let existing_bytes = u64::try_from(existing.contract_id.as_str().len() - MIN_ACCOUNT_ID_LEN + existing.token_id.len() - MIN_TOKEN_ID_LEN).unwrap();
+                credential_bytes.saturating_sub(existing_bytes)
+            },
+            None => {
+                let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+                self.storage_usage_settings.min_account_verification_size + account_extra_bytes + credential_bytes
+            }
+        };
+
+        // log!("verification storage_size bytes {}", storage_size);
+
+        self.calc_storage_fee(storage_size, self.admin_settings.update_profile_extra_fee_percent)
+    }
+
+    fn calc_request_account_link_fee(&self, primary_id: &AccountId, account_id: &AccountId) -> u128 {
+        let existing_request = self.accounts_link_requests.get(account_id);
+        let account_extra_bytes = if existing_request.is_none() {
+            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+        let primary_extra_bytes = match &existing_request {
+            Some(existing_primary_id) => {
+                u64::try_from(primary_id.as_str().len()).unwrap().saturating_sub(u64::try_from(existing_primary_id.as_str().len()).unwrap())
+            },
+            None => u64::try_from(primary_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        };
+        let min_account_link_request_size = if existing_request.is_none() {
+            self.storage_usage_settings.min_account_link_request_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("primary_extra_bytes bytes {}", primary_extra_bytes);
+
+        let storage_size = min_account_link_request_size
+            + account_extra_bytes
+            + primary_extra_bytes;
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_accept_account_link_fee(&self, account_id: &AccountId, primary_id: &AccountId) -> u128 {
+        let is_first = !self.accounts_links.contains_key(primary_id);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let primary_extra_bytes = u64::try_from(primary_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let primary_key_extra_bytes = if is_first {
+            primary_extra_bytes
+        } else {
+            0u64
+        };
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.account_links_collection_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("primary_extra_bytes bytes {}", primary_extra_bytes);
+        // log!("primary_key_extra_bytes bytes {}", primary_key_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+
+        let storage_size = self.storage_usage_settings.min_account_link_size
+            + (account_extra_bytes * 3) // identity key + UnorderedSet stores additional key in its 'elements: Vector<T>'
+            + primary_extra_bytes
+            + primary_key_extra_bytes
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    // Conversation lists have the same layout as the friend requests, so their storage measurement is reused
+    fn calc_send_direct_message_fee(&self, account_id: &AccountId, recipient_id: &AccountId, payload_len: usize) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let payload_extra_bytes = u64::try_from(payload_len - MIN_DIRECT_MESSAGE_LEN).unwrap();
+        let mut storage_size = self.storage_usage_settings.min_direct_message_size
+            + account_extra_bytes
+            + payload_extra_bytes;
+
+        if !self.conversations.contains_key(&ConversationId::new(account_id, recipient_id)) {
+            let recipient_extra_bytes = u64::try_from(recipient_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+            // Both accounts are stored in the conversation key and in the conversation lists of each other
+            storage_size += self.storage_usage_settings.conversation_collection_size
+                + account_extra_bytes
+                + recipient_extra_bytes
+                + self.calc_friend_request_bytes(self.accounts_conversations.contains_key(account_id), account_extra_bytes, recipient_extra_bytes)
+                + self.calc_friend_request_bytes(self.accounts_conversations.contains_key(recipient_id), recipient_extra_bytes, account_extra_bytes);
+        };
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    }
+
+    fn calc_post_on_wall_fee(&self, account_id: &AccountId, target_account: &AccountId, text: &str) -> u128 {
+        let is_first = !self.accounts_walls.contains_key(target_account);
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let target_account_extra_bytes = if is_first {
+            u64::try_from(target_account.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+        let text_extra_bytes = u64::try_from(text.len() - MIN_POST_MESSAGE_LEN).unwrap();
+        let collection_bytes = if is_first {
+            self.storage_usage_settings.wall_messages_collection_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("target_account_extra_bytes bytes {}", target_account_extra_bytes);
+        // log!("text_extra_bytes bytes {}", text_extra_bytes);
+        // log!("collection_bytes bytes {}", collection_bytes);
+
+        let storage_size = self.storage_usage_settings.min_wall_message_size
+            + account_extra_bytes
+            + target_account_extra_bytes
+            + text_extra_bytes
+            + collection_bytes;
+
+        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    }
+
+    fn calc_grant_posting_delegate_fee(&self, account_id: &AccountId, delegate_id: &AccountId) -> u128 {
+        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+        let delegate_id_extra_bytes = u64::try_from(delegate_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("delegate_id_extra_bytes bytes {}", delegate_id_extra_bytes);
+
+        let storage_size = self.storage_usage_settings.min_posting_delegate_size
+            + account_extra_bytes
+            + delegate_id_extra_bytes;
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_register_app_key_fee(&self, account_id: &AccountId, public_key: &PublicKey, scopes: &[AppScope]) -> u128 {
+        let app_key = AccountAppKey { account_id: account_id.clone(), public_key: public_key.clone() };
+        let existing_scopes = self.accounts_app_keys.get(&app_key);
+        let account_extra_bytes = if existing_scopes.is_none() {
+            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
+        } else {
+            0u64
+        };
+        // Storage is measured for ED25519 key (33 bytes) with a single scope
+        let public_key_extra_bytes = if existing_scopes.is_none() {
+            u64::try_from(public_key.as_bytes().len()).unwrap().saturating_sub(33)
+        } else {
+            0u64
+        };
+        let scopes_extra_bytes = match &existing_scopes {
+            Some(existing) => u64::try_from(scopes.len()).unwrap().saturating_sub(u64::try_from(existing.len()).unwrap()),
+            None => u64::try_from(scopes.len() - 1).unwrap()
+        };
+        let min_app_key_size = if existing_scopes.is_none() {
+            self.storage_usage_settings.min_app_key_size
+        } else {
+            0u64
+        };
+
+        // log!("account_extra_bytes bytes {}", account_extra_bytes);
+        // log!("public_key_extra_bytes bytes {}", public_key_extra_bytes);
+        // log!("scopes_extra_bytes bytes {}", scopes_extra_bytes);
+
+        let storage_size = min_app_key_size
+            + account_extra_bytes
+            + public_key_extra_bytes
+            + scopes_extra_bytes;
+
+        self.calc_storage_fee(storage_size, 0)
+    }
+
+    fn calc_probation_fee(&self, account_id: &AccountId, fee: u128) -> u128 {
+        if !self.is_account_on_probation(account_id) {
+            return fee
+        }
+        fee.saturating_mul(self.probation_policy.fee_multiplier_percent.into()).saturating_div(100u128)
+    }
+
+    fn calc_storage_fee(&self, storage_size: StorageUsage, call_extra_fee_percent: u8) -> u128 {
+        let near_fee = Balance::from(storage_size) * env::storage_byte_cost();
+        let activity_ft_fee = near_fee.saturating_mul(self.cached_config.activity_ft_exchange_rate());
+        // log!("storage_size {}", storage_size);
+        // log!("activity_ft_fee {}", activity_ft_fee);
+        let fee: u128 = if call_extra_fee_percent == 0 {
+            activity_ft_fee
+        } else {
+            let extra_fee = activity_ft_fee.saturating_mul(call_extra_fee_percent.into()).saturating_div(100u128);
+            // log!("extra_fee {}", extra_fee);
+            activity_ft_fee + extra_fee
+        };
+        // log!("fee {}", fee);
+        fee
+    }
+    
+    fn calc_storage_refund(&self, reclaimed_storage: StorageUsage) -> u128 {
+        if self.admin_settings.archive_refund_percent == 0 {
+            return 0
+        }
+        let near_refund = Balance::from(reclaimed_storage) * env::storage_byte_cost();
+        let activity_ft_refund = near_refund.saturating_mul(self.cached_config.activity_ft_exchange_rate());
+        activity_ft_refund.saturating_mul(self.admin_settings.archive_refund_percent.into()).saturating_div(100u128)
+    }
+
+    // Execute call logic
+
+    fn execute_add_message_to_post_call(&mut self, account_id: AccountId, post_id: PostId, text: String, delegate: Option<AccountId>, lang: Option<String>, sensitive: bool) -> MessageID {
+        let sensitive = self.get_sensitive_marker(&account_id, sensitive);
+        let mentions = self.get_message_mentions(&account_id, &text);
+        let msg_id = self.add_payload_to_post(account_id, post_id, MessagePayload::Text { text }, delegate, lang, sensitive);
+        self.add_account_mentions(mentions, &msg_id);
+        msg_id
+    }
+
+    fn execute_add_listing_to_post_call(&mut self, account_id: AccountId, post_id: PostId, marketplace: AccountId, listing_id: String) -> MessageID {
+        let sensitive = self.get_sensitive_marker(&account_id, false);
+        self.add_payload_to_post(account_id, post_id, MessagePayload::Listing { marketplace, listing_id }, None, None, sensitive)
+    }
+
+    fn execute_add_quote_to_post_call(&mut self, account_id: AccountId, post_id: PostId, quoted_post_id: PostId, quoted_msg_idx: Option<U64>, text: String) -> MessageID {
+        let sensitive = self.get_sensitive_marker(&account_id, false);
+        let mentions = self.get_message_mentions(&account_id, &text);
+        let payload = MessagePayload::Quote { quoted_post_id, quoted_msg_idx: quoted_msg_idx.map(u64::from), text };
+        let msg_id = self.add_payload_to_post(account_id, post_id, payload, None, None, sensitive);
+        self.add_account_mentions(mentions, &msg_id);
+        msg_id
+    }
+
+    fn execute_add_media_to_post_call(&mut self, account_id: AccountId, post_id: PostId, text: String, media_cid: String, mime_type: String) -> MessageID {
+        let sensitive = self.get_sensitive_marker(&account_id, false);
+        let mentions = self.get_message_mentions(&account_id, &text);
+        let payload = MessagePayload::Media { text, media_cid, mime_type };
+        let msg_id = self.add_payload_to_post(account_id, post_id, payload, None, None, sensitive);
+        self.add_account_mentions(mentions, &msg_id);
+        msg_id
+    }
+
+    fn execute_add_link_to_post_call(&mut self, account_id: AccountId, post_id: PostId, url: String, title: Option<String>, description: Option<String>) -> MessageID {
+        let sensitive = self.get_sensitive_marker(&account_id, false);
+        self.add_payload_to_post(account_id, post_id, MessagePayload::Link { url, title, description }, None, None, sensitive)
+    }
+
+    fn add_payload_to_post(&mut self, account_id: AccountId, post_id: PostId, payload: MessagePayload, delegate: Option<AccountId>, lang: Option<String>, sensitive: Option<SensitiveMarker>) -> MessageID {
+        let post_author_id = self.get_post_author_id(&post_id);
+        let post_id = self.continue_post_if_full(post_id);
+        let mut post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| {
+            self.add_post_messages_storage(&post_id)
+        });
+        
+        let msg_idx = post_messages.len();
+        let msg = Message {
+            account: account_id,
+            parent_idx: None,
+            payload,
+            timestamp: env::block_timestamp(),
+            edited_at: None,
+            delegate,
+            lang,
+            sensitive
+        };
+
+        post_messages.push(&msg);
+        self.posts_messages.insert(&post_id, &post_messages);
+        self.update_post_stats(&post_id, |post_stats| post_stats.messages_count += 1);
+        self.add_account_message_stat(&msg.account);
+        self.add_message_to_account_index(&msg.account, &MessageId { post_id: post_id.clone(), msg_idx });
+        self.add_message_to_author_index(&post_id, msg.account.clone(), msg_idx);
+
+        let msg_id: MessageID = MessageId { post_id, msg_idx }.into();
+        self.notify(post_author_id, &msg.account, NotificationKind::PostComment { msg_id: msg_id.clone() });
+        msg_id
+    }
+
+    fn execute_add_message_to_message_call(&mut self, account_id: AccountId, parent_msg_id: MessageId, text: String, delegate: Option<AccountId>, lang: Option<String>, sensitive: bool) -> MessageID {
+        let parent_author_id = self.get_message_author_id(&parent_msg_id);
+        let sensitive = self.get_sensitive_marker(&account_id, sensitive);
+        let mentions = self.get_message_mentions(&account_id, &text);
+        let mut post_messages = self.posts_messages.get(&parent_msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        
+        let msg_idx = post_messages.len();
+        let msg = Message {
+            account: account_id,
+            parent_idx: Some(parent_msg_id.msg_idx),
+            payload: MessagePayload::Text { text },
+            timestamp: env::block_timestamp(),
+            edited_at: None,
+            delegate,
+            lang,
+            sensitive
+        };
+        post_messages.push(&msg);
+        self.posts_messages.insert(&parent_msg_id.post_id, &post_messages);
+        self.update_post_stats(&parent_msg_id.post_id, |post_stats| post_stats.messages_count += 1);
+        self.add_account_message_stat(&msg.account);
+        self.add_message_to_account_index(&msg.account, &MessageId { post_id: parent_msg_id.post_id.clone(), msg_idx });
+        self.add_message_to_author_index(&parent_msg_id.post_id, msg.account.clone(), msg_idx);
+        self.add_message_to_replies_index(&parent_msg_id, msg_idx);
+
+        let msg_id: MessageID = MessageId { post_id: parent_msg_id.post_id, msg_idx }.into();
+        self.add_account_mentions(mentions, &msg_id);
+        self.notify(parent_author_id, &msg.account, NotificationKind::MessageReply { msg_id: msg_id.clone() });
+        msg_id
+    }
+    
+    fn execute_edit_message_call(&mut self, msg_id: MessageId, text: String) {
+        let mut post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let mut msg = post_messages.get(msg_id.msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        msg.payload = MessagePayload::Text { text };
+        msg.edited_at = Some(env::block_timestamp());
+        post_messages.replace(msg_id.msg_idx, &msg);
+    }
+
+    // The message stays in the post as a tombstone to keep indexes of the following messages and replies
+    fn execute_delete_message_call(&mut self, msg_id: MessageId) -> u128 {
+        let initial_storage_usage = env::storage_usage();
+        let author_id = self.remove_message_content(&msg_id);
+
+        let reclaimed_storage = initial_storage_usage.saturating_sub(env::storage_usage());
+        let refund = self.calc_storage_refund(reclaimed_storage);
+        if refund != 0 {
+            ext_ft::ext(self.fee_ft.clone())
+                .with_static_gas(Gas(5*TGAS))
+                .with_attached_deposit(1)
+                .ft_transfer(author_id, U128::from(refund), Some(String::from("Deleted message storage refund")));
+        };
+
+        refund
+    }
+
+    // The tombstone is marked, so clients can show a placeholder
+    fn execute_remove_message_from_own_post_call(&mut self, msg_id: MessageId) -> u128 {
+        let refund = self.execute_delete_message_call(msg_id.clone());
+
+        let mut post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let mut msg = post_messages.get(msg_id.msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+        msg.payload = MessagePayload::HiddenByAuthor;
+        post_messages.replace(msg_id.msg_idx, &msg);
+
+        refund
     }
 
-    pub fn add_message_to_message(&mut self, parent_msg_id: MessageID, text: String) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_add_message_to_message_call(&parent_msg_id, &text);
-        let fee = self.calc_add_message_to_message_fee(&account_id, &text);
-        // log!("add_message_to_message fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::AddMessageToMessage { parent_msg_id, text })
-    }
+    // Message is replaced with a tombstone and its likes are removed. Returns the message author
+    fn remove_message_content(&mut self, msg_id: &MessageId) -> AccountId {
+        let mut post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let mut msg = post_messages.get(msg_id.msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        msg.payload = MessagePayload::Deleted;
+        msg.edited_at = None;
+        msg.delegate = None;
+        msg.lang = None;
+        post_messages.replace(msg_id.msg_idx, &msg);
+        self.update_post_stats(&msg_id.post_id, |post_stats| {
+            post_stats.messages_count = post_stats.messages_count.saturating_sub(1);
+            if post_stats.pinned_msg_idx == Some(msg_id.msg_idx) {
+                post_stats.pinned_msg_idx = None;
+            };
+        });
+
+        if self.posts_messages_likes.contains_key(msg_id) {
+            self.remove_post_message_likes_storage(msg_id);
+        };
+
+        msg.account
+    }
+
+    fn execute_set_message_sensitive_call(&mut self, msg_id: MessageId, sensitive: bool) {
+        let mut post_messages = self.posts_messages.get(&msg_id.post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let mut msg = post_messages.get(msg_id.msg_idx).unwrap_or_else(|| ContractError::MessageNotFound.panic());
+
+        // A moderator mark stays as it is
+        if msg.sensitive != Some(SensitiveMarker::Moderator) {
+            msg.sensitive = if sensitive { Some(SensitiveMarker::Author) } else { None };
+            post_messages.replace(msg_id.msg_idx, &msg);
+        };
+    }
+
+    fn add_message_to_author_index(&mut self, post_id: &PostId, account_id: AccountId, msg_idx: u64) {
+        let post_author_id = PostAuthorId { post_id: post_id.clone(), account_id };
+        let mut author_messages = self.posts_authors_messages.get(&post_author_id).unwrap_or_else(|| {
+            self.update_post_stats(post_id, |post_stats| post_stats.unique_commenters += 1);
+            self.add_post_author_messages_storage(&post_author_id)
+        });
+
+        author_messages.push(&msg_idx);
+        self.posts_authors_messages.insert(&post_author_id, &author_messages);
+    }
+
+    // Mentioned accounts are resolved to their identities, self mentions and accounts that blocked the author are skipped
+    fn get_message_mentions(&self, account_id: &AccountId, text: &str) -> Vec<AccountId> {
+        let mut mentions: Vec<AccountId> = Vec::new();
+        for mentioned_id in parse_mentions(text) {
+            let mentioned_id = self.get_identity(mentioned_id);
+            let is_blocked = self.blocked_accounts
+                .get(&mentioned_id)
+                .is_some_and(|blocked_accounts| blocked_accounts.contains(account_id));
+            if mentioned_id != *account_id && !is_blocked && !mentions.contains(&mentioned_id) {
+                mentions.push(mentioned_id);
+            }
+        }
+        mentions
+    }
+
+    fn add_account_mentions(&mut self, mentions: Vec<AccountId>, msg_id: &MessageID) {
+        let msg_id: MessageId = msg_id.clone().into();
+        for mentioned_id in mentions {
+            let mut account_mentions = self.accounts_mentions.get(&mentioned_id).unwrap_or_else(|| {
+                Vector::new(StorageKeys::AccountMentions { account_id: env::sha256(mentioned_id.as_bytes()) })
+            });
+            account_mentions.push(&msg_id);
+            self.accounts_mentions.insert(&mentioned_id, &account_mentions);
+        }
+    }
+
+    // Accounts are not notified about their own actions and about actions of the accounts they blocked
+    fn is_notified(&self, recipient_id: &AccountId, account_id: &AccountId) -> bool {
+        let is_blocked = self.blocked_accounts
+            .get(recipient_id)
+            .is_some_and(|blocked_accounts| blocked_accounts.contains(account_id));
+        recipient_id != account_id && !is_blocked
+    }
+
+    fn notify(&mut self, recipient_id: Option<AccountId>, account_id: &AccountId, kind: NotificationKind) {
+        if let Some(recipient_id) = recipient_id {
+            if self.is_notified(&recipient_id, account_id) {
+                self.add_notification(&recipient_id, account_id.clone(), kind);
+            }
+        }
+    }
+
+    fn add_notification(&mut self, recipient_id: &AccountId, account_id: AccountId, kind: NotificationKind) {
+        let mut notifications_log = self.accounts_notifications.get(recipient_id).unwrap_or_else(|| NotificationsLog {
+            items: Vector::new(StorageKeys::AccountNotifications { account_id: env::sha256(recipient_id.as_bytes()) }),
+            total: 0,
+            read: 0
+        });
+        let notification = Notification { kind, actor: account_id, timestamp: env::block_timestamp() };
+        if notifications_log.items.len() < MAX_NOTIFICATIONS {
+            notifications_log.items.push(&notification);
+        } else {
+            notifications_log.items.replace(notifications_log.total % MAX_NOTIFICATIONS, &notification);
+        }
+        notifications_log.total += 1;
+        self.accounts_notifications.insert(recipient_id, &notifications_log);
+    }
+
+    fn execute_mark_notifications_read_call(&mut self, account_id: AccountId) {
+        if let Some(mut notifications_log) = self.accounts_notifications.get(&account_id) {
+            notifications_log.read = notifications_log.total;
+            self.accounts_notifications.insert(&account_id, &notifications_log);
+        }
+    }
+
+    fn remove_account_notifications_storage(&mut self, account_id: &AccountId) {
+        let mut notifications_log = self.accounts_notifications.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        notifications_log.items.clear();
+        self.accounts_notifications.remove(account_id);
+    }
+
+    fn add_message_to_replies_index(&mut self, parent_msg_id: &MessageId, msg_idx: u64) {
+        let mut message_replies = self.posts_messages_replies.get(parent_msg_id).unwrap_or_else(|| {
+            Vector::new(StorageKeys::PostMessageReplies { post_id: env::sha256(parent_msg_id.post_id.as_bytes()), msg_idx: parent_msg_id.msg_idx })
+        });
+        message_replies.push(&msg_idx);
+        self.posts_messages_replies.insert(parent_msg_id, &message_replies);
+    }
+
+    fn remove_message_replies_storage(&mut self, msg_id: &MessageId) {
+        let mut message_replies = self.posts_messages_replies.get(msg_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        message_replies.clear();
+        self.posts_messages_replies.remove(msg_id);
+    }
+
+    fn add_message_to_account_index(&mut self, account_id: &AccountId, msg_id: &MessageId) {
+        let mut account_messages = self.accounts_messages.get(account_id).unwrap_or_else(|| {
+            Vector::new(StorageKeys::AccountMessages { account_id: env::sha256(account_id.as_bytes()) })
+        });
+        account_messages.push(msg_id);
+        self.accounts_messages.insert(account_id, &account_messages);
+    }
+
+    fn remove_account_messages_storage(&mut self, account_id: &AccountId) {
+        let mut account_messages = self.accounts_messages.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_messages.clear();
+        self.accounts_messages.remove(account_id);
+    }
+
+    fn execute_repost_call(&mut self, account_id: AccountId, post_id: PostId) {
+        let mut post_reposts = self.posts_reposts.get(&post_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::PostReposts { post_id: env::sha256(post_id.as_bytes()) })
+        });
+        post_reposts.insert(&account_id);
+        self.posts_reposts.insert(&post_id, &post_reposts);
+    }
+
+    fn execute_like_post_call(&mut self, account_id: AccountId, post_id: PostId) -> AccountLike {
+        let mut post_likes = self.posts_likes.get(&post_id).unwrap_or_else(|| {
+            self.add_post_likes_storage(&post_id)
+        });
+        post_likes.insert(&account_id);
+        self.posts_likes.insert(&post_id, &post_likes);
+        self.update_post_stats(&post_id, |post_stats| post_stats.likes_count += 1);
+        self.update_likes_stats(&account_id, self.get_post_author_id(&post_id), true);
+        self.notify(self.get_post_author_id(&post_id), &account_id, NotificationKind::PostLike { post_id: post_id.clone() });
+
+        AccountLike::PostLike { post_id }
+    }
+
+    fn execute_unlike_post_call(&mut self, account_id: AccountId, post_id: PostId) -> AccountLike {
+        let mut post_likes = self.posts_likes.get(&post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_likes.remove(&account_id);                
+        self.posts_likes.insert(&post_id, &post_likes);
+        self.update_post_stats(&post_id, |post_stats| post_stats.likes_count = post_stats.likes_count.saturating_sub(1));
+        self.update_likes_stats(&account_id, self.get_post_author_id(&post_id), false);
+
+        AccountLike::PostLike { post_id }
+    }
+
+    fn execute_like_message_call(&mut self, account_id: AccountId, msg_id: MessageId) -> AccountLike {
+        let mut post_message_likes = self.posts_messages_likes.get(&msg_id).unwrap_or_else(|| {
+            self.add_post_message_likes_storage(&msg_id)
+        });
+        post_message_likes.insert(&account_id);
+        self.posts_messages_likes.insert(&msg_id, &post_message_likes);
+        self.update_likes_stats(&account_id, self.get_message_author_id(&msg_id), true);
+        self.notify(self.get_message_author_id(&msg_id), &account_id, NotificationKind::MessageLike { msg_id: msg_id.clone().into() });
+
+        AccountLike::MessageLike { msg_id }
+    }
+
+    fn execute_unlike_message_call(&mut self, account_id: AccountId, msg_id: MessageId) -> AccountLike  {
+        let mut post_message_likes = self.posts_messages_likes.get(&msg_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_message_likes.remove(&account_id);
+        self.posts_messages_likes.insert(&msg_id, &post_message_likes);
+        self.update_likes_stats(&account_id, self.get_message_author_id(&msg_id), false);
+
+        AccountLike::MessageLike { msg_id }
+    }
+
+    fn get_post_author_id(&self, post_id: &PostId) -> Option<AccountId> {
+        let post_messages = self.posts_messages.get(post_id)?;
+        self.get_post_owner_id(post_id, &post_messages)
+    }
+
+    fn get_message_author_id(&self, msg_id: &MessageId) -> Option<AccountId> {
+        self.posts_messages.get(&msg_id.post_id)
+            .and_then(|post_messages| post_messages.get(msg_id.msg_idx))
+            .map(|msg| msg.account)
+    }
+
+    fn update_likes_stats(&mut self, account_id: &AccountId, author_id: Option<AccountId>, liked: bool) {
+        let update = |count: u64| if liked { count.saturating_add(1) } else { count.saturating_sub(1) };
+        self.update_account_stats(account_id, |account_stats| account_stats.likes_given = update(account_stats.likes_given));
+        if let Some(author_id) = author_id {
+            if let Some(account_stats) = self.update_account_stats(&author_id, |account_stats| account_stats.likes_received = update(account_stats.likes_received)) {
+                self.update_leaderboard(LeaderboardKind::MostLiked, &author_id, account_stats.likes_received);
+            };
+        };
+    }
+
+    fn add_account_message_stat(&mut self, account_id: &AccountId) {
+        if let Some(account_stats) = self.update_account_stats(account_id, |account_stats| account_stats.messages_count += 1) {
+            self.update_leaderboard(LeaderboardKind::MostActive, account_id, account_stats.messages_count);
+        };
+    }
+
+    fn remove_account_message_stat(&mut self, account_id: &AccountId) {
+        if let Some(account_stats) = self.update_account_stats(account_id, |account_stats| account_stats.messages_count = account_stats.messages_count.saturating_sub(1)) {
+            self.update_leaderboard(LeaderboardKind::MostActive, account_id, account_stats.messages_count);
+        };
+    }
+
+    // Leaderboards are capped by 'leaderboard_size', so their storage is covered by the contract
+    fn update_leaderboard(&mut self, kind: LeaderboardKind, account_id: &AccountId, value: u64) {
+        if self.leaderboard_size == 0 {
+            return
+        }
+        let mut leaderboard = self.leaderboards.get(&kind).unwrap_or_default();
+        if let Some(idx) = leaderboard.iter().position(|(leader_id, _)| leader_id == account_id) {
+            if value == 0 {
+                leaderboard.swap_remove(idx);
+            } else {
+                leaderboard[idx].1 = value;
+            }
+        } else if value == 0 {
+            return
+        } else if leaderboard.len() < usize::from(self.leaderboard_size) {
+            leaderboard.push((account_id.clone(), value));
+        } else {
+            let (min_idx, min_value) = leaderboard
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, leader_value))| (idx, *leader_value))
+                .min_by_key(|(_, leader_value)| *leader_value)
+                .unwrap();
+            if value > min_value {
+                leaderboard[min_idx] = (account_id.clone(), value);
+            } else {
+                return
+            }
+        }
+        self.leaderboards.insert(&kind, &leaderboard);
+    }
+
+    fn add_message_tip(&mut self, msg_id: &MessageId, amount: u128) {
+        let tips = self.messages_tips.get(msg_id).unwrap_or(0);
+        self.messages_tips.insert(msg_id, &tips.saturating_add(amount));
+    }
+
+    // The tip is recorded even if the transfer fails (e.g. the author is not registered in the activity FT),
+    // the FT is kept by the contract then
+    fn execute_tip_message_call(&mut self, msg_id: MessageId, amount: u128) -> AccountId {
+        let author_id = self.posts_messages.get(&msg_id.post_id)
+            .and_then(|post_messages| post_messages.get(msg_id.msg_idx))
+            .unwrap_or_else(|| ContractError::MessageNotFound.panic())
+            .account;
+        self.add_message_tip(&msg_id, amount);
+        self.update_account_stats(&author_id, |account_stats| account_stats.tips_received = account_stats.tips_received.saturating_add(amount));
+
+        ext_ft::ext(self.fee_ft.clone())
+            .with_static_gas(Gas(5*TGAS))
+            .with_attached_deposit(1)
+            .ft_transfer(author_id.clone(), U128::from(amount), Some(String::from("Message tip")));
+        author_id
+    }
+
+    // Transfer to a nonexistent author account is refunded to the contract, the tip is kept in the platform cut balance then
+    fn execute_tip_message_near_call(&mut self, account_id: AccountId, msg_id: MessageId, amount: u128) -> (AccountId, u128) {
+        let author_id = self.posts_messages.get(&msg_id.post_id)
+            .and_then(|post_messages| post_messages.get(msg_id.msg_idx))
+            .unwrap_or_else(|| ContractError::MessageNotFound.panic())
+            .account;
+        let platform_cut = amount * u128::from(self.near_tip_cut_percent) / 100;
+
+        let tips = self.messages_near_tips.get(&msg_id).unwrap_or(0);
+        self.messages_near_tips.insert(&msg_id, &tips.saturating_add(amount));
+
+        let mut sender_tips = self.accounts_near_tips.get(&account_id).unwrap_or_default();
+        sender_tips.sent = sender_tips.sent.saturating_add(amount);
+        self.accounts_near_tips.insert(&account_id, &sender_tips);
+
+        let mut author_tips = self.accounts_near_tips.get(&author_id).unwrap_or_default();
+        author_tips.received = author_tips.received.saturating_add(amount - platform_cut);
+        self.accounts_near_tips.insert(&author_id, &author_tips);
+
+        self.near_tips_cut_balance += platform_cut;
+        if amount > platform_cut {
+            Promise::new(author_id.clone()).transfer(amount - platform_cut);
+        };
+        (author_id, platform_cut)
+    }
+
+    fn execute_super_like_post_call(&mut self, account_id: AccountId, post_id: PostId, weight: u8) {
+        let mut post_super_likes = self.posts_super_likes.get(&post_id).unwrap_or_else(|| {
+            self.add_post_super_likes_storage(&post_id)
+        });
+        post_super_likes.accounts.insert(&account_id, &weight);
+        post_super_likes.total_weight += u64::from(weight);
+        self.posts_super_likes.insert(&post_id, &post_super_likes);
+    }
+
+    // Returns the weight of the removed super like, 1 for a like
+    fn remove_post_super_like(&mut self, account_id: &AccountId, post_id: &PostId) -> u8 {
+        let mut post_super_likes = match self.posts_super_likes.get(post_id) {
+            Some(post_super_likes) => post_super_likes,
+            None => return 1
+        };
+        let weight = match post_super_likes.accounts.remove(account_id) {
+            Some(weight) => weight,
+            None => return 1
+        };
+        post_super_likes.total_weight -= u64::from(weight);
+        self.posts_super_likes.insert(post_id, &post_super_likes);
+        weight
+    }
+
+    fn execute_react_to_post_call(&mut self, account_id: AccountId, post_id: PostId, reaction: Reaction) {
+        let mut post_reactions = self.posts_reactions.get(&post_id).unwrap_or_else(|| {
+            self.add_post_reactions_storage(&post_id)
+        });
+        post_reactions.add(&account_id, reaction);
+        self.posts_reactions.insert(&post_id, &post_reactions);
+    }
+
+    fn execute_remove_post_reaction_call(&mut self, account_id: AccountId, post_id: PostId) -> Reaction {
+        let mut post_reactions = self.posts_reactions.get(&post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        let reaction = post_reactions.remove(&account_id).unwrap_or_else(|| ContractError::ReactionNotFound.panic());
+        self.posts_reactions.insert(&post_id, &post_reactions);
+        reaction
+    }
+
+    fn execute_react_to_message_call(&mut self, account_id: AccountId, msg_id: MessageId, reaction: Reaction) {
+        let mut message_reactions = self.posts_messages_reactions.get(&msg_id).unwrap_or_else(|| {
+            self.add_post_message_reactions_storage(&msg_id)
+        });
+        message_reactions.add(&account_id, reaction);
+        self.posts_messages_reactions.insert(&msg_id, &message_reactions);
+    }
+
+    fn execute_remove_message_reaction_call(&mut self, account_id: AccountId, msg_id: MessageId) -> Reaction {
+        let mut message_reactions = self.posts_messages_reactions.get(&msg_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        let reaction = message_reactions.remove(&account_id).unwrap_or_else(|| ContractError::ReactionNotFound.panic());
+        self.posts_messages_reactions.insert(&msg_id, &message_reactions);
+        reaction
+    }
+
+    fn execute_add_friend_call(&mut self, account_id: AccountId, friend_id: AccountId) {
+        let mut account_friends = self.accounts_friends.get(&account_id).unwrap_or_else(|| {
+            self.add_account_friends_storage(&account_id)
+        });
+
+        account_friends.insert(&friend_id);
+        self.accounts_friends.insert(&account_id, &account_friends);
+    }
+
+    fn execute_send_friend_request_call(&mut self, account_id: AccountId, friend_id: AccountId) {
+        let mut outgoing_requests = self.outgoing_friend_requests.get(&account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::AccountOutgoingFriendRequests { account_id: env::sha256(account_id.as_bytes()) })
+        });
+        outgoing_requests.insert(&friend_id);
+        self.outgoing_friend_requests.insert(&account_id, &outgoing_requests);
+
+        let mut incoming_requests = self.incoming_friend_requests.get(&friend_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::AccountIncomingFriendRequests { account_id: env::sha256(friend_id.as_bytes()) })
+        });
+        incoming_requests.insert(&account_id);
+        self.incoming_friend_requests.insert(&friend_id, &incoming_requests);
+    }
+
+    fn execute_report_content_call(&mut self, account_id: AccountId, content: ReportedContent, reason: ReportReason) {
+        let mut content_reports = self.content_reports.get(&content).unwrap_or_else(|| {
+            self.reported_contents.insert(&content);
+            UnorderedMap::new(StorageKeys::ContentReporters { content_hash: env::sha256(&content.try_to_vec().unwrap()) })
+        });
+        content_reports.insert(&account_id, &reason);
+        self.content_reports.insert(&content, &content_reports);
+    }
+
+    // Pending friend request of the blocked account is dropped
+    fn execute_block_account_call(&mut self, account_id: AccountId, blocked_id: AccountId) {
+        let mut blocked_accounts = self.blocked_accounts.get(&account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::AccountBlockedAccounts { account_id: env::sha256(account_id.as_bytes()) })
+        });
+        blocked_accounts.insert(&blocked_id);
+        self.blocked_accounts.insert(&account_id, &blocked_accounts);
+        self.remove_friend_request(&blocked_id, &account_id);
+    }
+
+    fn execute_accept_friend_request_call(&mut self, account_id: AccountId, requester_id: AccountId) {
+        self.remove_friend_request(&requester_id, &account_id);
+        if !self.has_friend(&account_id, &requester_id) {
+            self.execute_add_friend_call(account_id.clone(), requester_id.clone());
+            self.notify(Some(requester_id.clone()), &account_id, NotificationKind::FriendAdded);
+            log_event("friend_added", FriendAddedEventDTO { account_id: account_id.clone(), friend_id: requester_id.clone() });
+        };
+        if !self.has_friend(&requester_id, &account_id) {
+            self.execute_add_friend_call(requester_id.clone(), account_id.clone());
+            log_event("friend_added", FriendAddedEventDTO { account_id: requester_id, friend_id: account_id });
+        };
+    }
+
+    fn remove_friend_request(&mut self, requester_id: &AccountId, account_id: &AccountId) {
+        remove_account_set_entry(&mut self.outgoing_friend_requests, requester_id, account_id);
+        remove_account_set_entry(&mut self.incoming_friend_requests, account_id, requester_id);
+    }
+
+    fn execute_remove_friend_call(&mut self, account_id: AccountId, friend_id: AccountId) {
+        let mut account_friends = self.accounts_friends.get(&account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_friends.remove(&friend_id);
+        if account_friends.is_empty() {
+            self.remove_account_friends_storage(&account_id);
+        } else {
+            self.accounts_friends.insert(&account_id, &account_friends);
+        };
+    }
+
+    fn execute_update_profile_call(&mut self, account_id: AccountId, json_metadata: Option<String>, image: Option<Vec<u8>>, image_url: Option<String>, likes_visibility: Option<LikesVisibility>, wall_policy: Option<WallPolicy>) {
+        let mut account_profile = self.accounts_profiles.get(&account_id).unwrap_or_else(|| {
+            self.add_account_profile_storage(&account_id)
+        });
+
+        if let Some(metadata) = json_metadata {
+            account_profile.json_metadata = metadata;
+        };
+
+        if let Some(bytes) = image {
+            account_profile.image.set(&bytes);
+            account_profile.current_image_len = u64::try_from(bytes.len()).unwrap();
+        };
+
+        if let Some(url) = image_url {
+            account_profile.image_url = url;
+        };
+
+        if let Some(visibility) = likes_visibility {
+            account_profile.likes_visibility = visibility;
+        };
+
+        if let Some(policy) = wall_policy {
+            account_profile.wall_policy = policy;
+        };
+
+        self.accounts_profiles.insert(&account_id, &account_profile);
+    }
+
+    fn execute_verify_account_call(&mut self, account_id: AccountId, contract_id: AccountId, token_id: String, tier: u8) {
+        self.accounts_verifications.insert(&account_id, &AccountVerification {
+            tier,
+            contract_id,
+            token_id,
+            verified_at: env::block_timestamp()
+        });
+    }
+
+    fn execute_request_account_link_call(&mut self, primary_id: AccountId, account_id: AccountId) {
+        self.accounts_link_requests.insert(&account_id, &primary_id);
+    }
+
+    fn execute_accept_account_link_call(&mut self, account_id: AccountId, primary_id: AccountId) {
+        self.accounts_link_requests.remove(&account_id);
+
+        let mut account_links = self.accounts_links.get(&primary_id).unwrap_or_else(|| {
+            self.add_account_links_storage(&primary_id)
+        });
+
+        account_links.insert(&account_id);
+        self.accounts_links.insert(&primary_id, &account_links);
+        self.accounts_identities.insert(&account_id, &primary_id);
+        self.sync_marketplace_identity(account_id, Some(primary_id));
+    }
+
+    fn execute_unlink_account_call(&mut self, account_id: AccountId) {
+        if let Some(primary_id) = self.accounts_identities.remove(&account_id) {
+            let mut account_links = self.accounts_links.get(&primary_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+            account_links.remove(&account_id);
+            if account_links.is_empty() {
+                self.accounts_links.remove(&primary_id);
+            } else {
+                self.accounts_links.insert(&primary_id, &account_links);
+            }
+            self.sync_marketplace_identity(account_id, None);
+        };
+    }
+
+    // Detached, so a failed sync never reverts the link
+    fn sync_marketplace_identity(&self, account_id: AccountId, identity_id: Option<AccountId>) {
+        if let Some(marketplace) = self.marketplace.clone() {
+            ext_marketplace::ext(marketplace)
+                .with_static_gas(Gas(5*TGAS))
+                .set_account_identity(account_id, identity_id);
+        };
+    }
+
+    fn execute_post_on_wall_call(&mut self, account_id: AccountId, target_account: AccountId, text: String) -> u64 {
+        let mut account_wall = self.accounts_walls.get(&target_account).unwrap_or_else(|| {
+            self.add_account_wall_storage(&target_account)
+        });
+
+        let msg_idx = account_wall.len();
+        account_wall.push(&WallMessage {
+            account: account_id,
+            text,
+            timestamp: env::block_timestamp()
+        });
+        self.accounts_walls.insert(&target_account, &account_wall);
+        msg_idx
+    }
+
+    fn execute_send_direct_message_call(&mut self, account_id: AccountId, recipient_id: AccountId, payload: Vec<u8>) -> u64 {
+        let conversation_id = ConversationId::new(&account_id, &recipient_id);
+        if !self.conversations.contains_key(&conversation_id) {
+            self.add_account_conversation(&account_id, &recipient_id);
+            self.add_account_conversation(&recipient_id, &account_id);
+        };
+        self.add_direct_message(&conversation_id, DirectMessage {
+            sender: account_id,
+            payload,
+            timestamp: env::block_timestamp()
+        })
+    }
+
+    fn add_direct_message(&mut self, conversation_id: &ConversationId, msg: DirectMessage) -> u64 {
+        let mut conversation = self.conversations.get(conversation_id).unwrap_or_else(|| {
+            Vector::new(StorageKeys::Conversation { conversation_hash: env::sha256(&conversation_id.try_to_vec().unwrap()) })
+        });
+        let msg_idx = conversation.len();
+        conversation.push(&msg);
+        self.conversations.insert(conversation_id, &conversation);
+        msg_idx
+    }
+
+    fn add_account_conversation(&mut self, account_id: &AccountId, other_id: &AccountId) {
+        let mut account_conversations = self.accounts_conversations.get(account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKeys::AccountConversations { account_id: env::sha256(account_id.as_bytes()) })
+        });
+        account_conversations.insert(other_id);
+        self.accounts_conversations.insert(account_id, &account_conversations);
+    }
+
+    fn execute_grant_posting_delegate_call(&mut self, account_id: AccountId, delegate_id: AccountId) {
+        self.posting_delegates.insert(&PostingDelegate { account_id, delegate_id }, &env::block_timestamp());
+    }
+
+    fn execute_revoke_posting_delegate_call(&mut self, account_id: AccountId, delegate_id: AccountId) {
+        self.posting_delegates.remove(&PostingDelegate { account_id, delegate_id });
+    }
+
+    fn execute_register_app_key_call(&mut self, account_id: AccountId, public_key: PublicKey, scopes: Vec<AppScope>) {
+        self.accounts_app_keys.insert(&AccountAppKey { account_id, public_key }, &scopes);
+    }
+
+    fn execute_unregister_app_key_call(&mut self, account_id: AccountId, public_key: PublicKey) {
+        self.accounts_app_keys.remove(&AccountAppKey { account_id, public_key });
+    }
+
+    // Scores decay by half every TRENDING_SCORE_HALF_LIFE, linearly approximated within a half-life
+    fn calc_decayed_score(&self, post_score: &PostScore) -> u128 {
+        let elapsed = env::block_timestamp().saturating_sub(post_score.updated_at);
+        let half_lives = elapsed / TRENDING_SCORE_HALF_LIFE;
+        if half_lives >= 128 {
+            return 0
+        }
+        let score = post_score.score >> half_lives;
+        let remainder = u128::from(elapsed % TRENDING_SCORE_HALF_LIFE);
+        score - score * remainder / (2 * u128::from(TRENDING_SCORE_HALF_LIFE))
+    }
+
+    // Rollup of the likes of all post messages, kept up to date on every like, unlike and likes removal
+    fn add_post_message_likes(&mut self, post_id: &PostId, count: u64) {
+        let likes_count = self.posts_message_likes_counts.get(post_id).unwrap_or(0);
+        self.posts_message_likes_counts.insert(post_id, &(likes_count + count));
+    }
+
+    fn remove_post_message_likes(&mut self, post_id: &PostId, count: u64) {
+        if let Some(likes_count) = self.posts_message_likes_counts.get(post_id) {
+            let likes_count = likes_count.saturating_sub(count);
+            if likes_count == 0 {
+                self.posts_message_likes_counts.remove(post_id);
+            } else {
+                self.posts_message_likes_counts.insert(post_id, &likes_count);
+            };
+        };
+    }
+
+    fn add_post_engagement(&mut self, post_id: &PostId, weight: u128) {
+        self.update_post_score(post_id, |score| score + weight * TRENDING_SCORE_PRECISION);
+    }
+
+    // Removes post likes and reposts first, then messages from the last one while the gas reserve is not reached.
+    // Returns the number of entries left
+    fn delete_post_entries(&mut self, post_id: &PostId) -> u64 {
+        let has_gas = || env::prepaid_gas() - env::used_gas() > Gas(DELETE_POST_GAS_RESERVE);
+
+        if let Some(mut post_likes) = self.posts_likes.get(post_id) {
+            while has_gas() {
+                let next_like = post_likes.iter().next();
+                match next_like {
+                    Some(account_id) => {
+                        post_likes.remove(&account_id);
+                        self.remove_deleted_post_recent_like(&account_id, AccountLike::PostLike { post_id: post_id.clone() });
+                    },
+                    None => break
+                };
+            }
+            if !post_likes.is_empty() {
+                self.posts_likes.insert(post_id, &post_likes);
+                return post_likes.len() + self.posts_messages.get(post_id).map_or(0, |post_messages| post_messages.len())
+            }
+            self.posts_likes.remove(post_id);
+        };
+
+        if let Some(mut post_reposts) = self.posts_reposts.get(post_id) {
+            while has_gas() {
+                let next_repost = post_reposts.iter().next();
+                match next_repost {
+                    Some(account_id) => post_reposts.remove(&account_id),
+                    None => break
+                };
+            }
+            if !post_reposts.is_empty() {
+                self.posts_reposts.insert(post_id, &post_reposts);
+                return post_reposts.len() + self.posts_messages.get(post_id).map_or(0, |post_messages| post_messages.len())
+            }
+            self.posts_reposts.remove(post_id);
+        };
 
-    pub fn like_post(&mut self, post_id: PostId) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_like_post_call(&account_id, &post_id);
-        let fee = self.calc_like_post_fee(&account_id, &post_id) 
-            + self.calc_account_recent_likes_fee(&account_id, &post_id, false);
-        // log!("like_post fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::LikePost { post_id })
-    }
+        if let Some(mut post_super_likes) = self.posts_super_likes.get(post_id) {
+            while has_gas() {
+                let next_super_like = post_super_likes.accounts.keys().next();
+                match next_super_like {
+                    Some(account_id) => post_super_likes.accounts.remove(&account_id),
+                    None => break
+                };
+            }
+            if !post_super_likes.accounts.is_empty() {
+                self.posts_super_likes.insert(post_id, &post_super_likes);
+                return post_super_likes.accounts.len() + self.posts_messages.get(post_id).map_or(0, |post_messages| post_messages.len())
+            }
+            self.posts_super_likes.remove(post_id);
+        };
 
-    pub fn unlike_post(&mut self, post_id: PostId) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_unlike_post_call(&account_id, &post_id);
-        self.collect_fee_and_execute_call(1, account_id, Call::UnlikePost { post_id })
+        if let Some(mut post_reactions) = self.posts_reactions.get(post_id) {
+            while has_gas() {
+                let next_reaction = post_reactions.accounts.keys().next();
+                match next_reaction {
+                    Some(account_id) => post_reactions.remove(&account_id),
+                    None => break
+                };
+            }
+            if !post_reactions.accounts.is_empty() {
+                self.posts_reactions.insert(post_id, &post_reactions);
+                return post_reactions.accounts.len() + self.posts_messages.get(post_id).map_or(0, |post_messages| post_messages.len())
+            }
+            self.posts_reactions.remove(post_id);
+        };
+
+        // Likes of the last message are removed in chunks, the message is removed once they are gone
+        let mut post_messages = self.posts_messages.get(post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        while has_gas() {
+            if post_messages.is_empty() {
+                break
+            }
+            let msg_id = MessageId { post_id: post_id.clone(), msg_idx: post_messages.len() - 1 };
+            if let Some(mut post_message_likes) = self.posts_messages_likes.get(&msg_id) {
+                while has_gas() {
+                    let next_like = post_message_likes.iter().next();
+                    match next_like {
+                        Some(account_id) => {
+                            post_message_likes.remove(&account_id);
+                            self.remove_deleted_post_recent_like(&account_id, AccountLike::MessageLike { msg_id: msg_id.clone() });
+                        },
+                        None => break
+                    };
+                }
+                if !post_message_likes.is_empty() {
+                    self.posts_messages_likes.insert(&msg_id, &post_message_likes);
+                    break
+                }
+                self.posts_messages_likes.remove(&msg_id);
+            };
+            let msg = post_messages.pop().unwrap_or_else(|| ContractError::MessageNotFound.panic());
+            self.remove_account_message_stat(&msg.account);
+            if self.posts_messages_replies.contains_key(&msg_id) {
+                self.remove_message_replies_storage(&msg_id);
+            };
+            if self.posts_messages_reactions.contains_key(&msg_id) {
+                self.remove_post_message_reactions_storage(&msg_id);
+            };
+            self.messages_tips.remove(&msg_id);
+            self.messages_near_tips.remove(&msg_id);
+            let post_author_id = PostAuthorId { post_id: post_id.clone(), account_id: msg.account };
+            if self.posts_authors_messages.contains_key(&post_author_id) {
+                self.remove_post_author_messages_storage(&post_author_id);
+            };
+        }
+        if !post_messages.is_empty() {
+            self.posts_messages.insert(post_id, &post_messages);
+            return post_messages.len() + self.posts_messages_likes
+                .get(&MessageId { post_id: post_id.clone(), msg_idx: post_messages.len() - 1 })
+                .map_or(0, |post_message_likes| post_message_likes.len())
+        }
+
+        self.posts_messages.remove(post_id);
+        self.all_posts.remove(post_id);
+        self.posts_continuations.remove(post_id);
+        self.sensitive_posts.remove(post_id);
+        self.posts_message_likes_counts.remove(post_id);
+        self.posts_stats.remove(post_id);
+        self.posts_owners.remove(post_id);
+        self.posts_pending_owners.remove(post_id);
+        if self.posts_scores.remove(post_id).is_some() {
+            let mut trending_posts = self.trending_posts.get().unwrap_or_default();
+            trending_posts.retain(|(trending_post_id, _)| trending_post_id != post_id);
+            self.trending_posts.set(&trending_posts);
+        };
+        self.deleting_posts.remove(post_id);
+        // Entries of the post left in the accounts indexes (messages, mentions, likes history, notifications) are skipped
+        // by the views. The tombstone is covered by the contract
+        self.deleted_posts.insert(post_id);
+        0
     }
 
-    pub fn like_message(&mut self, msg_id: MessageID) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_like_message_call(&account_id, &msg_id);
-        let fee = self.calc_like_message_fee(&account_id, &msg_id)
-            + self.calc_account_recent_likes_fee(&account_id, &msg_id.post_id, true);
-        // log!("like_message fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::LikeMessage { msg_id })
+    // Stats are not created for accounts without them
+    fn remove_deleted_post_recent_like(&mut self, account_id: &AccountId, like: AccountLike) {
+        self.update_account_stats(account_id, |account_stats| account_stats.recent_likes.retain(|recent_like| recent_like != &like));
     }
 
-    pub fn unlike_message(&mut self, msg_id: MessageID) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_unlike_message_call(&account_id, &msg_id);
-        self.collect_fee_and_execute_call(1, account_id, Call::UnlikeMessage { msg_id })
+    fn remove_post_engagement(&mut self, post_id: &PostId, weight: u128) {
+        if !self.posts_scores.contains_key(post_id) {
+            return
+        }
+        self.update_post_score(post_id, |score| score.saturating_sub(weight * TRENDING_SCORE_PRECISION));
     }
 
-    pub fn add_friend(&mut self, friend_id: AccountId) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_add_friend_call(&account_id, &friend_id);
-        let fee = self.calc_add_friend_fee(&account_id, &friend_id);
-        // log!("add_friend fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::AddFriend { friend_id })
+    fn update_post_score<F: FnOnce(u128) -> u128>(&mut self, post_id: &PostId, update: F) {
+        let current_score = match self.posts_scores.get(post_id) {
+            Some(post_score) => self.calc_decayed_score(&post_score),
+            None => 0
+        };
+        let post_score = PostScore {
+            score: update(current_score),
+            updated_at: env::block_timestamp()
+        };
+        self.posts_scores.insert(post_id, &post_score);
+
+        // Trending list is capped by MAX_TRENDING_POSTS, so its storage is covered by the contract
+        let mut trending_posts = self.trending_posts.get().unwrap_or_default();
+        if let Some(idx) = trending_posts.iter().position(|(trending_post_id, _)| trending_post_id == post_id) {
+            trending_posts[idx].1 = post_score;
+        } else if trending_posts.len() < MAX_TRENDING_POSTS {
+            trending_posts.push((post_id.clone(), post_score));
+        } else {
+            // Posts of the previous windows are replaced first
+            let (min_idx, (min_is_current, min_score)) = trending_posts
+                .iter()
+                .enumerate()
+                .map(|(idx, (_, trending_post_score))| (idx, (is_current_trending_window(trending_post_score), self.calc_decayed_score(trending_post_score))))
+                .min_by_key(|(_, score)| *score)
+                .unwrap();
+            if !min_is_current || post_score.score > min_score {
+                trending_posts[min_idx] = (post_id.clone(), post_score);
+            } else {
+                return
+            }
+        }
+        self.trending_posts.set(&trending_posts);
     }
 
-    pub fn remove_friend(&mut self, friend_id: AccountId) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_remove_friend_call(&account_id, &friend_id);
-        self.collect_fee_and_execute_call(1, account_id, Call::RemoveFriend { friend_id })
+    // Counters are keyed by the action hash, so every counter takes the same storage
+    fn record_reversal(&mut self, action: ReversibleAction) {
+        if self.admin_settings.max_daily_reversals == 0 {
+            return
+        }
+
+        let key = get_reversal_key(&action);
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        let counter = match self.reversal_counters.get(&key) {
+            Some(counter) if counter.day == today => ReversalCounter { day: today, count: counter.count.saturating_add(1) },
+            _ => ReversalCounter { day: today, count: 1 }
+        };
+        self.reversal_counters.insert(&key, &counter);
     }
 
-    pub fn update_profile(&mut self, profile: AccountProfileData) -> Promise {
-        let account_id = env::predecessor_account_id();
-        self.assert_update_profile_call(&profile);
-        let update_profile_fee = self.calc_update_profile_fee(&account_id, &profile);
-        let fee: u128 = if update_profile_fee != 0 {
-            update_profile_fee
+    fn record_account_activity(&mut self, account_id: AccountId) {
+        let mut account_stats = self.accounts_stats.get(&account_id).unwrap_or_else(|| {
+            self.add_account_stat_storage(&account_id)
+        });
+
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        account_stats.day_actions_count = if account_stats.last_active_day == today {
+            account_stats.day_actions_count.saturating_add(1)
         } else {
             1
         };
-        // log!("update_profile fee {}", fee);
-        self.collect_fee_and_execute_call(fee, account_id, Call::UpdateProfile { profile })
-    }
-
-    pub fn update_admin_settings(&mut self, settings: AdminSettingsData) {
-        self.assert_owner();
-        if let Some(account_recent_likes_limit) = settings.account_recent_likes_limit {
-            self.admin_settings.account_recent_likes_limit = account_recent_likes_limit;
-        }
-        if let Some(add_message_extra_fee_percent) = settings.add_message_extra_fee_percent {
-            self.admin_settings.add_message_extra_fee_percent = add_message_extra_fee_percent;
+        if account_stats.current_streak != 0 && account_stats.last_active_day == today {
+            self.accounts_stats.insert(&account_id, &account_stats);
+            return
         }
-        if let Some(like_post_extra_fee_percent) = settings.like_post_extra_fee_percent {
-            self.admin_settings.like_post_extra_fee_percent = like_post_extra_fee_percent;
-        }
-        if let Some(like_message_extra_fee_percent) = settings.like_message_extra_fee_percent {
-            self.admin_settings.like_message_extra_fee_percent = like_message_extra_fee_percent;
+
+        account_stats.current_streak = self.calc_next_streak(&account_stats);
+        account_stats.longest_streak = std::cmp::max(account_stats.longest_streak, account_stats.current_streak);
+        account_stats.last_active_day = today;
+        self.accounts_stats.insert(&account_id, &account_stats);
+
+        if let Some(badge) = self.get_streak_badge(&account_id, account_stats.current_streak) {
+            let mut account_badges = self.accounts_badges.get(&account_id).unwrap_or_else(|| {
+                self.add_account_badges_storage(&account_id)
+            });
+            account_badges.insert(&badge);
+            self.accounts_badges.insert(&account_id, &account_badges);
         }
-        if let Some(add_friend_extra_fee_percent) = settings.add_friend_extra_fee_percent {
-            self.admin_settings.add_friend_extra_fee_percent = add_friend_extra_fee_percent;
+    }
+
+    // Stats are created by the first paid call of the account, accounts without stats are skipped
+    fn update_account_stats<F: FnOnce(&mut AccountStats)>(&mut self, account_id: &AccountId, update: F) -> Option<AccountStats> {
+        let mut account_stats = self.accounts_stats.get(account_id)?;
+        update(&mut account_stats);
+        self.accounts_stats.insert(account_id, &account_stats);
+        Some(account_stats)
+    }
+
+    // Accounts without stats have not made a paid call yet
+    fn is_account_on_probation(&self, account_id: &AccountId) -> bool {
+        if self.probation_policy.days == 0 {
+            return false
         }
-        if let Some(update_profile_extra_fee_percent) = settings.update_profile_extra_fee_percent {
-            self.admin_settings.update_profile_extra_fee_percent = update_profile_extra_fee_percent;
+        if !self.accounts_profiles.contains_key(account_id) {
+            return true
         }
-        if let Some(account_recent_like_extra_fee_percent) = settings.account_recent_like_extra_fee_percent {
-            self.admin_settings.account_recent_like_extra_fee_percent = account_recent_like_extra_fee_percent;
+        match self.accounts_stats.get(account_id) {
+            Some(account_stats) => {
+                let probation_end = account_stats.first_seen_at.saturating_add(u64::from(self.probation_policy.days) * NANOSECONDS_PER_DAY);
+                env::block_timestamp() < probation_end
+            },
+            None => true
         }
     }
-    
-    pub fn get_post_messages(&self, post_id: PostId, from_index: U64, limit: U64) -> Vec<MessageDTO> {
-        if let Some(post_messages) = self.posts_messages.get(&post_id) {
-            let from = u64::from(from_index);
-            let lim = u64::from(limit);
-            
-            (from..std::cmp::min(from + lim, post_messages.len()))
-                .map(|idx| {
-                    let msg = post_messages.get(idx).unwrap();
-                    let msg_id = MessageId { post_id: post_id.clone(), msg_idx: idx };
-                    match msg.payload {
-                        MessagePayload::Text { text } => {
-                            MessageDTO {
-                                msg_idx: U64(idx),
-                                parent_idx: match msg.parent_idx {
-                                    Some(parent_idx) => Some(U64(parent_idx)),
-                                    None => None
-                                },
-                                account: msg.account,
-                                text: Some(text),
-                                timestamp: U64(msg.timestamp),
-                                likes_count: match self.posts_messages_likes.get(&msg_id) {
-                                    Some(post_message_likes) => U64(post_message_likes.len()),
-                                    None => U64(0)
-                                }
-                            }
-                        }
-                    }
-                })
-                .collect()
+
+    fn calc_next_streak(&self, account_stats: &AccountStats) -> u32 {
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        if account_stats.current_streak == 0 {
+            1
+        } else if account_stats.last_active_day == today {
+            account_stats.current_streak
+        } else if account_stats.last_active_day + 1 == today {
+            account_stats.current_streak + 1
         } else {
-            env::panic_str("Post is not found");
+            1
         }
     }
 
-    pub fn get_post_message(&self, msg_id: MessageID) -> Option<MessageDTO> {
-        if let Some(post_messages) = self.posts_messages.get(&msg_id.post_id) {
-            let id : MessageId = msg_id.into();
-            if let Some(msg) = post_messages.get(id.msg_idx) {
-                match msg.payload {
-                    MessagePayload::Text { text } => {
-                        Some(MessageDTO {
-                            msg_idx: U64(id.msg_idx),
-                            parent_idx: match msg.parent_idx {
-                                Some(parent_idx) => Some(U64(parent_idx)),
-                                None => None
-                            },
-                            account: msg.account,
-                            text: Some(text),
-                            timestamp: U64(msg.timestamp),
-                            likes_count: match self.posts_messages_likes.get(&id) {
-                                Some(post_message_likes) => U64(post_message_likes.len()),
-                                None => U64(0)
-                            }
-                        })
-                    }
-                }
-            } else {
-                env::panic_str("Message is not found");
-            }
+    fn calc_current_streak(&self, account_stats: &AccountStats) -> u32 {
+        let today = env::block_timestamp() / NANOSECONDS_PER_DAY;
+        if account_stats.last_active_day + 1 >= today {
+            account_stats.current_streak
         } else {
-            env::panic_str("Post is not found");
+            0
         }
     }
 
-    pub fn get_post_likes(&self, post_id: PostId, from_index: U64, limit: U64) -> Vec<AccountId> {
-        if let Some(post_likes) = self.posts_likes.get(&post_id) {
-            use std::convert::TryFrom;
-            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
-                post_likes
-                    .iter()
-                    .skip(from)
-                    .take(lim)
-                    .collect()
-            } else {
-                env::panic_str("'usize' conversion failed");
-            }
-        } else {
-            Vec::new()
+    // Returns a badge for the streak if it reaches a threshold the account is not awarded with yet
+    fn get_streak_badge(&self, account_id: &AccountId, streak: u32) -> Option<Badge> {
+        if !STREAK_BADGE_THRESHOLDS.contains(&streak) {
+            return None
+        }
+        let badge = Badge::Streak { days: streak };
+        match self.accounts_badges.get(account_id) {
+            Some(account_badges) if account_badges.contains(&badge) => None,
+            _ => Some(badge)
         }
     }
 
-    pub fn get_post_likes_info(&self, post_id: PostId, account_id: AccountId) -> LikesInfoDTO {
-        if let Some(post_likes) = self.posts_likes.get(&post_id) {
-            LikesInfoDTO {
-                likes_count: U64(post_likes.len()),
-                is_liked: post_likes.contains(&account_id)
-            }
+    fn add_like_to_account_likes_stat(&mut self, account_id: AccountId, like: AccountLike) {
+        let mut account_stats = self.accounts_stats.get(&account_id).unwrap_or_else(|| {
+            self.add_account_stat_storage(&account_id)
+        });
+
+        let account_recent_likes_limit = usize::from(self.admin_settings.account_recent_likes_limit);
+
+        let updated_account_stats = if !account_stats.recent_likes.is_empty() && account_recent_likes_limit == 0 {
+            account_stats.recent_likes.clear();
+            account_stats
         } else {
-            LikesInfoDTO {
-                likes_count: U64(0),
-                is_liked: false
+            if account_stats.recent_likes.len() > account_recent_likes_limit {
+                let skip = account_stats.recent_likes.len() - account_recent_likes_limit;
+                account_stats.recent_likes = account_stats.recent_likes.into_iter().skip(skip + 1).collect();
+                account_stats.recent_likes.push(like);
+                account_stats
+            } else if account_stats.recent_likes.len() == account_recent_likes_limit {
+                let skip = 1;
+                account_stats.recent_likes = account_stats.recent_likes.into_iter().skip(skip).collect();
+                account_stats.recent_likes.push(like);
+                account_stats
+            } else {
+                account_stats.recent_likes.push(like);
+                account_stats
             }
-        }
+        };
+
+        self.accounts_stats.insert(&account_id, &updated_account_stats);
     }
 
-    pub fn get_message_likes(&self, msg_id: MessageID, from_index: U64, limit: U64) -> Vec<AccountId> {
-        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
-            use std::convert::TryFrom;
-            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
-                post_message_likes
-                    .iter()
-                    .skip(from)
-                    .take(lim)
-                    .collect()
-            } else {
-                env::panic_str("'usize' conversion failed");
-            }
-        } else {
-            Vec::new()
-        }
+    fn add_like_to_account_likes_history(&mut self, account_id: &AccountId, like: &AccountLike) {
+        if let Some(mut likes_history) = self.accounts_likes_history.get(account_id) {
+            likes_history.push(like);
+            self.accounts_likes_history.insert(account_id, &likes_history);
+        };
     }
 
-    pub fn get_message_likes_info(&self, msg_id: MessageID, account_id: AccountId) -> LikesInfoDTO {
-        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
-            LikesInfoDTO {
-                likes_count: U64(post_message_likes.len()),
-                is_liked: post_message_likes.contains(&account_id)
-            }
+    fn remove_like_from_account_likes_stat(&mut self, account_id: AccountId, like: AccountLike) {
+        let mut account_stats = self.accounts_stats.get(&account_id).unwrap_or_else(|| {
+            self.add_account_stat_storage(&account_id)
+        });
+
+        let updated_account_stats = if let Some(idx) = account_stats.recent_likes.iter().position(|l| l == &like) {
+            account_stats.recent_likes.remove(idx);
+            account_stats
         } else {
-            LikesInfoDTO {
-                likes_count: U64(0),
-                is_liked: false
+            account_stats
+        };
+
+        self.accounts_stats.insert(&account_id, &updated_account_stats);
+    }
+
+
+    // Add storage collections
+
+    fn continue_post_if_full(&mut self, post_id: PostId) -> PostId {
+        let (target_post_id, continued_post_id) = self.get_message_post_id(&post_id);
+        if let Some(continued_post_id) = continued_post_id {
+            self.posts_continuations.insert(&continued_post_id, &target_post_id);
+        };
+        target_post_id
+    }
+
+    fn add_post_messages_storage(&mut self, post_id: &PostId) -> Vector<Message> {
+        let post_messages = Vector::new(
+            StorageKeys::PostMessages { 
+                post_id: env::sha256(post_id.as_bytes()) 
             }
-        }
+        );
+
+        self.posts_messages.insert(post_id, &post_messages);
+        self.all_posts.insert(post_id);
+        self.add_post_stats_storage(post_id);
+        post_messages
     }
-    
-    pub fn get_account_last_likes(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<(PostId, Option<U64>)> {
-        if let Some(accounts_stats) = self.accounts_stats.get(&account_id) {
-            accounts_stats.recent_likes
-                .into_iter()
-                .skip(usize::try_from(u64::from(from_index)).ok().unwrap())
-                .take(usize::try_from(u64::from(limit)).ok().unwrap())
-                .map(|item| {
-                    match item {
-                        AccountLike::PostLike { post_id } => {
-                            (post_id, None)
-                        },
-                        AccountLike::MessageLike { msg_id } => {
-                            (msg_id.post_id, Some(U64(msg_id.msg_idx)))
-                        }
-                    }
-                })
-                .collect()
-        } else {
-            Vec::new()
-        }
+
+    fn remove_post_messages_storage(&mut self, post_id: &PostId) {
+        let mut post_messages = self.posts_messages.get(post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_messages.clear();
+        self.posts_messages.remove(post_id);
+        self.all_posts.remove(post_id);
+        self.posts_stats.remove(post_id);
     }
 
-    pub fn get_account_friends(&self, account_id: AccountId, from_index: U64, limit: U64) -> Vec<AccountId> {
-        if let Some(account_friends) = self.accounts_friends.get(&account_id) {
-            use std::convert::TryFrom;
-            if let (Ok(from), Ok(lim)) = (usize::try_from(u64::from(from_index)), usize::try_from(u64::from(limit))) {
-                account_friends
-                    .iter()
-                    .skip(from)
-                    .take(lim)
-                    .collect()
-            } else {
-                env::panic_str("'usize' conversion failed");
+    // Stats are created with the first messages or likes collection of the post, so their storage is paid with it
+    fn add_post_stats_storage(&mut self, post_id: &PostId) {
+        if !self.posts_stats.contains_key(post_id) {
+            self.posts_stats.insert(post_id, &PostStats::default());
+        };
+    }
+
+    fn update_post_stats<F: FnOnce(&mut PostStats)>(&mut self, post_id: &PostId, update: F) {
+        if let Some(mut post_stats) = self.posts_stats.get(post_id) {
+            update(&mut post_stats);
+            self.posts_stats.insert(post_id, &post_stats);
+        };
+    }
+
+    fn add_account_wall_storage(&mut self, account_id: &AccountId) -> Vector<WallMessage> {
+        let account_wall = Vector::new(
+            StorageKeys::AccountWall {
+                account_id: env::sha256(account_id.as_bytes())
             }
-        } else {
-            Vec::new()
-        }
+        );
+
+        self.accounts_walls.insert(account_id, &account_wall);
+        account_wall
     }
 
-    pub fn get_profile(&self, account_id: AccountId) -> Option<AccountProfileData> {
-        if let Some(account_profile) = self.accounts_profiles.get(&account_id) {
-            Some(AccountProfileData {
-              json_metadata: Some(account_profile.json_metadata),
-              image: match account_profile.image.get() {
-                  Some(vec) => Some(Base64VecU8::from(vec)),
-                  None => None
-              },
-              image_url: Some(account_profile.image_url)
-            })
-        } else {
-            None
-        }
+    fn remove_account_wall_storage(&mut self, account_id: &AccountId) {
+        let mut account_wall = self.accounts_walls.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_wall.clear();
+        self.accounts_walls.remove(account_id);
     }
 
-    pub fn get_admin_settings(&self) -> AdminSettings {
-        self.admin_settings.clone()
+    fn add_post_author_messages_storage(&mut self, post_author_id: &PostAuthorId) -> Vector<u64> {
+        let author_messages = Vector::new(
+            StorageKeys::PostAuthorMessages {
+                post_id: env::sha256(post_author_id.post_id.as_bytes()),
+                account_id: env::sha256(post_author_id.account_id.as_bytes())
+            }
+        );
+
+        self.posts_authors_messages.insert(post_author_id, &author_messages);
+        author_messages
     }
 
-    pub fn get_storage_settings(&self) -> StorageUsageSettings {
-        self.storage_usage_settings.clone()
+    fn remove_post_author_messages_storage(&mut self, post_author_id: &PostAuthorId) {
+        let mut author_messages = self.posts_authors_messages.get(post_author_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        author_messages.clear();
+        self.posts_authors_messages.remove(post_author_id);
     }
 
+    fn add_post_likes_storage(&mut self, post_id: &PostId) -> UnorderedSet<AccountId> {
+        let post_likes = UnorderedSet::new(
+            StorageKeys::PostLikes {
+                post_id: env::sha256(post_id.as_bytes())
+            }
+        );
 
-    // Assert incoming call
+        self.posts_likes.insert(post_id, &post_likes);
+        self.add_post_stats_storage(post_id);
+        post_likes
+    }
 
-    fn assert_add_message_to_post_call(&self, post_id: &PostId, text: &String) {
-        self.assert_text(text);
-        self.assert_post_id(post_id);
+    fn remove_post_likes_storage(&mut self, post_id: &PostId) {
+        let mut post_likes = self.posts_likes.get(post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_likes.clear();
+        self.posts_likes.remove(post_id);
+        self.posts_stats.remove(post_id);
     }
 
-    fn assert_add_message_to_message_call(&self, parent_msg_id: &MessageID, text: &String) {
-        self.assert_text(text);
-        self.assert_message_id(parent_msg_id);
+    fn add_post_message_likes_storage(&mut self, msg_id: &MessageId) -> UnorderedSet<AccountId> {
+        let post_message_likes = UnorderedSet::new(
+            StorageKeys::PostMessageLikes {
+                post_id: env::sha256(msg_id.post_id.as_bytes()),
+                msg_idx: msg_id.msg_idx 
+            }
+        );
 
-        let post_id = &parent_msg_id.post_id;
-        let msg_idx: u64 = parent_msg_id.msg_idx.into();
-        
-        if let Some(post_messages) = self.posts_messages.get(post_id) {
-            if !post_messages.get(msg_idx).is_some() {
-                env::panic_str("Parent message does not exist");
-            };
-        } else {
-            env::panic_str("Post does not exist");
-        };
+        self.posts_messages_likes.insert(msg_id, &post_message_likes);
+        post_message_likes
     }
 
-    fn assert_like_post_call(&self, account_id: &AccountId, post_id: &PostId) {
-        self.assert_post_id(post_id);
+    fn remove_post_message_likes_storage(&mut self, msg_id: &MessageId) {
+        let mut post_message_likes = self.posts_messages_likes.get(msg_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        self.remove_post_message_likes(&msg_id.post_id, post_message_likes.len());
+        post_message_likes.clear();
+        self.posts_messages_likes.remove(msg_id);
+    }
 
-        if let Some(post_likes) = self.posts_likes.get(post_id) {
-            if post_likes.contains(account_id) {
-                env::panic_str("Post is liked already");
-            };
+    fn add_post_super_likes_storage(&mut self, post_id: &PostId) -> SuperLikes {
+        let post_super_likes = SuperLikes {
+            accounts: UnorderedMap::new(StorageKeys::PostSuperLikes { post_id: env::sha256(post_id.as_bytes()) }),
+            total_weight: 0
         };
+
+        self.posts_super_likes.insert(post_id, &post_super_likes);
+        post_super_likes
     }
 
-    fn assert_unlike_post_call(&self, account_id: &AccountId, post_id: &PostId) {
-        self.assert_post_id(post_id);
+    fn remove_post_super_likes_storage(&mut self, post_id: &PostId) {
+        let mut post_super_likes = self.posts_super_likes.get(post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_super_likes.accounts.clear();
+        self.posts_super_likes.remove(post_id);
+    }
 
-        if let Some(post_likes) = self.posts_likes.get(post_id) {
-            if !post_likes.contains(account_id) {
-                env::panic_str("Post is not liked");
-            };
-        } else {
-            env::panic_str("Post is not liked");
+    fn add_post_reactions_storage(&mut self, post_id: &PostId) -> Reactions {
+        let post_reactions = Reactions {
+            accounts: UnorderedMap::new(StorageKeys::PostReactions { post_id: env::sha256(post_id.as_bytes()) }),
+            counts: vec![0; Reaction::ALL.len()]
         };
+
+        self.posts_reactions.insert(post_id, &post_reactions);
+        post_reactions
     }
 
-    fn assert_like_message_call(&self, account_id: &AccountId, msg_id: &MessageID) {        
-        self.assert_message_id(msg_id);
-        self.assert_message_existence(msg_id);
+    fn remove_post_reactions_storage(&mut self, post_id: &PostId) {
+        let mut post_reactions = self.posts_reactions.get(post_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        post_reactions.accounts.clear();
+        self.posts_reactions.remove(post_id);
+    }
 
-        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
-            if post_message_likes.contains(account_id) {
-                env::panic_str("Message is liked already");
-            };
+    fn add_post_message_reactions_storage(&mut self, msg_id: &MessageId) -> Reactions {
+        let message_reactions = Reactions {
+            accounts: UnorderedMap::new(StorageKeys::PostMessageReactions {
+                post_id: env::sha256(msg_id.post_id.as_bytes()),
+                msg_idx: msg_id.msg_idx
+            }),
+            counts: vec![0; Reaction::ALL.len()]
         };
+
+        self.posts_messages_reactions.insert(msg_id, &message_reactions);
+        message_reactions
     }
 
-    fn assert_unlike_message_call(&self, account_id: &AccountId, msg_id: &MessageID) {        
-        self.assert_message_id(msg_id);
-        self.assert_message_existence(msg_id);
+    fn remove_post_message_reactions_storage(&mut self, msg_id: &MessageId) {
+        let mut message_reactions = self.posts_messages_reactions.get(msg_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        message_reactions.accounts.clear();
+        self.posts_messages_reactions.remove(msg_id);
+    }
 
-        if let Some(post_message_likes) = self.posts_messages_likes.get(&msg_id.into()) {
-            if !post_message_likes.contains(account_id) {
-                env::panic_str("Message is not liked");
-            };
-        } else {
-            env::panic_str("Message is not liked");
-        };
+    fn add_account_likes_history_storage(&mut self, account_id: &AccountId) -> Vector<AccountLike> {
+        let likes_history = Vector::new(
+            StorageKeys::AccountLikesHistory {
+                account_id: env::sha256(account_id.as_bytes())
+            }
+        );
+
+        self.accounts_likes_history.insert(account_id, &likes_history);
+        likes_history
     }
 
-    fn assert_add_friend_call(&self, account_id: &AccountId, friend_id: &AccountId) {
-        if let Some(account_friends) = self.accounts_friends.get(account_id) {
-            if account_friends.contains(friend_id) {
-                env::panic_str("Friend is added already");
-            };
-        };
+    fn remove_account_likes_history_storage(&mut self, account_id: &AccountId) {
+        let mut likes_history = self.accounts_likes_history.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        likes_history.clear();
+        self.accounts_likes_history.remove(account_id);
     }
 
-    fn assert_remove_friend_call(&self, account_id: &AccountId, friend_id: &AccountId) {
-        if let Some(account_friends) = self.accounts_friends.get(account_id) {
-            if !account_friends.contains(friend_id) {
-                env::panic_str("Friend is not added");
-            };
+    fn add_account_stat_storage(&mut self, account_id: &AccountId) -> AccountStats {
+        let account_stat = AccountStats {
+            recent_likes: Vec::new(),
+            current_streak: 0,
+            longest_streak: 0,
+            last_active_day: 0,
+            first_seen_at: env::block_timestamp(),
+            day_actions_count: 0,
+            messages_count: 0,
+            likes_given: 0,
+            likes_received: 0,
+            tips_received: 0
         };
+
+        self.accounts_stats.insert(account_id, &account_stat);
+        account_stat
     }
 
-    fn assert_update_profile_call(&self, profile: &AccountProfileData) {
-        if let Some(json_metadata) = &profile.json_metadata {
-            let result : Result<Value> = serde_json::from_str(json_metadata);
-            if result.is_err() {
-                env::panic_str("Metadata is not a valid json string");
-            };
-        };
+    fn remove_account_stat_storage(&mut self, account_id: &AccountId) {
+        let mut account_stat = self.accounts_stats.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_stat.recent_likes.clear();
+        self.accounts_stats.remove(account_id);
     }
     
-    fn assert_post_id(&self, post_id: &PostId) {
-        // TODO: Add validation for post_id limit length
-        if post_id.trim().is_empty() {
-            env::panic_str("'post_id' is empty or whitespace");
-        };
+    fn add_account_badges_storage(&mut self, account_id: &AccountId) -> UnorderedSet<Badge> {
+        let account_badges = UnorderedSet::new(
+            StorageKeys::AccountBadges {
+                account_id: env::sha256(account_id.as_bytes())
+            }
+        );
 
-        if post_id.len() < MIN_POST_ID_LEN {
-            env::panic_str("'post_id' length is too small");
-        }
+        self.accounts_badges.insert(account_id, &account_badges);
+        account_badges
+    }
 
-        if post_id.len() > MAX_POST_ID_LEN {
-            env::panic_str("'post_id' cannot exceed 100 bytes");
-        }
+    fn remove_account_badges_storage(&mut self, account_id: &AccountId) {
+        let mut account_badges = self.accounts_badges.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_badges.clear();
+        self.accounts_badges.remove(account_id);
     }
 
-    fn assert_text(&self, text: &String) {
-        if text.trim().is_empty() {
-            env::panic_str("'text' is empty or whitespace");
-        };
+    fn add_account_friends_storage(&mut self, account_id: &AccountId) -> UnorderedSet<AccountId> {
+       let account_friends = UnorderedSet::new(
+            StorageKeys::AccountFriends { 
+                account_id: env::sha256(account_id.as_bytes()) 
+            }
+        );
 
-        // TODO: Add validation for text limit length
-        if text.len() < MIN_POST_MESSAGE_LEN {
-            env::panic_str("'text' length is too small");
-        };
+        self.accounts_friends.insert(account_id, &account_friends);
+        self.accounts_with_friends.insert(account_id);
+        account_friends
     }
 
-    fn assert_message_existence(&self, msg_id: &MessageID) {
-        if let Some(post_messages) = self.posts_messages.get(&msg_id.post_id) {
-            if post_messages.get(u64::from(msg_id.msg_idx)).is_none() {
-                env::panic_str("Message does not exist");
-            };
+    fn remove_account_friends_storage(&mut self, account_id: &AccountId) {
+        let mut account_friends = self.accounts_friends.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_friends.clear();
+        self.accounts_friends.remove(account_id);
+        self.accounts_with_friends.remove(account_id);
+    }
 
-        } else {
-            env::panic_str("Message does not exist");
+    fn add_account_profile_storage(&mut self, account_id: &AccountId) -> AccountProfile {
+        let account_profile = AccountProfile {
+            json_metadata: String::from(""),
+            image: LazyOption::new(
+                StorageKeys::AccountProfileImage { 
+                    account_id: env::sha256(account_id.as_bytes()),
+                },
+                None
+            ),
+            current_image_len: 0,
+            image_url: String::from(""),
+            likes_visibility: LikesVisibility::Public,
+            wall_policy: WallPolicy::Everyone
         };
+        
+        self.accounts_profiles.insert(account_id, &account_profile);
+        account_profile
     }
 
-    fn assert_message_id(&self, msg_id: &MessageID) {
-        let post_id = &msg_id.post_id;
-        self.assert_post_id(post_id);
+    fn remove_account_profile_storage(&mut self, account_id: &AccountId) {
+        let mut account_profile = self.accounts_profiles.get(account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_profile.image.remove();
+        self.accounts_profiles.remove(account_id);
     }
 
 
-    // Calculate call fee
+    fn add_account_links_storage(&mut self, account_id: &AccountId) -> UnorderedSet<AccountId> {
+        let account_links = UnorderedSet::new(
+            StorageKeys::AccountLinks {
+                account_id: env::sha256(account_id.as_bytes())
+            }
+        );
 
-    fn calc_add_message_to_post_fee(&mut self, account_id: &AccountId, post_id: &PostId, text: &String) -> u128 {
-        let is_first = !self.posts_messages.contains_key(post_id);
-        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
-        let post_id_extra_bytes = if is_first { 
-            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap()
-        } else {
-            0u64
-        };
-        let text_extra_bytes = u64::try_from(text.len() - MIN_POST_MESSAGE_LEN).unwrap();
-        let collection_bytes = if is_first {
-            self.storage_usage_settings.messages_collection_size
-        } else {
-            0u64
-        };
+        self.accounts_links.insert(account_id, &account_links);
+        account_links
+    }
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
-        // log!("text_extra_bytes bytes {}", text_extra_bytes);
-        // log!("collection_bytes bytes {}", collection_bytes);
 
-        let storage_size = self.storage_usage_settings.min_message_size 
-            + account_extra_bytes 
-            + post_id_extra_bytes
-            + text_extra_bytes 
-            + collection_bytes;
+    // Measure post storage usage
 
-        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
+    fn update_storage_usage_settings(&mut self) {
+        self.measure_message_storage_usage();
+        self.measure_post_likes_storage_usage();
+        self.measure_message_likes_storage_usage();
+        self.measure_account_friends_storage_usage();
+        self.measure_account_profile_storage_usage();
+        self.measure_account_recent_likes_storage_usage();
+        self.measure_account_links_storage_usage();
+        self.measure_author_messages_storage_usage();
+        self.measure_app_keys_storage_usage();
+        self.measure_account_badges_storage_usage();
+        self.measure_post_score_storage_usage();
+        self.measure_posting_delegates_storage_usage();
+        self.measure_wall_storage_usage();
+        self.measure_reversal_counters_storage_usage();
+        self.measure_post_continuations_storage_usage();
+        self.measure_friend_requests_storage_usage();
+        self.measure_content_reports_storage_usage();
+        self.measure_account_verifications_storage_usage();
+        self.measure_account_mentions_storage_usage();
+        self.measure_account_messages_storage_usage();
+        self.measure_post_message_likes_counts_storage_usage();
+        self.measure_account_likes_history_storage_usage();
+        self.measure_post_owners_storage_usage();
+        self.measure_message_replies_storage_usage();
+        self.measure_post_reactions_storage_usage();
+        self.measure_message_reactions_storage_usage();
+        self.measure_post_super_likes_storage_usage();
+        self.measure_message_tips_storage_usage();
+        self.measure_near_tips_storage_usage();
+        self.measure_notifications_storage_usage();
+        self.measure_direct_messages_storage_usage();
     }
 
-    fn calc_add_message_to_message_fee(&mut self, account_id: &AccountId, text: &String) -> u128 {
-        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
-        let text_extra_bytes = u64::try_from(text.len() - MIN_POST_MESSAGE_LEN).unwrap();
-        let msg_idx_bytes = 8u64;
-        
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("text_extra_bytes bytes {}", text_extra_bytes);
-        // log!("msg_idx_bytes bytes {}", msg_idx_bytes);
+    fn measure_direct_messages_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let conversation_id = ConversationId::new(&account_id, &AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN)));
+        let payload = vec![0u8; MIN_DIRECT_MESSAGE_LEN];
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_direct_message(&conversation_id, DirectMessage { sender: account_id.clone(), payload: payload.clone(), timestamp: 0 });
+        let after_first_message_storage_usage = env::storage_usage();
+
+        self.add_direct_message(&conversation_id, DirectMessage { sender: account_id, payload, timestamp: 0 });
+        let after_second_message_storage_usage = env::storage_usage();
+
+        let min_direct_message_size = after_second_message_storage_usage - after_first_message_storage_usage;
+        self.storage_usage_settings.min_direct_message_size = min_direct_message_size;
+        self.storage_usage_settings.conversation_collection_size = after_first_message_storage_usage - initial_storage_usage - min_direct_message_size;
+
+        let mut conversation = self.conversations.get(&conversation_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        conversation.clear();
+        self.conversations.remove(&conversation_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_notifications_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let recipient_id = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
+        let msg_id = MessageID { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: U64(0) };
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_notification(&recipient_id, account_id.clone(), NotificationKind::PostComment { msg_id: msg_id.clone() });
+        let after_first_notification_storage_usage = env::storage_usage();
+
+        self.add_notification(&recipient_id, account_id, NotificationKind::PostComment { msg_id });
+        let after_second_notification_storage_usage = env::storage_usage();
+
+        let min_notification_size = after_second_notification_storage_usage - after_first_notification_storage_usage;
+        self.storage_usage_settings.min_notification_size = min_notification_size;
+        self.storage_usage_settings.notifications_collection_size = after_first_notification_storage_usage - initial_storage_usage - min_notification_size;
+
+        self.remove_account_notifications_storage(&recipient_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_near_tips_storage_usage(&mut self) {
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 1 };
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.messages_near_tips.insert(&msg_id, &1);
+        let after_message_tips_storage_usage = env::storage_usage();
+
+        self.accounts_near_tips.insert(&account_id, &NearTips { sent: 1, received: 1 });
+        let after_account_tips_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_message_near_tips_size = after_message_tips_storage_usage - initial_storage_usage;
+        self.storage_usage_settings.min_account_near_tips_size = after_account_tips_storage_usage - after_message_tips_storage_usage;
+
+        self.accounts_near_tips.remove(&account_id);
+        self.messages_near_tips.remove(&msg_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_message_tips_storage_usage(&mut self) {
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 1 };
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_message_tip(&msg_id, 1);
+        let after_tips_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_message_tips_size = after_tips_storage_usage - initial_storage_usage;
+
+        self.messages_tips.remove(&msg_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_post_super_likes_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+        let account_1 = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let account_2 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
-        let storage_size = self.storage_usage_settings.min_message_size 
-            + account_extra_bytes 
-            + text_extra_bytes 
-            + msg_idx_bytes;
+        let initial_storage_usage = env::storage_usage();
 
-        self.calc_storage_fee(storage_size, self.admin_settings.add_message_extra_fee_percent)
-    }
+        self.execute_super_like_post_call(account_1, post_id.clone(), 1);
+        let after_first_super_like_storage_usage = env::storage_usage();
 
-    fn calc_like_post_fee(&mut self, account_id: &AccountId, post_id: &PostId) -> u128 {
-        let is_first = !self.posts_likes.contains_key(post_id);
-        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
-        let post_id_extra_bytes = if is_first {
-            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() 
-        } else {
-            0u64
-        };
-        let collection_bytes = if is_first {
-            self.storage_usage_settings.post_likes_collection_size
-        } else {
-            0u64
-        };
+        self.execute_super_like_post_call(account_2, post_id.clone(), 1);
+        let after_second_super_like_storage_usage = env::storage_usage();
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
-        // log!("collection_bytes bytes {}", collection_bytes);
-        
-        let storage_size = self.storage_usage_settings.min_post_like_size 
-            + (account_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
-            + post_id_extra_bytes
-            + collection_bytes;
+        self.storage_usage_settings.min_post_super_like_size = after_second_super_like_storage_usage - after_first_super_like_storage_usage;
+        self.storage_usage_settings.post_super_likes_collection_size = after_first_super_like_storage_usage - initial_storage_usage - self.storage_usage_settings.min_post_super_like_size;
 
-        self.calc_storage_fee(storage_size, self.admin_settings.like_post_extra_fee_percent)
+        self.remove_post_super_likes_storage(&post_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn calc_like_message_fee(&mut self, account_id: &AccountId, msg_id: &MessageID) -> u128 {
-        let is_first = !self.posts_messages_likes.contains_key(&msg_id.clone().into());
-        let account_extra_bytes = u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
-        let post_id_extra_bytes = if is_first { 
-            u64::try_from(msg_id.post_id.len() - MIN_POST_ID_LEN).unwrap() 
-        } else {
-            0u64
-        };
-        let collection_bytes = if is_first {
-            self.storage_usage_settings.message_likes_collection_size
-        } else {
-            0u64
-        };
+    fn measure_post_reactions_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+        let account_1 = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let account_2 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("post_id_extra_bytes bytes {}", post_id_extra_bytes);
-        // log!("collection_bytes bytes {}", collection_bytes);
-        
-        let storage_size = self.storage_usage_settings.min_message_like_size 
-            + (account_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
-            + post_id_extra_bytes
-            + collection_bytes;
+        let initial_storage_usage = env::storage_usage();
 
-        self.calc_storage_fee(storage_size, self.admin_settings.like_message_extra_fee_percent)
-    }
+        self.execute_react_to_post_call(account_1, post_id.clone(), Reaction::Fire);
+        let after_first_reaction_storage_usage = env::storage_usage();
 
+        self.execute_react_to_post_call(account_2, post_id.clone(), Reaction::Fire);
+        let after_second_reaction_storage_usage = env::storage_usage();
 
-    fn calc_account_recent_likes_fee(&mut self, account_id: &AccountId, post_id: &PostId, is_msg: bool) -> u128 {
-        if self.admin_settings.account_recent_likes_limit == 0 {
-            return 0
+        self.storage_usage_settings.min_post_reaction_size = after_second_reaction_storage_usage - after_first_reaction_storage_usage;
+        self.storage_usage_settings.post_reactions_collection_size = after_first_reaction_storage_usage - initial_storage_usage - self.storage_usage_settings.min_post_reaction_size;
+
+        self.remove_post_reactions_storage(&post_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
         }
-        
-        let existing_account_stats = self.accounts_stats.get(&account_id);
-        let account_extra_bytes = if existing_account_stats.is_none() {
-            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap() 
-        } else {
-            0u64
-        };
+    }
 
-        let msg_idx_bytes = if is_msg {
-            8u64
-        } else {
-            0u64
-        };
+    fn measure_message_reactions_storage_usage(&mut self) {
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 1 };
+        let account_1 = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let account_2 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
-        let like_extra_bytes = if existing_account_stats.is_some() {
-            let account_stats = existing_account_stats.as_ref().unwrap();
-            let recent_likes_len = u64::try_from(account_stats.recent_likes.len()).unwrap();
-            let recent_likes_limit = u64::try_from(self.admin_settings.account_recent_likes_limit).unwrap();
-            
-            if recent_likes_len == recent_likes_limit {
-                let like_to_delete = account_stats.recent_likes.get(0).expect("Old like is not found");
-                match like_to_delete {
-                    AccountLike::PostLike { post_id: post_id_to_delete } => {
-                        if post_id.len() > post_id_to_delete.len() {
-                            u64::try_from(post_id.len() - post_id_to_delete.len()).unwrap() + msg_idx_bytes
-                        } else {
-                            0u64 + msg_idx_bytes
-                        }
-                    },
-                    AccountLike::MessageLike { msg_id: msg_id_to_delete } => {
-                        if post_id.len() > msg_id_to_delete.post_id.len() {
-                            u64::try_from(post_id.len() - msg_id_to_delete.post_id.len()).unwrap()
-                        } else {
-                            0u64
-                        }
-                    }
-                }
+        let initial_storage_usage = env::storage_usage();
 
-            } else if recent_likes_len < recent_likes_limit {
-                u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() + msg_idx_bytes
-            } else {
-                0u64
-            }
-        } else  {
-            u64::try_from(post_id.len() - MIN_POST_ID_LEN).unwrap() + msg_idx_bytes
-        };
+        self.execute_react_to_message_call(account_1, msg_id.clone(), Reaction::Fire);
+        let after_first_reaction_storage_usage = env::storage_usage();
 
-        let collection_bytes = if existing_account_stats.is_none() {
-            self.storage_usage_settings.account_recent_likes_collection_size 
-        } else {
-            0u64
-        };
+        self.execute_react_to_message_call(account_2, msg_id.clone(), Reaction::Fire);
+        let after_second_reaction_storage_usage = env::storage_usage();
 
-        let min_account_recent_like_size = if existing_account_stats.is_some() {
-            let account_stats = existing_account_stats.as_ref().unwrap();
-            let recent_likes_len = u64::try_from(account_stats.recent_likes.len()).unwrap();
-            let recent_likes_limit = u64::try_from(self.admin_settings.account_recent_likes_limit).unwrap();
+        self.storage_usage_settings.min_message_reaction_size = after_second_reaction_storage_usage - after_first_reaction_storage_usage;
+        self.storage_usage_settings.message_reactions_collection_size = after_first_reaction_storage_usage - initial_storage_usage - self.storage_usage_settings.min_message_reaction_size;
 
-            if recent_likes_len < recent_likes_limit {
-                self.storage_usage_settings.min_account_recent_like_size
-            } else {
-                0u64
-            }
+        self.remove_post_message_reactions_storage(&msg_id);
 
-        } else {
-            self.storage_usage_settings.min_account_recent_like_size
-        };
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("like_extra_bytes bytes {}", like_extra_bytes);
-        // log!("collection_bytes bytes {}", collection_bytes);
-        // log!("min_account_recent_like_size bytes {}", min_account_recent_like_size);
+    fn measure_message_replies_storage_usage(&mut self) {
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 0 };
 
-        let storage_size = min_account_recent_like_size
-            + account_extra_bytes 
-            + like_extra_bytes
-            + collection_bytes;
+        let initial_storage_usage = env::storage_usage();
 
-        self.calc_storage_fee(storage_size, self.admin_settings.account_recent_like_extra_fee_percent)
-    }
+        self.add_message_to_replies_index(&msg_id, 1);
+        let after_first_reply_storage_usage = env::storage_usage();
 
-    fn calc_add_friend_fee(&mut self, account_id: &AccountId, friend_id: &AccountId) -> u128 {
-        let is_first = !self.accounts_friends.contains_key(&account_id);
-        let account_extra_bytes = if is_first { 
-            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap() 
-        } else {
-            0u64
-        };
-        let friend_id_extra_bytes = u64::try_from(friend_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap();
-        let collection_bytes = if is_first {
-            self.storage_usage_settings.account_friends_collection_size
-        } else {
-            0u64
-        };
+        self.add_message_to_replies_index(&msg_id, 2);
+        let after_second_reply_storage_usage = env::storage_usage();
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("friend_id_extra_bytes bytes {}", friend_id_extra_bytes);
-        // log!("collection_bytes bytes {}", collection_bytes);
+        self.storage_usage_settings.min_message_reply_size = after_second_reply_storage_usage - after_first_reply_storage_usage;
+        self.storage_usage_settings.message_replies_collection_size = after_first_reply_storage_usage - initial_storage_usage - self.storage_usage_settings.min_message_reply_size;
 
-        let storage_size = self.storage_usage_settings.min_account_friend_size 
-            + account_extra_bytes 
-            + (friend_id_extra_bytes * 2) // UnorderedSet stores additional key in its 'elements: Vector<T>'
-            + collection_bytes;
+        self.remove_message_replies_storage(&msg_id);
 
-        self.calc_storage_fee(storage_size, self.admin_settings.add_friend_extra_fee_percent)
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn calc_update_profile_fee(&mut self, account_id: &AccountId, profile_update: &AccountProfileData) -> u128 {
-        let existing_profile = self.accounts_profiles.get(&account_id);
-        let account_extra_bytes = if existing_profile.is_none() {
-            u64::try_from(account_id.as_str().len() - MIN_ACCOUNT_ID_LEN).unwrap()
-        } else {
-            0u64
-        };
-        let json_metadata_extra_bytes = match &profile_update.json_metadata {
-            Some(metadata) => {
-              if let Some(p) = &existing_profile {
-                  match u64::try_from(metadata.len()).unwrap().checked_sub(u64::try_from(p.json_metadata.len()).unwrap()) {
-                      Some(diff) => diff,
-                      None => 0u64
-                  }
-              } else {
-                  u64::try_from(metadata.len()).unwrap()
-              }
-            },
-            None => 0u64
-        };
-        let image_extra_bytes = match &profile_update.image {
-            Some(bytes) => {
-                let v : Vec<u8> = bytes.clone().into();
-                if let Some(p) = &existing_profile {
-                    match u64::try_from(v.len()).unwrap().checked_sub(p.current_image_len) {
-                        Some(diff) => diff,
-                        None => 0u64
-                    }
-                } else {
-                    u64::try_from(v.len()).unwrap()
-                }
-            },
-            None => 0u64
-        };
+    fn measure_post_owners_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
 
-        let image_url_extra_bytes = match &profile_update.image_url {
-            Some(image_url) => {
-              if let Some(p) = &existing_profile {
-                  match u64::try_from(image_url.len()).unwrap().checked_sub(u64::try_from(p.image_url.len()).unwrap()) {
-                      Some(diff) => diff,
-                      None => 0u64
-                  }
-              } else {
-                  u64::try_from(image_url.len()).unwrap()
-              }
-            },
-            None => 0u64
-        };
+        let initial_storage_usage = env::storage_usage();
 
-        let min_account_profile_size = if existing_profile.is_none() {
-            self.storage_usage_settings.min_account_profile_size 
-        } else {
-            0u64
-        };
+        self.posts_owners.insert(&post_id, &AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN)));
+        let after_owner_storage_usage = env::storage_usage();
 
-        // log!("account_extra_bytes bytes {}", account_extra_bytes);
-        // log!("json_metadata_extra_bytes bytes {}", json_metadata_extra_bytes);
-        // log!("image_url_extra_bytes bytes {}", image_url_extra_bytes);
-        // log!("image_extra_bytes bytes {}", image_extra_bytes);
-        // log!("min_account_profile_size bytes {}", min_account_profile_size);
+        self.storage_usage_settings.min_post_owner_size = after_owner_storage_usage - initial_storage_usage;
 
-        let storage_size = min_account_profile_size
-            + account_extra_bytes 
-            + json_metadata_extra_bytes
-            + image_extra_bytes
-            + image_url_extra_bytes;
+        self.posts_owners.remove(&post_id);
 
-        self.calc_storage_fee(storage_size, self.admin_settings.update_profile_extra_fee_percent)
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn calc_storage_fee(&self, storage_size: StorageUsage, call_extra_fee_percent: u8) -> u128 {
-        let near_fee = Balance::from(storage_size) * env::storage_byte_cost();
-        let activity_ft_fee = near_fee.saturating_mul(ACTIVITY_FT_EXCHANGE_RATE);
-        // log!("storage_size {}", storage_size);
-        // log!("activity_ft_fee {}", activity_ft_fee);
-        let fee: u128 = if call_extra_fee_percent == 0 {
-            activity_ft_fee
-        } else {
-            let extra_fee = activity_ft_fee.saturating_mul(call_extra_fee_percent.into()).saturating_div(100u128);
-            // log!("extra_fee {}", extra_fee);
-            activity_ft_fee + extra_fee
-        };
-        // log!("fee {}", fee);
-        fee
+    fn measure_account_likes_history_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let like = AccountLike::PostLike { post_id: "a".repeat(MIN_POST_ID_LEN) };
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_account_likes_history_storage(&account_id);
+        let after_collection_storage_usage = env::storage_usage();
+
+        self.add_like_to_account_likes_history(&account_id, &like);
+        let after_like_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.account_likes_history_collection_size = after_collection_storage_usage - initial_storage_usage;
+        self.storage_usage_settings.min_account_like_history_size = after_like_storage_usage - after_collection_storage_usage;
+
+        self.remove_account_likes_history_storage(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
-    
-    // Execute call logic
 
-    fn execute_add_message_to_post_call(&mut self, account_id: AccountId, post_id: PostId, text: String) -> MessageID {
-        let mut post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| {
-            self.add_post_messages_storage(&post_id)
-        });
-        
-        let msg_idx = post_messages.len();
-        let msg = Message {
-            account: account_id,
-            parent_idx: None,
-            payload: MessagePayload::Text { text },
-            timestamp: env::block_timestamp()
-        };
+    fn measure_post_message_likes_counts_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+
+        let initial_storage_usage = env::storage_usage();
 
-        post_messages.push(&msg);
-        self.posts_messages.insert(&post_id, &post_messages);
+        self.add_post_message_likes(&post_id, 1);
+        let after_likes_count_storage_usage = env::storage_usage();
 
-        let msg_id = MessageId { post_id, msg_idx };
-        msg_id.into()
-    }
+        self.storage_usage_settings.min_post_message_likes_count_size = after_likes_count_storage_usage - initial_storage_usage;
 
-    fn execute_add_message_to_message_call(&mut self, account_id: AccountId, parent_msg_id: MessageId, text: String) -> MessageID {
-        let mut post_messages = self.posts_messages.get(&parent_msg_id.post_id).expect("Post is not found");
-        
-        let msg_idx = post_messages.len();
-        let msg = Message {
-            account: account_id,
-            parent_idx: Some(parent_msg_id.msg_idx),
-            payload: MessagePayload::Text { text },
-            timestamp: env::block_timestamp()
-        };
-        post_messages.push(&msg);
-        self.posts_messages.insert(&parent_msg_id.post_id, &post_messages);
+        self.posts_message_likes_counts.remove(&post_id);
 
-        let msg_id = MessageId { post_id: parent_msg_id.post_id, msg_idx };
-        msg_id.into()
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
-    
-    fn execute_like_post_call(&mut self, account_id: AccountId, post_id: PostId) -> AccountLike {
-        let mut post_likes = self.posts_likes.get(&post_id).unwrap_or_else(|| {
-            self.add_post_likes_storage(&post_id)
-        });
-        post_likes.insert(&account_id);
-        self.posts_likes.insert(&post_id, &post_likes);
 
-        AccountLike::PostLike { post_id }
-    }
+    fn measure_account_messages_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 0 };
 
-    fn execute_unlike_post_call(&mut self, account_id: AccountId, post_id: PostId) -> AccountLike {
-        let mut post_likes = self.posts_likes.get(&post_id).expect("Post like is not found");
-        post_likes.remove(&account_id);                
-        self.posts_likes.insert(&post_id, &post_likes);
+        let initial_storage_usage = env::storage_usage();
 
-        AccountLike::PostLike { post_id }
-    }
+        self.add_message_to_account_index(&account_id, &msg_id);
+        let after_first_index_storage_usage = env::storage_usage();
 
-    fn execute_like_message_call(&mut self, account_id: AccountId, msg_id: MessageId) -> AccountLike {
-        let mut post_message_likes = self.posts_messages_likes.get(&msg_id).unwrap_or_else(|| {
-            self.add_post_message_likes_storage(&msg_id)
-        });
-        post_message_likes.insert(&account_id);
-        self.posts_messages_likes.insert(&msg_id, &post_message_likes);
+        self.add_message_to_account_index(&account_id, &msg_id);
+        let after_second_index_storage_usage = env::storage_usage();
 
-        AccountLike::MessageLike { msg_id }
+        let min_account_message_size = after_second_index_storage_usage - after_first_index_storage_usage;
+        self.storage_usage_settings.account_messages_collection_size = after_first_index_storage_usage - initial_storage_usage - min_account_message_size;
+
+        self.remove_account_messages_storage(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn execute_unlike_message_call(&mut self, account_id: AccountId, msg_id: MessageId) -> AccountLike  {
-        let mut post_message_likes = self.posts_messages_likes.get(&msg_id).expect("Message like is not found");
-        post_message_likes.remove(&account_id);
-        self.posts_messages_likes.insert(&msg_id, &post_message_likes);
+    fn measure_account_mentions_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let msg_id = MessageID { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: U64(0) };
 
-        AccountLike::MessageLike { msg_id }
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_account_mentions(vec![account_id.clone()], &msg_id);
+        let after_first_mention_storage_usage = env::storage_usage();
+
+        self.add_account_mentions(vec![account_id.clone()], &msg_id);
+        let after_second_mention_storage_usage = env::storage_usage();
+
+        let min_account_mention_size = after_second_mention_storage_usage - after_first_mention_storage_usage;
+        self.storage_usage_settings.min_account_mention_size = min_account_mention_size;
+        self.storage_usage_settings.account_mentions_collection_size = after_first_mention_storage_usage - initial_storage_usage - min_account_mention_size;
+
+        let mut account_mentions = self.accounts_mentions.get(&account_id).unwrap_or_else(|| ContractError::StorageNotFound.panic());
+        account_mentions.clear();
+        self.accounts_mentions.remove(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn execute_add_friend_call(&mut self, account_id: AccountId, friend_id: AccountId) {
-        let mut account_friends = self.accounts_friends.get(&account_id).unwrap_or_else(|| {
-            self.add_account_friends_storage(&account_id)
-        });
+    fn measure_account_verifications_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
 
-        account_friends.insert(&friend_id);
-        self.accounts_friends.insert(&account_id, &account_friends);
+        let initial_storage_usage = env::storage_usage();
+
+        self.execute_verify_account_call(
+            account_id.clone(),
+            AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN)),
+            "a".repeat(MIN_TOKEN_ID_LEN),
+            1
+        );
+        let after_verification_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_account_verification_size = after_verification_storage_usage - initial_storage_usage;
+
+        self.accounts_verifications.remove(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn execute_remove_friend_call(&mut self, account_id: AccountId, friend_id: AccountId) {
-        let mut account_friends = self.accounts_friends.get(&account_id).expect("Account friends list is not found");
-        account_friends.remove(&friend_id);
-        self.accounts_friends.insert(&account_id, &account_friends);
+    fn measure_content_reports_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+        let content = ReportedContent::Post { post_id: post_id.clone() };
+        let initial_storage_usage = env::storage_usage();
+
+        self.execute_report_content_call(AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN)), ReportedContent::Post { post_id: post_id.clone() }, ReportReason::Other);
+        let after_first_report_storage_usage = env::storage_usage();
+
+        self.execute_report_content_call(AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN)), ReportedContent::Post { post_id: post_id.clone() }, ReportReason::Other);
+        let after_second_report_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_content_report_size = after_second_report_storage_usage - after_first_report_storage_usage;
+        self.storage_usage_settings.content_reports_collection_size = after_first_report_storage_usage - initial_storage_usage - self.storage_usage_settings.min_content_report_size;
+
+        let mut content_reports = self.content_reports.get(&content).unwrap();
+        content_reports.clear();
+        self.content_reports.remove(&content);
+        self.reported_contents.remove(&content);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn execute_update_profile_call(&mut self, account_id: AccountId, json_metadata: Option<String>, image: Option<Vec<u8>>, image_url: Option<String>) {
-        let mut account_profile = self.accounts_profiles.get(&account_id).unwrap_or_else(|| {
-            self.add_account_profile_storage(&account_id)
-        });
+    // Incoming and outgoing requests have the same layout, so the outgoing ones are measured only
+    fn measure_friend_requests_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let initial_storage_usage = env::storage_usage();
 
-        if let Some(metadata) = json_metadata {
-            account_profile.json_metadata = metadata;
-        };
+        let mut outgoing_requests = UnorderedSet::new(StorageKeys::AccountOutgoingFriendRequests { account_id: env::sha256(account_id.as_bytes()) });
+        outgoing_requests.insert(&AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN)));
+        self.outgoing_friend_requests.insert(&account_id, &outgoing_requests);
+        let after_first_request_storage_usage = env::storage_usage();
 
-        if let Some(bytes) = image {
-            account_profile.image.set(&bytes);
-            account_profile.current_image_len = u64::try_from(bytes.len()).unwrap();
-        };
+        outgoing_requests.insert(&AccountId::new_unchecked("c".repeat(MIN_ACCOUNT_ID_LEN)));
+        self.outgoing_friend_requests.insert(&account_id, &outgoing_requests);
+        let after_second_request_storage_usage = env::storage_usage();
 
-        if let Some(url) = image_url {
-            account_profile.image_url = url;
-        };
+        self.storage_usage_settings.min_friend_request_size = after_second_request_storage_usage - after_first_request_storage_usage;
+        self.storage_usage_settings.friend_requests_collection_size = after_first_request_storage_usage - initial_storage_usage - self.storage_usage_settings.min_friend_request_size;
 
-        self.accounts_profiles.insert(&account_id, &account_profile);
+        outgoing_requests.clear();
+        self.outgoing_friend_requests.remove(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn add_like_to_account_likes_stat(&mut self, account_id: AccountId, like: AccountLike) {
-        let mut account_stats = self.accounts_stats.get(&account_id).unwrap_or_else(|| {
-            self.add_account_stat_storage(&account_id)
-        });
+    fn measure_post_continuations_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+        let continuation_id = "b".repeat(MIN_POST_ID_LEN);
 
-        let account_recent_likes_limit = usize::from(self.admin_settings.account_recent_likes_limit);
+        let initial_storage_usage = env::storage_usage();
 
-        let updated_account_stats = if account_stats.recent_likes.len() > 0 && account_recent_likes_limit == 0 {
-            account_stats.recent_likes.clear();
-            account_stats
-        } else {
-            if account_stats.recent_likes.len() > account_recent_likes_limit {
-                let skip = account_stats.recent_likes.len() - account_recent_likes_limit;
-                account_stats.recent_likes = account_stats.recent_likes.into_iter().skip(skip + 1).collect();
-                account_stats.recent_likes.push(like);
-                account_stats
-            } else if account_stats.recent_likes.len() == account_recent_likes_limit {
-                let skip = 1;
-                account_stats.recent_likes = account_stats.recent_likes.into_iter().skip(skip).collect();
-                account_stats.recent_likes.push(like);
-                account_stats
-            } else {
-                account_stats.recent_likes.push(like);
-                account_stats
-            }
-        };
+        self.posts_continuations.insert(&post_id, &continuation_id);
+        let after_continuation_storage_usage = env::storage_usage();
 
-        self.accounts_stats.insert(&account_id, &updated_account_stats);
+        self.storage_usage_settings.min_post_continuation_size = after_continuation_storage_usage - initial_storage_usage;
+
+        self.posts_continuations.remove(&post_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn remove_like_from_account_likes_stat(&mut self, account_id: AccountId, like: AccountLike) {
-        let mut account_stats = self.accounts_stats.get(&account_id).unwrap_or_else(|| {
-            self.add_account_stat_storage(&account_id)
+    fn measure_reversal_counters_storage_usage(&mut self) {
+        let key = get_reversal_key(&ReversibleAction::Friend {
+            account_id: AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN)),
+            friend_id: AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN))
         });
 
-        let updated_account_stats = if let Some(idx) = account_stats.recent_likes.iter().position(|l| l == &like) {
-            account_stats.recent_likes.remove(idx);
-            account_stats
-        } else {
-            account_stats
-        };
+        let initial_storage_usage = env::storage_usage();
 
-        self.accounts_stats.insert(&account_id, &updated_account_stats);
+        self.reversal_counters.insert(&key, &ReversalCounter { day: 0, count: 0 });
+        let after_reversal_counter_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_reversal_counter_size = after_reversal_counter_storage_usage - initial_storage_usage;
+
+        self.reversal_counters.remove(&key);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
+    fn measure_wall_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let target_account = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
+        let text = "a".repeat(MIN_POST_MESSAGE_LEN);
 
-    // Add storage collections
+        let initial_storage_usage = env::storage_usage();
 
-    fn add_post_messages_storage(&mut self, post_id: &PostId) -> Vector<Message> {
-        let post_messages = Vector::new(
-            StorageKeys::PostMessages { 
-                post_id: env::sha256(post_id.as_bytes()) 
-            }
-        );
+        self.execute_post_on_wall_call(account_id.clone(), target_account.clone(), text.clone());
+        let after_first_wall_message_storage_usage = env::storage_usage();
 
-        self.posts_messages.insert(post_id, &post_messages);
-        post_messages
-    }
+        self.execute_post_on_wall_call(account_id, target_account.clone(), text);
+        let after_second_wall_message_storage_usage = env::storage_usage();
 
-    fn remove_post_messages_storage(&mut self, post_id: &PostId) {
-        let mut post_messages = self.posts_messages.get(&post_id).expect("Post messages storage is not found");
-        post_messages.clear();
-        self.posts_messages.remove(&post_id);
-    }
+        self.storage_usage_settings.min_wall_message_size = after_second_wall_message_storage_usage - after_first_wall_message_storage_usage;
+        self.storage_usage_settings.wall_messages_collection_size = after_first_wall_message_storage_usage - initial_storage_usage - self.storage_usage_settings.min_wall_message_size;
 
-    fn add_post_likes_storage(&mut self, post_id: &PostId) -> UnorderedSet<AccountId> {
-        let post_likes = UnorderedSet::new(
-            StorageKeys::PostLikes {
-                post_id: env::sha256(post_id.as_bytes())
-            }
-        );
+        self.remove_account_wall_storage(&target_account);
 
-        self.posts_likes.insert(post_id, &post_likes);
-        post_likes
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn remove_post_likes_storage(&mut self, post_id: &PostId) {
-        let mut post_likes = self.posts_likes.get(&post_id).expect("Post likes storage is not found");
-        post_likes.clear();
-        self.posts_likes.remove(&post_id);
-    }
+    fn measure_posting_delegates_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let delegate_id = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
-    fn add_post_message_likes_storage(&mut self, msg_id: &MessageId) -> UnorderedSet<AccountId> {
-        let post_message_likes = UnorderedSet::new(
-            StorageKeys::PostMessageLikes {
-                post_id: env::sha256(msg_id.post_id.as_bytes()),
-                msg_idx: msg_id.msg_idx 
-            }
-        );
+        let initial_storage_usage = env::storage_usage();
 
-        self.posts_messages_likes.insert(&msg_id, &post_message_likes);
-        post_message_likes
-    }
+        self.execute_grant_posting_delegate_call(account_id.clone(), delegate_id.clone());
+        let after_posting_delegate_storage_usage = env::storage_usage();
 
-    fn remove_post_message_likes_storage(&mut self, msg_id: &MessageId) {
-        let mut post_message_likes = self.posts_messages_likes.get(&msg_id).expect("Messages likes storage is not found");
-        post_message_likes.clear();
-        self.posts_messages_likes.remove(&msg_id);
-    }
+        self.storage_usage_settings.min_posting_delegate_size = after_posting_delegate_storage_usage - initial_storage_usage;
 
-    fn add_account_stat_storage(&mut self, account_id: &AccountId) -> AccountStats {
-        let account_stat = AccountStats {
-            recent_likes: Vec::new()
-        };
+        self.execute_revoke_posting_delegate_call(account_id, delegate_id);
 
-        self.accounts_stats.insert(account_id, &account_stat);
-        account_stat
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
-    fn remove_account_stat_storage(&mut self, account_id: &AccountId) {
-        let mut account_stat = self.accounts_stats.get(&account_id).expect("Account stats storage is not found");
-        account_stat.recent_likes.clear();
-        self.accounts_stats.remove(&account_id);
-    }
-    
-    fn add_account_friends_storage(&mut self, account_id: &AccountId) -> UnorderedSet<AccountId> {
-       let account_friends = UnorderedSet::new(
-            StorageKeys::AccountFriends { 
-                account_id: env::sha256(account_id.as_bytes()) 
-            }
-        );
+    fn measure_post_score_storage_usage(&mut self) {
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
 
-        self.accounts_friends.insert(account_id, &account_friends);
-        account_friends
-    }
+        let initial_storage_usage = env::storage_usage();
 
-    fn remove_account_friends_storage(&mut self, account_id: &AccountId) {
-        let mut account_friends = self.accounts_friends.get(&account_id).expect("Account friends storage is not found");
-        account_friends.clear();
-        self.accounts_friends.remove(&account_id);
-    }
+        self.posts_scores.insert(&post_id, &PostScore { score: 0, updated_at: 0 });
+        let after_post_score_storage_usage = env::storage_usage();
 
-    fn add_account_profile_storage(&mut self, account_id: &AccountId) -> AccountProfile {
-        let account_profile = AccountProfile {
-            json_metadata: String::from(""),
-            image: LazyOption::new(
-                StorageKeys::AccountProfileImage { 
-                    account_id: env::sha256(account_id.as_bytes()),
-                },
-                None
-            ),
-            current_image_len: 0,
-            image_url: String::from("")
-        };
-        
-        self.accounts_profiles.insert(account_id, &account_profile);
-        account_profile
-    }
+        self.storage_usage_settings.min_post_score_size = after_post_score_storage_usage - initial_storage_usage;
 
-    fn remove_account_profile_storage(&mut self, account_id: &AccountId) {
-        let mut account_profile = self.accounts_profiles.get(&account_id).expect("Account profile storage is not found");
-        account_profile.image.remove();
-        self.accounts_profiles.remove(&account_id);
-    }
+        self.posts_scores.remove(&post_id);
 
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
 
-    // Measure post storage usage
+    fn measure_account_badges_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
 
-    fn update_storage_usage_settings(&mut self) {
-        self.measure_message_storage_usage();
-        self.measure_post_likes_storage_usage();
-        self.measure_message_likes_storage_usage();
-        self.measure_account_friends_storage_usage();
-        self.measure_account_profile_storage_usage();
-        self.measure_account_recent_likes_storage_usage();
+        let initial_storage_usage = env::storage_usage();
+
+        let mut account_badges = self.add_account_badges_storage(&account_id);
+        account_badges.insert(&Badge::Streak { days: STREAK_BADGE_THRESHOLDS[0] });
+        self.accounts_badges.insert(&account_id, &account_badges);
+        let after_first_badge_storage_usage = env::storage_usage();
+
+        account_badges.insert(&Badge::Streak { days: STREAK_BADGE_THRESHOLDS[1] });
+        self.accounts_badges.insert(&account_id, &account_badges);
+        let after_second_badge_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_account_badge_size = after_second_badge_storage_usage - after_first_badge_storage_usage;
+        self.storage_usage_settings.account_badges_collection_size = after_first_badge_storage_usage - initial_storage_usage - self.storage_usage_settings.min_account_badge_size;
+
+        self.remove_account_badges_storage(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
     }
 
     fn measure_message_storage_usage(&mut self) {
         let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
-        let post_id = String::from("a".repeat(MIN_POST_ID_LEN));
-        let text = String::from("a".repeat(MIN_POST_MESSAGE_LEN));
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
+        let text = "a".repeat(MIN_POST_MESSAGE_LEN);
 
         let initial_storage_usage = env::storage_usage();
 
+        // The account index collection is measured separately, so it is created before the measured messages
+        self.add_message_to_account_index(&account_id, &MessageId { post_id: post_id.clone(), msg_idx: 0 });
+        let before_messages_storage_usage = env::storage_usage();
+
         self.execute_add_message_to_post_call(
             account_id.clone(), 
             post_id.clone(), 
-            text.clone()
+            text.clone(),
+            None,
+            None,
+            false
         );
         let after_first_message_storage_usage = env::storage_usage();
         
         self.execute_add_message_to_post_call(
-            account_id, 
+            account_id.clone(), 
             post_id.clone(),
-            text
+            text,
+            None,
+            None,
+            false
         );
         let after_second_message_storage_usage = env::storage_usage();
       
         self.storage_usage_settings.min_message_size = after_second_message_storage_usage - after_first_message_storage_usage;
-        self.storage_usage_settings.messages_collection_size = after_first_message_storage_usage - initial_storage_usage - self.storage_usage_settings.min_message_size;
+        self.storage_usage_settings.messages_collection_size = after_first_message_storage_usage - before_messages_storage_usage - self.storage_usage_settings.min_message_size;
 
         self.remove_post_messages_storage(&post_id);
+        self.remove_post_author_messages_storage(&PostAuthorId { post_id, account_id: account_id.clone() });
+        self.remove_account_messages_storage(&account_id);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_author_messages_storage_usage(&mut self) {
+        let post_author_id = PostAuthorId {
+            post_id: "a".repeat(MIN_POST_ID_LEN),
+            account_id: AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN))
+        };
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.add_message_to_author_index(&post_author_id.post_id, post_author_id.account_id.clone(), 0);
+        let after_first_index_storage_usage = env::storage_usage();
+
+        self.add_message_to_author_index(&post_author_id.post_id, post_author_id.account_id.clone(), 1);
+        let after_second_index_storage_usage = env::storage_usage();
+
+        let min_author_message_size = after_second_index_storage_usage - after_first_index_storage_usage;
+        self.storage_usage_settings.author_messages_collection_size = after_first_index_storage_usage - initial_storage_usage - min_author_message_size;
+
+        self.remove_post_author_messages_storage(&post_author_id);
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of message storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
     fn measure_post_likes_storage_usage(&mut self) {
-        let post_id = String::from("a".repeat(MIN_POST_ID_LEN));
+        let post_id = "a".repeat(MIN_POST_ID_LEN);
         let account_1 = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
         let account_2 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
@@ -1363,12 +7687,12 @@ impl Contract {
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of post likes storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
     fn measure_message_likes_storage_usage(&mut self) {
-        let msg_id = MessageId { post_id: String::from("a".repeat(MIN_POST_ID_LEN)), msg_idx: 1 };
+        let msg_id = MessageId { post_id: "a".repeat(MIN_POST_ID_LEN), msg_idx: 1 };
         let account_1 = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
         let account_2 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
 
@@ -1393,7 +7717,7 @@ impl Contract {
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of message likes storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
@@ -1404,13 +7728,13 @@ impl Contract {
 
         self.add_like_to_account_likes_stat(
             account_id.clone(), 
-            AccountLike::PostLike { post_id: String::from("a".repeat(MIN_POST_ID_LEN)) }
+            AccountLike::PostLike { post_id: "a".repeat(MIN_POST_ID_LEN) }
         );
         let after_first_account_like_storage_usage = env::storage_usage();
 
         self.add_like_to_account_likes_stat(
             account_id.clone(), 
-            AccountLike::PostLike { post_id: String::from("b".repeat(MIN_POST_ID_LEN)) }
+            AccountLike::PostLike { post_id: "b".repeat(MIN_POST_ID_LEN) }
         );
         let after_second_account_like_storage_usage = env::storage_usage();
 
@@ -1421,7 +7745,7 @@ impl Contract {
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of account stat likes storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
@@ -1449,7 +7773,7 @@ impl Contract {
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of account friends storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
@@ -1462,7 +7786,9 @@ impl Contract {
             account_id.clone(),
             Some(String::from("")), 
             Some(Vec::new()),
-            Some(String::from(""))
+            Some(String::from("")),
+            Some(LikesVisibility::Public),
+            Some(WallPolicy::Everyone)
         );
         let after_profile_update_storage_usage = env::storage_usage();
 
@@ -1472,100 +7798,678 @@ impl Contract {
 
         let final_storage_usage = env::storage_usage();
         if initial_storage_usage != final_storage_usage {
-            env::panic_str("Measurement of account profile storage aborted due to data leak");
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_app_keys_storage_usage(&mut self) {
+        let account_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let public_key: PublicKey = "ed25519:11111111111111111111111111111111".parse().unwrap();
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.execute_register_app_key_call(
+            account_id.clone(),
+            public_key.clone(),
+            vec![AppScope::Likes]
+        );
+        let after_app_key_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_app_key_size = after_app_key_storage_usage - initial_storage_usage;
+
+        self.execute_unregister_app_key_call(account_id, public_key);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
+        }
+    }
+
+    fn measure_account_links_storage_usage(&mut self) {
+        let primary_id = AccountId::new_unchecked("a".repeat(MIN_ACCOUNT_ID_LEN));
+        let account_1 = AccountId::new_unchecked("b".repeat(MIN_ACCOUNT_ID_LEN));
+        let account_2 = AccountId::new_unchecked("c".repeat(MIN_ACCOUNT_ID_LEN));
+
+        let initial_storage_usage = env::storage_usage();
+
+        self.execute_request_account_link_call(
+            primary_id.clone(),
+            account_1.clone()
+        );
+        let after_link_request_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_account_link_request_size = after_link_request_storage_usage - initial_storage_usage;
+
+        self.execute_accept_account_link_call(
+            account_1.clone(),
+            primary_id.clone()
+        );
+        let after_first_link_storage_usage = env::storage_usage();
+
+        self.execute_request_account_link_call(
+            primary_id.clone(),
+            account_2.clone()
+        );
+        self.execute_accept_account_link_call(
+            account_2.clone(),
+            primary_id.clone()
+        );
+        let after_second_link_storage_usage = env::storage_usage();
+
+        self.storage_usage_settings.min_account_link_size = after_second_link_storage_usage - after_first_link_storage_usage;
+        self.storage_usage_settings.account_links_collection_size = after_first_link_storage_usage - initial_storage_usage - self.storage_usage_settings.min_account_link_size;
+
+        self.execute_unlink_account_call(account_1);
+        self.execute_unlink_account_call(account_2);
+
+        let final_storage_usage = env::storage_usage();
+        if initial_storage_usage != final_storage_usage {
+            ContractError::StorageMeasurementLeak.panic();
         }
     }
 
 
+    // The fee is collected from the calling account, which is also the sender of a delegated (NEP-366) action
     fn collect_fee_and_execute_call(&mut self, fee: u128, caller_id: AccountId, call: Call) -> Promise {
+        self.collect_fee_from_and_execute_call(fee, env::predecessor_account_id(), caller_id, call)
+    }
+
+    // Every paid call passes here, so banned accounts are rejected before the fee is collected
+    fn collect_fee_from_and_execute_call(&mut self, fee: u128, payer_id: AccountId, caller_id: AccountId, call: Call) -> Promise {
+        self.assert_not_paused();
+        self.assert_not_banned(&caller_id);
+        self.assert_not_banned(&payer_id);
+        self.assert_probation_actions_allowed(&caller_id);
+        // Tips are passed on to the message author, so the probation multiplier is not applied to them
+        let tip = match &call {
+            Call::TipMessage { amount, .. } => u128::from(*amount),
+            _ => 0
+        };
+        let fee = self.calc_probation_fee(&caller_id, fee - tip + self.calc_account_activity_fee(&caller_id)) + tip;
+
+        let operation_id = self.next_operation_id;
+        self.next_operation_id += 1;
+        self.pending_operations.insert(&operation_id, &PendingOperation {
+            caller_id,
+            payer_id: payer_id.clone(),
+            fee,
+            call,
+            block_height: env::block_height(),
+            fee_charged: false
+        });
+
         ext_ft::ext(self.fee_ft.clone())
             .with_static_gas(Gas(5*TGAS))
-            .ft_collect_fee(U128::from(fee))
+            .ft_collect_fee(payer_id, U128::from(fee))
                 .then(
                     ext_self::ext(env::current_account_id())
                     .with_static_gas(Gas(5*TGAS))
-                    .on_fee_collected(caller_id, call)
+                    .on_fee_collected(U64(operation_id))
                 )
     }
 
+    pub fn replay_pending(&mut self, operation_id: U64, refund: bool) -> Option<String> {
+        self.assert_not_paused();
+        let operation_id = u64::from(operation_id);
+        let operation = self.pending_operations.get(&operation_id).unwrap_or_else(|| ContractError::PendingOperationNotFound.panic());
+
+        if self.get_identity(env::predecessor_account_id()) != operation.caller_id && env::predecessor_account_id() != *self.ownership.owner() {
+            ContractError::NotOperationCaller.panic();
+        };
+
+        // The fee collection callback may still be in flight for a recent operation
+        if env::block_height() < operation.block_height + PENDING_OPERATION_REPLAY_DELAY {
+            ContractError::PendingOperationInFlight.panic();
+        };
+
+        self.pending_operations.remove(&operation_id);
+        if refund {
+            refund_attached_near(&operation);
+            // The fee is refunded only when its collection was recorded as successful
+            if operation.fee_charged {
+                ext_ft::ext(self.fee_ft.clone())
+                    .with_static_gas(Gas(5*TGAS))
+                    .with_attached_deposit(1)
+                    .ft_transfer(operation.payer_id, U128::from(operation.fee), Some(String::from("Pending operation fee refund")));
+            };
+            None
+        } else {
+            if !operation.fee_charged {
+                ContractError::PendingOperationFeeNotCharged.panic();
+            };
+            // The state may have changed since the operation was created, so the call is asserted again
+            self.assert_call(&operation.caller_id, &operation.call);
+            self.execute_call(operation.caller_id, operation.call)
+        }
+    }
+
+    pub fn get_pending_operation(&self, operation_id: U64) -> Option<PendingOperationDTO> {
+        self.pending_operations.get(&operation_id.into()).map(|operation| PendingOperationDTO {
+            operation_id,
+            caller_id: operation.caller_id,
+            payer_id: operation.payer_id,
+            fee: U128(operation.fee),
+            call: operation.call,
+            block_height: U64(operation.block_height),
+            fee_charged: operation.fee_charged
+        })
+    }
+
 
     #[private]
-    pub fn on_fee_collected(&mut self, caller_id: AccountId, call: Call) -> Option<String> {
+    pub fn on_fee_collected(&mut self, operation_id: U64) -> PromiseOrValue<Option<String>> {
+        let operation_id = u64::from(operation_id);
+        let mut operation = self.pending_operations.get(&operation_id).unwrap_or_else(|| ContractError::PendingOperationNotFound.panic());
 
         if is_promise_success() {
-            match call {
-                Call::AddMessageToPost { post_id, text } => {
-                    let msg_id = self.execute_add_message_to_post_call(caller_id, post_id, text);
-                    serde_json::to_string(&msg_id).ok()
-                },
-                Call::AddMessageToMessage { parent_msg_id, text } => {
-                    let msg_id = self.execute_add_message_to_message_call(caller_id, parent_msg_id.into(), text);
-                    serde_json::to_string(&msg_id).ok()
-                },
-                Call::LikePost { post_id } => {
-                    let like = self.execute_like_post_call(caller_id.clone(), post_id);
-                    self.add_like_to_account_likes_stat(caller_id, like);
-                    None
-                },
-                Call::UnlikePost { post_id } => {
-                    let like = self.execute_unlike_post_call(caller_id.clone(), post_id);
-                    self.remove_like_from_account_likes_stat(caller_id, like);
-                    None
-                },
-                Call::LikeMessage { msg_id } => {
-                    let like = self.execute_like_message_call(caller_id.clone(), msg_id.into());
-                    self.add_like_to_account_likes_stat(caller_id, like);
-                    None
-                },
-                Call::UnlikeMessage { msg_id } => {
-                    let like = self.execute_unlike_message_call(caller_id.clone(), msg_id.into());
-                    self.remove_like_from_account_likes_stat(caller_id, like);
-                    None
-                },
-                Call::AddFriend { friend_id } => {
-                    self.execute_add_friend_call(caller_id, friend_id);
-                    None
-                },
-                Call::RemoveFriend { friend_id } => {
-                    self.execute_remove_friend_call(caller_id, friend_id);
-                    None
-                },
-                Call::UpdateProfile { profile } => {
-                    let image: Option<Vec<u8>> = match profile.image {
-                        Some(vec) => Some(vec.into()),
-                        None => None
-                    };
-                    self.execute_update_profile_call(caller_id, profile.json_metadata, image, profile.image_url);
-                    None
-                },
-            }
+            // The charged fee is recorded before the call is executed by the next callback,
+            // so an operation left behind by a failed execution can be refunded
+            operation.fee_charged = true;
+            self.pending_operations.insert(&operation_id, &operation);
+            PromiseOrValue::Promise(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(5*TGAS))
+                    .execute_pending_operation(U64(operation_id))
+            )
+        } else {
+            self.pending_operations.remove(&operation_id);
+            refund_attached_near(&operation);
+            // Failing in this callback would restore the removed operation, the failure is reported by the next one
+            PromiseOrValue::Promise(
+                ext_self::ext(env::current_account_id())
+                    .with_static_gas(Gas(3*TGAS))
+                    .on_fee_not_charged()
+            )
+        }
+    }
+
+    #[private]
+    pub fn on_fee_not_charged(&mut self) {
+        ContractError::FeeNotCharged.panic()
+    }
+
+    #[private]
+    pub fn execute_pending_operation(&mut self, operation_id: U64) -> Option<String> {
+        let operation = self.pending_operations.remove(&operation_id.into()).unwrap_or_else(|| ContractError::PendingOperationNotFound.panic());
+        self.execute_call(operation.caller_id, operation.call)
+    }
+
+    fn execute_call(&mut self, caller_id: AccountId, call: Call) -> Option<String> {
+        // Every call of a batch is recorded as it is executed
+        if !matches!(call, Call::Batch { .. }) {
+            self.record_account_activity(caller_id.clone());
+        }
+        match call {
+            Call::AddMessageToPost { post_id, text, delegate, lang, sensitive } => {
+                self.add_post_engagement(&post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_message_to_post_call(caller_id.clone(), post_id, text, delegate, lang, sensitive);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: None });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::AddListingToPost { post_id, marketplace, listing_id } => {
+                self.add_post_engagement(&post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_listing_to_post_call(caller_id.clone(), post_id, marketplace, listing_id);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: None });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::AddQuoteToPost { post_id, quoted_post_id, quoted_msg_idx, text } => {
+                self.add_post_engagement(&post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_quote_to_post_call(caller_id.clone(), post_id, quoted_post_id, quoted_msg_idx, text);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: None });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::AddMediaToPost { post_id, text, media_cid, mime_type } => {
+                self.add_post_engagement(&post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_media_to_post_call(caller_id.clone(), post_id, text, media_cid, mime_type);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: None });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::AddLinkToPost { post_id, url, title, description } => {
+                self.add_post_engagement(&post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_link_to_post_call(caller_id.clone(), post_id, url, title, description);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: None });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::AddMessageToMessage { parent_msg_id, text, delegate, lang, sensitive } => {
+                self.add_post_engagement(&parent_msg_id.post_id, COMMENT_ENGAGEMENT_WEIGHT);
+                let msg_id = self.execute_add_message_to_message_call(caller_id.clone(), parent_msg_id.clone().into(), text, delegate, lang, sensitive);
+                log_event("message_added", MessageAddedEventDTO { account_id: caller_id, msg_id: msg_id.clone(), parent_msg_id: Some(parent_msg_id) });
+                serde_json::to_string(&msg_id).ok()
+            },
+            Call::EditMessage { msg_id, text } => {
+                self.execute_edit_message_call(msg_id.into(), text);
+                None
+            },
+            Call::LikePost { post_id } => {
+                self.add_post_engagement(&post_id, LIKE_ENGAGEMENT_WEIGHT);
+                let like = self.execute_like_post_call(caller_id.clone(), post_id.clone());
+                self.add_like_to_account_likes_history(&caller_id, &like);
+                self.add_like_to_account_likes_stat(caller_id.clone(), like);
+                log_event("post_liked", PostLikeEventDTO { account_id: caller_id, post_id });
+                None
+            },
+            Call::TipMessage { msg_id, amount } => {
+                let author_id = self.execute_tip_message_call(msg_id.clone().into(), amount.into());
+                log_event("message_tipped", MessageTippedEventDTO { account_id: caller_id, msg_id, author_id, amount });
+                None
+            },
+            Call::TipMessageNear { msg_id, amount } => {
+                let (author_id, platform_cut) = self.execute_tip_message_near_call(caller_id.clone(), msg_id.clone().into(), amount.into());
+                log_event("message_tipped_near", MessageTippedNearEventDTO { account_id: caller_id, msg_id, author_id, amount, platform_cut: U128(platform_cut) });
+                None
+            },
+            Call::SuperLikePost { post_id } => {
+                let weight = self.admin_settings.super_like_fee_multiplier;
+                self.add_post_engagement(&post_id, LIKE_ENGAGEMENT_WEIGHT * u128::from(weight));
+                let like = self.execute_like_post_call(caller_id.clone(), post_id.clone());
+                self.execute_super_like_post_call(caller_id.clone(), post_id.clone(), weight);
+                self.add_like_to_account_likes_history(&caller_id, &like);
+                self.add_like_to_account_likes_stat(caller_id.clone(), like);
+                log_event("post_super_liked", PostSuperLikeEventDTO { account_id: caller_id, post_id, weight });
+                None
+            },
+            Call::UnlikePost { post_id } => {
+                let weight = self.remove_post_super_like(&caller_id, &post_id);
+                self.remove_post_engagement(&post_id, LIKE_ENGAGEMENT_WEIGHT * u128::from(weight));
+                self.record_reversal(ReversibleAction::PostLike { account_id: caller_id.clone(), post_id: post_id.clone() });
+                let like = self.execute_unlike_post_call(caller_id.clone(), post_id.clone());
+                self.remove_like_from_account_likes_stat(caller_id.clone(), like);
+                log_event("post_unliked", PostLikeEventDTO { account_id: caller_id, post_id });
+                None
+            },
+            Call::Repost { post_id } => {
+                self.add_post_engagement(&post_id, REPOST_ENGAGEMENT_WEIGHT);
+                self.execute_repost_call(caller_id.clone(), post_id.clone());
+                log_event("post_reposted", PostRepostedEventDTO { account_id: caller_id, post_id });
+                None
+            },
+            Call::LikeMessage { msg_id } => {
+                let like = self.execute_like_message_call(caller_id.clone(), msg_id.clone().into());
+                self.add_post_message_likes(&msg_id.post_id, 1);
+                self.add_like_to_account_likes_history(&caller_id, &like);
+                self.add_like_to_account_likes_stat(caller_id.clone(), like);
+                log_event("message_liked", MessageLikeEventDTO { account_id: caller_id, msg_id });
+                None
+            },
+            Call::UnlikeMessage { msg_id } => {
+                self.record_reversal(ReversibleAction::MessageLike { account_id: caller_id.clone(), msg_id: (&msg_id).into() });
+                let like = self.execute_unlike_message_call(caller_id.clone(), msg_id.clone().into());
+                self.remove_post_message_likes(&msg_id.post_id, 1);
+                self.remove_like_from_account_likes_stat(caller_id.clone(), like);
+                log_event("message_unliked", MessageLikeEventDTO { account_id: caller_id, msg_id });
+                None
+            },
+            Call::AddFriend { friend_id } => {
+                self.execute_add_friend_call(caller_id.clone(), friend_id.clone());
+                self.notify(Some(friend_id.clone()), &caller_id, NotificationKind::FriendAdded);
+                log_event("friend_added", FriendAddedEventDTO { account_id: caller_id, friend_id });
+                None
+            },
+            Call::SendFriendRequest { friend_id } => {
+                self.execute_send_friend_request_call(caller_id, friend_id);
+                None
+            },
+            Call::AcceptFriendRequest { requester_id } => {
+                self.execute_accept_friend_request_call(caller_id, requester_id);
+                None
+            },
+            Call::DeclineFriendRequest { requester_id } => {
+                self.remove_friend_request(&requester_id, &caller_id);
+                None
+            },
+            Call::BlockAccount { blocked_id } => {
+                self.execute_block_account_call(caller_id, blocked_id);
+                None
+            },
+            Call::UnblockAccount { blocked_id } => {
+                remove_account_set_entry(&mut self.blocked_accounts, &caller_id, &blocked_id);
+                None
+            },
+            Call::MuteAccount { muted_id } => {
+                let mut muted_accounts = self.muted_accounts.get(&caller_id).unwrap_or_else(|| {
+                    UnorderedSet::new(StorageKeys::AccountMutedAccounts { account_id: env::sha256(caller_id.as_bytes()) })
+                });
+                muted_accounts.insert(&muted_id);
+                self.muted_accounts.insert(&caller_id, &muted_accounts);
+                None
+            },
+            Call::UnmuteAccount { muted_id } => {
+                remove_account_set_entry(&mut self.muted_accounts, &caller_id, &muted_id);
+                None
+            },
+            Call::ReportMessage { msg_id, reason } => {
+                self.execute_report_content_call(caller_id, ReportedContent::Message { msg_id: msg_id.into() }, reason);
+                None
+            },
+            Call::ReportPost { post_id, reason } => {
+                self.execute_report_content_call(caller_id, ReportedContent::Post { post_id }, reason);
+                None
+            },
+            Call::RemoveFriend { friend_id } => {
+                self.record_reversal(ReversibleAction::Friend { account_id: caller_id.clone(), friend_id: friend_id.clone() });
+                self.execute_remove_friend_call(caller_id, friend_id);
+                None
+            },
+            Call::ImportFriends { friends } => {
+                for friend_id in friends {
+                    self.execute_add_friend_call(caller_id.clone(), friend_id);
+                }
+                None
+            },
+            Call::TransferPost { post_id, new_owner } => {
+                self.posts_pending_owners.insert(&post_id, &new_owner);
+                None
+            },
+            Call::AcceptPostTransfer { post_id } => {
+                let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+                let old_owner = self.get_post_owner_id(&post_id, &post_messages).unwrap_or_else(|| ContractError::PostNotFound.panic());
+                self.posts_pending_owners.remove(&post_id);
+                self.posts_owners.insert(&post_id, &caller_id);
+                log_event("post_transferred", PostTransferredEventDTO { post_id, old_owner, new_owner: caller_id });
+                None
+            },
+            Call::CancelPostTransfer { post_id } => {
+                self.posts_pending_owners.remove(&post_id);
+                None
+            },
+            Call::Batch { calls } => {
+                let results: Vec<Option<Value>> = calls
+                    .into_iter()
+                    .map(|call| {
+                        self.execute_call(caller_id.clone(), call.into())
+                            .and_then(|result| serde_json::from_str::<Value>(&result).ok())
+                    })
+                    .collect();
+                serde_json::to_string(&results).ok()
+            },
+            Call::ReactToPost { post_id, reaction } => {
+                self.execute_react_to_post_call(caller_id.clone(), post_id.clone(), reaction);
+                log_event("post_reacted", PostReactionEventDTO { account_id: caller_id, post_id, reaction });
+                None
+            },
+            Call::RemovePostReaction { post_id } => {
+                let reaction = self.execute_remove_post_reaction_call(caller_id.clone(), post_id.clone());
+                log_event("post_reaction_removed", PostReactionEventDTO { account_id: caller_id, post_id, reaction });
+                None
+            },
+            Call::ReactToMessage { msg_id, reaction } => {
+                self.execute_react_to_message_call(caller_id.clone(), msg_id.clone().into(), reaction);
+                log_event("message_reacted", MessageReactionEventDTO { account_id: caller_id, msg_id, reaction });
+                None
+            },
+            Call::RemoveMessageReaction { msg_id } => {
+                let reaction = self.execute_remove_message_reaction_call(caller_id.clone(), msg_id.clone().into());
+                log_event("message_reaction_removed", MessageReactionEventDTO { account_id: caller_id, msg_id, reaction });
+                None
+            },
+            Call::EnableLikesHistory => {
+                self.add_account_likes_history_storage(&caller_id);
+                None
+            },
+            Call::DisableLikesHistory => {
+                self.remove_account_likes_history_storage(&caller_id);
+                None
+            },
+            Call::MarkNotificationsRead => {
+                self.execute_mark_notifications_read_call(caller_id);
+                None
+            },
+            Call::VerifyAccount { contract_id, token_id, tier } => {
+                self.execute_verify_account_call(caller_id, contract_id, token_id, tier);
+                None
+            },
+            Call::RequestAccountLink { account_id } => {
+                self.execute_request_account_link_call(caller_id, account_id);
+                None
+            },
+            Call::AcceptAccountLink { primary_id } => {
+                self.execute_accept_account_link_call(caller_id, primary_id);
+                None
+            },
+            Call::UnlinkAccount { account_id } => {
+                self.execute_unlink_account_call(account_id);
+                None
+            },
+            Call::RegisterAppKey { public_key, scopes } => {
+                self.execute_register_app_key_call(caller_id, public_key, scopes);
+                None
+            },
+            Call::UnregisterAppKey { public_key } => {
+                self.execute_unregister_app_key_call(caller_id, public_key);
+                None
+            },
+            Call::PostOnWall { target_account, text } => {
+                let msg_idx = self.execute_post_on_wall_call(caller_id, target_account, text);
+                serde_json::to_string(&U64(msg_idx)).ok()
+            },
+            Call::SendDirectMessage { recipient_id, payload } => {
+                let msg_idx = self.execute_send_direct_message_call(caller_id.clone(), recipient_id.clone(), payload.into());
+                log_event("direct_message_sent", DirectMessageSentEventDTO { account_id: caller_id, recipient_id, msg_idx: U64(msg_idx) });
+                serde_json::to_string(&U64(msg_idx)).ok()
+            },
+            Call::DeleteMessage { msg_id } => {
+                let refund = self.execute_delete_message_call(msg_id.into());
+                serde_json::to_string(&U128(refund)).ok()
+            },
+            Call::RemoveMessageFromOwnPost { msg_id } => {
+                let refund = self.execute_remove_message_from_own_post_call(msg_id.into());
+                serde_json::to_string(&U128(refund)).ok()
+            },
+            Call::SetMessageSensitive { msg_id, sensitive } => {
+                self.execute_set_message_sensitive_call(msg_id.into(), sensitive);
+                None
+            },
+            Call::GrantPostingDelegate { delegate_id } => {
+                self.execute_grant_posting_delegate_call(caller_id, delegate_id);
+                None
+            },
+            Call::RevokePostingDelegate { delegate_id } => {
+                self.execute_revoke_posting_delegate_call(caller_id, delegate_id);
+                None
+            },
+            Call::UpdateProfile { profile } => {
+                let image: Option<Vec<u8>> = profile.image.map(|vec| vec.into());
+                self.execute_update_profile_call(caller_id.clone(), profile.json_metadata, image, profile.image_url, profile.likes_visibility, profile.wall_policy);
+                log_event("profile_updated", ProfileUpdatedEventDTO { account_id: caller_id });
+                None
+            },
+        }
+    }
+
+}
+
+// Delegated message stores 'Some(delegate)' instead of 'None' in addition to its payload
+// Delegated messages are paid from the granting account's balance, other messages by the caller
+fn get_message_fee_payer(account_id: &AccountId, delegate: &Option<AccountId>) -> AccountId {
+    match delegate {
+        Some(_) => account_id.clone(),
+        None => env::predecessor_account_id()
+    }
+}
+
+fn calc_delegate_bytes(delegate: &Option<AccountId>) -> usize {
+    match delegate {
+        Some(delegate_id) => delegate_id.as_str().len() + 4,
+        None => 0
+    }
+}
+
+fn has_friend_request(requests: &LookupMap<AccountId, UnorderedSet<AccountId>>, account_id: &AccountId, other_id: &AccountId) -> bool {
+    requests.get(account_id).is_some_and(|account_requests| account_requests.contains(other_id))
+}
+
+// The accounts collection of the account is removed with its last entry
+fn remove_account_set_entry(accounts: &mut LookupMap<AccountId, UnorderedSet<AccountId>>, account_id: &AccountId, other_id: &AccountId) {
+    if let Some(mut account_set) = accounts.get(account_id) {
+        account_set.remove(other_id);
+        if account_set.is_empty() {
+            accounts.remove(account_id);
         } else {
-            env::panic_str("Fee was not charged")
+            accounts.insert(account_id, &account_set);
+        };
+    };
+}
+
+// Settings are listed by their field names in alphabetical order
+fn refund_attached_near(operation: &PendingOperation) {
+    if let Call::TipMessageNear { amount, .. } = &operation.call {
+        Promise::new(operation.payer_id.clone()).transfer(u128::from(*amount));
+    };
+}
+
+fn get_storage_settings_entries(settings: &StorageUsageSettings) -> Vec<(String, u64)> {
+    match serde_json::to_value(settings).unwrap() {
+        Value::Object(entries) => entries
+            .into_iter()
+            .map(|(setting, size)| (setting, size.as_u64().unwrap_or(0)))
+            .collect(),
+        _ => Vec::new()
+    }
+}
+
+// Mentions are '@account_id' words, at most 'MAX_MESSAGE_MENTIONS' distinct valid account ids are taken
+fn parse_mentions(text: &str) -> Vec<AccountId> {
+    let mut mentions: Vec<AccountId> = Vec::new();
+    let words = text.split(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || "._-@".contains(c)));
+    for word in words {
+        if mentions.len() == MAX_MESSAGE_MENTIONS {
+            break;
+        }
+        let mentioned_id = match word.strip_prefix('@') {
+            Some(mentioned_id) => mentioned_id.trim_end_matches(|c: char| "._-".contains(c)),
+            None => continue
+        };
+        if let Ok(mentioned_id) = AccountId::try_from(mentioned_id.to_string()) {
+            if !mentions.contains(&mentioned_id) {
+                mentions.push(mentioned_id);
+            }
         }
     }
+    mentions
+}
 
+fn is_announcement_active(announcement: &Announcement) -> bool {
+    announcement.expires_at.is_none_or(|expires_at| expires_at > env::block_timestamp())
 }
 
-pub trait Ownable {
-    fn assert_owner(&self) {
-        assert_eq!(
-            env::predecessor_account_id(),
-            self.get_owner(),
-            "This operation is restricted to the contract owner."
-        );
+fn log_event<T: Serialize>(event: &str, data: T) {
+    artfans_common::log_event(EVENT_STANDARD, EVENT_STANDARD_VERSION, event, data);
+}
+
+fn get_reactions_counts(likes_count: u64, reactions: Option<Reactions>) -> HashMap<String, U64> {
+    let mut counts: HashMap<String, U64> = HashMap::new();
+    if likes_count != 0 {
+        counts.insert(String::from(Reaction::Like.emoji()), U64(likes_count));
+    }
+    if let Some(reactions) = reactions {
+        for reaction in Reaction::ALL.iter().filter(|reaction| **reaction != Reaction::Like) {
+            let count = reactions.counts[reaction.index()];
+            if count != 0 {
+                counts.insert(String::from(reaction.emoji()), U64(count));
+            }
+        }
     }
-    fn get_owner(&self) -> AccountId;
-    fn set_owner(&mut self, owner: AccountId);
+    counts
 }
 
-#[near_bindgen]
-impl Ownable for Contract {
-    fn get_owner(&self) -> AccountId {
-        self.owner.clone()
+fn is_muted_author(muted_accounts: &Option<UnorderedSet<AccountId>>, author_id: &AccountId) -> bool {
+    muted_accounts.as_ref().is_some_and(|muted_accounts| muted_accounts.contains(author_id))
+}
+
+fn get_accounts_set_page(accounts: Option<UnorderedSet<AccountId>>, from_index: U64, limit: U64, reverse: bool) -> Vec<AccountId> {
+    match accounts {
+        Some(accounts) => {
+            get_page_indexes(accounts.len(), from_index, limit, reverse)
+                .map(|idx| accounts.as_vector().get(idx).unwrap())
+                .collect()
+        },
+        None => Vec::new()
     }
+}
 
-    fn set_owner(&mut self, owner: AccountId) {
-        self.assert_owner();
-        self.owner = owner;
+fn get_accounts_set_cursor_page(accounts: Option<UnorderedSet<AccountId>>, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> PageDTO<AccountId> {
+    match accounts {
+        Some(accounts) => {
+            let (indexes, next_cursor) = get_cursor_page_indexes(accounts.len(), cursor, limit, reverse);
+            let items = indexes
+                .into_iter()
+                .map(|idx| accounts.as_vector().get(idx).unwrap())
+                .collect();
+            PageDTO { items, next_cursor }
+        },
+        None => PageDTO { items: Vec::new(), next_cursor: None }
+    }
+}
+
+// The first page starts from the first item, or from the last one with 'reverse'. The following pages keep
+// the direction of the cursor, 'reverse' is ignored for them. There is no cursor after the last page
+fn get_cursor_page_indexes(len: u64, cursor: Option<Base64VecU8>, limit: U64, reverse: Option<bool>) -> (Vec<u64>, Option<Base64VecU8>) {
+    let cursor = match cursor {
+        Some(cursor) => PageCursor::try_from_slice(&cursor.0).unwrap_or_else(|_| ContractError::InvalidPageCursor.panic()),
+        None => {
+            let reverse = reverse.unwrap_or(false);
+            PageCursor { next_index: if reverse { len.saturating_sub(1) } else { 0 }, reverse }
+        }
+    };
+    let lim = u64::from(limit);
+    if len == 0 || lim == 0 || (!cursor.reverse && cursor.next_index >= len) {
+        return (Vec::new(), None)
+    }
+
+    let (indexes, next_cursor): (Vec<u64>, Option<PageCursor>) = if cursor.reverse {
+        // Removal from a set can leave the cursor past the last item
+        let first = std::cmp::min(cursor.next_index, len - 1);
+        let last = first.saturating_sub(lim - 1);
+        let next_cursor = if last > 0 { Some(PageCursor { next_index: last - 1, reverse: true }) } else { None };
+        ((last..=first).rev().collect(), next_cursor)
+    } else {
+        let end = std::cmp::min(cursor.next_index.saturating_add(lim), len);
+        let next_cursor = if end < len { Some(PageCursor { next_index: end, reverse: false }) } else { None };
+        ((cursor.next_index..end).collect(), next_cursor)
+    };
+    (indexes, next_cursor.map(|next_cursor| Base64VecU8(next_cursor.try_to_vec().unwrap())))
+}
+
+// With 'reverse' the page is counted from the last item, so 'from_index' = 0 starts with the newest one
+fn get_page_indexes(len: u64, from_index: U64, limit: U64, reverse: bool) -> impl Iterator<Item = u64> {
+    let from = std::cmp::min(u64::from(from_index), len);
+    let to = std::cmp::min(from.saturating_add(u64::from(limit)), len);
+    (from..to).map(move |idx| if reverse { len - 1 - idx } else { idx })
+}
+
+// Sensitive message stores 'Some(marker)' instead of 'None'
+fn calc_sensitive_bytes(sensitive: &Option<SensitiveMarker>) -> usize {
+    match sensitive {
+        Some(_) => 1,
+        None => 0
+    }
+}
+
+// Tagged message stores 'Some(lang)' instead of 'None' in addition to its payload
+fn is_current_trending_window(post_score: &PostScore) -> bool {
+    post_score.updated_at / TRENDING_WINDOW == env::block_timestamp() / TRENDING_WINDOW
+}
+
+fn calc_lang_bytes(lang: &Option<String>) -> usize {
+    match lang {
+        Some(lang) => lang.len() + 4,
+        None => 0
+    }
+}
+
+fn get_reversal_key(action: &ReversibleAction) -> Vec<u8> {
+    env::sha256(&action.try_to_vec().unwrap())
+}
+
+impl Pausable for Contract {
+    fn is_paused(&self) -> bool {
+        self.cached_config.is_paused()
     }
-}
\ No newline at end of file
+
+    fn panic_paused(&self) -> ! {
+        ContractError::Paused.panic()
+    }
+}
+
+artfans_common::impl_ownable!(Contract, ownership, ContractError::NotOwner, ContractError::NotPendingOwner);
+artfans_common::impl_version!(Contract);
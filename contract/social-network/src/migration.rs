@@ -0,0 +1,201 @@
+use crate::*;
+
+// Contract state before the upgrade. The collections keep their storage prefixes, only the settings are carried over
+#[allow(dead_code)]
+#[derive(BorshDeserialize)]
+pub struct OldContract {
+    owner: AccountId,
+    fee_ft: AccountId,
+    admin_settings: OldAdminSettings,
+    storage_usage_settings: OldStorageUsageSettings,
+    posts_messages: LookupMap<PostId, Vector<OldMessage>>,
+    posts_likes: LookupMap<PostId, UnorderedSet<AccountId>>,
+    posts_messages_likes: LookupMap<MessageId, UnorderedSet<AccountId>>,
+    accounts_friends: LookupMap<AccountId, UnorderedSet<AccountId>>,
+    accounts_profiles: LookupMap<AccountId, OldAccountProfile>,
+    accounts_stats: LookupMap<AccountId, OldAccountStats>,
+}
+
+#[derive(BorshDeserialize)]
+pub struct OldAdminSettings {
+    account_recent_likes_limit: u8,
+    add_message_extra_fee_percent: u8,
+    like_post_extra_fee_percent: u8,
+    like_message_extra_fee_percent: u8,
+    add_friend_extra_fee_percent: u8,
+    update_profile_extra_fee_percent: u8,
+    account_recent_like_extra_fee_percent: u8
+}
+
+// Measured again on migration
+#[allow(dead_code)]
+#[derive(BorshDeserialize)]
+pub struct OldStorageUsageSettings {
+    min_message_size: StorageUsage,
+    messages_collection_size: StorageUsage,
+    min_post_like_size: StorageUsage,
+    post_likes_collection_size: StorageUsage,
+    min_message_like_size: StorageUsage,
+    message_likes_collection_size: StorageUsage,
+    min_account_friend_size: StorageUsage,
+    account_friends_collection_size: StorageUsage,
+    min_account_profile_size: StorageUsage,
+    min_account_recent_like_size: StorageUsage,
+    account_recent_likes_collection_size: StorageUsage
+}
+
+#[derive(BorshDeserialize)]
+pub enum OldMessagePayload {
+    Text { text: String }
+}
+
+#[derive(BorshDeserialize)]
+pub struct OldMessage {
+    account: AccountId,
+    parent_idx: Option<u64>,
+    payload: OldMessagePayload,
+    timestamp: u64,
+}
+
+#[derive(BorshDeserialize)]
+pub struct OldAccountStats {
+    recent_likes: Vec<AccountLike>
+}
+
+#[derive(BorshDeserialize)]
+pub struct OldAccountProfile {
+    json_metadata: String,
+    image: LazyOption<Vec<u8>>,
+    current_image_len: u64,
+    image_url: String
+}
+
+impl From<OldMessage> for Message {
+    fn from(message: OldMessage) -> Self {
+        let OldMessagePayload::Text { text } = message.payload;
+        Message {
+            account: message.account,
+            parent_idx: message.parent_idx,
+            payload: MessagePayload::Text { text },
+            timestamp: message.timestamp,
+            edited_at: None,
+            delegate: None,
+            lang: None,
+            sensitive: None
+        }
+    }
+}
+
+impl From<OldAccountStats> for AccountStats {
+    fn from(account_stats: OldAccountStats) -> Self {
+        AccountStats {
+            recent_likes: account_stats.recent_likes,
+            current_streak: 0,
+            longest_streak: 0,
+            last_active_day: 0,
+            first_seen_at: 0,
+            day_actions_count: 0,
+            messages_count: 0,
+            likes_given: 0,
+            likes_received: 0,
+            tips_received: 0
+        }
+    }
+}
+
+impl From<OldAccountProfile> for AccountProfile {
+    fn from(account_profile: OldAccountProfile) -> Self {
+        AccountProfile {
+            json_metadata: account_profile.json_metadata,
+            image: account_profile.image,
+            current_image_len: account_profile.current_image_len,
+            image_url: account_profile.image_url,
+            likes_visibility: LikesVisibility::Public,
+            wall_policy: WallPolicy::Everyone
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+
+    // The old state is removed, so 'new' initializes the settings and collections added since with their defaults
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let old_state: OldContract = env::state_read().unwrap_or_else(|| ContractError::OldStateNotFound.panic());
+        env::storage_remove(b"STATE");
+
+        let settings = old_state.admin_settings;
+        Self::new(old_state.owner, old_state.fee_ft, AdminSettingsData {
+            account_recent_likes_limit: Some(settings.account_recent_likes_limit),
+            add_message_extra_fee_percent: Some(settings.add_message_extra_fee_percent),
+            like_post_extra_fee_percent: Some(settings.like_post_extra_fee_percent),
+            like_message_extra_fee_percent: Some(settings.like_message_extra_fee_percent),
+            add_friend_extra_fee_percent: Some(settings.add_friend_extra_fee_percent),
+            update_profile_extra_fee_percent: Some(settings.update_profile_extra_fee_percent),
+            account_recent_like_extra_fee_percent: Some(settings.account_recent_like_extra_fee_percent),
+            archive_refund_percent: None,
+            message_edit_window_sec: None,
+            max_daily_reversals: None,
+            max_post_messages: None,
+            sensitive_reports_threshold: None,
+            super_like_fee_multiplier: None,
+            max_media_cid_len: None,
+            max_media_mime_type_len: None
+        })
+    }
+
+    // Messages written before the upgrade keep the old layout, and calls reading them fail until they are migrated.
+    // Messages already in the current layout are skipped, so a range can be migrated again. Returns the number of migrated messages
+    pub fn migrate_post_messages(&mut self, post_id: PostId, from_index: U64, limit: U64) -> U64 {
+        self.assert_owner();
+        let post_messages = self.posts_messages.get(&post_id).unwrap_or_else(|| ContractError::PostNotFound.panic());
+        let prefix = StorageKeys::PostMessages { post_id: env::sha256(post_id.as_bytes()) }.try_to_vec().unwrap();
+
+        let from = u64::from(from_index);
+        let mut migrated_count = 0;
+        for idx in from..std::cmp::min(from.saturating_add(limit.into()), post_messages.len()) {
+            let key = [prefix.as_slice(), &idx.to_le_bytes()].concat();
+            if migrate_entry::<OldMessage, Message>(&key) {
+                migrated_count += 1;
+            };
+        }
+        self.all_posts.insert(&post_id);
+        U64(migrated_count)
+    }
+
+    // Stats and profiles of the accounts, skipped when already in the current layout. Returns the number of migrated entries.
+    // Accounts with friends are also added to 'accounts_with_friends', which did not exist before the upgrade
+    pub fn migrate_accounts(&mut self, account_ids: Vec<AccountId>) -> U64 {
+        self.assert_owner();
+        let stats_prefix = StorageKeys::AccountsStats.try_to_vec().unwrap();
+        let profiles_prefix = StorageKeys::AccountsProfiles.try_to_vec().unwrap();
+
+        let mut migrated_count = 0;
+        for account_id in account_ids {
+            let account_key = account_id.try_to_vec().unwrap();
+            if migrate_entry::<OldAccountStats, AccountStats>(&[stats_prefix.as_slice(), &account_key].concat()) {
+                migrated_count += 1;
+            };
+            if migrate_entry::<OldAccountProfile, AccountProfile>(&[profiles_prefix.as_slice(), &account_key].concat()) {
+                migrated_count += 1;
+            };
+            if self.accounts_friends.get(&account_id).is_some_and(|account_friends| !account_friends.is_empty()) {
+                self.accounts_with_friends.insert(&account_id);
+            };
+        }
+        U64(migrated_count)
+    }
+}
+
+// An entry in the current layout has more bytes than the old one, so it never deserializes as the old entry
+fn migrate_entry<Old: BorshDeserialize, New: BorshSerialize + From<Old>>(key: &[u8]) -> bool {
+    match env::storage_read(key).and_then(|value| Old::try_from_slice(&value).ok()) {
+        Some(old_entry) => {
+            env::storage_write(key, &New::from(old_entry).try_to_vec().unwrap());
+            true
+        },
+        None => false
+    }
+}
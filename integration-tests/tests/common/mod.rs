@@ -0,0 +1,235 @@
+#![allow(dead_code)]
+
+use near_workspaces::network::Sandbox;
+use near_workspaces::types::NearToken;
+use near_workspaces::{Account, AccountId, Contract, Worker};
+use serde_json::{json, Value};
+
+pub const NFT_PRICE: u128 = 3_500_000_000_000_000_000_000_000;
+pub const NFT_REGISTRATION_FEE: u128 = 100_000_000_000_000_000_000_000;
+pub const ONE_NEAR: u128 = 1_000_000_000_000_000_000_000_000;
+
+pub struct Suite {
+    pub worker: Worker<Sandbox>,
+    pub owner: Account,
+    pub beneficiary: Account,
+    pub alice: Account,
+    pub bob: Account,
+    pub activity_ft: Contract,
+    pub social_network: Contract,
+    pub nft: Contract,
+    pub marketplace: Contract,
+    pub config: Contract,
+}
+
+pub async fn compile(name: &str) -> anyhow::Result<Vec<u8>> {
+    let wasm = near_workspaces::compile_project(&format!("../contract/{}", name)).await?;
+    Ok(wasm)
+}
+
+pub async fn create_account(root: &Account, name: &str, balance: u128) -> anyhow::Result<Account> {
+    let account = root
+        .create_subaccount(name)
+        .initial_balance(NearToken::from_near(balance))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(account)
+}
+
+pub async fn deploy(root: &Account, name: &str, wasm: &[u8]) -> anyhow::Result<Contract> {
+    let account = create_account(root, name, 20).await?;
+    let contract = account.deploy(wasm).await?.into_result()?;
+    Ok(contract)
+}
+
+/// Deploys the NFT contract with `owner` as the owner and token metadata admin
+pub async fn deploy_nft(root: &Account, name: &str, owner: &Account, beneficiary: &AccountId) -> anyhow::Result<Contract> {
+    let nft = deploy(root, name, &compile("nft").await?).await?;
+    nft
+        .call("new")
+        .args_json(json!({
+            "owner": owner.id(),
+            "contract_metadata": { "spec": "nft-1.0.0", "name": "Artfans NFT collection", "symbol": "ABC" },
+            "default_token_metadata": { "title": "Very beautiful NFT!" },
+            "beneficiary": beneficiary
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(nft)
+}
+
+/// Deploys all contracts and wires them the same way as the deployment steps in the root README and the suite factory.
+/// The config treasury is the marketplace beneficiary, so activity FT purchases are paid to it before and after the refresh
+pub async fn init() -> anyhow::Result<Suite> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+
+    let owner = create_account(&root, "owner", 50).await?;
+    let beneficiary = create_account(&root, "beneficiary", 10).await?;
+    let alice = create_account(&root, "alice", 50).await?;
+    let bob = create_account(&root, "bob", 50).await?;
+
+    let activity_ft = deploy(&root, "ft", &compile("activity-ft").await?).await?;
+    let social_network = deploy(&root, "social", &compile("social-network").await?).await?;
+    let nft = deploy_nft(&root, "nft", &owner, beneficiary.id()).await?;
+    let marketplace = deploy(&root, "market", &compile("marketplace").await?).await?;
+    let config = deploy(&root, "config", &compile("config").await?).await?;
+
+    activity_ft
+        .call("new")
+        .args_json(json!({
+            "owner": owner.id(),
+            "metadata": { "spec": "ft-1.0.0", "name": "Activity Token", "symbol": "TST", "decimals": 24 }
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    social_network
+        .call("new")
+        .args_json(json!({
+            "owner": owner.id(),
+            "fee_ft": activity_ft.id(),
+            "settings": {
+                "account_recent_likes_limit": 5,
+                "add_message_extra_fee_percent": 20,
+                "like_post_extra_fee_percent": 20,
+                "like_message_extra_fee_percent": 20,
+                "add_friend_extra_fee_percent": 20,
+                "update_profile_extra_fee_percent": 20,
+                "account_recent_like_extra_fee_percent": 20
+            }
+        }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    marketplace
+        .call("new")
+        .args_json(json!({
+            "owner": owner.id(),
+            "activity_ft": activity_ft.id(),
+            "activity_ft_beneficiary": beneficiary.id()
+        }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    config
+        .call("new")
+        .args_json(json!({ "owner": owner.id(), "config": { "treasury": beneficiary.id() } }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    owner
+        .call(activity_ft.id(), "add_fee_collector")
+        .args_json(json!({ "account_id": social_network.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    owner
+        .call(activity_ft.id(), "add_minter")
+        .args_json(json!({ "account_id": marketplace.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    owner
+        .call(marketplace.id(), "set_artfans_nft")
+        .args_json(json!({ "artfans_nft": nft.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    owner
+        .call(nft.id(), "set_marketplace")
+        .args_json(json!({ "marketplace": marketplace.id(), "marketplace_only": false }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    for consumer in [&activity_ft, &social_network, &nft, &marketplace] {
+        owner
+            .call(consumer.id(), "set_config_contract")
+            .args_json(json!({ "config_contract": config.id() }))
+            .transact()
+            .await?
+            .into_result()?;
+        refresh_config(&owner, consumer).await?;
+    }
+
+    Ok(Suite { worker, owner, beneficiary, alice, bob, activity_ft, social_network, nft, marketplace, config })
+}
+
+pub async fn refresh_config(account: &Account, consumer: &Contract) -> anyhow::Result<()> {
+    let refreshed: bool = account
+        .call(consumer.id(), "refresh_config")
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?;
+    assert!(refreshed);
+    Ok(())
+}
+
+/// Buys the next NFT directly on the NFT contract and returns its id
+pub async fn buy_nft(nft: &Contract, account: &Account) -> anyhow::Result<String> {
+    let token: Value = account
+        .call(nft.id(), "nft_buy_mint_approve")
+        .args_json(json!({}))
+        .deposit(NearToken::from_yoctonear(NFT_PRICE))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?;
+    Ok(token["token_id"].as_str().unwrap().to_string())
+}
+
+pub async fn nft_approve(nft: &Contract, account: &Account, token_id: &str, receiver: &AccountId, msg: Value) -> anyhow::Result<()> {
+    account
+        .call(nft.id(), "nft_approve")
+        .args_json(json!({ "token_id": token_id, "account_id": receiver, "msg": msg.to_string() }))
+        .deposit(NearToken::from_millinear(10))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+pub async fn nft_owner_of(nft: &Contract, token_id: &str) -> anyhow::Result<String> {
+    let token: Value = nft.view("nft_token").args_json(json!({ "token_id": token_id })).await?.json()?;
+    Ok(token["owner_id"].as_str().unwrap().to_string())
+}
+
+pub async fn balance_of(account: &Account) -> anyhow::Result<u128> {
+    Ok(account.view_account().await?.balance.as_yoctonear())
+}
+
+pub async fn buy_activity_ft(suite: &Suite, account: &Account, near_amount: u128) -> anyhow::Result<()> {
+    account
+        .call(suite.marketplace.id(), "buy_activity_ft")
+        .deposit(NearToken::from_near(near_amount))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(())
+}
+
+pub async fn ft_balance_of(suite: &Suite, account: &Account) -> anyhow::Result<u128> {
+    let balance: String = suite
+        .activity_ft
+        .view("ft_balance_of")
+        .args_json(json!({ "account_id": account.id() }))
+        .await?
+        .json()?;
+    Ok(balance.parse()?)
+}
@@ -0,0 +1,104 @@
+mod common;
+
+use common::{compile, create_account, deploy};
+use near_workspaces::types::NearToken;
+use near_workspaces::AccountId;
+use serde_json::{json, Value};
+
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn to_base64(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity((bytes.len() + 2) / 3 * 4);
+    for chunk in bytes.chunks(3) {
+        let buf = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (u32::from(buf[0]) << 16) | (u32::from(buf[1]) << 8) | u32::from(buf[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                result.push(BASE64_CHARS[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                result.push('=');
+            }
+        }
+    }
+    result
+}
+
+#[tokio::test]
+async fn deploy_suite_deploys_and_wires_contracts() -> anyhow::Result<()> {
+    let worker = near_workspaces::sandbox().await?;
+    let root = worker.root_account()?;
+    let admin = create_account(&root, "admin", 100).await?;
+    let factory = deploy(&root, "factory", &compile("factory").await?).await?;
+    // The stored code is paid by the factory
+    root.transfer_near(factory.id(), NearToken::from_near(100)).await?.into_result()?;
+
+    factory
+        .call("new")
+        .args_json(json!({ "owner": admin.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    for (kind, name) in [("ActivityFt", "activity-ft"), ("SocialNetwork", "social-network"), ("Nft", "nft"), ("Marketplace", "marketplace"), ("Config", "config")] {
+        admin
+            .call(factory.id(), "store_contract_code")
+            .args_json(json!({ "kind": kind, "code": to_base64(&compile(name).await?) }))
+            .max_gas()
+            .transact()
+            .await?
+            .into_result()?;
+    }
+
+    let suite_deposit: String = factory.view("get_suite_deposit").await?.json()?;
+    let deployed: bool = admin
+        .call(factory.id(), "deploy_suite")
+        .args_json(json!({ "config": {
+            "prefix": "staging",
+            "owner": admin.id(),
+            "ft_metadata": { "spec": "ft-1.0.0", "name": "Activity Token", "symbol": "TST", "decimals": 24 },
+            "social_network_settings": { "account_recent_likes_limit": 5 },
+            "nft_contract_metadata": { "spec": "nft-1.0.0", "name": "Artfans NFT collection", "symbol": "ABC" },
+            "nft_default_token_metadata": { "title": "Very beautiful NFT!" },
+            "nft_beneficiary": admin.id(),
+            "config_settings": { "treasury": admin.id() }
+        }}))
+        .deposit(NearToken::from_yoctonear(suite_deposit.parse()?))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?;
+    assert!(deployed);
+
+    let suite: Value = factory.view("get_suite_accounts").args_json(json!({ "prefix": "staging" })).await?.json()?;
+    let account = |name: &str| -> anyhow::Result<AccountId> { Ok(suite[name].as_str().unwrap().parse()?) };
+    let (activity_ft, social_network, nft, marketplace, config) = (
+        account("activity_ft")?, account("social_network")?, account("nft")?, account("marketplace")?, account("config")?
+    );
+
+    // Ownership of every contract initialized by the factory is proposed to the suite owner
+    for contract_id in [&activity_ft, &social_network, &nft, &marketplace] {
+        let pending_owner: Option<AccountId> = worker.view(contract_id, "get_pending_owner").await?.json()?;
+        assert_eq!(pending_owner.as_ref(), Some(admin.id()));
+    }
+    let config_owner: AccountId = worker.view(&config, "get_owner").await?.json()?;
+    assert_eq!(&config_owner, admin.id());
+
+    let marketplace_config: Value = worker.view(&marketplace, "get_config").await?.json()?;
+    assert_eq!(marketplace_config["artfans_nft"], json!(nft));
+    assert_eq!(marketplace_config["config_contract"], json!(config));
+    assert_eq!(marketplace_config["social_network"], json!(social_network));
+
+    let ft_metadata: Value = worker.view(&activity_ft, "ft_metadata").await?.json()?;
+    assert_eq!(ft_metadata["symbol"], json!("TST"));
+
+    // The accepted owner loads the shared config
+    admin.call(&marketplace, "accept_ownership").transact().await?.into_result()?;
+    let refreshed: bool = admin.call(&marketplace, "refresh_config").max_gas().transact().await?.into_result()?.json()?;
+    assert!(refreshed);
+    let cached_config: Value = worker.view(&marketplace, "get_cached_config").await?.json()?;
+    assert_eq!(cached_config["version"], json!("1"));
+    assert_eq!(cached_config["treasury"], json!(admin.id()));
+
+    Ok(())
+}
@@ -0,0 +1,386 @@
+mod common;
+
+use common::{
+    balance_of, buy_activity_ft, buy_nft, deploy_nft, ft_balance_of, init, nft_approve, nft_owner_of,
+    NFT_PRICE, NFT_REGISTRATION_FEE, ONE_NEAR,
+};
+use near_workspaces::types::{Gas, NearToken};
+use serde_json::{json, Value};
+
+const ACTIVITY_FT_EXCHANGE_RATE: u128 = 100;
+const ACTIVITY_FT_REGISTRATION_FEE: u128 = 1_250_000_000_000_000_000_000;
+const LISTING_PRICE: u128 = 5 * ONE_NEAR;
+const NANOSECONDS_PER_DAY: u64 = 86_400_000_000_000;
+
+#[tokio::test]
+async fn buy_activity_ft_mints_tokens_and_pays_beneficiary() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let beneficiary_balance = suite.beneficiary.view_account().await?.balance.as_yoctonear();
+
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+
+    // The first purchase pays for the FT storage registration of the buyer
+    let expected_ft = (ONE_NEAR - ACTIVITY_FT_REGISTRATION_FEE) * ACTIVITY_FT_EXCHANGE_RATE;
+    assert_eq!(ft_balance_of(&suite, &suite.alice).await?, expected_ft);
+
+    let received = suite.beneficiary.view_account().await?.balance.as_yoctonear() - beneficiary_balance;
+    assert_eq!(received, ONE_NEAR - ACTIVITY_FT_REGISTRATION_FEE);
+
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+    assert_eq!(
+        ft_balance_of(&suite, &suite.alice).await?,
+        expected_ft + ONE_NEAR * ACTIVITY_FT_EXCHANGE_RATE
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn buy_activity_ft_is_refunded_when_marketplace_is_not_a_minter() -> anyhow::Result<()> {
+    let suite = init().await?;
+
+    suite
+        .owner
+        .call(suite.activity_ft.id(), "remove_minter")
+        .args_json(serde_json::json!({ "account_id": suite.marketplace.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let alice_balance = suite.alice.view_account().await?.balance.as_yoctonear();
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+
+    assert_eq!(ft_balance_of(&suite, &suite.alice).await?, 0);
+    // Only gas is spent, the attached deposit is returned by the callback
+    let spent = alice_balance - suite.alice.view_account().await?.balance.as_yoctonear();
+    assert!(spent < ONE_NEAR / 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn buy_activity_ft_applies_bonus_tier() -> anyhow::Result<()> {
+    let suite = init().await?;
+
+    suite
+        .owner
+        .call(suite.marketplace.id(), "set_ft_bonus_tiers")
+        .args_json(serde_json::json!({ "tiers": [{ "min_near_amount": ONE_NEAR.to_string(), "bonus_percent": 10 }] }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+    let ft_balance = ft_balance_of(&suite, &suite.alice).await?;
+    let beneficiary_balance = suite.beneficiary.view_account().await?.balance.as_yoctonear();
+
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+    assert_eq!(
+        ft_balance_of(&suite, &suite.alice).await? - ft_balance,
+        ONE_NEAR * ACTIVITY_FT_EXCHANGE_RATE * 11 / 10
+    );
+    // The bonus is minted on top, the beneficiary receives only the attached deposit
+    let received = suite.beneficiary.view_account().await?.balance.as_yoctonear() - beneficiary_balance;
+    assert_eq!(received, ONE_NEAR);
+
+    Ok(())
+}
+
+async fn get_purchase_gas(suite: &common::Suite, purchase: &str) -> anyhow::Result<u64> {
+    let config: serde_json::Value = suite.marketplace.view("get_config").await?.json()?;
+    Ok(config[purchase].as_str().unwrap().parse()?)
+}
+
+#[tokio::test]
+async fn buy_activity_ft_with_registration_fits_in_purchase_gas() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let purchase_gas = get_purchase_gas(&suite, "activity_ft_purchase_gas").await?;
+
+    // The first purchase takes the FT storage registration path
+    let result = suite
+        .alice
+        .call(suite.marketplace.id(), "buy_activity_ft")
+        .deposit(NearToken::from_near(1))
+        .gas(Gas::from_gas(purchase_gas))
+        .transact()
+        .await?;
+    assert!(result.is_success());
+    assert!(result.total_gas_burnt.as_gas() <= purchase_gas);
+
+    let expected_ft = (ONE_NEAR - ACTIVITY_FT_REGISTRATION_FEE) * ACTIVITY_FT_EXCHANGE_RATE;
+    assert_eq!(ft_balance_of(&suite, &suite.alice).await?, expected_ft);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn buy_activity_ft_fails_without_step_gas() -> anyhow::Result<()> {
+    let suite = init().await?;
+
+    let result = suite
+        .alice
+        .call(suite.marketplace.id(), "buy_activity_ft")
+        .deposit(NearToken::from_near(1))
+        .gas(Gas::from_tgas(15))
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+    assert_eq!(ft_balance_of(&suite, &suite.alice).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn gas_settings_must_fit_in_one_transaction() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let tgas = |gas: u64| (gas * 1_000_000_000_000).to_string();
+    let gas_settings = |nft_buy_mint_approve: u64| serde_json::json!({ "gas_settings": {
+        "ft_mint": tgas(10),
+        "nft_buy_mint_approve": tgas(nft_buy_mint_approve),
+        "nft_transfer_payout": tgas(15),
+        "nft_transfer": tgas(15),
+        "purchase_resolve": tgas(5),
+        "transfer_resolve": tgas(15),
+        "cart_step_resolve": tgas(20)
+    }});
+
+    let result = suite
+        .owner
+        .call(suite.marketplace.id(), "set_gas_settings")
+        .args_json(gas_settings(270))
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    suite
+        .owner
+        .call(suite.marketplace.id(), "set_gas_settings")
+        .args_json(gas_settings(60))
+        .transact()
+        .await?
+        .into_result()?;
+
+    // The purchase chain configured by the owner still fits in one transaction
+    let purchase_gas = get_purchase_gas(&suite, "activity_ft_purchase_gas").await?;
+    assert!(purchase_gas <= 300 * 1_000_000_000_000);
+    suite
+        .alice
+        .call(suite.marketplace.id(), "buy_activity_ft")
+        .deposit(NearToken::from_near(1))
+        .gas(Gas::from_gas(purchase_gas))
+        .transact()
+        .await?
+        .into_result()?;
+    assert!(ft_balance_of(&suite, &suite.alice).await? > 0);
+
+    Ok(())
+}
+
+async fn list_token(suite: &common::Suite, token_id: &str, price: u128) -> anyhow::Result<()> {
+    nft_approve(&suite.nft, &suite.alice, token_id, suite.marketplace.id(), json!({ "price": price.to_string() })).await
+}
+
+#[tokio::test]
+async fn buy_listing_transfers_token_and_pays_owner() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let token_id = buy_nft(&suite.nft, &suite.alice).await?;
+    list_token(&suite, &token_id, LISTING_PRICE).await?;
+    let alice_balance = balance_of(&suite.alice).await?;
+
+    suite
+        .bob
+        .call(suite.marketplace.id(), "buy_listing")
+        .args_json(json!({ "listing_id": "0" }))
+        .deposit(NearToken::from_yoctonear(LISTING_PRICE))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    assert_eq!(nft_owner_of(&suite.nft, &token_id).await?, suite.bob.id().to_string());
+    let listing: Option<Value> = suite.marketplace.view("get_listing").args_json(json!({ "listing_id": "0" })).await?.json()?;
+    assert!(listing.is_none());
+    // The approval storage may be refunded to the seller on top of the price
+    let received = balance_of(&suite.alice).await? - alice_balance;
+    assert!(received >= LISTING_PRICE && received < LISTING_PRICE + ONE_NEAR / 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn checkout_executes_items_and_refunds_failed_ones() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let token_id = buy_nft(&suite.nft, &suite.alice).await?;
+    list_token(&suite, &token_id, LISTING_PRICE).await?;
+
+    // The listing stays in the marketplace, but its transfer fails without the approval
+    suite
+        .alice
+        .call(suite.nft.id(), "nft_revoke")
+        .args_json(json!({ "token_id": token_id, "account_id": suite.marketplace.id() }))
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let results: Vec<bool> = suite
+        .bob
+        .call(suite.marketplace.id(), "checkout")
+        .args_json(json!({ "items": ["ArtfansNft", { "Listing": { "listing_id": "0" } }, { "ActivityFt": { "near_amount": ONE_NEAR.to_string() } }] }))
+        .deposit(NearToken::from_yoctonear(NFT_PRICE + LISTING_PRICE + ONE_NEAR))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json()?;
+    assert_eq!(results, vec![true, false, true]);
+
+    assert_eq!(nft_owner_of(&suite.nft, "2").await?, suite.bob.id().to_string());
+    assert_eq!(nft_owner_of(&suite.nft, &token_id).await?, suite.alice.id().to_string());
+    assert_eq!(
+        ft_balance_of(&suite, &suite.bob).await?,
+        (ONE_NEAR - ACTIVITY_FT_REGISTRATION_FEE) * ACTIVITY_FT_EXCHANGE_RATE
+    );
+
+    let refund: String = suite.marketplace.view("get_refund").args_json(json!({ "account_id": suite.bob.id() })).await?.json()?;
+    assert_eq!(refund, LISTING_PRICE.to_string());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn collection_offer_is_accepted_by_holder() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let offer_price = 4 * ONE_NEAR;
+    suite
+        .bob
+        .call(suite.marketplace.id(), "make_collection_offer")
+        .args_json(json!({ "price": offer_price.to_string() }))
+        .deposit(NearToken::from_yoctonear(offer_price))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let token_id = buy_nft(&suite.nft, &suite.alice).await?;
+    let alice_balance = balance_of(&suite.alice).await?;
+    nft_approve(&suite.nft, &suite.alice, &token_id, suite.marketplace.id(), json!({ "offer_id": "0" })).await?;
+
+    assert_eq!(nft_owner_of(&suite.nft, &token_id).await?, suite.bob.id().to_string());
+    let offer: Option<Value> = suite.marketplace.view("get_collection_offer").args_json(json!({ "offer_id": "0" })).await?.json()?;
+    assert!(offer.is_none());
+    // The approval deposit and gas are paid out of the offer price
+    let received = balance_of(&suite.alice).await? - alice_balance;
+    assert!(received > offer_price - ONE_NEAR / 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn fiat_purchase_is_settled_once() -> anyhow::Result<()> {
+    let suite = init().await?;
+    suite
+        .owner
+        .call(suite.marketplace.id(), "set_payment_processor")
+        .args_json(json!({ "payment_processor": suite.owner.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let settle = || suite
+        .owner
+        .call(suite.marketplace.id(), "settle_fiat_purchase")
+        .args_json(json!({ "buyer": suite.alice.id(), "item": "ArtfansNft", "external_ref": "pi_3NQ2xA" }))
+        .deposit(NearToken::from_yoctonear(NFT_PRICE))
+        .max_gas();
+
+    let completed: bool = settle().transact().await?.into_result()?.json()?;
+    assert!(completed);
+    assert_eq!(nft_owner_of(&suite.nft, "1").await?, suite.alice.id().to_string());
+
+    let settlement: Value = suite
+        .marketplace
+        .view("get_fiat_settlement")
+        .args_json(json!({ "external_ref": "pi_3NQ2xA" }))
+        .await?
+        .json()?;
+    assert_eq!(settlement["status"], json!("Completed"));
+    assert_eq!(settlement["buyer_id"], json!(suite.alice.id()));
+
+    assert!(settle().transact().await?.is_failure());
+    let supply: String = suite.nft.view("nft_total_supply").await?.json()?;
+    assert_eq!(supply, "1");
+
+    Ok(())
+}
+
+/// NFT paying its proceeds to the marketplace escrow, as required by the primary sale escrow
+async fn deploy_escrow_nft(suite: &common::Suite) -> anyhow::Result<near_workspaces::Contract> {
+    let nft = deploy_nft(&suite.worker.root_account()?, "escrow-nft", &suite.owner, suite.marketplace.id()).await?;
+    suite
+        .owner
+        .call(nft.id(), "set_marketplace")
+        .args_json(json!({ "marketplace": suite.marketplace.id(), "marketplace_only": false }))
+        .transact()
+        .await?
+        .into_result()?;
+    suite
+        .owner
+        .call(suite.marketplace.id(), "set_artfans_nft")
+        .args_json(json!({ "artfans_nft": nft.id() }))
+        .transact()
+        .await?
+        .into_result()?;
+    Ok(nft)
+}
+
+#[tokio::test]
+async fn primary_sale_is_escrowed_and_refunded() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let nft = deploy_escrow_nft(&suite).await?;
+    suite
+        .owner
+        .call(suite.marketplace.id(), "set_primary_sale_escrow")
+        .args_json(json!({ "beneficiary": suite.beneficiary.id(), "period": NANOSECONDS_PER_DAY.to_string(), "refund_fee_percent": 10 }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let token_id = buy_nft(&nft, &suite.alice).await?;
+    let sale: Value = suite.marketplace.view("get_primary_sale").args_json(json!({ "token_id": token_id })).await?.json()?;
+    let escrowed = NFT_PRICE - NFT_REGISTRATION_FEE;
+    assert_eq!(sale["buyer_id"], json!(suite.alice.id()));
+    assert_eq!(sale["amount"], json!(escrowed.to_string()));
+
+    let alice_balance = balance_of(&suite.alice).await?;
+    let beneficiary_balance = balance_of(&suite.beneficiary).await?;
+    nft_approve(&nft, &suite.alice, &token_id, suite.marketplace.id(), json!({ "refund_primary_sale": true })).await?;
+
+    assert_eq!(nft_owner_of(&nft, &token_id).await?, suite.beneficiary.id().to_string());
+    let sale: Option<Value> = suite.marketplace.view("get_primary_sale").args_json(json!({ "token_id": token_id })).await?.json()?;
+    assert!(sale.is_none());
+
+    let fee = escrowed / 100 * 10;
+    assert_eq!(balance_of(&suite.beneficiary).await? - beneficiary_balance, fee);
+    let refunded = balance_of(&suite.alice).await? - alice_balance;
+    assert!(refunded > escrowed - fee - ONE_NEAR / 10);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn primary_sale_proceeds_are_retained_while_escrow_is_disabled() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let nft = deploy_escrow_nft(&suite).await?;
+    let retained_fees: String = nft.view("get_retained_fees").await?.json()?;
+
+    let token_id = buy_nft(&nft, &suite.alice).await?;
+    assert_eq!(nft_owner_of(&nft, &token_id).await?, suite.alice.id().to_string());
+
+    let sale: Option<Value> = suite.marketplace.view("get_primary_sale").args_json(json!({ "token_id": token_id })).await?.json()?;
+    assert!(sale.is_none());
+    // The rejected proceeds come back to the NFT contract on top of the retained registration fee
+    let retained = nft.view("get_retained_fees").await?.json::<String>()?.parse::<u128>()? - retained_fees.parse::<u128>()?;
+    assert!(retained >= NFT_PRICE - NFT_REGISTRATION_FEE);
+
+    Ok(())
+}
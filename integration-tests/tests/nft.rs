@@ -0,0 +1,94 @@
+mod common;
+
+use common::{init, NFT_PRICE, NFT_REGISTRATION_FEE};
+use near_workspaces::types::NearToken;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn nft_buy_mint_approve_mints_token_and_pays_beneficiary() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let beneficiary_balance = suite.beneficiary.view_account().await?.balance.as_yoctonear();
+
+    let token: Value = suite
+        .alice
+        .call(suite.nft.id(), "nft_buy_mint_approve")
+        .args_json(json!({}))
+        .deposit(NearToken::from_yoctonear(NFT_PRICE))
+        .max_gas()
+        .transact()
+        .await?
+        .json()?;
+    assert_eq!(token["token_id"], json!("1"));
+    assert_eq!(token["owner_id"], json!(suite.alice.id()));
+
+    let received = suite.beneficiary.view_account().await?.balance.as_yoctonear() - beneficiary_balance;
+    assert_eq!(received, NFT_PRICE - NFT_REGISTRATION_FEE);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn nft_buy_mint_approve_rejects_wrong_deposit() -> anyhow::Result<()> {
+    let suite = init().await?;
+
+    let result = suite
+        .alice
+        .call(suite.nft.id(), "nft_buy_mint_approve")
+        .args_json(json!({}))
+        .deposit(NearToken::from_near(1))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    let supply: String = suite.nft.view("nft_total_supply").await?.json()?;
+    assert_eq!(supply, "0");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn nft_buy_mint_approve_lists_token_on_marketplace() -> anyhow::Result<()> {
+    let suite = init().await?;
+    let alice_balance = suite.alice.view_account().await?.balance.as_yoctonear();
+
+    let outcome = suite
+        .alice
+        .call(suite.nft.id(), "nft_buy_mint_approve")
+        .args_json(json!({
+            "approve_receiver_id": suite.marketplace.id(),
+            "approve_msg": json!({ "price": "5000000000000000000000000" }).to_string()
+        }))
+        .deposit(NearToken::from_yoctonear(NFT_PRICE))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let token: Value = outcome.json()?;
+    assert_eq!(token["token_id"], json!("1"));
+
+    let listing: Value = suite
+        .marketplace
+        .view("get_listing")
+        .args_json(json!({ "listing_id": "0" }))
+        .await?
+        .json()?;
+    assert_eq!(listing["token_id"], json!("1"));
+    assert_eq!(listing["owner_id"], json!(suite.alice.id()));
+    assert_eq!(listing["price"], json!("5000000000000000000000000"));
+
+    let is_approved: bool = suite
+        .nft
+        .view("nft_is_approved")
+        .args_json(json!({ "token_id": "1", "approved_account_id": suite.marketplace.id(), "approval_id": 1 }))
+        .await?
+        .json()?;
+    assert!(is_approved);
+
+    // The attached deposit is not refunded by the approval
+    let spent = alice_balance - suite.alice.view_account().await?.balance.as_yoctonear();
+    assert!(spent >= NFT_PRICE);
+
+    Ok(())
+}
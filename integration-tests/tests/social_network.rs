@@ -0,0 +1,175 @@
+mod common;
+
+use common::{buy_activity_ft, ft_balance_of, init};
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn add_message_collects_fee_and_stores_message() -> anyhow::Result<()> {
+    let suite = init().await?;
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+    let alice_ft = ft_balance_of(&suite, &suite.alice).await?;
+
+    suite
+        .alice
+        .call(suite.social_network.id(), "add_message_to_post")
+        .args_json(json!({ "post_id": "post_number_one", "text": "This is a test message" }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let fee = alice_ft - ft_balance_of(&suite, &suite.alice).await?;
+    assert!(fee > 0);
+    assert_eq!(ft_balance_of(&suite, suite.social_network.as_account()).await?, fee);
+
+    let messages: Vec<Value> = suite
+        .social_network
+        .view("get_post_messages")
+        .args_json(json!({ "post_id": "post_number_one", "from_index": "0", "limit": "10" }))
+        .await?
+        .json()?;
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0]["account"], json!(suite.alice.id()));
+    assert_eq!(messages[0]["text"], json!("This is a test message"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn call_is_not_executed_without_activity_ft() -> anyhow::Result<()> {
+    let suite = init().await?;
+
+    let result = suite
+        .bob
+        .call(suite.social_network.id(), "like_post")
+        .args_json(json!({ "post_id": "post_number_one" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    let likes: Vec<String> = suite
+        .social_network
+        .view("get_post_likes")
+        .args_json(json!({ "post_id": "post_number_one", "from_index": "0", "limit": "10" }))
+        .await?
+        .json()?;
+    assert!(likes.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn linked_account_likes_aggregate_to_primary_identity() -> anyhow::Result<()> {
+    let suite = init().await?;
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+    buy_activity_ft(&suite, &suite.bob, 1).await?;
+
+    suite
+        .alice
+        .call(suite.social_network.id(), "request_account_link")
+        .args_json(json!({ "account_id": suite.bob.id() }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    suite
+        .bob
+        .call(suite.social_network.id(), "accept_account_link")
+        .args_json(json!({ "primary_id": suite.alice.id() }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let identity: String = suite
+        .social_network
+        .view("get_identity")
+        .args_json(json!({ "account_id": suite.bob.id() }))
+        .await?
+        .json()?;
+    assert_eq!(identity, suite.alice.id().to_string());
+
+    suite
+        .bob
+        .call(suite.social_network.id(), "like_post")
+        .args_json(json!({ "post_id": "post_number_one" }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let likes: Vec<String> = suite
+        .social_network
+        .view("get_post_likes")
+        .args_json(json!({ "post_id": "post_number_one", "from_index": "0", "limit": "10" }))
+        .await?
+        .json()?;
+    assert_eq!(likes, vec![suite.alice.id().to_string()]);
+
+    let result = suite
+        .alice
+        .call(suite.social_network.id(), "like_post")
+        .args_json(json!({ "post_id": "post_number_one" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn message_edit_is_allowed_within_edit_window() -> anyhow::Result<()> {
+    let suite = init().await?;
+    buy_activity_ft(&suite, &suite.alice, 1).await?;
+
+    suite
+        .alice
+        .call(suite.social_network.id(), "add_message_to_post")
+        .args_json(json!({ "post_id": "post_number_one", "text": "This is a test message" }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let msg_id = json!({ "post_id": "post_number_one", "msg_idx": "0" });
+    let result = suite
+        .alice
+        .call(suite.social_network.id(), "edit_message")
+        .args_json(json!({ "msg_id": msg_id, "text": "This is an edited message" }))
+        .max_gas()
+        .transact()
+        .await?;
+    assert!(result.is_failure());
+
+    suite
+        .owner
+        .call(suite.social_network.id(), "update_admin_settings")
+        .args_json(json!({ "settings": { "message_edit_window_sec": 900 } }))
+        .transact()
+        .await?
+        .into_result()?;
+
+    suite
+        .alice
+        .call(suite.social_network.id(), "edit_message")
+        .args_json(json!({ "msg_id": msg_id, "text": "This is an edited message" }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    let message: Value = suite
+        .social_network
+        .view("get_post_message")
+        .args_json(json!({ "msg_id": msg_id }))
+        .await?
+        .json()?;
+    assert_eq!(message["text"], json!("This is an edited message"));
+    assert_eq!(message["edited"], json!(true));
+    assert!(message["edited_at"].is_string());
+
+    Ok(())
+}